@@ -0,0 +1,122 @@
+use hft_types::{MarketTick, StreamKey};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Identity of a tick for duplicate detection
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TickKey {
+    /// Sequences count per venue's stream of a symbol
+    Sequence(StreamKey, u64),
+    /// Unsequenced ticks are identified by their raw payload
+    Payload(u64),
+}
+
+impl TickKey {
+    fn of(tick: &MarketTick, payload: &[u8]) -> Self {
+        match tick.sequence {
+            Some(sequence) => TickKey::Sequence(tick.stream(), sequence),
+            None => {
+                let mut hasher = DefaultHasher::new();
+                payload.hash(&mut hasher);
+                TickKey::Payload(hasher.finish())
+            }
+        }
+    }
+}
+
+/// Remembers the most recently seen tick keys, evicting the least recently
+/// seen once `capacity` is reached so memory stays flat at any tick rate
+pub struct TickDeduplicator {
+    capacity: usize,
+    /// Key -> generation of its latest sighting
+    seen: HashMap<TickKey, u64>,
+    /// Sightings oldest first; entries superseded by a later sighting are
+    /// skipped on eviction
+    order: VecDeque<(TickKey, u64)>,
+    generation: u64,
+}
+
+impl TickDeduplicator {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            seen: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            generation: 0,
+        }
+    }
+
+    /// Record the tick and report whether it was already seen
+    pub fn is_duplicate(&mut self, tick: &MarketTick, payload: &[u8]) -> bool {
+        let key = TickKey::of(tick, payload);
+        self.generation += 1;
+        let duplicate = self.seen.insert(key.clone(), self.generation).is_some();
+        self.order.push_back((key, self.generation));
+
+        while self.seen.len() > self.capacity {
+            let Some((key, generation)) = self.order.pop_front() else {
+                break;
+            };
+            if self.seen.get(&key) == Some(&generation) {
+                self.seen.remove(&key);
+            }
+        }
+        // Repeated hits leave stale entries behind; drop them before they pile up
+        if self.order.len() > 2 * self.capacity {
+            let seen = &self.seen;
+            self.order.retain(|(key, generation)| seen.get(key) == Some(generation));
+        }
+
+        duplicate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(symbol: &str, sequence: u64) -> MarketTick {
+        MarketTick::new(symbol.to_string(), 100.0, 1, 1).with_sequence(sequence)
+    }
+
+    #[test]
+    fn test_keys_and_lru_eviction() {
+        let mut dedup = TickDeduplicator::new(2);
+
+        assert!(!dedup.is_duplicate(&tick("BTC/USD", 1), b""));
+        // Same sequence on another symbol is a different tick
+        assert!(!dedup.is_duplicate(&tick("ETH/USD", 1), b""));
+        // Touch BTC so ETH becomes least recently seen
+        assert!(dedup.is_duplicate(&tick("BTC/USD", 1), b""));
+        assert!(!dedup.is_duplicate(&tick("BTC/USD", 2), b""));
+        assert_eq!(dedup.seen.len(), 2);
+
+        assert!(dedup.is_duplicate(&tick("BTC/USD", 1), b""));
+        assert!(!dedup.is_duplicate(&tick("ETH/USD", 1), b""), "ETH should have been evicted");
+
+        // Unsequenced ticks fall back to the payload
+        let unsequenced = MarketTick::new("SOL/USD".to_string(), 100.0, 1, 1);
+        assert!(!dedup.is_duplicate(&unsequenced, b"a"));
+        assert!(dedup.is_duplicate(&unsequenced, b"a"));
+        assert!(!dedup.is_duplicate(&unsequenced, b"b"));
+
+        // So is the same sequence from another venue
+        let mut dedup = TickDeduplicator::new(4);
+        assert!(!dedup.is_duplicate(&tick("BTC/USD", 1).with_venue("A"), b""));
+        assert!(!dedup.is_duplicate(&tick("BTC/USD", 1).with_venue("B"), b""));
+        assert!(!dedup.is_duplicate(&tick("BTC/USD", 1), b""));
+        assert!(dedup.is_duplicate(&tick("BTC/USD", 1).with_venue("A"), b""));
+    }
+
+    #[test]
+    fn test_memory_stays_bounded() {
+        let mut dedup = TickDeduplicator::new(16);
+        for i in 0..10_000 {
+            dedup.is_duplicate(&tick("BTC/USD", i % 8), b"");
+            dedup.is_duplicate(&tick("ETH/USD", i), b"");
+        }
+        assert_eq!(dedup.seen.len(), 16);
+        assert!(dedup.order.len() <= 32);
+    }
+}
@@ -0,0 +1,1274 @@
+mod dedup;
+#[cfg(feature = "nats")]
+mod nats;
+mod publisher;
+mod sink;
+
+use anyhow::Result;
+use clap::{value_parser, Arg, ArgAction, ArgGroup, Command};
+use crossbeam::channel::{bounded, Receiver, Sender, TrySendError};
+use dedup::TickDeduplicator;
+use hft_types::backpressure::{ChannelMonitor, DEFAULT_SUSTAIN, SAMPLE_INTERVAL};
+use hft_types::cli;
+use hft_types::config::SymbolUniverse;
+use hft_types::health::Readiness;
+use hft_types::messaging::{parse_socket_addr, PROBE_ACK, PROBE_REQUEST};
+use hft_types::multicast::MulticastGroup;
+use hft_types::precision::PricePrecision;
+use hft_types::transport::Transport;
+use hft_types::wire::{TickWire, WireFormat, WireSymbols};
+use hft_types::{EnrichedTick, RoundingMode};
+use publisher::TickPublisher;
+use sink::{FileSink, SinkHandle, TickSink};
+use lazy_static::lazy_static;
+use prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge, Registry};
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::UdpSocket;
+use tracing::{info, warn};
+
+/// Ticks the strategy channel holds before the overflow policy applies
+const STRATEGY_CHANNEL_CAPACITY: usize = 100_000;
+
+/// Largest datagram received whole unless `--recv-buffer` says otherwise
+const DEFAULT_RECV_BUFFER: usize = 4096;
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+    pub static ref TICKS_RECEIVED: IntCounter = IntCounter::new(
+        "feed_ticks_received_total",
+        "Total number of market ticks received"
+    )
+    .unwrap();
+    pub static ref LATENCY_HISTOGRAM: Histogram = Histogram::with_opts(
+        HistogramOpts::new("feed_latency_micros", "Tick processing latency in microseconds")
+            .buckets(hft_types::latency::latency_buckets())
+    )
+    .unwrap();
+    pub static ref BATCH_SIZE: Histogram = Histogram::with_opts(
+        HistogramOpts::new("feed_batch_size", "Datagrams drained per socket wakeup")
+            .buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0])
+    )
+    .unwrap();
+    pub static ref TICKS_DROPPED: IntCounter = IntCounter::new(
+        "feed_ticks_dropped_total",
+        "Total number of ticks dropped because the strategy channel was full"
+    )
+    .unwrap();
+    pub static ref INVALID_TICKS: IntCounter = IntCounter::new(
+        "feed_invalid_ticks_total",
+        "Total number of ticks rejected for a bad price or timestamp"
+    )
+    .unwrap();
+    pub static ref UNKNOWN_SYMBOLS: IntCounter = IntCounter::new(
+        "feed_unknown_symbols_total",
+        "Total number of ticks dropped for a symbol outside the configured universe"
+    )
+    .unwrap();
+    pub static ref UNKNOWN_FORMAT: IntCounter = IntCounter::new(
+        "feed_unknown_format_total",
+        "Total number of datagrams dropped for an unrecognised header"
+    )
+    .unwrap();
+    pub static ref DUPLICATES: IntCounter = IntCounter::new(
+        "feed_duplicates_total",
+        "Total number of duplicate ticks skipped by deduplication"
+    )
+    .unwrap();
+    /// Same as `strategy_channel_len`, under the name existing dashboards use
+    pub static ref STRATEGY_CHANNEL_DEPTH: IntGauge = IntGauge::new(
+        "strategy_channel_depth",
+        "Ticks queued for the strategy consumer"
+    )
+    .unwrap();
+    pub static ref SINK_DROPPED: IntCounter = IntCounter::new(
+        "feed_sink_dropped_total",
+        "Total number of ticks dropped because a tick sink's queue was full"
+    )
+    .unwrap();
+    pub static ref SINK_ERRORS: IntCounter = IntCounter::new(
+        "feed_sink_errors_total",
+        "Total number of ticks a tick sink failed to publish"
+    )
+    .unwrap();
+    pub static ref TRUNCATED: IntCounter = IntCounter::new(
+        "feed_truncated_datagrams_total",
+        "Total number of datagrams dropped for not fitting the receive buffer"
+    )
+    .unwrap();
+    pub static ref CLOCK_SKEW: IntCounter = IntCounter::new(
+        "feed_clock_skew_total",
+        "Total number of ticks stamped ahead of the receive clock"
+    )
+    .unwrap();
+}
+
+pub fn init_metrics() {
+    REGISTRY
+        .register(Box::new(TICKS_RECEIVED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(LATENCY_HISTOGRAM.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(CLOCK_SKEW.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(BATCH_SIZE.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(TICKS_DROPPED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(DUPLICATES.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(STRATEGY_CHANNEL_DEPTH.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(INVALID_TICKS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(UNKNOWN_SYMBOLS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(UNKNOWN_FORMAT.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(TRUNCATED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(SINK_DROPPED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(SINK_ERRORS.clone()))
+        .unwrap();
+}
+
+/// What to do with a tick when the strategy channel is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop the new tick
+    Drop,
+    /// Block the receive loop until the consumer catches up
+    Block,
+    /// Evict the oldest queued tick to make room
+    DropOldest,
+}
+
+/// Sending half of the strategy channel with an overflow policy
+pub struct TickForwarder {
+    tx: Sender<EnrichedTick>,
+    // Held to evict the oldest tick under `Overflow::DropOldest`
+    rx: Receiver<EnrichedTick>,
+    policy: Overflow,
+}
+
+impl TickForwarder {
+    pub fn new(tx: Sender<EnrichedTick>, rx: Receiver<EnrichedTick>, policy: Overflow) -> Self {
+        Self { tx, rx, policy }
+    }
+
+    /// Forward a tick, returning false if it (or an evicted tick) was dropped
+    fn forward(&self, enriched: EnrichedTick) -> bool {
+        let enriched = match self.tx.try_send(enriched) {
+            Ok(()) => return true,
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Strategy channel disconnected");
+                return false;
+            }
+            Err(TrySendError::Full(enriched)) => enriched,
+        };
+
+        match self.policy {
+            Overflow::Drop => {
+                TICKS_DROPPED.inc();
+                false
+            }
+            Overflow::Block => self.tx.send(enriched).is_ok(),
+            Overflow::DropOldest => {
+                if self.rx.try_recv().is_ok() {
+                    TICKS_DROPPED.inc();
+                }
+                if self.tx.try_send(enriched).is_err() {
+                    TICKS_DROPPED.inc();
+                }
+                false
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(Overflow::Drop),
+            "block" => Ok(Overflow::Block),
+            "drop_oldest" => Ok(Overflow::DropOldest),
+            other => Err(format!("unknown overflow policy: {}", other)),
+        }
+    }
+}
+
+/// Decode a datagram into an enriched tick, updating feed metrics
+fn process_datagram(
+    data: &[u8],
+    receive_time_nanos: u128,
+    wire: &TickWire,
+    universe: Option<&SymbolUniverse>,
+    normalize: bool,
+    dedup: Option<&mut TickDeduplicator>,
+) -> Option<EnrichedTick> {
+    if hft_types::wire::detect_format(data).is_none() {
+        UNKNOWN_FORMAT.inc();
+        tracing::debug!("Dropping datagram with unknown header {:02x?}", data.get(..2).unwrap_or(data));
+        return None;
+    }
+    match wire.decode(data) {
+        Ok(mut tick) => {
+            if let Err(e) = tick.validate() {
+                INVALID_TICKS.inc();
+                tracing::debug!("Rejecting {} tick: {}", tick.symbol, e);
+                return None;
+            }
+            if let Some(universe) = universe {
+                let Some(config) = universe.get(&tick.symbol) else {
+                    UNKNOWN_SYMBOLS.inc();
+                    tracing::debug!("Dropping tick for unconfigured symbol {}", tick.symbol);
+                    return None;
+                };
+                if normalize {
+                    tick.price = config.round_price(tick.price, RoundingMode::Nearest);
+                }
+            }
+            if dedup.is_some_and(|dedup| dedup.is_duplicate(&tick, data)) {
+                DUPLICATES.inc();
+                return None;
+            }
+
+            // A sender clock running ahead would otherwise underflow into a huge latency
+            let latency_micros = hft_types::latency_micros(tick.timestamp_nanos, receive_time_nanos)
+                .unwrap_or_else(|| {
+                    CLOCK_SKEW.inc();
+                    0.0
+                });
+
+            // Update metrics
+            TICKS_RECEIVED.inc();
+            LATENCY_HISTOGRAM.observe(latency_micros);
+
+            Some(EnrichedTick {
+                tick,
+                receive_time_nanos,
+                decoded_nanos: Some(hft_types::latency::monotonic_nanos()),
+                latency_micros,
+            })
+        }
+        Err(e) => {
+            warn!("Failed to parse tick: {}", e);
+            None
+        }
+    }
+}
+
+/// Reusable buffers for draining several datagrams per socket wakeup
+#[derive(Default)]
+struct BatchReceiver {
+    bufs: Vec<Vec<u8>>,
+    lens: Vec<usize>,
+    sources: Vec<Option<SocketAddr>>,
+    /// The datagram was longer than `limit` and has been cut short
+    truncated: Vec<bool>,
+    /// Longest datagram received whole. Buffers hold one byte more, so a
+    /// datagram that overflows the limit can be told from one that fills it.
+    limit: usize,
+}
+
+impl BatchReceiver {
+    fn new(batch_size: usize, limit: usize) -> Self {
+        Self {
+            bufs: vec![vec![0u8; limit + 1]; batch_size.max(1)],
+            lens: vec![0; batch_size.max(1)],
+            sources: vec![None; batch_size.max(1)],
+            truncated: vec![false; batch_size.max(1)],
+            limit,
+        }
+    }
+
+    /// Datagrams filled by the last `drain` call, with their senders and
+    /// whether each was truncated
+    fn datagrams(&self, count: usize) -> impl Iterator<Item = (&[u8], Option<SocketAddr>, bool)> {
+        self.bufs
+            .iter()
+            .zip(&self.lens)
+            .zip(&self.sources)
+            .zip(&self.truncated)
+            .take(count)
+            .map(|(((buf, &len), &source), &truncated)| (&buf[..len], source, truncated))
+    }
+
+    /// Drain up to `batch_size` queued datagrams without blocking. The OS
+    /// doesn't say when it cut a datagram short here; one that spills into
+    /// the buffer's spare byte was longer than `limit`.
+    #[cfg(not(all(target_os = "linux", feature = "batch-recv")))]
+    fn drain(&mut self, socket: &UdpSocket) -> std::io::Result<usize> {
+        let mut count = 0;
+        while count < self.bufs.len() {
+            match socket.try_recv_from(&mut self.bufs[count]) {
+                Ok((n, addr)) => {
+                    self.lens[count] = n.min(self.limit);
+                    self.sources[count] = Some(addr);
+                    self.truncated[count] = n > self.limit;
+                    count += 1;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Drain up to `batch_size` queued datagrams with a single recvmmsg(2)
+    #[cfg(all(target_os = "linux", feature = "batch-recv"))]
+    fn drain(&mut self, socket: &UdpSocket) -> std::io::Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        let mut iovecs: Vec<libc::iovec> = self
+            .bufs
+            .iter_mut()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        // SAFETY: sockaddr_storage is plain data; zeroed is a valid empty address
+        let mut names: Vec<libc::sockaddr_storage> =
+            vec![unsafe { std::mem::zeroed() }; self.bufs.len()];
+        let mut msgs: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .zip(names.iter_mut())
+            .map(|(iov, name)| {
+                // SAFETY: mmsghdr is plain data; zeroed is a valid empty header
+                let mut msg: libc::mmsghdr = unsafe { std::mem::zeroed() };
+                msg.msg_hdr.msg_iov = iov;
+                msg.msg_hdr.msg_iovlen = 1;
+                msg.msg_hdr.msg_name = name as *mut libc::sockaddr_storage as *mut libc::c_void;
+                msg.msg_hdr.msg_namelen =
+                    std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                msg
+            })
+            .collect();
+
+        let received = socket.try_io(tokio::io::Interest::READABLE, || {
+            // SAFETY: every header points at a live iovec backed by a buffer in self.bufs
+            let n = unsafe {
+                libc::recvmmsg(
+                    socket.as_raw_fd(),
+                    msgs.as_mut_ptr(),
+                    msgs.len() as libc::c_uint,
+                    libc::MSG_DONTWAIT,
+                    std::ptr::null_mut(),
+                )
+            };
+            if n < 0 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(n as usize)
+            }
+        });
+
+        match received {
+            Ok(count) => {
+                for (i, msg) in msgs.iter().enumerate().take(count) {
+                    let len = msg.msg_len as usize;
+                    self.lens[i] = len.min(self.limit);
+                    self.sources[i] = sockaddr_to_std(&names[i]);
+                    self.truncated[i] = len > self.limit || msg.msg_hdr.msg_flags & libc::MSG_TRUNC != 0;
+                }
+                Ok(count)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Convert a kernel-filled source address into a `SocketAddr`
+#[cfg(all(target_os = "linux", feature = "batch-recv"))]
+fn sockaddr_to_std(storage: &libc::sockaddr_storage) -> Option<SocketAddr> {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            // SAFETY: the family says the storage holds a sockaddr_in
+            let addr = unsafe { &*(storage as *const _ as *const libc::sockaddr_in) };
+            Some(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)),
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            // SAFETY: the family says the storage holds a sockaddr_in6
+            let addr = unsafe { &*(storage as *const _ as *const libc::sockaddr_in6) };
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(addr.sin6_addr.s6_addr),
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes, filters and forwards the ticks arriving on its UDP socket, or
+/// on a transport given to `run_transport`
+pub struct FeedHandler {
+    socket: UdpSocket,
+    forwarder: TickForwarder,
+    batch: BatchReceiver,
+    dedup: Option<TickDeduplicator>,
+    wire: TickWire,
+    universe: Option<SymbolUniverse>,
+    normalize: bool,
+    sinks: Vec<SinkHandle>,
+}
+
+impl FeedHandler {
+    pub async fn new(listen_addr: SocketAddr, forwarder: TickForwarder, batch_size: usize) -> Result<Self> {
+        let socket = UdpSocket::bind(listen_addr).await?;
+        info!("Feed handler listening on {} (batch size {})", listen_addr, batch_size);
+        Ok(Self::from_socket(socket, forwarder, batch_size))
+    }
+
+    /// Receive from a multicast group, sharing its port with any other
+    /// feed handlers on this host
+    fn with_multicast(group: MulticastGroup, forwarder: TickForwarder, batch_size: usize) -> Result<Self> {
+        let socket = UdpSocket::from_std(group.join()?)?;
+        info!(
+            "Feed handler joined multicast {} on interface {} (batch size {})",
+            group.addr(),
+            group.interface,
+            batch_size
+        );
+        Ok(Self::from_socket(socket, forwarder, batch_size))
+    }
+
+    fn from_socket(socket: UdpSocket, forwarder: TickForwarder, batch_size: usize) -> Self {
+        Self {
+            socket,
+            forwarder,
+            batch: BatchReceiver::new(batch_size, DEFAULT_RECV_BUFFER),
+            dedup: None,
+            wire: TickWire::Json,
+            universe: None,
+            normalize: false,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Receive datagrams of up to `bytes` whole; longer ones are dropped
+    fn with_recv_buffer(mut self, bytes: usize) -> Self {
+        self.batch = BatchReceiver::new(self.batch.bufs.len(), bytes);
+        self
+    }
+
+    /// Decode datagrams with `wire` instead of JSON
+    fn with_wire(mut self, wire: TickWire) -> Self {
+        info!("Decoding {} ticks", wire.format());
+        self.wire = wire;
+        self
+    }
+
+    /// Drop ticks for symbols outside `universe`
+    fn with_universe(mut self, universe: SymbolUniverse) -> Self {
+        info!(
+            "Accepting ticks for {}",
+            universe.names().collect::<Vec<_>>().join(", ")
+        );
+        self.universe = Some(universe);
+        self
+    }
+
+    /// Snap tick prices to their symbol's tick size from the universe
+    fn with_normalized_prices(mut self) -> Self {
+        info!("Normalizing tick prices to each symbol's tick size");
+        self.normalize = true;
+        self
+    }
+
+    /// Skip ticks already seen among the last `capacity` distinct ticks
+    fn with_dedup(mut self, capacity: usize) -> Self {
+        self.dedup = Some(TickDeduplicator::new(capacity));
+        self
+    }
+
+    /// Also broadcast every accepted tick to `sink`, on its own thread
+    /// behind a queue of `capacity` ticks
+    fn with_sink(mut self, sink: Box<dyn TickSink>, capacity: usize) -> Self {
+        self.sinks.push(SinkHandle::spawn(sink, capacity));
+        self
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        loop {
+            let count = self.recv_batch().await?;
+            // One receive stamp per wakeup; the batch arrived together
+            let receive_time_nanos = hft_types::latency::monotonic_nanos();
+
+            // Lent out so the datagrams can be handled while borrowed from it
+            let batch = std::mem::take(&mut self.batch);
+            for (data, source, truncated) in batch.datagrams(count) {
+                // The rest of the datagram is gone; parsing what's left
+                // would only fail confusingly
+                if truncated {
+                    TRUNCATED.inc();
+                    warn!(
+                        "Dropping datagram cut short at {} bytes; raise --recv-buffer to receive larger ones",
+                        data.len()
+                    );
+                    continue;
+                }
+                // Publishers probe on startup to confirm someone is listening
+                if data == PROBE_REQUEST {
+                    if let Some(source) = source {
+                        if let Err(e) = self.socket.try_send_to(PROBE_ACK, source) {
+                            warn!("Failed to acknowledge probe from {}: {}", source, e);
+                        }
+                    }
+                    continue;
+                }
+                self.on_datagram(data, receive_time_nanos);
+            }
+            self.batch = batch;
+        }
+    }
+
+    /// Handle each frame `input` delivers as if it had arrived on the
+    /// socket, until the sender hangs up
+    pub fn run_transport(&mut self, mut input: impl Transport) -> Result<()> {
+        while let Some(frame) = input.recv()? {
+            self.on_datagram(&frame, hft_types::latency::monotonic_nanos());
+        }
+        Ok(())
+    }
+
+    /// Decode a tick, then record it to the sinks and forward it to the
+    /// strategy if it is accepted
+    fn on_datagram(&mut self, data: &[u8], receive_time_nanos: u128) {
+        let enriched = process_datagram(
+            data,
+            receive_time_nanos,
+            &self.wire,
+            self.universe.as_ref(),
+            self.normalize,
+            self.dedup.as_mut(),
+        );
+        if let Some(enriched) = enriched {
+            for sink in &self.sinks {
+                sink.publish(&enriched);
+            }
+            // Forward to strategy engine per the overflow policy
+            self.forwarder.forward(enriched);
+        }
+    }
+
+    /// Wait for the socket to become readable, then drain a batch
+    async fn recv_batch(&mut self) -> std::io::Result<usize> {
+        loop {
+            self.socket.readable().await?;
+            let count = self.batch.drain(&self.socket)?;
+            if count > 0 {
+                BATCH_SIZE.observe(count as f64);
+                return Ok(count);
+            }
+        }
+    }
+}
+
+fn cli() -> Command {
+    let command = Command::new("feed_handler")
+        .about("Receives market ticks over UDP and forwards them to the strategy")
+        .arg(
+            Arg::new("listen")
+                .long("listen")
+                .value_name("ADDR")
+                .help("UDP address to receive ticks on; only the port is used with --multicast-group")
+                .value_parser(parse_socket_addr)
+                .default_value("127.0.0.1:9001"),
+        )
+        .arg(
+            Arg::new("publish")
+                .long("publish")
+                .value_name("ADDR")
+                .help("TCP address strategy engines connect to with --feed-addr for ticks")
+                .value_parser(parse_socket_addr)
+                .default_value("127.0.0.1:9002"),
+        )
+        .arg(
+            Arg::new("multicast-group")
+                .long("multicast-group")
+                .value_name("IP")
+                .help("Join this IPv4 multicast group instead of listening on a unicast address")
+                .value_parser(|s: &str| match s.parse::<Ipv4Addr>() {
+                    Ok(ip) if ip.is_multicast() => Ok(ip),
+                    Ok(ip) => Err(format!("{} is not a multicast address (224.0.0.0/4)", ip)),
+                    Err(e) => Err(e.to_string()),
+                }),
+        )
+        .arg(
+            Arg::new("multicast-interface")
+                .long("multicast-interface")
+                .value_name("IP")
+                .help("Local interface address to join the group on")
+                .value_parser(value_parser!(Ipv4Addr))
+                .default_value("0.0.0.0")
+                .requires("multicast-group"),
+        )
+        .arg(
+            Arg::new("batch-size")
+                .long("batch-size")
+                .value_name("N")
+                .help("Datagrams drained per socket wakeup")
+                .value_parser(value_parser!(u64).range(1..=1024))
+                .default_value("64"),
+        )
+        .arg(
+            Arg::new("recv-buffer")
+                .long("recv-buffer")
+                .value_name("BYTES")
+                .help("Largest datagram received whole; longer ones are counted and dropped")
+                .value_parser(value_parser!(u64).range(64..=65_536))
+                .default_value("4096"),
+        )
+        .arg(
+            Arg::new("dedup-capacity")
+                .long("dedup-capacity")
+                .value_name("N")
+                .help("Skip duplicate ticks, remembering the last N distinct ticks (off by default)")
+                .value_parser(value_parser!(u64).range(1..=10_000_000)),
+        )
+        .arg(
+            Arg::new("wire")
+                .long("wire")
+                .value_name("FORMAT")
+                .help("Tick encoding sent by the publisher: json or binary")
+                .value_parser(|s: &str| s.parse::<WireFormat>())
+                .default_value("json")
+                .requires_if("binary", "wire-symbols"),
+        )
+        .arg(
+            Arg::new("symbols")
+                .long("symbols")
+                .value_name("SYMBOL,...")
+                .help("Binary symbol ids, in the publisher's --symbols order")
+                .value_delimiter(','),
+        )
+        .arg(cli::universe("Drop ticks for symbols outside the [[symbols]] of a shared config file"))
+        .arg(
+            Arg::new("normalize-prices")
+                .long("normalize-prices")
+                .help("Snap tick prices to the nearest tick_size from --universe")
+                .action(ArgAction::SetTrue)
+                .requires("universe"),
+        )
+        .arg(
+            Arg::new("price-decimals")
+                .long("price-decimals")
+                .value_name("N")
+                .help("Decimals recorded prices are written with, for symbols without a tick_size")
+                .value_parser(value_parser!(u32).range(0..=12)),
+        )
+        // Binary ids come from --symbols, or else the universe's order
+        .group(ArgGroup::new("wire-symbols").args(["symbols", "universe"]).multiple(true))
+        .arg(
+            Arg::new("overflow")
+                .long("overflow")
+                .value_name("POLICY")
+                .help("When the strategy channel is full: drop, block or drop_oldest")
+                .env("HFT_FEED_OVERFLOW")
+                .value_parser(|s: &str| s.parse::<Overflow>())
+                .default_value("drop"),
+        )
+        .arg(
+            Arg::new("recv-core")
+                .long("recv-core")
+                .value_name("CORE")
+                .help("Pin the UDP receive loop to this CPU core")
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("consumer-core")
+                .long("consumer-core")
+                .value_name("CORE")
+                .help("Pin the strategy consumer thread to this CPU core")
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("channel-watermark")
+                .long("channel-watermark")
+                .value_name("PERCENT")
+                .help("Warn when the strategy channel stays more than PERCENT full for 5s")
+                .value_parser(value_parser!(u8).range(1..=100)),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_name("PATH")
+                .help("Also record accepted ticks to a replayable JSONL file"),
+        )
+        .arg(
+            Arg::new("sink-capacity")
+                .long("sink-capacity")
+                .value_name("N")
+                .help("Ticks queued per sink before new ones are dropped")
+                .value_parser(value_parser!(u64).range(1..=10_000_000))
+                .default_value("10000"),
+        )
+        .arg(cli::health_listen("0.0.0.0:9202"))
+        .arg(cli::log_format());
+    #[cfg(feature = "nats")]
+    let command = command
+        .arg(
+            Arg::new("nats")
+                .long("nats")
+                .value_name("ADDR")
+                .help("Publish accepted ticks as JSON to the NATS server at ADDR")
+                .value_parser(parse_socket_addr),
+        )
+        .arg(
+            Arg::new("nats-subject")
+                .long("nats-subject")
+                .value_name("TEMPLATE")
+                .help("Subject per tick; {symbol} becomes the tick's symbol")
+                .default_value("ticks.{symbol}")
+                .requires("nats"),
+        );
+    command
+}
+
+/// Entry point of the `feed_handler` binary
+pub async fn run() -> Result<()> {
+    let args = cli::parse_args(cli());
+    hft_types::logging::init("feed_handler", *args.get_one("log-format").unwrap());
+
+    init_metrics();
+    let pusher = hft_types::pushgateway::MetricsPusher::from_env("feed_handler", &REGISTRY)?;
+    hft_types::heartbeat::spawn_emitter(
+        "feed_handler",
+        hft_types::heartbeat::DEFAULT_HEARTBEAT_ADDR,
+        std::time::Duration::from_secs(1),
+    )?;
+
+    let listen_addr: SocketAddr = *args.get_one("listen").unwrap();
+    let readiness = Readiness::new();
+    readiness.require("socket");
+    hft_types::health::spawn_server(*args.get_one("health-listen").unwrap(), readiness.clone(), &REGISTRY)?;
+
+    // Create bounded channel to strategy engine (lock-free, high throughput)
+    let (strategy_tx, strategy_rx) = bounded::<EnrichedTick>(STRATEGY_CHANNEL_CAPACITY);
+
+    let overflow: Overflow = *args.get_one("overflow").unwrap();
+    info!("Strategy channel overflow policy: {:?}", overflow);
+    let forwarder = TickForwarder::new(strategy_tx, strategy_rx.clone(), overflow);
+
+    // Spawn strategy consumer in separate thread
+    let publisher = TickPublisher::bind(*args.get_one("publish").unwrap())?;
+    info!("Publishing ticks to strategy engines on {}", publisher.local_addr());
+    hft_types::affinity::spawn_pinned(
+        "strategy-consumer",
+        args.get_one::<usize>("consumer-core").copied(),
+        move || strategy_consumer(strategy_rx, publisher),
+    );
+
+    let batch_size = *args.get_one::<u64>("batch-size").unwrap() as usize;
+    // Sample channel depth so backpressure is visible before drops start
+    let depth_tx = forwarder.tx.clone();
+    let mut monitor = ChannelMonitor::register("strategy", STRATEGY_CHANNEL_CAPACITY, &REGISTRY)?;
+    if let Some(&percent) = args.get_one::<u8>("channel-watermark") {
+        monitor = monitor.with_watermark(percent as f64 / 100.0, DEFAULT_SUSTAIN);
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            STRATEGY_CHANNEL_DEPTH.set(depth_tx.len() as i64);
+            monitor.sample(depth_tx.len(), std::time::Instant::now());
+        }
+    });
+
+    let mut handler = match args.get_one::<Ipv4Addr>("multicast-group") {
+        Some(&group) => {
+            let interface = *args.get_one("multicast-interface").unwrap();
+            let group = MulticastGroup::new(group, listen_addr.port(), interface)?;
+            FeedHandler::with_multicast(group, forwarder, batch_size)?
+        }
+        None => FeedHandler::new(listen_addr, forwarder, batch_size).await?,
+    };
+    readiness.set("socket", true);
+    let recv_buffer = *args.get_one::<u64>("recv-buffer").unwrap() as usize;
+    if recv_buffer != DEFAULT_RECV_BUFFER {
+        info!("Receiving datagrams of up to {} bytes", recv_buffer);
+        handler = handler.with_recv_buffer(recv_buffer);
+    }
+    if let Some(&capacity) = args.get_one::<u64>("dedup-capacity") {
+        info!("Deduplicating ticks over the last {} seen", capacity);
+        handler = handler.with_dedup(capacity as usize);
+    }
+    let universe = args.get_one::<SymbolUniverse>("universe");
+    if *args.get_one::<WireFormat>("wire").unwrap() == WireFormat::Binary {
+        let symbols = match (args.get_many::<String>("symbols"), universe) {
+            (Some(symbols), _) => WireSymbols::new(symbols.cloned())?,
+            (None, Some(universe)) => WireSymbols::new(universe.names())?,
+            (None, None) => unreachable!("clap requires --symbols or --universe with --wire binary"),
+        };
+        handler = handler.with_wire(TickWire::Binary(symbols));
+    }
+    if let Some(universe) = universe {
+        handler = handler.with_universe(universe.clone());
+    }
+    if args.get_flag("normalize-prices") {
+        handler = handler.with_normalized_prices();
+    }
+    let sink_capacity = *args.get_one::<u64>("sink-capacity").unwrap() as usize;
+    if let Some(path) = args.get_one::<String>("record") {
+        let mut precision = universe.map(PricePrecision::from_universe).unwrap_or_default();
+        if let Some(&decimals) = args.get_one::<u32>("price-decimals") {
+            precision = precision.with_default(decimals);
+        }
+        let sink = FileSink::create(path)?.with_precision(precision);
+        handler = handler.with_sink(Box::new(sink), sink_capacity);
+    }
+    #[cfg(feature = "nats")]
+    if let Some(&addr) = args.get_one::<SocketAddr>("nats") {
+        let subject = args.get_one::<String>("nats-subject").unwrap().clone();
+        handler = handler.with_sink(Box::new(nats::NatsSink::new(addr, subject)), sink_capacity);
+    }
+    // The receive loop runs on this thread, not a runtime worker
+    if let Some(&core) = args.get_one::<usize>("recv-core") {
+        hft_types::affinity::pin_current("udp-receive", core);
+    }
+    tokio::select! {
+        result = handler.run() => result?,
+        result = hft_types::shutdown::signal() => result?,
+    }
+
+    // Closes the strategy channel and stops the sinks
+    drop(handler);
+    if let Some(pusher) = pusher {
+        pusher.finish();
+    }
+    Ok(())
+}
+
+/// Send forwarded ticks on to the strategy engines until the receive loop
+/// hangs up
+pub fn strategy_consumer(rx: crossbeam::channel::Receiver<EnrichedTick>, mut out: impl Transport) {
+    info!("Strategy consumer started");
+
+    for enriched in rx.iter() {
+        if let Err(e) = out.send_message(&hft_types::messaging::Message::EnrichedTick(enriched)) {
+            warn!("Failed to publish a tick to the strategy: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::MarketTick;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        for args in [
+            &["--listen", "127.0.0.1:70000"][..],
+            &["--overflow", "sometimes"],
+            &["--no-such-flag"],
+            &["--multicast-group", "10.0.0.1"],
+            &["--multicast-interface", "127.0.0.1"],
+            &["--wire", "binary"],
+            &["--wire", "protobuf"],
+        ] {
+            let result = cli().try_get_matches_from(std::iter::once(&"feed_handler").chain(args));
+            assert!(result.is_err(), "{:?} should fail", args);
+        }
+    }
+
+    const LOCALHOST: SocketAddr =
+        SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+
+    #[test]
+    fn test_custom_latency_buckets() {
+        use prometheus::core::Collector;
+
+        let buckets = hft_types::latency::parse_latency_buckets("2,20,200").unwrap();
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("custom_latency_micros", "Custom latency").buckets(buckets),
+        )
+        .unwrap();
+        histogram.observe(15.0);
+
+        let families = histogram.collect();
+        let bounds: Vec<f64> = families[0].get_metric()[0]
+            .get_histogram()
+            .get_bucket()
+            .iter()
+            .map(|b| b.get_upper_bound())
+            .collect();
+        assert_eq!(bounds, vec![2.0, 20.0, 200.0]);
+    }
+
+    #[test]
+    fn test_future_timestamp_clamps_latency() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let tick = MarketTick::new("BTC/USD".to_string(), 45000.0, 10, now + 1_000_000_000);
+        let payload = serde_json::to_vec(&tick).unwrap();
+
+        let skew_before = CLOCK_SKEW.get();
+        let enriched = process_datagram(&payload, now, &TickWire::Json, None, false, None).unwrap();
+
+        assert_eq!(enriched.latency_micros, 0.0);
+        assert_eq!(CLOCK_SKEW.get(), skew_before + 1);
+    }
+
+    #[test]
+    fn test_invalid_ticks_are_counted_and_dropped() {
+        let invalid_before = INVALID_TICKS.get();
+        for tick in [
+            MarketTick::new("BTC/USD".to_string(), -45000.0, 10, 1),
+            MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 0),
+        ] {
+            let payload = serde_json::to_vec(&tick).unwrap();
+            assert!(process_datagram(&payload, 2, &TickWire::Json, None, false, None).is_none());
+        }
+        assert_eq!(INVALID_TICKS.get() - invalid_before, 2);
+
+        let valid = serde_json::to_vec(&MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 1)).unwrap();
+        assert!(process_datagram(&valid, 2, &TickWire::Json, None, false, None).is_some());
+    }
+
+    #[test]
+    fn test_datagram_format_is_detected_from_header() {
+        let symbols = WireSymbols::new(["BTC/USD"]).unwrap();
+        let wire = TickWire::Binary(symbols.clone());
+        let tick = MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 1);
+        let unknown_before = UNKNOWN_FORMAT.get();
+
+        // Legacy JSON senders still work against a binary feed handler
+        let legacy = serde_json::to_vec(&tick).unwrap();
+        assert!(process_datagram(&legacy, 2, &wire, None, false, None).is_some());
+
+        let headered = wire.encode(&tick).unwrap();
+        let enriched = process_datagram(&headered, 2, &wire, None, false, None).unwrap();
+        assert_eq!(enriched.tick.price, 45000.0);
+
+        // Headerless binary from an old sender is no longer recognised
+        let raw = tick.encode_binary(&symbols).unwrap();
+        assert!(process_datagram(&raw, 2, &wire, None, false, None).is_none());
+        assert_eq!(UNKNOWN_FORMAT.get() - unknown_before, 1);
+    }
+
+    #[test]
+    fn test_unknown_symbols_are_counted_and_dropped() {
+        let path = "/tmp/hft_test_feed_universe.toml";
+        std::fs::write(
+            path,
+            "[[symbols]]\nsymbol = \"BTC/USD\"\nbase_price = 45000.0\n\n\
+             [[symbols]]\nsymbol = \"ETH/USD\"\nbase_price = 2500.0\n",
+        )
+        .unwrap();
+        let matches = cli().get_matches_from(["feed_handler", "--universe", path]);
+        let universe = matches.get_one::<SymbolUniverse>("universe").unwrap();
+
+        let unknown_before = UNKNOWN_SYMBOLS.get();
+        for (symbol, accepted) in [("BTC/USD", true), ("DOGE/USD", false), ("ETH/USD", true)] {
+            let tick = MarketTick::new(symbol.to_string(), 100.0, 10, 1);
+            let payload = serde_json::to_vec(&tick).unwrap();
+            let enriched = process_datagram(&payload, 2, &TickWire::Json, Some(universe), false, None);
+            assert_eq!(enriched.is_some(), accepted, "{}", symbol);
+        }
+        assert_eq!(UNKNOWN_SYMBOLS.get() - unknown_before, 1);
+
+        // The universe also supplies binary symbol ids
+        assert!(cli()
+            .try_get_matches_from(["feed_handler", "--wire", "binary", "--universe", path])
+            .is_ok());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_prices_are_normalized_to_tick_size() {
+        let path = "/tmp/hft_test_feed_normalize.toml";
+        std::fs::write(
+            path,
+            "[[symbols]]\nsymbol = \"BTC/USD\"\nbase_price = 45000.0\ntick_size = 0.5\n\n\
+             [[symbols]]\nsymbol = \"ETH/USD\"\nbase_price = 2500.0\n",
+        )
+        .unwrap();
+        let matches = cli().get_matches_from(["feed_handler", "--universe", path, "--normalize-prices"]);
+        assert!(matches.get_flag("normalize-prices"));
+        let universe = matches.get_one::<SymbolUniverse>("universe").unwrap();
+
+        let price_after = |symbol: &str, price, normalize| {
+            let payload = serde_json::to_vec(&MarketTick::new(symbol.to_string(), price, 10, 1)).unwrap();
+            process_datagram(&payload, 2, &TickWire::Json, Some(universe), normalize, None)
+                .unwrap()
+                .tick
+                .price
+        };
+        assert_eq!(price_after("BTC/USD", 45001.37, true), 45001.5);
+        assert_eq!(price_after("BTC/USD", 45001.37, false), 45001.37);
+        // No tick size configured
+        assert_eq!(price_after("ETH/USD", 2500.123, true), 2500.123);
+
+        // Normalizing needs the universe's tick sizes
+        assert!(cli().try_get_matches_from(["feed_handler", "--normalize-prices"]).is_err());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_batch_receive_has_no_loss() {
+        let (tx, rx) = bounded::<EnrichedTick>(16);
+        let forwarder = TickForwarder::new(tx, rx, Overflow::Drop);
+        let mut handler = FeedHandler::new(LOCALHOST, forwarder, 32).await.unwrap();
+        let target = handler.socket.local_addr().unwrap();
+
+        let sender = std::thread::spawn(move || {
+            let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            for i in 0..1000u32 {
+                socket.send_to(&i.to_le_bytes(), target).unwrap();
+                if i % 100 == 99 {
+                    // Stay within the default socket receive buffer
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+            }
+        });
+
+        let mut seen = vec![false; 1000];
+        let mut received = 0;
+        while received < 1000 {
+            let count = tokio::time::timeout(std::time::Duration::from_secs(5), handler.recv_batch())
+                .await
+                .expect("timed out waiting for datagrams")
+                .unwrap();
+            for (data, _, _) in handler.batch.datagrams(count) {
+                seen[u32::from_le_bytes(data.try_into().unwrap()) as usize] = true;
+            }
+            received += count;
+        }
+        sender.join().unwrap();
+
+        assert_eq!(received, 1000);
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    fn enriched(price: f64) -> EnrichedTick {
+        EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), price, 1, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_overflow_drop_counts_rejections() {
+        let (tx, rx) = bounded::<EnrichedTick>(4);
+        let forwarder = TickForwarder::new(tx, rx, Overflow::Drop);
+        let dropped_before = TICKS_DROPPED.get();
+
+        let accepted = (0..10).filter(|&i| forwarder.forward(enriched(i as f64))).count();
+
+        assert_eq!(accepted, 4);
+        assert_eq!(forwarder.tx.len(), 4);
+        assert_eq!(TICKS_DROPPED.get() - dropped_before, 6);
+    }
+
+    #[test]
+    fn test_overflow_drop_oldest_keeps_newest() {
+        let (tx, rx) = bounded::<EnrichedTick>(2);
+        let forwarder = TickForwarder::new(tx, rx.clone(), Overflow::DropOldest);
+
+        for i in 0..5 {
+            forwarder.forward(enriched(i as f64));
+        }
+
+        let prices: Vec<f64> = rx.try_iter().map(|e| e.tick.price).collect();
+        assert_eq!(prices, vec![3.0, 4.0]);
+    }
+
+    #[tokio::test]
+    async fn test_probe_is_acknowledged() {
+        let (tx, rx) = bounded::<EnrichedTick>(16);
+        let forwarder = TickForwarder::new(tx, rx, Overflow::Drop);
+        let mut handler = FeedHandler::new(LOCALHOST, forwarder, 8).await.unwrap();
+        let target = handler.socket.local_addr().unwrap();
+        tokio::spawn(async move { handler.run().await });
+
+        let publisher = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        publisher.send_to(PROBE_REQUEST, target).await.unwrap();
+
+        let mut buf = [0u8; 64];
+        let (n, _) = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            publisher.recv_from(&mut buf),
+        )
+        .await
+        .expect("no probe ack")
+        .unwrap();
+        assert_eq!(&buf[..n], PROBE_ACK);
+    }
+
+    #[tokio::test]
+    async fn test_multicast_reaches_every_feed_handler() {
+        // Pick a free port for the group, then release it for the handlers
+        let port = std::net::UdpSocket::bind(LOCALHOST).unwrap().local_addr().unwrap().port();
+        let group = MulticastGroup::new(Ipv4Addr::new(239, 255, 77, 1), port, Ipv4Addr::LOCALHOST).unwrap();
+
+        let mut receivers = Vec::new();
+        for _ in 0..2 {
+            let (tx, rx) = bounded::<EnrichedTick>(16);
+            let forwarder = TickForwarder::new(tx, rx.clone(), Overflow::Drop);
+            let mut handler = match FeedHandler::with_multicast(group, forwarder, 8) {
+                Ok(handler) => handler,
+                Err(e) => {
+                    eprintln!("skipping: multicast unavailable here: {}", e);
+                    return;
+                }
+            };
+            tokio::spawn(async move { handler.run().await });
+            receivers.push(rx);
+        }
+
+        let publisher = std::net::UdpSocket::bind(LOCALHOST).unwrap();
+        hft_types::multicast::configure_sender(&publisher, Ipv4Addr::LOCALHOST, 1).unwrap();
+        let tick = MarketTick::new("SOL/USD".to_string(), 100.0, 1, 1);
+        if let Err(e) = publisher.send_to(&serde_json::to_vec(&tick).unwrap(), group.addr()) {
+            eprintln!("skipping: multicast send failed here: {}", e);
+            return;
+        }
+
+        for rx in receivers {
+            let enriched = tokio::task::spawn_blocking(move || {
+                rx.recv_timeout(std::time::Duration::from_secs(2))
+            })
+            .await
+            .unwrap()
+            .expect("feed handler missed the multicast tick");
+            assert_eq!(enriched.tick.symbol, "SOL/USD");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_tick_is_processed_once() {
+        let (tx, rx) = bounded::<EnrichedTick>(16);
+        let forwarder = TickForwarder::new(tx, rx.clone(), Overflow::Drop);
+        let mut handler = FeedHandler::new(LOCALHOST, forwarder, 8).await.unwrap().with_dedup(64);
+        let target = handler.socket.local_addr().unwrap();
+        let duplicates_before = DUPLICATES.get();
+        tokio::spawn(async move { handler.run().await });
+
+        let publisher = UdpSocket::bind(LOCALHOST).await.unwrap();
+        let tick = MarketTick::new("ETH/USD".to_string(), 2500.0, 5, 1).with_sequence(7);
+        let retransmit = MarketTick::new("ETH/USD".to_string(), 2500.0, 5, 2).with_sequence(7);
+        let next = MarketTick::new("ETH/USD".to_string(), 2501.0, 5, 3).with_sequence(8);
+        for tick in [tick, retransmit, next] {
+            publisher.send_to(&serde_json::to_vec(&tick).unwrap(), target).await.unwrap();
+        }
+
+        let received = tokio::task::spawn_blocking(move || {
+            let timeout = std::time::Duration::from_secs(2);
+            let first = rx.recv_timeout(timeout).unwrap();
+            let second = rx.recv_timeout(timeout).unwrap();
+            (first, second, rx.try_recv().is_err())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(received.0.tick.sequence, Some(7));
+        assert_eq!(received.1.tick.sequence, Some(8));
+        assert!(received.2, "the retransmit should have been skipped");
+        assert_eq!(DUPLICATES.get() - duplicates_before, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_datagram_is_counted_as_truncated() {
+        let (tx, rx) = bounded::<EnrichedTick>(16);
+        let forwarder = TickForwarder::new(tx, rx.clone(), Overflow::Drop);
+        let mut handler = FeedHandler::new(LOCALHOST, forwarder, 8)
+            .await
+            .unwrap()
+            .with_recv_buffer(512);
+        let target = handler.socket.local_addr().unwrap();
+        let truncated_before = TRUNCATED.get();
+        tokio::spawn(async move { handler.run().await });
+
+        let publisher = UdpSocket::bind(LOCALHOST).await.unwrap();
+        let jumbo = MarketTick::new("X".repeat(2000), 1.0, 1, 1);
+        let tick = MarketTick::new("ETH/USD".to_string(), 2500.0, 5, 2);
+        for tick in [jumbo, tick] {
+            publisher.send_to(&serde_json::to_vec(&tick).unwrap(), target).await.unwrap();
+        }
+
+        let received = tokio::task::spawn_blocking(move || {
+            rx.recv_timeout(std::time::Duration::from_secs(2)).unwrap()
+        })
+        .await
+        .unwrap();
+        assert_eq!(received.tick.symbol, "ETH/USD");
+        assert_eq!(TRUNCATED.get() - truncated_before, 1);
+    }
+
+    #[tokio::test]
+    async fn test_only_datagrams_over_the_limit_are_truncated() {
+        let socket = UdpSocket::bind(LOCALHOST).await.unwrap();
+        let target = socket.local_addr().unwrap();
+        let publisher = UdpSocket::bind(LOCALHOST).await.unwrap();
+        let mut batch = BatchReceiver::new(4, 64);
+        for len in [64, 65] {
+            publisher.send_to(&vec![b'x'; len], target).await.unwrap();
+        }
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            socket.readable().await.unwrap();
+            let count = batch.drain(&socket).unwrap();
+            received.extend(batch.datagrams(count).map(|(data, _, truncated)| (data.len(), truncated)));
+        }
+        assert_eq!(received, vec![(64, false), (64, true)]);
+    }
+
+    #[tokio::test]
+    async fn test_binary_wire_ticks_are_decoded() {
+        let symbols = WireSymbols::new(["BTC/USD", "ETH/USD"]).unwrap();
+        let (tx, rx) = bounded::<EnrichedTick>(16);
+        let forwarder = TickForwarder::new(tx, rx.clone(), Overflow::Drop);
+        let mut handler = FeedHandler::new(LOCALHOST, forwarder, 8)
+            .await
+            .unwrap()
+            .with_wire(TickWire::Binary(symbols.clone()));
+        let target = handler.socket.local_addr().unwrap();
+        tokio::spawn(async move { handler.run().await });
+
+        let publisher = UdpSocket::bind(LOCALHOST).await.unwrap();
+        let tick = MarketTick::new("ETH/USD".to_string(), 2500.5, 5, 1).with_sequence(3);
+        publisher
+            .send_to(&TickWire::Binary(symbols).encode(&tick).unwrap(), target)
+            .await
+            .unwrap();
+
+        let received = tokio::task::spawn_blocking(move || {
+            rx.recv_timeout(std::time::Duration::from_secs(2)).unwrap()
+        })
+        .await
+        .unwrap();
+        assert_eq!(received.tick.symbol, "ETH/USD");
+        assert_eq!(received.tick.price, 2500.5);
+        assert_eq!(received.tick.sequence, Some(3));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::sink::TickSink;
+use hft_types::EnrichedTick;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Wait between reconnect attempts; ticks published meanwhile fail fast
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Subject for `symbol` under `template`, e.g. `ticks.{symbol}` gives
+/// `ticks.BTC_USD` for `BTC/USD`. Characters NATS treats specially in a
+/// subject token become underscores.
+pub fn subject_for(template: &str, symbol: &str) -> String {
+    let token: String = symbol
+        .chars()
+        .map(|c| match c {
+            '.' | '*' | '>' | '/' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+    template.replace("{symbol}", &token)
+}
+
+/// Publishes ticks as JSON to a NATS server, one subject per symbol.
+/// Speaks just enough of the text protocol to publish: CONNECT, PUB and
+/// PONG replies to the server's keepalive PINGs.
+pub struct NatsSink {
+    addr: SocketAddr,
+    subject: String,
+    name: String,
+    conn: Option<NatsConnection>,
+    next_connect: Instant,
+}
+
+struct NatsConnection {
+    writer: Arc<Mutex<BufWriter<TcpStream>>>,
+    stream: TcpStream,
+}
+
+impl Drop for NatsConnection {
+    fn drop(&mut self) {
+        // Unblocks the reader thread
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl NatsSink {
+    /// Sink publishing to `addr` on subjects from `subject`, which should
+    /// contain `{symbol}`. Connects lazily on the first tick.
+    pub fn new(addr: SocketAddr, subject: String) -> Self {
+        Self {
+            name: format!("nats://{}", addr),
+            addr,
+            subject,
+            conn: None,
+            next_connect: Instant::now(),
+        }
+    }
+
+    fn connect(&self) -> std::io::Result<NatsConnection> {
+        let stream = TcpStream::connect_timeout(&self.addr, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        let writer = Arc::new(Mutex::new(BufWriter::new(stream.try_clone()?)));
+        {
+            let mut writer = writer.lock().unwrap();
+            writer.write_all(
+                b"CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"feed_handler\"}\r\n",
+            )?;
+            writer.flush()?;
+        }
+
+        let reader = BufReader::new(stream.try_clone()?);
+        let pong = Arc::clone(&writer);
+        let name = self.name.clone();
+        std::thread::Builder::new()
+            .name("nats-reader".to_string())
+            .spawn(move || {
+                for line in reader.lines() {
+                    let Ok(line) = line else { break };
+                    if line == "PING" {
+                        let mut writer = pong.lock().unwrap();
+                        if writer
+                            .write_all(b"PONG\r\n")
+                            .and_then(|_| writer.flush())
+                            .is_err()
+                        {
+                            break;
+                        }
+                    } else if let Some(err) = line.strip_prefix("-ERR") {
+                        warn!("{} error:{}", name, err);
+                    }
+                }
+            })?;
+
+        info!("Connected to {}", self.name);
+        Ok(NatsConnection { writer, stream })
+    }
+
+    fn connection(&mut self) -> std::io::Result<&NatsConnection> {
+        if self.conn.is_none() {
+            if Instant::now() < self.next_connect {
+                return Err(std::io::ErrorKind::NotConnected.into());
+            }
+            match self.connect() {
+                Ok(conn) => self.conn = Some(conn),
+                Err(e) => {
+                    self.next_connect = Instant::now() + RECONNECT_BACKOFF;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(self.conn.as_ref().unwrap())
+    }
+
+    /// Run `write` against the connection, dropping it on failure so the
+    /// next tick reconnects
+    fn with_writer(
+        &mut self,
+        write: impl FnOnce(&mut BufWriter<TcpStream>) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        let result = write(&mut self.connection()?.writer.lock().unwrap());
+        if let Err(e) = &result {
+            warn!("Lost connection to {}: {}", self.name, e);
+            self.conn = None;
+            self.next_connect = Instant::now() + RECONNECT_BACKOFF;
+        }
+        result
+    }
+}
+
+impl TickSink for NatsSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&mut self, tick: &EnrichedTick) -> std::io::Result<()> {
+        let payload = serde_json::to_vec(tick)?;
+        let subject = subject_for(&self.subject, &tick.tick.symbol);
+        self.with_writer(|writer| {
+            write!(writer, "PUB {} {}\r\n", subject, payload.len())?;
+            writer.write_all(&payload)?;
+            writer.write_all(b"\r\n")
+        })
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.conn.is_none() {
+            return Ok(());
+        }
+        self.with_writer(|writer| writer.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::SinkHandle;
+    use hft_types::MarketTick;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_subjects_are_per_symbol() {
+        assert_eq!(subject_for("ticks.{symbol}", "BTC/USD"), "ticks.BTC_USD");
+        assert_eq!(
+            subject_for("md.{symbol}.trades", "BRK.B"),
+            "md.BRK_B.trades"
+        );
+        assert_eq!(subject_for("ticks", "BTC/USD"), "ticks");
+    }
+
+    /// Accept one client the way a NATS server would and collect what it
+    /// publishes
+    fn mock_broker(listener: TcpListener, expected: usize) -> Vec<(String, EnrichedTick)> {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream
+            .write_all(b"INFO {\"server_id\":\"mock\"}\r\nPING\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut published = Vec::new();
+        let mut ponged = false;
+        while published.len() < expected || !ponged {
+            let mut line = String::new();
+            assert!(reader.read_line(&mut line).unwrap() > 0, "client hung up");
+            let line = line.trim_end();
+            if line == "PONG" {
+                ponged = true;
+            } else if let Some(args) = line.strip_prefix("PUB ") {
+                let (subject, len) = args.split_once(' ').unwrap();
+                let mut payload = vec![0; len.parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut payload).unwrap();
+                assert_eq!(&payload[payload.len() - 2..], b"\r\n");
+                let tick = serde_json::from_slice(&payload[..payload.len() - 2]).unwrap();
+                published.push((subject.to_string(), tick));
+            } else {
+                assert!(line.starts_with("CONNECT "), "unexpected {:?}", line);
+            }
+        }
+        published
+    }
+
+    #[test]
+    fn test_published_ticks_match_ingested() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let ingested: Vec<EnrichedTick> = (0..20)
+            .map(|i| EnrichedTick {
+                tick: MarketTick::new(
+                    ["BTC/USD", "ETH/USD"][i % 2].to_string(),
+                    100.0 + i as f64,
+                    1,
+                    i as u128,
+                )
+                .with_sequence(i as u64),
+                receive_time_nanos: i as u128 + 5,
+                decoded_nanos: Some(i as u128 + 6),
+                latency_micros: 0.005,
+            })
+            .collect();
+        let expected = ingested.len();
+        let broker = std::thread::spawn(move || mock_broker(listener, expected));
+
+        let sink = SinkHandle::spawn(
+            Box::new(NatsSink::new(addr, "ticks.{symbol}".to_string())),
+            64,
+        );
+        for tick in &ingested {
+            assert!(sink.publish(tick));
+        }
+        let published = broker.join().unwrap();
+        drop(sink);
+
+        for ((subject, got), sent) in published.iter().zip(&ingested) {
+            assert_eq!(*subject, subject_for("ticks.{symbol}", &sent.tick.symbol));
+            assert_eq!(got.tick.symbol, sent.tick.symbol);
+            assert_eq!(got.tick.price, sent.tick.price);
+            assert_eq!(got.tick.sequence, sent.tick.sequence);
+            assert_eq!(got.receive_time_nanos, sent.receive_time_nanos);
+            assert_eq!(got.decoded_nanos, sent.decoded_nanos);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use hft_types::messaging::Message;
+use hft_types::orderbook::OrderBookManager;
+use hft_types::transport::{TcpTransport, Transport};
+use std::collections::HashSet;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// A subscriber that can't take a frame within this long is dropped; it
+/// reconnects and starts again from a fresh snapshot
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct Subscriber {
+    transport: TcpTransport,
+    peer: Option<SocketAddr>,
+    /// Symbols whose book this subscriber has been sent
+    synced: HashSet<String>,
+}
+
+/// Serves accepted ticks to strategy engines over TCP, framed as
+/// `Message::EnrichedTick`. Before a subscriber's first tick of a symbol it
+/// is sent an `OrderBookUpdate` of that symbol's book, which is what a
+/// reconnecting `FeedClient` waits for before trading again.
+pub struct TickPublisher {
+    incoming: Receiver<TcpStream>,
+    subscribers: Vec<Subscriber>,
+    books: OrderBookManager,
+    local_addr: SocketAddr,
+}
+
+impl TickPublisher {
+    /// Listen on `addr`, accepting subscribers on a background thread
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (tx, incoming) = channel();
+        std::thread::Builder::new()
+            .name("feed-publisher".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if tx.send(stream).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Failed to accept a feed subscriber: {}", e),
+                    }
+                }
+            })?;
+        Ok(Self {
+            incoming,
+            subscribers: Vec::new(),
+            books: OrderBookManager::new(),
+            local_addr,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Start sending to anyone who connected since the last frame
+    fn accept_pending(&mut self) {
+        for stream in self.incoming.try_iter() {
+            let peer = stream.peer_addr().ok();
+            if let Err(e) = stream
+                .set_write_timeout(Some(WRITE_TIMEOUT))
+                .and(stream.set_nodelay(true))
+            {
+                warn!("Dropping feed subscriber {:?}: {}", peer, e);
+                continue;
+            }
+            info!("Feed subscriber {:?} connected", peer);
+            self.subscribers.push(Subscriber {
+                transport: TcpTransport::new(stream),
+                peer,
+                synced: HashSet::new(),
+            });
+        }
+    }
+
+    /// Send `frame` to every current subscriber, dropping any that fail
+    fn broadcast(&mut self, frame: &[u8]) {
+        self.subscribers
+            .retain_mut(|subscriber| match subscriber.transport.send(frame) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Dropping feed subscriber {:?}: {}", subscriber.peer, e);
+                    false
+                }
+            });
+    }
+}
+
+impl Transport for TickPublisher {
+    /// Send `frame` to every subscriber, dropping any that fail
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.accept_pending();
+        self.broadcast(frame);
+        Ok(())
+    }
+
+    fn send_message(&mut self, message: &Message) -> io::Result<()> {
+        let Message::EnrichedTick(enriched) = message else {
+            return self.send(&message.serialize()?);
+        };
+        self.accept_pending();
+        let symbol = &enriched.tick.symbol;
+        self.books.update_from_tick(&enriched.tick);
+        if let Some(book) = self.books.snapshot(symbol) {
+            let snapshot = Message::OrderBookUpdate(book).serialize()?;
+            self.subscribers.retain_mut(|subscriber| {
+                if subscriber.synced.contains(symbol) {
+                    return true;
+                }
+                match subscriber.transport.send(&snapshot) {
+                    Ok(()) => {
+                        subscriber.synced.insert(symbol.clone());
+                        true
+                    }
+                    Err(e) => {
+                        warn!("Dropping feed subscriber {:?}: {}", subscriber.peer, e);
+                        false
+                    }
+                }
+            });
+        }
+        // Without accepting again, so no one gets the tick before the snapshot
+        self.broadcast(&message.serialize()?);
+        Ok(())
+    }
+
+    /// Subscribers only listen, so there is never anything to receive
+    fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::messaging::read_frame;
+    use hft_types::{EnrichedTick, MarketTick};
+
+    fn tick(symbol: &str, price: f64) -> Message {
+        Message::EnrichedTick(EnrichedTick {
+            tick: MarketTick::new(symbol.to_string(), price, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 0.0,
+        })
+    }
+
+    fn next(stream: &mut TcpStream) -> Message {
+        read_frame(stream).unwrap().parse_message().unwrap()
+    }
+
+    /// Publish until the accept thread has handed `stream` over and it
+    /// receives a frame, returning that frame
+    fn connect(publisher: &mut TickPublisher, symbol: &str, price: f64) -> (TcpStream, Message) {
+        let mut stream = TcpStream::connect(publisher.local_addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        for _ in 0..100 {
+            publisher.send_message(&tick(symbol, price)).unwrap();
+            if let Ok(frame) = read_frame(&mut stream) {
+                stream.set_read_timeout(None).unwrap();
+                return (stream, frame.parse_message().unwrap());
+            }
+        }
+        panic!("subscriber never received anything");
+    }
+
+    #[test]
+    fn test_each_symbol_starts_with_a_snapshot() {
+        let mut publisher = TickPublisher::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+
+        let (mut first, message) = connect(&mut publisher, "BTC/USD", 45000.0);
+        assert!(matches!(message, Message::OrderBookUpdate(book) if book.symbol == "BTC/USD"));
+        assert!(matches!(next(&mut first), Message::EnrichedTick(_)));
+
+        // A second symbol gets its own snapshot; a known one doesn't
+        publisher.send_message(&tick("ETH/USD", 2500.0)).unwrap();
+        assert!(
+            matches!(next(&mut first), Message::OrderBookUpdate(book) if book.symbol == "ETH/USD")
+        );
+        assert!(matches!(next(&mut first), Message::EnrichedTick(_)));
+        publisher.send_message(&tick("BTC/USD", 45001.0)).unwrap();
+        assert!(matches!(next(&mut first), Message::EnrichedTick(e) if e.tick.price == 45001.0));
+
+        // A late subscriber is synced from the book so far
+        let (mut second, message) = connect(&mut publisher, "BTC/USD", 45002.0);
+        let Message::OrderBookUpdate(book) = message else {
+            panic!("expected a snapshot, got {:?}", message);
+        };
+        assert_eq!(book.symbol, "BTC/USD");
+        assert!(matches!(next(&mut second), Message::EnrichedTick(e) if e.tick.price == 45002.0));
+
+        // A subscriber that hangs up is dropped without affecting the rest
+        drop(first);
+        for _ in 0..10 {
+            publisher.send_message(&tick("BTC/USD", 45003.0)).unwrap();
+        }
+        assert_eq!(publisher.subscribers.len(), 1);
+        assert!(matches!(next(&mut second), Message::EnrichedTick(_)));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{SINK_DROPPED, SINK_ERRORS};
+use crossbeam::channel::{bounded, Sender, TrySendError};
+use hft_types::precision::PricePrecision;
+use hft_types::replay::MarketRecorder;
+use hft_types::EnrichedTick;
+use std::path::Path;
+use std::thread::JoinHandle;
+use tracing::{debug, info};
+
+/// Somewhere besides the strategy that enriched ticks are broadcast to.
+/// Implementations may block; `SinkHandle` keeps them off the hot path.
+pub trait TickSink: Send {
+    /// Short name for logs
+    fn name(&self) -> &str;
+
+    fn publish(&mut self, tick: &EnrichedTick) -> std::io::Result<()>;
+
+    /// Push out anything buffered; called whenever the queue drains
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Records ticks to a file `MarketReplayer` can play back
+pub struct FileSink {
+    name: String,
+    recorder: MarketRecorder,
+}
+
+impl FileSink {
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self {
+            name: format!("file:{}", path.as_ref().display()),
+            recorder: MarketRecorder::new(path)?,
+        })
+    }
+
+    /// Record prices with `precision`'s decimals
+    pub fn with_precision(mut self, precision: PricePrecision) -> Self {
+        self.recorder.set_precision(precision);
+        self
+    }
+}
+
+impl TickSink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&mut self, tick: &EnrichedTick) -> std::io::Result<()> {
+        self.recorder.record_tick(&tick.tick)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.recorder.flush()
+    }
+}
+
+/// Runs a sink on its own thread behind a bounded queue. `publish` never
+/// blocks the receive loop: a tick that doesn't fit is dropped and counted
+/// in `feed_sink_dropped_total`, and sink failures are counted in
+/// `feed_sink_errors_total`.
+pub struct SinkHandle {
+    tx: Option<Sender<EnrichedTick>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SinkHandle {
+    pub fn spawn(mut sink: Box<dyn TickSink>, capacity: usize) -> Self {
+        let (tx, rx) = bounded::<EnrichedTick>(capacity);
+        info!("Broadcasting ticks to {}", sink.name());
+        let handle = std::thread::Builder::new()
+            .name(format!("sink-{}", sink.name()))
+            .spawn(move || {
+                for tick in rx.iter() {
+                    if let Err(e) = sink.publish(&tick) {
+                        SINK_ERRORS.inc();
+                        debug!(
+                            "{} failed to publish {} tick: {}",
+                            sink.name(),
+                            tick.tick.symbol,
+                            e
+                        );
+                    }
+                    // Batch writes while ticks are queued
+                    if rx.is_empty() {
+                        if let Err(e) = sink.flush() {
+                            SINK_ERRORS.inc();
+                            debug!("{} failed to flush: {}", sink.name(), e);
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn tick sink");
+        Self {
+            tx: Some(tx),
+            handle: Some(handle),
+        }
+    }
+
+    /// Queue a tick for the sink, returning false if it was dropped
+    pub fn publish(&self, tick: &EnrichedTick) -> bool {
+        let Some(tx) = &self.tx else {
+            return false;
+        };
+        match tx.try_send(tick.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                SINK_DROPPED.inc();
+                false
+            }
+        }
+    }
+}
+
+impl Drop for SinkHandle {
+    /// Let the sink drain its queue and flush before returning
+    fn drop(&mut self) {
+        drop(self.tx.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::replay::MarketReplayer;
+    use hft_types::MarketTick;
+    use std::sync::{Arc, Barrier};
+
+    fn enriched(symbol: &str, price: f64, timestamp_nanos: u128) -> EnrichedTick {
+        EnrichedTick {
+            tick: MarketTick::new(symbol.to_string(), price, 1, timestamp_nanos),
+            receive_time_nanos: timestamp_nanos,
+            decoded_nanos: None,
+            latency_micros: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_file_sink_records_every_tick() {
+        let path = "/tmp/hft_test_file_sink.jsonl";
+        let sink = SinkHandle::spawn(Box::new(FileSink::create(path).unwrap()), 16);
+        for i in 0..10 {
+            assert!(sink.publish(&enriched("BTC/USD", 100.0 + i as f64, i)));
+        }
+        drop(sink);
+
+        let mut replayer = MarketReplayer::new(path).unwrap();
+        let mut prices = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            prices.push(tick.price);
+        }
+        assert_eq!(
+            prices,
+            (0..10).map(|i| 100.0 + i as f64).collect::<Vec<_>>()
+        );
+    }
+
+    /// Holds the sink thread on its first tick until the test lets it go
+    struct StalledSink(Arc<Barrier>);
+
+    impl TickSink for StalledSink {
+        fn name(&self) -> &str {
+            "stalled"
+        }
+
+        fn publish(&mut self, _tick: &EnrichedTick) -> std::io::Result<()> {
+            self.0.wait();
+            Err(std::io::Error::other("broker unavailable"))
+        }
+    }
+
+    #[test]
+    fn test_slow_sink_drops_instead_of_blocking() {
+        let release = Arc::new(Barrier::new(2));
+        let dropped_before = SINK_DROPPED.get();
+        let errors_before = SINK_ERRORS.get();
+        let sink = SinkHandle::spawn(Box::new(StalledSink(Arc::clone(&release))), 2);
+
+        let accepted = (0..10)
+            .filter(|&i| sink.publish(&enriched("ETH/USD", 2500.0, i)))
+            .count();
+        // One tick held by the stalled sink plus a full queue at most
+        assert!(accepted <= 3, "accepted {}", accepted);
+        assert!(SINK_DROPPED.get() - dropped_before >= (10 - accepted) as u64);
+
+        for _ in 0..accepted {
+            release.wait();
+        }
+        drop(sink);
+        assert!(SINK_ERRORS.get() - errors_before >= accepted as u64);
+    }
+}
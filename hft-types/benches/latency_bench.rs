@@ -1,7 +1,40 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use hft_types::replay::{MarketRecorder, MarketReplayer, MmapReplayer};
+use hft_types::symbol::SymbolTable;
+use hft_types::wire::WireSymbols;
 use hft_types::{MarketTick, OrderSide, Order};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// System allocator that counts allocations, so benches can report them
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Heap allocations per call of `f`, averaged over `iterations`
+fn allocations_per_call<F: FnMut()>(iterations: u64, mut f: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..iterations {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / iterations as f64
+}
+
 fn bench_tick_serialization(c: &mut Criterion) {
     let tick = MarketTick::new(
         "BTC/USD".to_string(),
@@ -33,6 +66,87 @@ fn bench_tick_deserialization(c: &mut Criterion) {
     });
 }
 
+fn bench_tick_binary_decode(c: &mut Criterion) {
+    let symbols = WireSymbols::new(["BTC/USD", "ETH/USD", "SOL/USD", "AVAX/USD"]).unwrap();
+    let tick = MarketTick::new(
+        "BTC/USD".to_string(),
+        45000.0,
+        100,
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+    )
+    .with_sequence(1);
+    let json = serde_json::to_vec(&tick).unwrap();
+    let binary = tick.encode_binary(&symbols).unwrap();
+
+    let mut group = c.benchmark_group("tick_decode");
+    group.bench_function("json", |b| {
+        b.iter(|| black_box(serde_json::from_slice::<MarketTick>(black_box(&json)).unwrap()))
+    });
+    group.bench_function("binary", |b| {
+        b.iter(|| black_box(MarketTick::decode_binary(black_box(&binary), &symbols).unwrap()))
+    });
+    group.finish();
+}
+
+fn bench_symbol_handle(c: &mut Criterion) {
+    let tick = MarketTick::new("BTC/USD".to_string(), 45000.0, 100, 1);
+    let mut symbols = SymbolTable::with_capacity(16);
+    let interned = tick.intern(&mut symbols).unwrap();
+
+    let cloned = allocations_per_call(10_000, || {
+        black_box(black_box(&tick).clone());
+    });
+    let copied = allocations_per_call(10_000, || {
+        black_box(*black_box(&interned));
+    });
+    println!("symbol_handle allocations per tick: string {:.2}, interned {:.2}", cloned, copied);
+
+    let mut group = c.benchmark_group("symbol_handle");
+    group.bench_function("string_clone", |b| b.iter(|| black_box(black_box(&tick).clone())));
+    group.bench_function("interned_copy", |b| b.iter(|| black_box(*black_box(&interned))));
+    group.bench_function("intern_lookup", |b| {
+        b.iter(|| black_box(symbols.get(black_box("BTC/USD"))))
+    });
+    group.finish();
+}
+
+fn bench_replay_scan(c: &mut Criterion) {
+    const TICKS: u64 = 500_000;
+    let path = "/tmp/hft_bench_replay.jsonl";
+    {
+        let mut recorder = MarketRecorder::new(path).unwrap();
+        for i in 0..TICKS {
+            let symbol = ["BTC/USD", "ETH/USD", "SOL/USD", "AVAX/USD"][i as usize % 4];
+            let tick = MarketTick::new(symbol.to_string(), 100.0 + (i % 1000) as f64 * 0.01, i % 100, i as u128)
+                .with_sequence(i);
+            recorder.record_tick(&tick).unwrap();
+        }
+    }
+
+    let mut group = c.benchmark_group("replay_scan");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(TICKS));
+    group.bench_function("buffered", |b| {
+        b.iter(|| {
+            let mut replayer = MarketReplayer::new(path).unwrap();
+            while let Some(tick) = replayer.next_tick().unwrap() {
+                black_box(tick);
+            }
+        })
+    });
+    group.bench_function("mmap", |b| {
+        let mut replayer = MmapReplayer::new(path).unwrap();
+        b.iter(|| {
+            replayer.rewind();
+            while let Some(tick) = replayer.next_tick().unwrap() {
+                black_box(tick);
+            }
+        })
+    });
+    group.finish();
+    let _ = std::fs::remove_file(path);
+}
+
 fn bench_order_creation(c: &mut Criterion) {
     c.bench_function("order_create", |b| {
         b.iter(|| {
@@ -62,6 +176,9 @@ criterion_group!(
     benches,
     bench_tick_serialization,
     bench_tick_deserialization,
+    bench_tick_binary_decode,
+    bench_symbol_handle,
+    bench_replay_scan,
     bench_order_creation,
     bench_latency_measurement
 );
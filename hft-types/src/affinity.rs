@@ -0,0 +1,70 @@
+use core_affinity::CoreId;
+use std::thread::JoinHandle;
+use tracing::{info, warn};
+
+/// Pin the calling thread to `core`, logging where `thread` landed. Where
+/// the platform can't pin threads or `core` doesn't exist the thread is
+/// left to the scheduler and this returns false.
+pub fn pin_current(thread: &str, core: usize) -> bool {
+    let available = core_affinity::get_core_ids().unwrap_or_default();
+    if !available.iter().any(|id| id.id == core) {
+        warn!(
+            "Not pinning {}: core {} is not among the {} available",
+            thread,
+            core,
+            available.len()
+        );
+        return false;
+    }
+    if core_affinity::set_for_current(CoreId { id: core }) {
+        info!("Pinned {} to core {}", thread, core);
+        true
+    } else {
+        warn!("Not pinning {}: this platform does not support it", thread);
+        false
+    }
+}
+
+/// Spawn a named thread, pinned to `core` before it runs `f` when one is given
+pub fn spawn_pinned<F, T>(name: &str, core: Option<usize>, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let thread = name.to_string();
+    std::thread::Builder::new()
+        .name(thread.clone())
+        .spawn(move || {
+            if let Some(core) = core {
+                pin_current(&thread, core);
+            }
+            f()
+        })
+        .unwrap_or_else(|e| panic!("failed to spawn {}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_spawned_worker_runs_on_requested_core() {
+        let core = core_affinity::get_core_ids().unwrap().last().unwrap().id;
+        let allowed = spawn_pinned("pinned-worker", Some(core), || {
+            let status = std::fs::read_to_string("/proc/thread-self/status").unwrap();
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+                .unwrap()
+                .trim()
+                .to_string()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(allowed, core.to_string());
+
+        // A core that doesn't exist leaves the thread unpinned
+        assert!(!pin_current("unpinned-worker", usize::MAX));
+    }
+}
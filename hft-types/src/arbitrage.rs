@@ -0,0 +1,216 @@
+use crate::{Order, OrderSide};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Top of book for a symbol on one venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueQuote {
+    pub venue: String,
+    pub symbol: String,
+    pub bid: f64,
+    pub bid_qty: f64,
+    pub ask: f64,
+    pub ask_qty: f64,
+}
+
+/// Cross-venue opportunity: the ask on one venue is below the bid on another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArbOpportunity {
+    pub symbol: String,
+    pub buy_venue: String,
+    pub buy_price: f64,
+    pub sell_venue: String,
+    pub sell_price: f64,
+    /// Smaller of the two displayed sizes
+    pub quantity: f64,
+}
+
+impl ArbOpportunity {
+    /// Find the best cross across venues quoting the same symbol
+    pub fn detect(quotes: &[VenueQuote]) -> Option<Self> {
+        let cheapest = quotes
+            .iter()
+            .filter(|q| q.ask_qty > 0.0)
+            .min_by(|a, b| a.ask.total_cmp(&b.ask))?;
+        let richest = quotes
+            .iter()
+            .filter(|q| q.bid_qty > 0.0 && q.symbol == cheapest.symbol)
+            .max_by(|a, b| a.bid.total_cmp(&b.bid))?;
+
+        if cheapest.venue == richest.venue || richest.bid <= cheapest.ask {
+            return None;
+        }
+
+        Some(Self {
+            symbol: cheapest.symbol.clone(),
+            buy_venue: cheapest.venue.clone(),
+            buy_price: cheapest.ask,
+            sell_venue: richest.venue.clone(),
+            sell_price: richest.bid,
+            quantity: cheapest.ask_qty.min(richest.bid_qty),
+        })
+    }
+}
+
+/// One leg of an arbitrage, routed to a specific venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbLeg {
+    pub venue: String,
+    pub correlation_id: u64,
+    pub order: Order,
+}
+
+/// Per-venue taker fees and position limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArbExecutorConfig {
+    pub default_fee_bps: f64,
+    pub fee_bps: HashMap<String, f64>,
+    /// Largest quantity that may be sent to a venue in one leg
+    pub max_qty: HashMap<String, f64>,
+}
+
+/// Turns cross-venue opportunities into paired buy/sell orders
+#[derive(Debug)]
+pub struct ArbExecutor {
+    config: ArbExecutorConfig,
+    next_correlation_id: u64,
+    next_order_id: u64,
+}
+
+impl ArbExecutor {
+    pub fn new(config: ArbExecutorConfig) -> Self {
+        Self {
+            config,
+            next_correlation_id: 1,
+            next_order_id: 1,
+        }
+    }
+
+    fn fee_bps(&self, venue: &str) -> f64 {
+        self.config
+            .fee_bps
+            .get(venue)
+            .copied()
+            .unwrap_or(self.config.default_fee_bps)
+    }
+
+    fn venue_limit(&self, venue: &str) -> f64 {
+        self.config.max_qty.get(venue).copied().unwrap_or(f64::INFINITY)
+    }
+
+    /// Net PnL of executing `quantity` of the opportunity after fees on both legs
+    pub fn net_edge(&self, opp: &ArbOpportunity, quantity: f64) -> f64 {
+        let gross = (opp.sell_price - opp.buy_price) * quantity;
+        let fees = opp.buy_price * quantity * self.fee_bps(&opp.buy_venue) / 10000.0
+            + opp.sell_price * quantity * self.fee_bps(&opp.sell_venue) / 10000.0;
+        gross - fees
+    }
+
+    /// Produce a buy on the cheap venue and a sell on the rich venue, or
+    /// nothing if venue limits leave no size or fees eat the edge
+    pub fn execute(&mut self, opp: &ArbOpportunity, timestamp_nanos: u128) -> Vec<ArbLeg> {
+        let quantity = opp
+            .quantity
+            .min(self.venue_limit(&opp.buy_venue))
+            .min(self.venue_limit(&opp.sell_venue));
+
+        if quantity <= 0.0 || self.net_edge(opp, quantity) <= 0.0 {
+            return Vec::new();
+        }
+
+        let correlation_id = self.next_correlation_id;
+        self.next_correlation_id += 1;
+
+        let legs = [
+            (&opp.buy_venue, OrderSide::Buy, opp.buy_price),
+            (&opp.sell_venue, OrderSide::Sell, opp.sell_price),
+        ];
+        legs.into_iter()
+            .map(|(venue, side, price)| {
+                let order_id = self.next_order_id;
+                self.next_order_id += 1;
+                ArbLeg {
+                    venue: venue.clone(),
+                    correlation_id,
+                    order: Order::new(
+                        order_id,
+                        opp.symbol.clone(),
+                        side,
+                        price,
+                        quantity,
+                        timestamp_nanos,
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(venue: &str, bid: f64, ask: f64, qty: f64) -> VenueQuote {
+        VenueQuote {
+            venue: venue.to_string(),
+            symbol: "BTC/USD".to_string(),
+            bid,
+            bid_qty: qty,
+            ask,
+            ask_qty: qty,
+        }
+    }
+
+    #[test]
+    fn test_profitable_cross_produces_paired_orders() {
+        let quotes = [
+            quote("alpha", 44990.0, 45000.0, 2.0),
+            quote("beta", 45050.0, 45060.0, 1.0),
+        ];
+        let opp = ArbOpportunity::detect(&quotes).unwrap();
+        assert_eq!(opp.buy_venue, "alpha");
+        assert_eq!(opp.sell_venue, "beta");
+        assert_eq!(opp.quantity, 1.0);
+
+        let mut executor = ArbExecutor::new(ArbExecutorConfig {
+            default_fee_bps: 2.0,
+            max_qty: HashMap::from([("beta".to_string(), 0.5)]),
+            ..ArbExecutorConfig::default()
+        });
+        let legs = executor.execute(&opp, 1);
+
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].venue, "alpha");
+        assert_eq!(legs[0].order.side, OrderSide::Buy);
+        assert_eq!(legs[1].venue, "beta");
+        assert_eq!(legs[1].order.side, OrderSide::Sell);
+        assert!(legs.iter().all(|l| l.order.quantity == 0.5));
+        assert_eq!(legs[0].correlation_id, legs[1].correlation_id);
+    }
+
+    #[test]
+    fn test_unprofitable_after_fees_does_nothing() {
+        // 10 of edge on 45k is ~2.2bps, less than 2 x 5bps of fees
+        let quotes = [
+            quote("alpha", 44990.0, 45000.0, 1.0),
+            quote("beta", 45010.0, 45020.0, 1.0),
+        ];
+        let opp = ArbOpportunity::detect(&quotes).unwrap();
+
+        let mut executor = ArbExecutor::new(ArbExecutorConfig {
+            default_fee_bps: 5.0,
+            ..ArbExecutorConfig::default()
+        });
+        assert!(executor.net_edge(&opp, 1.0) < 0.0);
+        assert!(executor.execute(&opp, 1).is_empty());
+    }
+
+    #[test]
+    fn test_no_cross_no_opportunity() {
+        let quotes = [
+            quote("alpha", 44990.0, 45000.0, 1.0),
+            quote("beta", 44995.0, 45005.0, 1.0),
+        ];
+        assert!(ArbOpportunity::detect(&quotes).is_none());
+    }
+}
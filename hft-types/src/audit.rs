@@ -0,0 +1,286 @@
+use crate::matching::Fill;
+use crate::{HftError, HftResult, Order, TradingSignal};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Events queued for the writer before `record` starts dropping them
+pub const DEFAULT_QUEUE_CAPACITY: usize = 65_536;
+
+/// Longest a recorded event sits in the write buffer
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Something a service decided or did, in enough detail to reconstruct why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    SignalGenerated(TradingSignal),
+    OrderPlaced(Box<Order>),
+    Fill(Fill),
+    Cancel {
+        order_id: u64,
+        cancelled_qty: f64,
+        reason: String,
+    },
+    Reject {
+        order_id: u64,
+        reason: String,
+    },
+}
+
+/// One line of an audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Wall clock when the event was recorded
+    pub timestamp_nanos: u128,
+    /// Service that recorded it, e.g. `order_gateway`
+    pub source: String,
+    pub event: AuditEvent,
+}
+
+/// What the writer thread is asked to do
+enum Entry {
+    Record(AuditRecord),
+    /// Flush everything queued before it, then answer
+    Flush(SyncSender<()>),
+}
+
+/// Append-only JSONL log of `AuditEvent`s. `record` only queues the event;
+/// a writer thread buffers the file and flushes every flush interval, on
+/// `flush` and when the log is dropped, so the hot path never waits on disk.
+pub struct AuditLog {
+    source: String,
+    tx: Option<SyncSender<Entry>>,
+    handle: Option<JoinHandle<()>>,
+    dropped: AtomicU64,
+}
+
+impl AuditLog {
+    /// Append to the log at `path`, creating it if need be
+    pub fn open<P: AsRef<Path>>(path: P, source: &str) -> std::io::Result<Self> {
+        Self::with_options(path, source, DEFAULT_QUEUE_CAPACITY, DEFAULT_FLUSH_INTERVAL)
+    }
+
+    pub fn with_options<P: AsRef<Path>>(
+        path: P,
+        source: &str,
+        capacity: usize,
+        flush_interval: Duration,
+    ) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (tx, rx) = sync_channel::<Entry>(capacity.max(1));
+        let name = path.display().to_string();
+        let handle = std::thread::Builder::new()
+            .name("audit-log".to_string())
+            .spawn(move || {
+                let mut writer = BufWriter::new(file);
+                let mut last_flush = Instant::now();
+                loop {
+                    let wait = flush_interval.saturating_sub(last_flush.elapsed());
+                    let mut flushed = None;
+                    match rx.recv_timeout(wait) {
+                        Ok(Entry::Record(record)) => {
+                            let written = serde_json::to_writer(&mut writer, &record)
+                                .map_err(std::io::Error::from)
+                                .and_then(|_| writer.write_all(b"\n"));
+                            if let Err(e) = written {
+                                warn!("Failed to write audit event to {}: {}", name, e);
+                            }
+                            if last_flush.elapsed() < flush_interval {
+                                continue;
+                            }
+                        }
+                        Ok(Entry::Flush(done)) => flushed = Some(done),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    if let Err(e) = writer.flush() {
+                        warn!("Failed to flush audit log {}: {}", name, e);
+                    }
+                    last_flush = Instant::now();
+                    if let Some(done) = flushed {
+                        let _ = done.send(());
+                    }
+                }
+                if let Err(e) = writer.flush() {
+                    warn!("Failed to flush audit log {}: {}", name, e);
+                }
+            })?;
+        Ok(Self {
+            source: source.to_string(),
+            tx: Some(tx),
+            handle: Some(handle),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queue `event`, stamped now. Returns false, without blocking, if the
+    /// writer has fallen a full queue behind and the event was dropped.
+    pub fn record(&self, event: AuditEvent) -> bool {
+        let Some(tx) = &self.tx else {
+            return false;
+        };
+        let record = AuditRecord {
+            timestamp_nanos: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+            source: self.source.clone(),
+            event,
+        };
+        match tx.try_send(Entry::Record(record)) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!("Audit log queue full; dropping events");
+                }
+                false
+            }
+        }
+    }
+
+    /// Write out everything recorded so far and wait until it is flushed,
+    /// e.g. before the process exits
+    pub fn flush(&self) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        let (done, flushed) = sync_channel(1);
+        if tx.send(Entry::Flush(done)).is_ok() {
+            let _ = flushed.recv();
+        }
+    }
+
+    /// Events `record` has had to drop
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for AuditLog {
+    /// Write out everything queued before returning
+    fn drop(&mut self) {
+        drop(self.tx.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Reads an audit log back in the order it was written, e.g. to replay
+/// fills into a `PositionTracker` and rebuild positions
+pub struct AuditReader {
+    lines: std::io::Lines<BufReader<File>>,
+}
+
+impl AuditReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self {
+            lines: BufReader::new(File::open(path)?).lines(),
+        })
+    }
+}
+
+impl Iterator for AuditReader {
+    type Item = HftResult<AuditRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(HftError::from(e))),
+        };
+        Some(serde_json::from_str(&line).map_err(HftError::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OrderSide, SignalType};
+
+    #[test]
+    fn test_events_read_back_in_order_intact() {
+        let path = "/tmp/hft_test_audit.jsonl";
+        std::fs::remove_file(path).ok();
+        let signal = TradingSignal {
+            symbol: "BTC/USD".to_string(),
+            side: OrderSide::Buy,
+            price: 45000.5,
+            quantity: 2.0,
+            signal_type: SignalType::Threshold,
+            timestamp_nanos: 1_700_000_000_000_000_001,
+            strength: 0.75,
+        };
+        let order = Order::new(
+            7,
+            "BTC/USD".to_string(),
+            OrderSide::Buy,
+            45000.5,
+            2.0,
+            1_700_000_000_000_000_002,
+        )
+        .with_client_id("desk-1");
+        let fill = Fill {
+            order_id: 7,
+            symbol: "BTC/USD".to_string(),
+            side: OrderSide::Buy,
+            fill_price: 45000.0,
+            fill_qty: 1.5,
+            timestamp_nanos: 1_700_000_000_000_000_003,
+            is_maker: false,
+            client_id: None,
+        };
+        let events = vec![
+            AuditEvent::SignalGenerated(signal),
+            AuditEvent::OrderPlaced(Box::new(order)),
+            AuditEvent::Fill(fill),
+            AuditEvent::Cancel {
+                order_id: 7,
+                cancelled_qty: 0.5,
+                reason: "ioc remainder".to_string(),
+            },
+            AuditEvent::Reject {
+                order_id: 8,
+                reason: "circuit open".to_string(),
+            },
+        ];
+        {
+            let log = AuditLog::open(path, "test").unwrap();
+            for event in &events {
+                assert!(log.record(event.clone()));
+            }
+        }
+
+        let records: Vec<AuditRecord> = AuditReader::open(path)
+            .unwrap()
+            .collect::<HftResult<_>>()
+            .unwrap();
+        assert_eq!(records.len(), events.len());
+        for (record, event) in records.iter().zip(&events) {
+            assert_eq!(record.source, "test");
+            assert_eq!(
+                serde_json::to_value(&record.event).unwrap(),
+                serde_json::to_value(event).unwrap()
+            );
+        }
+        assert!(records
+            .windows(2)
+            .all(|pair| pair[0].timestamp_nanos <= pair[1].timestamp_nanos));
+
+        // Reopening appends rather than truncating, and a flush writes
+        // events out while the log stays open
+        let log = AuditLog::with_options(path, "test", 16, Duration::from_secs(3600)).unwrap();
+        log.record(events[4].clone());
+        log.flush();
+        assert_eq!(AuditReader::open(path).unwrap().count(), events.len() + 1);
+        drop(log);
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,166 @@
+use prometheus::{IntCounter, IntGauge, Registry};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How often services sample their channels
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a channel must stay above its watermark before it warns
+pub const DEFAULT_SUSTAIN: Duration = Duration::from_secs(5);
+
+/// Length and capacity gauges for one bounded channel, `<name>_channel_len`
+/// and `<name>_channel_capacity`, plus an optional high watermark: a
+/// channel that stays above it for the sustain period logs a warning and
+/// counts in `<name>_channel_high_watermark_total`, once until it drains
+/// back below.
+pub struct ChannelMonitor {
+    name: String,
+    capacity: usize,
+    len: IntGauge,
+    watermark: Option<Watermark>,
+    warnings: IntCounter,
+}
+
+struct Watermark {
+    threshold: usize,
+    sustain: Duration,
+    above_since: Option<Instant>,
+    warned: bool,
+}
+
+impl ChannelMonitor {
+    /// Register the channel's metrics with `registry`
+    pub fn register(name: &str, capacity: usize, registry: &Registry) -> prometheus::Result<Self> {
+        let len = IntGauge::new(
+            format!("{}_channel_len", name),
+            format!("Messages queued on the {} channel", name),
+        )?;
+        let capacity_gauge = IntGauge::new(
+            format!("{}_channel_capacity", name),
+            format!("Capacity of the {} channel", name),
+        )?;
+        let warnings = IntCounter::new(
+            format!("{}_channel_high_watermark_total", name),
+            format!("Times the {} channel stayed above its high watermark", name),
+        )?;
+        capacity_gauge.set(capacity as i64);
+        registry.register(Box::new(len.clone()))?;
+        registry.register(Box::new(capacity_gauge))?;
+        registry.register(Box::new(warnings.clone()))?;
+        Ok(Self {
+            name: name.to_string(),
+            capacity,
+            len,
+            watermark: None,
+            warnings,
+        })
+    }
+
+    /// Warn once the channel has been more than `fraction` full for `sustain`
+    pub fn with_watermark(mut self, fraction: f64, sustain: Duration) -> Self {
+        self.watermark = Some(Watermark {
+            threshold: (self.capacity as f64 * fraction).ceil() as usize,
+            sustain,
+            above_since: None,
+            warned: false,
+        });
+        self
+    }
+
+    /// Record the channel's length as of `now`
+    pub fn sample(&mut self, len: usize, now: Instant) {
+        self.len.set(len as i64);
+        let Some(watermark) = &mut self.watermark else {
+            return;
+        };
+        if len < watermark.threshold {
+            if watermark.warned {
+                info!(
+                    "{} channel back below its high watermark ({}/{})",
+                    self.name, len, self.capacity
+                );
+            }
+            watermark.above_since = None;
+            watermark.warned = false;
+            return;
+        }
+        let since = *watermark.above_since.get_or_insert(now);
+        if !watermark.warned && now.duration_since(since) >= watermark.sustain {
+            watermark.warned = true;
+            self.warnings.inc();
+            warn!(
+                "{} channel above its high watermark for {:?} ({}/{}); drops may follow",
+                self.name, watermark.sustain, len, self.capacity
+            );
+        }
+    }
+
+    /// Whether the channel is currently flagged as backing up
+    pub fn is_high(&self) -> bool {
+        self.watermark
+            .as_ref()
+            .is_some_and(|watermark| watermark.warned)
+    }
+
+    pub fn warnings(&self) -> u64 {
+        self.warnings.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::sync_channel;
+
+    #[test]
+    fn test_sustained_fill_trips_watermark_until_drained() {
+        let registry = Registry::new();
+        let mut monitor = ChannelMonitor::register("test", 10, &registry)
+            .unwrap()
+            .with_watermark(0.8, Duration::from_secs(5));
+        let (tx, rx) = sync_channel::<u32>(10);
+        let start = Instant::now();
+        let mut queued = 0;
+
+        for i in 0..9 {
+            tx.try_send(i).unwrap();
+            queued += 1;
+        }
+        // Above the watermark, but not yet for long enough
+        monitor.sample(queued, start);
+        monitor.sample(queued, start + Duration::from_secs(4));
+        assert!(!monitor.is_high());
+        assert_eq!(monitor.warnings(), 0);
+
+        monitor.sample(queued, start + Duration::from_secs(5));
+        assert!(monitor.is_high());
+        assert_eq!(monitor.warnings(), 1);
+        // Still full: no repeat warning
+        monitor.sample(queued, start + Duration::from_secs(9));
+        assert_eq!(monitor.warnings(), 1);
+
+        while rx.try_recv().is_ok() {
+            queued -= 1;
+        }
+        monitor.sample(queued, start + Duration::from_secs(10));
+        assert!(!monitor.is_high());
+
+        // Another sustained stretch warns again
+        monitor.sample(8, start + Duration::from_secs(11));
+        monitor.sample(8, start + Duration::from_secs(16));
+        assert_eq!(monitor.warnings(), 2);
+
+        let families = registry.gather();
+        let gauge = |name: &str| {
+            families
+                .iter()
+                .find(|family| family.get_name() == name)
+                .unwrap()
+                .get_metric()[0]
+                .get_gauge()
+                .get_value()
+        };
+        assert_eq!(gauge("test_channel_len"), 8.0);
+        assert_eq!(gauge("test_channel_capacity"), 10.0);
+    }
+}
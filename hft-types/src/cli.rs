@@ -0,0 +1,93 @@
+use crate::config::SymbolUniverse;
+use crate::logging::LogFormat;
+use crate::messaging::parse_socket_addr;
+use clap::{Arg, ArgMatches, Command};
+
+/// `--log-format`, also read from `HFT_LOG_FORMAT`
+pub fn log_format() -> Arg {
+    Arg::new("log-format")
+        .long("log-format")
+        .value_name("FORMAT")
+        .help("Log output: pretty or json")
+        .env("HFT_LOG_FORMAT")
+        .value_parser(|s: &str| s.parse::<LogFormat>())
+        .default_value("pretty")
+}
+
+/// `--health-listen`, where the service answers its probes unless told
+/// otherwise
+pub fn health_listen(default: &'static str) -> Arg {
+    Arg::new("health-listen")
+        .long("health-listen")
+        .value_name("ADDR")
+        .help("HTTP address for the /healthz and /readyz probes")
+        .value_parser(parse_socket_addr)
+        .default_value(default)
+}
+
+/// `--universe`, read into a `SymbolUniverse`; `help` says what the
+/// service does with it
+pub fn universe(help: &'static str) -> Arg {
+    Arg::new("universe")
+        .long("universe")
+        .value_name("PATH")
+        .help(help)
+        .value_parser(|s: &str| SymbolUniverse::from_file(s).map_err(|e| e.to_string()))
+}
+
+/// Parse the process arguments with `command`. `--help` prints and exits;
+/// anything else wrong prints the error with the usage and exits with 2.
+pub fn parse_args(command: Command) -> ArgMatches {
+    let mut usage = command.clone();
+    command.try_get_matches().unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            // --help
+            e.exit();
+        }
+        eprint!("{}", usage_message(&e, &mut usage));
+        std::process::exit(2);
+    })
+}
+
+/// `error` as rendered by clap, followed by the usage when clap left it out
+fn usage_message(error: &clap::Error, command: &mut Command) -> String {
+    let message = error.render().to_string();
+    if message.contains("Usage:") {
+        message
+    } else {
+        format!("{}\n{}\n", message, command.render_usage())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("service")
+            .arg(health_listen("0.0.0.0:9200"))
+            .arg(log_format())
+    }
+
+    #[test]
+    fn test_every_usage_error_shows_the_usage() {
+        for args in [
+            &["--health-listen", "localhost"][..],
+            &["--log-format", "xml"],
+            &["--no-such-flag"],
+        ] {
+            let error = command()
+                .try_get_matches_from(std::iter::once(&"service").chain(args))
+                .unwrap_err();
+            let message = usage_message(&error, &mut command());
+            assert!(message.contains("Usage:"), "{:?}: {}", args, message);
+        }
+
+        let matches = command().try_get_matches_from(["service", "--log-format", "json"]).unwrap();
+        assert_eq!(matches.get_one::<LogFormat>("log-format"), Some(&LogFormat::Json));
+        assert_eq!(
+            matches.get_one::<std::net::SocketAddr>("health-listen"),
+            Some(&"0.0.0.0:9200".parse().unwrap())
+        );
+    }
+}
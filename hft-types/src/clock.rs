@@ -0,0 +1,86 @@
+use crate::latency::monotonic_nanos;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Source of "now" for time-dependent logic (cooldowns, pacing, staleness),
+/// so it can be driven by hand in tests and by tick timestamps in backtests
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Nanoseconds since the epoch
+    fn now_nanos(&self) -> u128;
+}
+
+/// A clock shared between the components that read it
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall-clock time via `latency::monotonic_nanos`, so readings never go
+/// backwards when the system clock is stepped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_nanos(&self) -> u128 {
+        monotonic_nanos()
+    }
+}
+
+/// Clock that only moves when told to. Clones share the same time, so a
+/// test can keep one and hand another to the code under test.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    nanos: Arc<Mutex<u128>>,
+}
+
+impl MockClock {
+    pub fn new(start_nanos: u128) -> Self {
+        Self {
+            nanos: Arc::new(Mutex::new(start_nanos)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.nanos.lock().unwrap() += by.as_nanos();
+    }
+
+    /// Jump to `nanos`, e.g. the timestamp of the tick being replayed
+    pub fn set(&self, nanos: u128) {
+        *self.nanos.lock().unwrap() = nanos;
+    }
+
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for MockClock {
+    fn now_nanos(&self) -> u128 {
+        *self.nanos.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_is_shared_between_clones() {
+        let clock = MockClock::new(1_000);
+        let shared = clock.shared();
+        assert_eq!(shared.now_nanos(), 1_000);
+
+        clock.advance(Duration::from_micros(2));
+        assert_eq!(shared.now_nanos(), 3_000);
+
+        clock.set(10);
+        assert_eq!(shared.now_nanos(), 10);
+
+        let system = SystemClock;
+        assert!(system.now_nanos() <= system.now_nanos());
+    }
+}
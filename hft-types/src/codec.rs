@@ -0,0 +1,155 @@
+use crate::messaging::{check_frame_len, Message, MessageFrame, MAX_FRAME_LEN};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Length-prefixed `Message` framing for tokio streams, the same wire format
+/// as `write_message`/`read_frame`:
+///
+/// ```ignore
+/// let mut framed = Framed::new(stream, MessageCodec::default());
+/// while let Some(message) = framed.next().await { ... }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MessageCodec {
+    max_frame_length: usize,
+}
+
+impl MessageCodec {
+    /// Reject frames whose payload is longer than `max_frame_length` bytes,
+    /// so a corrupt length prefix can't make us buffer gigabytes. The limit
+    /// can be tightened but never raised above `MAX_FRAME_LEN`.
+    pub fn with_max_frame_length(max_frame_length: usize) -> Self {
+        Self {
+            max_frame_length: max_frame_length.min(MAX_FRAME_LEN as usize),
+        }
+    }
+
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    fn frame_too_long(&self, length: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "frame of {} bytes exceeds the {} byte limit",
+                length, self.max_frame_length
+            ),
+        )
+    }
+}
+
+impl Default for MessageCodec {
+    fn default() -> Self {
+        Self::with_max_frame_length(MAX_FRAME_LEN as usize)
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Message;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Message>> {
+        let Some(prefix) = src.get(..4) else {
+            return Ok(None);
+        };
+        let length = check_frame_len(u32::from_be_bytes(prefix.try_into().unwrap()))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if length > self.max_frame_length {
+            return Err(self.frame_too_long(length));
+        }
+        if src.len() < 4 + length {
+            // Partial frame: make room for the rest and wait for more bytes
+            src.reserve(4 + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(4);
+        let payload = src.split_to(length);
+        MessageFrame::from_length_and_payload(length as u32, payload.to_vec())
+            .parse_message()
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl Encoder<Message> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(&item, dst)
+    }
+}
+
+impl Encoder<&Message> for MessageCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &Message, dst: &mut BytesMut) -> io::Result<()> {
+        let frame = MessageFrame::new(item)?;
+        if frame.payload.len() > self.max_frame_length {
+            return Err(self.frame_too_long(frame.payload.len()));
+        }
+        dst.reserve(4 + frame.payload.len());
+        dst.put_u32(frame.length);
+        dst.extend_from_slice(&frame.payload);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MarketTick, Order, OrderSide};
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    #[tokio::test]
+    async fn test_round_trip_over_duplex() {
+        // A tiny buffer forces frames to arrive across several reads
+        let (client, server) = tokio::io::duplex(7);
+        let mut writer = FramedWrite::new(client, MessageCodec::default());
+        let mut reader = FramedRead::new(server, MessageCodec::default());
+
+        let messages = vec![
+            Message::Tick(MarketTick::new("BTC/USD".to_string(), 45000.0, 3, 1).with_sequence(9)),
+            Message::Order(Order::new(7, "ETH/USD".to_string(), OrderSide::Sell, 2500.0, 1.5, 2)),
+            Message::Heartbeat {
+                sender: "feed_handler".to_string(),
+                timestamp: 3,
+            },
+            Message::Shutdown,
+        ];
+        let sent = messages.clone();
+        tokio::spawn(async move {
+            for message in sent {
+                writer.send(message).await.unwrap();
+            }
+        });
+
+        for expected in messages {
+            let received = reader.next().await.unwrap().unwrap();
+            assert_eq!(
+                serde_json::to_string(&received).unwrap(),
+                serde_json::to_string(&expected).unwrap()
+            );
+        }
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_rejected() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FramedRead::new(server, MessageCodec::with_max_frame_length(16));
+
+        client.write_all(&1_000u32.to_be_bytes()).await.unwrap();
+        let err = reader.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut dst = BytesMut::new();
+        let mut codec = MessageCodec::with_max_frame_length(4);
+        assert!(codec.encode(&Message::Shutdown, &mut dst).is_err());
+        assert!(dst.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::replay::{MarketRecorder, MarketReplayer};
+use crate::{HftError, HftResult, MarketTick, TopOfBook};
+use arrow_array::builder::{
+    Float64Builder, StringDictionaryBuilder, TimestampNanosecondBuilder, UInt64Builder,
+};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float64Type, Int32Type, TimestampNanosecondType, UInt64Type};
+use arrow_array::{Array, ArrayAccessor, ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Ticks per row group
+const BATCH_ROWS: usize = 65_536;
+
+/// Columns of a Parquet recording. Symbols and venues are dictionary
+/// encoded; a tick's optional fields are null where it has none.
+pub fn schema() -> SchemaRef {
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    Arc::new(Schema::new(vec![
+        Field::new("symbol", dictionary.clone(), false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("volume", DataType::UInt64, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("sequence", DataType::UInt64, true),
+        Field::new("venue", dictionary, true),
+        Field::new("bid", DataType::Float64, true),
+        Field::new("bid_size", DataType::Float64, true),
+        Field::new("ask", DataType::Float64, true),
+        Field::new("ask_size", DataType::Float64, true),
+    ]))
+}
+
+fn arrow_error(e: impl std::fmt::Display) -> HftError {
+    HftError::SerializationError(e.to_string())
+}
+
+/// Column builders for one batch of ticks
+struct TickColumns {
+    symbol: StringDictionaryBuilder<Int32Type>,
+    price: Float64Builder,
+    volume: UInt64Builder,
+    timestamp: TimestampNanosecondBuilder,
+    sequence: UInt64Builder,
+    venue: StringDictionaryBuilder<Int32Type>,
+    quote: [Float64Builder; 4],
+    rows: usize,
+}
+
+impl TickColumns {
+    fn new() -> Self {
+        Self {
+            symbol: StringDictionaryBuilder::new(),
+            price: Float64Builder::new(),
+            volume: UInt64Builder::new(),
+            timestamp: TimestampNanosecondBuilder::new().with_timezone("UTC"),
+            sequence: UInt64Builder::new(),
+            venue: StringDictionaryBuilder::new(),
+            quote: std::array::from_fn(|_| Float64Builder::new()),
+            rows: 0,
+        }
+    }
+
+    fn push(&mut self, tick: &MarketTick) -> HftResult<()> {
+        // Arrow timestamps are i64 nanoseconds, good until 2262
+        let timestamp = i64::try_from(tick.timestamp_nanos)
+            .map_err(|_| HftError::InvalidTimestamp(tick.timestamp_nanos))?;
+        self.symbol.append_value(&tick.symbol);
+        self.price.append_value(tick.price);
+        self.volume.append_value(tick.volume);
+        self.timestamp.append_value(timestamp);
+        self.sequence.append_option(tick.sequence);
+        self.venue.append_option(tick.venue.as_deref());
+        let quote = tick.quote.map(|q| [q.bid, q.bid_size, q.ask, q.ask_size]);
+        for (i, column) in self.quote.iter_mut().enumerate() {
+            column.append_option(quote.map(|values| values[i]));
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self, schema: &SchemaRef) -> HftResult<RecordBatch> {
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.symbol.finish()),
+            Arc::new(self.price.finish()),
+            Arc::new(self.volume.finish()),
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.sequence.finish()),
+            Arc::new(self.venue.finish()),
+        ];
+        columns.extend(
+            self.quote
+                .iter_mut()
+                .map(|column| Arc::new(column.finish()) as ArrayRef),
+        );
+        self.rows = 0;
+        RecordBatch::try_new(schema.clone(), columns).map_err(arrow_error)
+    }
+}
+
+/// Convert a JSONL recording (gzipped or not) to a Snappy-compressed
+/// Parquet file. Returns the number of ticks written.
+pub fn to_parquet<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> HftResult<u64> {
+    let mut replayer = MarketReplayer::new(input)?;
+    let schema = schema();
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(output)?, schema.clone(), Some(properties))
+        .map_err(arrow_error)?;
+
+    let mut columns = TickColumns::new();
+    let mut written = 0;
+    while let Some(tick) = replayer.next_tick()? {
+        columns.push(&tick)?;
+        written += 1;
+        if columns.rows == BATCH_ROWS {
+            writer
+                .write(&columns.finish(&schema)?)
+                .map_err(arrow_error)?;
+        }
+    }
+    if columns.rows > 0 {
+        writer
+            .write(&columns.finish(&schema)?)
+            .map_err(arrow_error)?;
+    }
+    writer.close().map_err(arrow_error)?;
+    Ok(written)
+}
+
+/// Convert a Parquet file written by `to_parquet` back to a JSONL
+/// recording. Returns the number of ticks written.
+pub fn from_parquet<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> HftResult<u64> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(input)?)
+        .and_then(|builder| builder.build())
+        .map_err(arrow_error)?;
+    let mut recorder = MarketRecorder::new(output)?;
+
+    let mut written = 0;
+    for batch in reader {
+        for tick in batch_ticks(&batch.map_err(arrow_error)?)? {
+            recorder.record_tick(&tick)?;
+            written += 1;
+        }
+    }
+    recorder.flush()?;
+    Ok(written)
+}
+
+/// Rebuild the ticks in one batch
+fn batch_ticks(batch: &RecordBatch) -> HftResult<Vec<MarketTick>> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .ok_or_else(|| arrow_error(format!("Parquet recording has no {} column", name)))
+    };
+    let strings = |name: &str| -> HftResult<_> {
+        column(name)?
+            .as_dictionary_opt::<Int32Type>()
+            .and_then(|dictionary| dictionary.downcast_dict::<StringArray>())
+            .ok_or_else(|| arrow_error(format!("{} is not a string dictionary", name)))
+    };
+    let floats = |name: &str| -> HftResult<_> {
+        column(name)?
+            .as_primitive_opt::<Float64Type>()
+            .ok_or_else(|| arrow_error(format!("{} is not a float64 column", name)))
+    };
+    let integers = |name: &str| -> HftResult<_> {
+        column(name)?
+            .as_primitive_opt::<UInt64Type>()
+            .ok_or_else(|| arrow_error(format!("{} is not a uint64 column", name)))
+    };
+
+    let (symbol, venue) = (strings("symbol")?, strings("venue")?);
+    let (price, volume, sequence) = (floats("price")?, integers("volume")?, integers("sequence")?);
+    let timestamp = column("timestamp")?
+        .as_primitive_opt::<TimestampNanosecondType>()
+        .ok_or_else(|| arrow_error("timestamp is not a nanosecond timestamp column"))?;
+    let quote = [
+        floats("bid")?,
+        floats("bid_size")?,
+        floats("ask")?,
+        floats("ask_size")?,
+    ];
+
+    (0..batch.num_rows())
+        .map(|row| {
+            let timestamp = u128::try_from(timestamp.value(row))
+                .map_err(|_| arrow_error(format!("negative timestamp in row {}", row)))?;
+            let mut tick = MarketTick::new(
+                symbol.value(row).to_string(),
+                price.value(row),
+                volume.value(row),
+                timestamp,
+            );
+            tick.sequence = sequence.is_valid(row).then(|| sequence.value(row));
+            tick.venue = venue.is_valid(row).then(|| venue.value(row).to_string());
+            if quote.iter().all(|column| column.is_valid(row)) {
+                tick.quote = Some(TopOfBook {
+                    bid: quote[0].value(row),
+                    bid_size: quote[1].value(row),
+                    ask: quote[2].value(row),
+                    ask_size: quote[3].value(row),
+                });
+            }
+            Ok(tick)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trips_through_parquet() {
+        let jsonl = "/tmp/hft_test_columnar.jsonl";
+        let parquet = "/tmp/hft_test_columnar.parquet";
+        let back = "/tmp/hft_test_columnar_back.jsonl";
+        let ticks = vec![
+            MarketTick::new("BTC/USD".to_string(), 45000.5, 3, 1_700_000_000_000_000_001)
+                .with_sequence(7),
+            MarketTick::new(
+                "ETH/USD".to_string(),
+                2500.25,
+                10,
+                1_700_000_000_000_000_002,
+            )
+            .with_venue("kraken"),
+            MarketTick::new("BTC/USD".to_string(), 45001.0, 1, 1_700_000_000_000_000_003)
+                .with_quote(TopOfBook {
+                    bid: 45000.0,
+                    bid_size: 2.0,
+                    ask: 45001.5,
+                    ask_size: 0.5,
+                }),
+        ];
+        {
+            let mut recorder = MarketRecorder::new(jsonl).unwrap();
+            for tick in &ticks {
+                recorder.record_tick(tick).unwrap();
+            }
+        }
+
+        assert_eq!(to_parquet(jsonl, parquet).unwrap(), 3);
+        assert_eq!(from_parquet(parquet, back).unwrap(), 3);
+
+        let mut replayer = MarketReplayer::new(back).unwrap();
+        let mut replayed = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            replayed.push(serde_json::to_string(&tick).unwrap());
+        }
+        let expected: Vec<String> = ticks
+            .iter()
+            .map(|tick| serde_json::to_string(tick).unwrap())
+            .collect();
+        assert_eq!(replayed, expected);
+
+        // Symbols are stored once per row group, not once per row
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(parquet).unwrap()).unwrap();
+        let symbol = reader.schema().field_with_name("symbol").unwrap().clone();
+        assert!(matches!(symbol.data_type(), DataType::Dictionary(..)));
+
+        for path in [jsonl, parquet, back] {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
@@ -0,0 +1,572 @@
+use crate::strategies::{
+    CrossBookArbStrategy, EnsemblePolicy, EwmaReversionStrategy, Guarded, MarketMakingStrategy,
+    MeanReversionStrategy, NonFinitePolicy, PairsConfig, PairsStrategy, Strategy,
+    StrategyEnsemble, ThresholdStrategy,
+};
+use crate::fees::FeeSchedule;
+use crate::risk::CircuitBreakerConfig;
+use crate::{HftError, HftResult, SymbolConfig};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Buy below `low`, sell above `high`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBand {
+    pub low: f64,
+    pub high: f64,
+}
+
+/// Which strategy to run and its parameters, selected by `type`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StrategyConfig {
+    Threshold {
+        order_size: f64,
+        thresholds: HashMap<String, ThresholdBand>,
+    },
+    MarketMaking {
+        spread_bps: f64,
+        order_size: f64,
+        /// Extra bps of spread per bp of recent per-tick volatility
+        #[serde(default)]
+        vol_multiplier: f64,
+        /// Bps the quote mid shifts per unit of inventory
+        #[serde(default)]
+        inventory_skew_bps: f64,
+    },
+    MeanReversion {
+        window_size: usize,
+        std_dev_threshold: f64,
+        order_size: f64,
+    },
+    EwmaReversion {
+        /// Ticks for a price's weight to halve
+        half_life: f64,
+        std_dev_threshold: f64,
+        order_size: f64,
+    },
+    CrossBookArb {
+        min_edge_bps: f64,
+    },
+    Pairs {
+        symbol_a: String,
+        symbol_b: String,
+        hedge_ratio: f64,
+        window_size: usize,
+        entry_z: f64,
+        exit_z: f64,
+        order_size: f64,
+    },
+    /// Several strategies at once, their signals merged per `policy`
+    Ensemble {
+        policy: EnsemblePolicy,
+        strategies: Vec<StrategyConfig>,
+    },
+}
+
+/// A config file; only the `[strategy]` section is read, so the shared
+/// config.toml can be passed as-is
+#[derive(Debug, Deserialize)]
+struct ConfigFile {
+    strategy: StrategyConfig,
+}
+
+/// Parse a `.json` or TOML config file; binaries use it to read the
+/// sections only they have settings in
+pub fn read_config_file<T: DeserializeOwned>(path: &Path) -> HftResult<T> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| HftError::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    }
+    .map_err(|e| HftError::InvalidConfig(format!("{}: {}", path.display(), e)))
+}
+
+impl StrategyConfig {
+    /// Load the `strategy` section of a `.json` or TOML file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> HftResult<Self> {
+        let file: ConfigFile = read_config_file(path.as_ref())?;
+        file.strategy.validate()?;
+        Ok(file.strategy)
+    }
+
+    /// Reject parameters that deserialize but make no sense
+    pub fn validate(&self) -> HftResult<()> {
+        let order_size = match self {
+            StrategyConfig::Threshold { order_size, thresholds } => {
+                if let Some((symbol, _)) = thresholds.iter().find(|(_, band)| band.low >= band.high) {
+                    return Err(HftError::InvalidConfig(format!(
+                        "threshold for {} has low >= high",
+                        symbol
+                    )));
+                }
+                *order_size
+            }
+            StrategyConfig::MarketMaking { vol_multiplier, inventory_skew_bps, order_size, .. } => {
+                if *vol_multiplier < 0.0 || *inventory_skew_bps < 0.0 {
+                    return Err(HftError::InvalidConfig(
+                        "market_making vol_multiplier and inventory_skew_bps cannot be negative"
+                            .to_string(),
+                    ));
+                }
+                *order_size
+            }
+            StrategyConfig::MeanReversion { window_size, order_size, .. } => {
+                if *window_size < 2 {
+                    return Err(HftError::InvalidConfig(
+                        "mean_reversion window_size must be at least 2".to_string(),
+                    ));
+                }
+                *order_size
+            }
+            StrategyConfig::EwmaReversion { half_life, order_size, .. } => {
+                if *half_life <= 0.0 {
+                    return Err(HftError::InvalidConfig(
+                        "ewma_reversion half_life must be positive".to_string(),
+                    ));
+                }
+                *order_size
+            }
+            StrategyConfig::CrossBookArb { .. } => return Ok(()),
+            StrategyConfig::Ensemble { strategies, .. } => {
+                if strategies.is_empty() {
+                    return Err(HftError::InvalidConfig(
+                        "ensemble needs at least one strategy".to_string(),
+                    ));
+                }
+                return strategies.iter().try_for_each(StrategyConfig::validate);
+            }
+            StrategyConfig::Pairs { window_size, entry_z, exit_z, order_size, .. } => {
+                if *window_size < 2 {
+                    return Err(HftError::InvalidConfig(
+                        "pairs window_size must be at least 2".to_string(),
+                    ));
+                }
+                if *exit_z >= *entry_z {
+                    return Err(HftError::InvalidConfig(
+                        "pairs exit_z must be below entry_z".to_string(),
+                    ));
+                }
+                *order_size
+            }
+        };
+
+        if order_size <= 0.0 {
+            return Err(HftError::InvalidQuantity(order_size));
+        }
+        Ok(())
+    }
+}
+
+/// The optional `[circuit_breaker]` section of a config file
+#[derive(Debug, Deserialize)]
+struct CircuitBreakerFile {
+    #[serde(default)]
+    circuit_breaker: CircuitBreakerConfig,
+}
+
+impl CircuitBreakerConfig {
+    /// Load the `circuit_breaker` section of a `.json` or TOML file,
+    /// falling back to the defaults when it has none
+    pub fn from_file<P: AsRef<Path>>(path: P) -> HftResult<Self> {
+        let file: CircuitBreakerFile = read_config_file(path.as_ref())?;
+        if file.circuit_breaker.cooldown_secs < 0.0 {
+            return Err(HftError::InvalidConfig(
+                "circuit_breaker cooldown_secs must not be negative".to_string(),
+            ));
+        }
+        Ok(file.circuit_breaker)
+    }
+}
+
+/// The optional `[gateway.fees]` section of a config file
+#[derive(Debug, Deserialize)]
+struct FeesFile {
+    #[serde(default)]
+    gateway: GatewayFees,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GatewayFees {
+    #[serde(default)]
+    fees: FeeSchedule,
+}
+
+impl FeeSchedule {
+    /// Load the `gateway.fees` section of a `.json` or TOML file, charging
+    /// nothing when it has none
+    pub fn from_file<P: AsRef<Path>>(path: P) -> HftResult<Self> {
+        let file: FeesFile = read_config_file(path.as_ref())?;
+        Ok(file.gateway.fees)
+    }
+}
+
+/// The `[[symbols]]` section of a config file
+#[derive(Debug, Deserialize)]
+struct UniverseFile {
+    symbols: Vec<SymbolConfig>,
+}
+
+/// The symbols every service works with, loaded from the shared config so
+/// the simulator, feed handler and strategy engine agree. Order is kept,
+/// so it also serves as the binary wire's symbol id order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolUniverse {
+    symbols: Vec<SymbolConfig>,
+}
+
+impl SymbolUniverse {
+    pub fn new(symbols: Vec<SymbolConfig>) -> HftResult<Self> {
+        if symbols.is_empty() {
+            return Err(HftError::InvalidConfig("symbol universe is empty".to_string()));
+        }
+        for (i, config) in symbols.iter().enumerate() {
+            if symbols[..i].iter().any(|other| other.symbol == config.symbol) {
+                return Err(HftError::InvalidConfig(format!(
+                    "duplicate symbol {} in universe",
+                    config.symbol
+                )));
+            }
+            if !(config.base_price.is_finite() && config.base_price > 0.0) {
+                return Err(HftError::InvalidPrice(config.base_price));
+            }
+            if config.thresholds.is_some_and(|band| band.low >= band.high) {
+                return Err(HftError::InvalidConfig(format!(
+                    "threshold for {} has low >= high",
+                    config.symbol
+                )));
+            }
+            for (name, size) in [
+                ("tick_size", config.tick_size),
+                ("lot_size", config.lot_size),
+                ("min_notional", config.min_notional),
+                ("activity_weight", config.activity_weight),
+            ] {
+                if size.is_some_and(|size| !(size.is_finite() && size > 0.0)) {
+                    return Err(HftError::InvalidConfig(format!(
+                        "{} for {} must be positive",
+                        name, config.symbol
+                    )));
+                }
+            }
+        }
+        Ok(Self { symbols })
+    }
+
+    /// Load the `symbols` entries of a `.json` or TOML file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> HftResult<Self> {
+        let file: UniverseFile = read_config_file(path.as_ref())?;
+        Self::new(file.symbols)
+    }
+
+    pub fn symbols(&self) -> &[SymbolConfig] {
+        &self.symbols
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&SymbolConfig> {
+        self.symbols.iter().find(|config| config.symbol == symbol)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.get(symbol).is_some()
+    }
+
+    /// Symbol names in universe order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.symbols.iter().map(|config| config.symbol.as_str())
+    }
+
+    /// Threshold strategy over every symbol that has a band
+    pub fn threshold_config(&self, order_size: f64) -> StrategyConfig {
+        StrategyConfig::Threshold {
+            order_size,
+            thresholds: self
+                .symbols
+                .iter()
+                .filter_map(|config| Some((config.symbol.clone(), config.thresholds?)))
+                .collect(),
+        }
+    }
+}
+
+/// Instantiate the strategy described by a config, dropping any signal
+/// with a NaN or infinity in it
+pub fn build_strategy(config: &StrategyConfig) -> Box<dyn Strategy> {
+    build_guarded_strategy(config, NonFinitePolicy::default())
+}
+
+/// Build the strategy `config` describes, its signals checked for NaN and
+/// infinity under `policy`
+pub fn build_guarded_strategy(config: &StrategyConfig, policy: NonFinitePolicy) -> Box<dyn Strategy> {
+    Box::new(Guarded::new(build_unguarded(config), policy))
+}
+
+/// Ensemble members are checked once, on the ensemble's way out
+fn build_unguarded(config: &StrategyConfig) -> Box<dyn Strategy> {
+    match config {
+        StrategyConfig::Threshold { order_size, thresholds } => Box::new(ThresholdStrategy::new(
+            thresholds
+                .iter()
+                .map(|(symbol, band)| (symbol.clone(), (band.low, band.high)))
+                .collect(),
+            *order_size,
+        )),
+        StrategyConfig::MarketMaking { spread_bps, order_size, vol_multiplier, inventory_skew_bps } => {
+            Box::new(
+                MarketMakingStrategy::new(*spread_bps, *order_size)
+                    .with_vol_multiplier(*vol_multiplier)
+                    .with_inventory_skew(*inventory_skew_bps),
+            )
+        }
+        StrategyConfig::MeanReversion { window_size, std_dev_threshold, order_size } => Box::new(
+            MeanReversionStrategy::new(*window_size, *std_dev_threshold, *order_size),
+        ),
+        StrategyConfig::EwmaReversion { half_life, std_dev_threshold, order_size } => Box::new(
+            EwmaReversionStrategy::with_half_life(*half_life, *std_dev_threshold, *order_size),
+        ),
+        StrategyConfig::CrossBookArb { min_edge_bps } => {
+            Box::new(CrossBookArbStrategy::new(*min_edge_bps))
+        }
+        StrategyConfig::Pairs {
+            symbol_a,
+            symbol_b,
+            hedge_ratio,
+            window_size,
+            entry_z,
+            exit_z,
+            order_size,
+        } => Box::new(PairsStrategy::new(PairsConfig {
+            symbol_a: symbol_a.clone(),
+            symbol_b: symbol_b.clone(),
+            hedge_ratio: *hedge_ratio,
+            window_size: *window_size,
+            entry_z: *entry_z,
+            exit_z: *exit_z,
+            order_size: *order_size,
+        })),
+        StrategyConfig::Ensemble { policy, strategies } => Box::new(StrategyEnsemble::new(
+            strategies.iter().map(build_unguarded).collect(),
+            *policy,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnrichedTick, MarketTick, Order, OrderSide, OrderType, RoundingMode};
+
+    fn write_config(name: &str, contents: &str) -> String {
+        let path = format!("/tmp/hft_test_strategy_config_{}", name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_threshold_config_builds_strategy() {
+        let path = write_config(
+            "threshold.toml",
+            r#"
+[system]
+name = "ignored"
+
+[strategy]
+type = "threshold"
+order_size = 2.5
+
+[strategy.thresholds]
+"BTC/USD" = { low = 44000.0, high = 46000.0 }
+"#,
+        );
+
+        let config = StrategyConfig::from_file(&path).unwrap();
+        let mut strategy = build_strategy(&config);
+        assert_eq!(strategy.name(), "ThresholdStrategy");
+
+        let enriched = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 46500.0, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+        let signal = strategy.process_tick(&enriched).unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+        assert_eq!(signal.quantity, 2.5);
+
+        // The same config survives a JSON round trip
+        let json_path = write_config(
+            "threshold.json",
+            &serde_json::json!({ "strategy": config }).to_string(),
+        );
+        assert_eq!(StrategyConfig::from_file(&json_path).unwrap(), config);
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(json_path).unwrap();
+    }
+
+    #[test]
+    fn test_ensemble_config_builds_strategy() {
+        let path = write_config(
+            "ensemble.toml",
+            r#"
+[strategy]
+type = "ensemble"
+policy = "net"
+
+[[strategy.strategies]]
+type = "threshold"
+order_size = 1.0
+thresholds = { "BTC/USD" = { low = 44000.0, high = 46000.0 } }
+
+[[strategy.strategies]]
+type = "threshold"
+order_size = 3.0
+thresholds = { "BTC/USD" = { low = 43000.0, high = 46000.0 } }
+"#,
+        );
+        let config = StrategyConfig::from_file(&path).unwrap();
+        let mut strategy = build_strategy(&config);
+        assert_eq!(strategy.name(), "StrategyEnsemble");
+
+        let enriched = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 46500.0, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+        let signal = strategy.process_tick(&enriched).unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+        assert_eq!(signal.quantity, 4.0);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_shared_config_universe() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../config.toml");
+        let universe = SymbolUniverse::from_file(path).unwrap();
+        assert_eq!(
+            universe.names().collect::<Vec<_>>(),
+            ["BTC/USD", "ETH/USD", "SOL/USD", "AVAX/USD"]
+        );
+        assert_eq!(universe.get("ETH/USD").unwrap().base_price, 2500.0);
+        let btc = universe.get("BTC/USD").unwrap();
+        assert_eq!(btc.round_price(45001.37, RoundingMode::Down), 45001.0);
+        assert_eq!(btc.round_quantity(0.0125, RoundingMode::Down), 0.012);
+        assert!(!universe.contains("DOGE/USD"));
+
+        // The universe's bands agree with the strategy section
+        assert_eq!(universe.threshold_config(1.0), StrategyConfig::from_file(path).unwrap());
+
+        let breaker = CircuitBreakerConfig::from_file(path).unwrap();
+        assert_eq!(breaker.max_realized_loss, Some(10_000.0));
+
+        let fees = FeeSchedule::from_file(path).unwrap();
+        assert_eq!(fees.rates("ETH/USD").taker_bps, 5.0);
+        assert_eq!(fees.rates("BTC/USD").maker_bps, -0.5);
+    }
+
+    #[test]
+    fn test_invalid_universes_are_rejected() {
+        let symbol = |name: &str, base_price| SymbolConfig {
+            symbol: name.to_string(),
+            base_price,
+            thresholds: None,
+            tick_size: None,
+            lot_size: None,
+            min_notional: None,
+            activity_weight: None,
+        };
+        assert!(SymbolUniverse::new(vec![]).is_err());
+        assert!(SymbolUniverse::new(vec![symbol("BTC/USD", 1.0), symbol("BTC/USD", 2.0)]).is_err());
+        assert!(SymbolUniverse::new(vec![symbol("BTC/USD", 0.0)]).is_err());
+        let zero_tick = SymbolConfig {
+            tick_size: Some(0.0),
+            ..symbol("BTC/USD", 1.0)
+        };
+        let err = SymbolUniverse::new(vec![zero_tick]).unwrap_err().to_string();
+        assert!(err.contains("tick_size for BTC/USD must be positive"), "{}", err);
+
+        let path = write_config(
+            "universe.toml",
+            "[[symbols]]\nsymbol = \"BTC/USD\"\nbase_price = 45000.0\nthresholds = { low = 2.0, high = 1.0 }\n",
+        );
+        let err = SymbolUniverse::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("low >= high"), "{}", err);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_orders_checked_against_lot_and_min_notional() {
+        use crate::OrderSide;
+
+        // Trades in 0.1 lots, but nothing worth less than 10
+        let config = SymbolConfig {
+            symbol: "DOGE/USD".to_string(),
+            base_price: 0.15,
+            thresholds: None,
+            tick_size: Some(0.0001),
+            lot_size: Some(0.1),
+            min_notional: Some(10.0),
+            activity_weight: None,
+        };
+        let order = |price, quantity| {
+            Order::new(1, "DOGE/USD".to_string(), OrderSide::Buy, price, quantity, 1)
+        };
+
+        // Fractional, but a whole number of lots and above the minimum
+        config.validate_order(&order(0.15, 100.3)).unwrap();
+        let err = config.validate_order(&order(0.15, 50.0)).unwrap_err();
+        assert!(matches!(err, HftError::BelowMinNotional { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Notional 7.5 for DOGE/USD is below the minimum of 10"
+        );
+        assert!(matches!(
+            config.validate_order(&order(0.15, 100.25)),
+            Err(HftError::OddLot { lot_size, .. }) if lot_size == 0.1
+        ));
+        // A market order's notional isn't known up front
+        config
+            .validate_order(&order(0.0, 1.0).with_order_type(OrderType::Market))
+            .unwrap();
+
+        // Without a lot size any fraction goes, as long as it's worth enough
+        let fractional = SymbolConfig {
+            lot_size: None,
+            ..config
+        };
+        fractional.validate_order(&order(0.15, 66.6667)).unwrap();
+        assert!(fractional.validate_order(&order(0.15, 66.0)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_configs_are_rejected() {
+        let unknown = write_config("unknown.toml", "[strategy]\ntype = \"momentum\"\n");
+        let err = StrategyConfig::from_file(&unknown).unwrap_err().to_string();
+        assert!(err.contains("unknown variant `momentum`"), "{}", err);
+
+        let missing = write_config(
+            "missing.toml",
+            "[strategy]\ntype = \"mean_reversion\"\nwindow_size = 20\n",
+        );
+        let err = StrategyConfig::from_file(&missing).unwrap_err().to_string();
+        assert!(err.contains("missing field `std_dev_threshold`"), "{}", err);
+
+        let negative = write_config(
+            "negative.toml",
+            "[strategy]\ntype = \"market_making\"\nspread_bps = 5.0\norder_size = 1.0\nvol_multiplier = -1.0\n",
+        );
+        let err = StrategyConfig::from_file(&negative).unwrap_err().to_string();
+        assert!(err.contains("cannot be negative"), "{}", err);
+
+        std::fs::remove_file(unknown).unwrap();
+        std::fs::remove_file(missing).unwrap();
+        std::fs::remove_file(negative).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use crate::matching::Fill;
+use crate::vwap::VwapTracker;
+use crate::{HftError, HftResult, OrderSide};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Execution quality for one symbol of a parent order. Costs are signed so
+/// that positive is worse for the trader: buying above, or selling below,
+/// the benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolExecution {
+    pub symbol: String,
+    pub filled_qty: f64,
+    pub avg_fill_price: f64,
+    /// Price when the parent order was released
+    pub arrival_price: f64,
+    /// Market VWAP over the execution window; `None` if nothing traded
+    pub interval_vwap: Option<f64>,
+    /// Cost versus arrival, in price units times quantity
+    pub implementation_shortfall: f64,
+    pub slippage_vs_arrival_bps: f64,
+    pub slippage_vs_vwap_bps: Option<f64>,
+}
+
+/// Execution quality for a whole parent order; the aggregate slippage is
+/// weighted by each symbol's benchmark notional
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub symbols: Vec<SymbolExecution>,
+    pub implementation_shortfall: f64,
+    pub slippage_vs_arrival_bps: f64,
+    /// Over the symbols that have an interval VWAP
+    pub slippage_vs_vwap_bps: Option<f64>,
+}
+
+/// Per-symbol totals of a parent order's fills
+#[derive(Debug, Default)]
+struct FillTotals {
+    qty: f64,
+    notional: f64,
+    /// Quantity bought minus quantity sold
+    signed_qty: f64,
+    /// Buys count positive and sells negative
+    signed_notional: f64,
+}
+
+impl FillTotals {
+    /// Cost of these fills against `benchmark`, in price units
+    fn cost(&self, benchmark: f64) -> f64 {
+        self.signed_notional - benchmark * self.signed_qty
+    }
+}
+
+/// Measures a parent order's fills against its arrival prices and the
+/// market VWAP over the execution window
+#[derive(Debug, Default)]
+pub struct ExecutionAnalyzer {
+    arrival_prices: BTreeMap<String, f64>,
+    fills: BTreeMap<String, FillTotals>,
+}
+
+impl ExecutionAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the benchmark price for `symbol` when the order was released
+    pub fn set_arrival_price(&mut self, symbol: &str, price: f64) -> HftResult<()> {
+        if !(price.is_finite() && price > 0.0) {
+            return Err(HftError::InvalidPrice(price));
+        }
+        self.arrival_prices.insert(symbol.to_string(), price);
+        Ok(())
+    }
+
+    pub fn on_fill(&mut self, fill: &Fill) {
+        let totals = self.fills.entry(fill.symbol.clone()).or_default();
+        let sign = match fill.side {
+            OrderSide::Buy => 1.0,
+            OrderSide::Sell => -1.0,
+        };
+        totals.qty += fill.fill_qty;
+        totals.notional += fill.fill_price * fill.fill_qty;
+        totals.signed_qty += sign * fill.fill_qty;
+        totals.signed_notional += sign * fill.fill_price * fill.fill_qty;
+    }
+
+    /// Build the report, reading interval VWAPs from `vwap`. Fails if a
+    /// filled symbol has no arrival price.
+    pub fn report(&self, vwap: &VwapTracker) -> HftResult<ExecutionReport> {
+        let mut report = ExecutionReport {
+            symbols: Vec::new(),
+            implementation_shortfall: 0.0,
+            slippage_vs_arrival_bps: 0.0,
+            slippage_vs_vwap_bps: None,
+        };
+        let mut arrival_notional = 0.0;
+        let mut vwap_cost = 0.0;
+        let mut vwap_notional = 0.0;
+
+        for (symbol, totals) in self.fills.iter().filter(|(_, t)| t.qty > 0.0) {
+            let arrival_price = *self
+                .arrival_prices
+                .get(symbol)
+                .ok_or_else(|| HftError::SymbolNotFound(symbol.clone()))?;
+            let interval_vwap = vwap.vwap(symbol);
+
+            let shortfall = totals.cost(arrival_price);
+            report.implementation_shortfall += shortfall;
+            arrival_notional += arrival_price * totals.qty;
+            if let Some(price) = interval_vwap {
+                vwap_cost += totals.cost(price);
+                vwap_notional += price * totals.qty;
+            }
+
+            report.symbols.push(SymbolExecution {
+                symbol: symbol.clone(),
+                filled_qty: totals.qty,
+                avg_fill_price: totals.notional / totals.qty,
+                arrival_price,
+                interval_vwap,
+                implementation_shortfall: shortfall,
+                slippage_vs_arrival_bps: to_bps(shortfall, arrival_price * totals.qty),
+                slippage_vs_vwap_bps: interval_vwap
+                    .map(|price| to_bps(totals.cost(price), price * totals.qty)),
+            });
+        }
+
+        report.slippage_vs_arrival_bps = to_bps(report.implementation_shortfall, arrival_notional);
+        if vwap_notional > 0.0 {
+            report.slippage_vs_vwap_bps = Some(to_bps(vwap_cost, vwap_notional));
+        }
+        Ok(report)
+    }
+}
+
+fn to_bps(cost: f64, notional: f64) -> f64 {
+    if notional > 0.0 {
+        cost / notional * 10_000.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MarketTick;
+    use std::time::Duration;
+
+    fn fill(symbol: &str, side: OrderSide, price: f64, qty: f64) -> Fill {
+        Fill {
+            order_id: 1,
+            symbol: symbol.to_string(),
+            side,
+            fill_price: price,
+            fill_qty: qty,
+            timestamp_nanos: 1,
+            is_maker: false,
+            client_id: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_slippage_against_arrival_and_vwap() {
+        let mut vwap = VwapTracker::new(Duration::from_secs(60));
+        // Market VWAP of 101 for BTC; nothing traded in ETH
+        vwap.on_tick(&MarketTick::new("BTC/USD".to_string(), 100.0, 1, 1));
+        vwap.on_tick(&MarketTick::new("BTC/USD".to_string(), 102.0, 1, 2));
+
+        let mut analyzer = ExecutionAnalyzer::new();
+        analyzer.set_arrival_price("BTC/USD", 100.0).unwrap();
+        analyzer.set_arrival_price("ETH/USD", 50.0).unwrap();
+        assert!(analyzer.set_arrival_price("ETH/USD", 0.0).is_err());
+
+        // Bought 4 at an average of 100.5
+        analyzer.on_fill(&fill("BTC/USD", OrderSide::Buy, 100.0, 2.0));
+        analyzer.on_fill(&fill("BTC/USD", OrderSide::Buy, 101.0, 2.0));
+        // Sold 10 at 49.9, below arrival
+        analyzer.on_fill(&fill("ETH/USD", OrderSide::Sell, 49.9, 10.0));
+
+        let report = analyzer.report(&vwap).unwrap();
+        let btc = &report.symbols[0];
+        assert_eq!(btc.symbol, "BTC/USD");
+        assert_close(btc.avg_fill_price, 100.5);
+        // Paid 0.5 over arrival on 4 units: 50 bps worse
+        assert_close(btc.implementation_shortfall, 2.0);
+        assert_close(btc.slippage_vs_arrival_bps, 50.0);
+        // But 0.5 under the interval VWAP: better than the market
+        assert_eq!(btc.interval_vwap, Some(101.0));
+        assert_close(btc.slippage_vs_vwap_bps.unwrap(), -0.5 / 101.0 * 10_000.0);
+
+        let eth = &report.symbols[1];
+        assert_close(eth.implementation_shortfall, 1.0);
+        assert_close(eth.slippage_vs_arrival_bps, 20.0);
+        assert_eq!(eth.slippage_vs_vwap_bps, None);
+
+        // 3 of cost on 900 of arrival notional; VWAP only covers BTC
+        assert_close(report.implementation_shortfall, 3.0);
+        assert_close(report.slippage_vs_arrival_bps, 3.0 / 900.0 * 10_000.0);
+        assert_close(report.slippage_vs_vwap_bps.unwrap(), btc.slippage_vs_vwap_bps.unwrap());
+
+        // A fill with no benchmark can't be judged
+        analyzer.on_fill(&fill("SOL/USD", OrderSide::Buy, 20.0, 1.0));
+        assert!(matches!(analyzer.report(&vwap), Err(HftError::SymbolNotFound(_))));
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Charges for executions. Rates are in basis points of notional; makers
+/// add liquidity by resting on the book, takers remove it.
+pub trait FeeModel {
+    /// Rate for resting-order fills, in bps; negative for a rebate
+    fn maker_fee(&self) -> f64;
+
+    /// Rate for aggressing fills, in bps
+    fn taker_fee(&self) -> f64;
+
+    /// Flat charge on top of the rate for each order, however many
+    /// executions it takes to fill
+    fn per_order_fee(&self) -> f64 {
+        0.0
+    }
+
+    /// The rate's share of the fee for an execution of `notional`
+    fn rate_fee(&self, notional: f64, is_maker: bool) -> f64 {
+        let bps = if is_maker {
+            self.maker_fee()
+        } else {
+            self.taker_fee()
+        };
+        notional.abs() * bps / 10_000.0
+    }
+
+    /// Fee for an order filled in one execution of `notional`
+    fn compute_fee(&self, notional: f64, is_maker: bool) -> f64 {
+        self.rate_fee(notional, is_maker) + self.per_order_fee()
+    }
+}
+
+/// Maker and taker rates plus an optional flat fee
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeRates {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+    pub per_order: f64,
+}
+
+impl FeeRates {
+    /// The same rate whichever side of the book the fill was on
+    pub fn flat_bps(bps: f64) -> Self {
+        Self {
+            maker_bps: bps,
+            taker_bps: bps,
+            per_order: 0.0,
+        }
+    }
+}
+
+impl FeeModel for FeeRates {
+    fn maker_fee(&self) -> f64 {
+        self.maker_bps
+    }
+
+    fn taker_fee(&self) -> f64 {
+        self.taker_bps
+    }
+
+    fn per_order_fee(&self) -> f64 {
+        self.per_order
+    }
+}
+
+/// Default rates with per-symbol overrides, mirroring `[gateway.fees]`:
+///
+/// ```toml
+/// [gateway.fees]
+/// maker_bps = 1.0
+/// taker_bps = 5.0
+/// symbols = { "BTC/USD" = { maker_bps = -0.5, taker_bps = 4.0 } }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeSchedule {
+    #[serde(flatten)]
+    pub default: FeeRates,
+    pub symbols: HashMap<String, FeeRates>,
+}
+
+impl FeeSchedule {
+    pub fn new(default: FeeRates) -> Self {
+        Self {
+            default,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Override the default rates for one symbol
+    pub fn with_symbol(mut self, symbol: &str, rates: FeeRates) -> Self {
+        self.symbols.insert(symbol.to_string(), rates);
+        self
+    }
+
+    pub fn rates(&self, symbol: &str) -> &FeeRates {
+        self.symbols.get(symbol).unwrap_or(&self.default)
+    }
+
+    /// Fee for an execution of `notional` in `symbol`. The flat
+    /// `per_order` fee is only charged on an order's `first_fill`.
+    pub fn fee(&self, symbol: &str, notional: f64, is_maker: bool, first_fill: bool) -> f64 {
+        let rates = self.rates(symbol);
+        let per_order = if first_fill { rates.per_order_fee() } else { 0.0 };
+        rates.rate_fee(notional, is_maker) + per_order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maker_and_taker_rates_differ() {
+        let rates = FeeRates {
+            maker_bps: -1.0,
+            taker_bps: 5.0,
+            per_order: 0.0,
+        };
+        assert!((rates.compute_fee(100_000.0, true) + 10.0).abs() < 1e-9);
+        assert!((rates.compute_fee(100_000.0, false) - 50.0).abs() < 1e-9);
+        // Sign of the notional doesn't matter
+        assert!((rates.compute_fee(-100_000.0, false) - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_symbol_tiers_and_flat_fee() {
+        let schedule = FeeSchedule::new(FeeRates {
+            maker_bps: 2.0,
+            taker_bps: 4.0,
+            per_order: 0.0,
+        })
+        .with_symbol(
+            "BTC/USD",
+            FeeRates {
+                maker_bps: 0.0,
+                taker_bps: 1.0,
+                per_order: 0.25,
+            },
+        );
+        assert!((schedule.fee("ETH/USD", 10_000.0, false, true) - 4.0).abs() < 1e-9);
+        assert!((schedule.fee("BTC/USD", 10_000.0, false, true) - 1.25).abs() < 1e-9);
+        assert!((schedule.fee("BTC/USD", 10_000.0, true, true) - 0.25).abs() < 1e-9);
+        // The flat fee is charged once per order, not on every fill
+        assert!((schedule.fee("BTC/USD", 10_000.0, false, false) - 1.0).abs() < 1e-9);
+
+        let parsed: FeeSchedule = toml::from_str(
+            r#"
+            maker_bps = 2.0
+            taker_bps = 4.0
+            symbols = { "BTC/USD" = { taker_bps = 1.0, per_order = 0.25 } }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(parsed, schedule);
+    }
+}
@@ -0,0 +1,171 @@
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use prometheus::{Encoder, Registry, TextEncoder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// Whether the dependencies a service needs are connected. Each named check
+/// is flipped by the code that owns the dependency; `/readyz` reports ready
+/// once every check is.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    checks: Arc<Mutex<BTreeMap<String, bool>>>,
+}
+
+/// Body of a `/readyz` response
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: BTreeMap<String, bool>,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold readiness until `check` is set
+    pub fn require(&self, check: &str) {
+        self.checks
+            .lock()
+            .unwrap()
+            .entry(check.to_string())
+            .or_insert(false);
+    }
+
+    pub fn set(&self, check: &str, ready: bool) {
+        self.checks.lock().unwrap().insert(check.to_string(), ready);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.checks.lock().unwrap().values().all(|&ready| ready)
+    }
+
+    pub fn report(&self) -> ReadinessReport {
+        let checks = self.checks.lock().unwrap().clone();
+        ReadinessReport {
+            ready: checks.values().all(|&ready| ready),
+            checks,
+        }
+    }
+}
+
+/// `/healthz` answers 200 while the process is up; `/readyz` answers 200
+/// once `readiness` is ready and 503 until then
+pub fn router(readiness: Readiness) -> Router {
+    Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        .route(
+            "/readyz",
+            get(move || async move {
+                let report = readiness.report();
+                let status = if report.ready {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                };
+                (status, Json(report))
+            }),
+        )
+}
+
+/// `/metrics` serves `registry` in the Prometheus text format
+pub fn metrics_router(registry: Registry) -> Router {
+    Router::new().route(
+        "/metrics",
+        get(move || async move {
+            let encoder = TextEncoder::new();
+            let mut buffer = Vec::new();
+            match encoder.encode(&registry.gather(), &mut buffer) {
+                Ok(()) => ([(header::CONTENT_TYPE, encoder.format_type().to_string())], buffer).into_response(),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            }
+        }),
+    )
+}
+
+/// Serve the probe endpoints and `registry`'s `/metrics` on `addr` from a
+/// background thread, returning the bound address. Works whether or not
+/// the caller runs on tokio.
+pub fn spawn_server(addr: SocketAddr, readiness: Readiness, registry: &Registry) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let bound = listener.local_addr()?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    let registry = registry.clone();
+    std::thread::Builder::new()
+        .name("health".to_string())
+        .spawn(move || {
+            runtime.block_on(async move {
+                let result = match tokio::net::TcpListener::from_std(listener) {
+                    Ok(listener) => axum::serve(listener, router(readiness).merge(metrics_router(registry))).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    warn!("Health endpoint on {} failed: {}", bound, e);
+                }
+            })
+        })?;
+    info!("Health probes on http://{}/healthz and /readyz, metrics on /metrics", bound);
+    Ok(bound)
+}
+
+/// Status code of a `GET path` against a probe server, for tests
+pub fn probe(addr: SocketAddr, path: &str) -> std::io::Result<u16> {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(addr)?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    response
+        .split(' ')
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_once_every_check_passes() {
+        let readiness = Readiness::new();
+        let addr = spawn_server("127.0.0.1:0".parse().unwrap(), readiness.clone(), &Registry::new()).unwrap();
+        readiness.require("socket");
+        readiness.require("feed");
+
+        assert_eq!(probe(addr, "/healthz").unwrap(), 200);
+        assert_eq!(probe(addr, "/readyz").unwrap(), 503);
+
+        readiness.set("socket", true);
+        assert_eq!(probe(addr, "/readyz").unwrap(), 503);
+        readiness.set("feed", true);
+        assert_eq!(probe(addr, "/readyz").unwrap(), 200);
+        // Requiring an already passing check doesn't reset it
+        readiness.require("feed");
+        assert!(readiness.is_ready());
+
+        readiness.set("feed", false);
+        assert_eq!(probe(addr, "/readyz").unwrap(), 503);
+        assert_eq!(
+            readiness.report().checks.into_iter().collect::<Vec<_>>(),
+            vec![("feed".to_string(), false), ("socket".to_string(), true)]
+        );
+        assert_eq!(probe(addr, "/missing").unwrap(), 404);
+        assert_eq!(probe(addr, "/metrics").unwrap(), 200);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::clock::{Clock, SystemClock};
+use crate::messaging::Message;
+use std::collections::BTreeMap;
+use std::net::UdpSocket;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Where services send heartbeats unless configured otherwise
+pub const DEFAULT_HEARTBEAT_ADDR: &str = "127.0.0.1:9091";
+
+/// Liveness of a monitored sender
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    Alive,
+    /// No heartbeat within the timeout, or none ever received
+    Stale,
+}
+
+/// Tracks when each named sender was last heard from
+#[derive(Debug)]
+pub struct HeartbeatMonitor {
+    timeout_nanos: u128,
+    last_seen: BTreeMap<String, Option<u128>>,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout_nanos: timeout.as_nanos(),
+            last_seen: BTreeMap::new(),
+        }
+    }
+
+    /// Expect heartbeats from `sender`; it reports `Stale` until the first one
+    pub fn watch(&mut self, sender: &str) {
+        self.last_seen.entry(sender.to_string()).or_insert(None);
+    }
+
+    /// Record a heartbeat from `sender` seen at `seen_nanos` (local clock)
+    pub fn record(&mut self, sender: &str, seen_nanos: u128) {
+        let last = self.last_seen.entry(sender.to_string()).or_insert(None);
+        *last = Some(last.unwrap_or(0).max(seen_nanos));
+    }
+
+    /// Record a `Message::Heartbeat`; returns false for any other message
+    pub fn on_message(&mut self, message: &Message, now_nanos: u128) -> bool {
+        match message {
+            Message::Heartbeat { sender, .. } => {
+                self.record(sender, now_nanos);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn status(&self, sender: &str, now_nanos: u128) -> Option<Liveness> {
+        self.last_seen
+            .get(sender)
+            .map(|last| self.liveness(*last, now_nanos))
+    }
+
+    /// Status of every known sender, in name order
+    pub fn statuses(&self, now_nanos: u128) -> Vec<(String, Liveness)> {
+        self.last_seen
+            .iter()
+            .map(|(sender, last)| (sender.clone(), self.liveness(*last, now_nanos)))
+            .collect()
+    }
+
+    fn liveness(&self, last_seen: Option<u128>, now_nanos: u128) -> Liveness {
+        match last_seen {
+            Some(seen) if now_nanos.saturating_sub(seen) <= self.timeout_nanos => Liveness::Alive,
+            _ => Liveness::Stale,
+        }
+    }
+}
+
+/// Send a `Message::Heartbeat` from `sender` to `target` every `interval`
+/// on a background thread
+pub fn spawn_emitter(
+    sender: &str,
+    target: &str,
+    interval: Duration,
+) -> std::io::Result<JoinHandle<()>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(target)?;
+    let sender = sender.to_string();
+
+    Ok(std::thread::spawn(move || loop {
+        let heartbeat = Message::Heartbeat {
+            sender: sender.clone(),
+            timestamp: SystemClock.now_nanos(),
+        };
+        if let Ok(payload) = heartbeat.serialize() {
+            // Nobody listening is expected when telemetry isn't running
+            let _ = socket.send(&payload);
+        }
+        std::thread::sleep(interval);
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_sender_goes_stale_after_timeout() {
+        let mut monitor = HeartbeatMonitor::new(Duration::from_secs(3));
+        monitor.watch("strategy_engine");
+        assert_eq!(monitor.status("strategy_engine", 0), Some(Liveness::Stale));
+
+        let heartbeat = Message::Heartbeat {
+            sender: "feed_handler".to_string(),
+            timestamp: 0,
+        };
+        assert!(monitor.on_message(&heartbeat, 10 * SEC));
+        assert_eq!(monitor.status("feed_handler", 12 * SEC), Some(Liveness::Alive));
+        assert_eq!(monitor.status("feed_handler", 13 * SEC), Some(Liveness::Alive));
+        assert_eq!(monitor.status("feed_handler", 14 * SEC), Some(Liveness::Stale));
+
+        // A new heartbeat revives it
+        monitor.record("feed_handler", 15 * SEC);
+        assert_eq!(
+            monitor.statuses(16 * SEC),
+            vec![
+                ("feed_handler".to_string(), Liveness::Alive),
+                ("strategy_engine".to_string(), Liveness::Stale),
+            ]
+        );
+        assert_eq!(monitor.status("order_gateway", 16 * SEC), None);
+    }
+}
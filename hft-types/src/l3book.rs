@@ -0,0 +1,254 @@
+use crate::{BookLevel, HftError, HftResult, OrderBook, OrderSide};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// One resting order in an order-by-order book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L3Order {
+    pub order_id: u64,
+    pub side: OrderSide,
+    pub price: f64,
+    /// Quantity still open
+    pub quantity: f64,
+}
+
+/// Order ids resting at one price, in arrival order
+#[derive(Debug)]
+struct L3Level {
+    price: f64,
+    queue: VecDeque<u64>,
+}
+
+/// Level 3 book: every resting order by id, queued FIFO within its price
+/// level. `to_l2` aggregates it into the usual `OrderBook`.
+#[derive(Debug)]
+pub struct L3OrderBook {
+    symbol: String,
+    orders: HashMap<u64, L3Order>,
+    /// Best price first
+    bids: Vec<L3Level>,
+    asks: Vec<L3Level>,
+}
+
+impl L3OrderBook {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            orders: HashMap::new(),
+            bids: Vec::new(),
+            asks: Vec::new(),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn order(&self, order_id: u64) -> Option<&L3Order> {
+        self.orders.get(&order_id)
+    }
+
+    /// Queue `order_id` at the back of its price level
+    pub fn add_order(
+        &mut self,
+        order_id: u64,
+        side: OrderSide,
+        price: f64,
+        quantity: f64,
+    ) -> HftResult<()> {
+        if !(price.is_finite() && price > 0.0) {
+            return Err(HftError::InvalidPrice(price));
+        }
+        if !(quantity.is_finite() && quantity > 0.0) {
+            return Err(HftError::InvalidQuantity(quantity));
+        }
+        if self.orders.contains_key(&order_id) {
+            return Err(HftError::DuplicateOrderId(order_id));
+        }
+
+        let levels = self.levels_mut(&side);
+        let pos = levels.partition_point(|level| is_better(&side, level.price, price));
+        match levels.get_mut(pos) {
+            Some(level) if level.price == price => level.queue.push_back(order_id),
+            _ => levels.insert(
+                pos,
+                L3Level {
+                    price,
+                    queue: VecDeque::from([order_id]),
+                },
+            ),
+        }
+        self.orders.insert(
+            order_id,
+            L3Order {
+                order_id,
+                side,
+                price,
+                quantity,
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove an order entirely, returning what was left of it
+    pub fn cancel_order(&mut self, order_id: u64) -> Option<L3Order> {
+        let order = self.orders.remove(&order_id)?;
+        let levels = self.levels_mut(&order.side);
+        if let Some(pos) = levels.iter().position(|level| level.price == order.price) {
+            levels[pos].queue.retain(|&id| id != order_id);
+            if levels[pos].queue.is_empty() {
+                levels.remove(pos);
+            }
+        }
+        Some(order)
+    }
+
+    /// Trade up to `quantity` against an order, removing it once fully
+    /// filled. Returns the quantity executed, or `None` for an unknown id.
+    pub fn execute(&mut self, order_id: u64, quantity: f64) -> Option<f64> {
+        let order = self.orders.get_mut(&order_id)?;
+        let executed = quantity.max(0.0).min(order.quantity);
+        order.quantity -= executed;
+        if order.quantity <= f64::EPSILON {
+            self.cancel_order(order_id);
+        }
+        Some(executed)
+    }
+
+    /// Orders and total quantity queued ahead of `order_id` at its price
+    pub fn queue_position(&self, order_id: u64) -> Option<(usize, f64)> {
+        let order = self.orders.get(&order_id)?;
+        let levels = match order.side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        };
+        let level = levels.iter().find(|level| level.price == order.price)?;
+        let ahead = level.queue.iter().position(|&id| id == order_id)?;
+        let quantity_ahead = level
+            .queue
+            .iter()
+            .take(ahead)
+            .filter_map(|id| self.orders.get(id))
+            .map(|o| o.quantity)
+            .sum();
+        Some((ahead, quantity_ahead))
+    }
+
+    /// Aggregate into an L2 book of total quantity per price
+    pub fn to_l2(&self, timestamp_nanos: u128) -> OrderBook {
+        let aggregate = |levels: &[L3Level]| {
+            levels
+                .iter()
+                .map(|level| BookLevel {
+                    price: level.price,
+                    quantity: level
+                        .queue
+                        .iter()
+                        .filter_map(|id| self.orders.get(id))
+                        .map(|o| o.quantity)
+                        .sum(),
+                })
+                .collect()
+        };
+        let mut book = OrderBook::new(self.symbol.clone(), timestamp_nanos);
+        book.bids = aggregate(&self.bids);
+        book.asks = aggregate(&self.asks);
+        book
+    }
+
+    fn levels_mut(&mut self, side: &OrderSide) -> &mut Vec<L3Level> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+}
+
+/// Whether `level` sorts strictly ahead of `price` on this side
+fn is_better(side: &OrderSide, level: f64, price: f64) -> bool {
+    match side {
+        OrderSide::Buy => level > price,
+        OrderSide::Sell => level < price,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[BookLevel]) -> Vec<(f64, f64)> {
+        levels.iter().map(|l| (l.price, l.quantity)).collect()
+    }
+
+    fn book() -> L3OrderBook {
+        let mut book = L3OrderBook::new("BTC/USD".to_string());
+        book.add_order(1, OrderSide::Buy, 99.0, 2.0).unwrap();
+        book.add_order(2, OrderSide::Buy, 100.0, 1.0).unwrap();
+        book.add_order(3, OrderSide::Buy, 99.0, 3.0).unwrap();
+        book.add_order(4, OrderSide::Sell, 102.0, 4.0).unwrap();
+        book.add_order(5, OrderSide::Sell, 101.0, 1.5).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_orders_at_multiple_prices() {
+        let mut book = book();
+        assert_eq!(book.len(), 5);
+        assert_eq!(book.queue_position(1), Some((0, 0.0)));
+        assert_eq!(book.queue_position(3), Some((1, 2.0)));
+        assert_eq!(book.queue_position(2), Some((0, 0.0)));
+
+        assert!(matches!(
+            book.add_order(3, OrderSide::Sell, 105.0, 1.0),
+            Err(HftError::DuplicateOrderId(3))
+        ));
+        assert!(book.add_order(6, OrderSide::Sell, 0.0, 1.0).is_err());
+        assert!(book.add_order(6, OrderSide::Sell, 105.0, 0.0).is_err());
+
+        // Cancelling the front of the queue moves the rest up
+        assert_eq!(book.cancel_order(1).unwrap().quantity, 2.0);
+        assert_eq!(book.queue_position(3), Some((0, 0.0)));
+        assert!(book.cancel_order(1).is_none());
+    }
+
+    #[test]
+    fn test_partial_execution_from_front_of_queue() {
+        let mut book = book();
+        assert_eq!(book.execute(1, 0.5), Some(0.5));
+        assert_eq!(book.order(1).unwrap().quantity, 1.5);
+        assert_eq!(book.queue_position(3), Some((1, 1.5)));
+
+        // Executing more than is open fills what's left and removes it
+        assert_eq!(book.execute(1, 5.0), Some(1.5));
+        assert!(book.order(1).is_none());
+        assert_eq!(book.queue_position(3), Some((0, 0.0)));
+        assert_eq!(book.execute(1, 1.0), None);
+    }
+
+    #[test]
+    fn test_aggregates_into_l2_levels() {
+        let mut book = book();
+        let l2 = book.to_l2(7);
+        assert_eq!(l2.symbol, "BTC/USD");
+        assert_eq!(l2.timestamp_nanos, 7);
+        assert_eq!(levels(&l2.bids), vec![(100.0, 1.0), (99.0, 5.0)]);
+        assert_eq!(levels(&l2.asks), vec![(101.0, 1.5), (102.0, 4.0)]);
+        assert_eq!(l2.spread(), Some(1.0));
+
+        // Emptied levels drop out of the view
+        book.execute(2, 1.0);
+        book.execute(3, 1.0);
+        book.cancel_order(5);
+        let l2 = book.to_l2(8);
+        assert_eq!(levels(&l2.bids), vec![(99.0, 4.0)]);
+        assert_eq!(levels(&l2.asks), vec![(102.0, 4.0)]);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::{EnrichedTick, HftError, HftResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Nanoseconds since the epoch for stage stamps. Wall time is read once per
+/// process and advanced by `Instant`, so stamps never go backwards when the
+/// system clock is stepped.
+pub fn monotonic_nanos() -> u128 {
+    static ANCHOR: OnceLock<(Instant, u128)> = OnceLock::new();
+    let (instant, wall_nanos) = ANCHOR.get_or_init(|| {
+        let wall_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        (Instant::now(), wall_nanos)
+    });
+    wall_nanos + instant.elapsed().as_nanos()
+}
+
+/// Env var overriding the latency histogram buckets, as comma-separated
+/// upper bounds in microseconds
+pub const LATENCY_BUCKETS_ENV: &str = "HFT_LATENCY_BUCKETS";
+
+/// Default latency histogram upper bounds in microseconds
+pub const DEFAULT_LATENCY_BUCKETS: [f64; 12] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// Parse a comma-separated bucket list. Bounds must be finite and
+/// strictly increasing.
+pub fn parse_latency_buckets(spec: &str) -> HftResult<Vec<f64>> {
+    let buckets = spec
+        .split(',')
+        .map(|bound| {
+            bound.trim().parse::<f64>().map_err(|e| {
+                HftError::InvalidConfig(format!("latency bucket {:?}: {}", bound.trim(), e))
+            })
+        })
+        .collect::<HftResult<Vec<f64>>>()?;
+
+    if buckets.iter().any(|bound| !bound.is_finite()) {
+        return Err(HftError::InvalidConfig(format!(
+            "latency buckets must be finite: {}",
+            spec
+        )));
+    }
+    if buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(HftError::InvalidConfig(format!(
+            "latency buckets must be strictly increasing: {}",
+            spec
+        )));
+    }
+    Ok(buckets)
+}
+
+/// Latency histogram buckets shared by every service: `HFT_LATENCY_BUCKETS`
+/// when set and valid, otherwise the defaults
+pub fn latency_buckets() -> Vec<f64> {
+    match std::env::var(LATENCY_BUCKETS_ENV) {
+        Ok(spec) => parse_latency_buckets(&spec).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring {}: {}; using default buckets", LATENCY_BUCKETS_ENV, e);
+            DEFAULT_LATENCY_BUCKETS.to_vec()
+        }),
+        Err(_) => DEFAULT_LATENCY_BUCKETS.to_vec(),
+    }
+}
+
+/// Pipeline stage measured between two consecutive trace stamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Stage {
+    /// Exchange/simulator timestamp to feed receive
+    Network,
+    /// Feed receive to decoded tick
+    Decode,
+    /// Decoded tick to strategy signal
+    Strategy,
+    /// Strategy signal to order handed to the gateway
+    Order,
+    /// Order handed to the gateway to order placed
+    Place,
+}
+
+impl Stage {
+    /// The service the stage's time is spent in
+    pub fn component(self) -> Component {
+        match self {
+            Stage::Network | Stage::Decode => Component::FeedIngest,
+            Stage::Strategy | Stage::Order => Component::StrategyDecision,
+            Stage::Place => Component::GatewayPlace,
+        }
+    }
+}
+
+/// Stages grouped by the service that owns them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Component {
+    /// Exchange timestamp to decoded tick in the feed handler
+    FeedIngest,
+    /// Decoded tick to order sent by the strategy engine
+    StrategyDecision,
+    /// Order received to placed by the gateway
+    GatewayPlace,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::FeedIngest => write!(f, "feed_ingest"),
+            Component::StrategyDecision => write!(f, "strategy_decision"),
+            Component::GatewayPlace => write!(f, "gateway_place"),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Network => write!(f, "network"),
+            Stage::Decode => write!(f, "decode"),
+            Stage::Strategy => write!(f, "strategy"),
+            Stage::Order => write!(f, "order"),
+            Stage::Place => write!(f, "place"),
+        }
+    }
+}
+
+/// Timestamps stamped by each stage as a tick flows through the pipeline.
+/// Missing stamps are skipped; the next present stamp absorbs their time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyTrace {
+    pub tick_nanos: u128,
+    pub recv_nanos: Option<u128>,
+    pub decoded_nanos: Option<u128>,
+    pub signal_nanos: Option<u128>,
+    pub order_nanos: Option<u128>,
+    pub placed_nanos: Option<u128>,
+}
+
+impl LatencyTrace {
+    pub fn new(tick_nanos: u128) -> Self {
+        Self {
+            tick_nanos,
+            ..Self::default()
+        }
+    }
+
+    /// Trace carrying the feed handler's stamps from `enriched`
+    pub fn from_enriched(enriched: &EnrichedTick) -> Self {
+        Self {
+            recv_nanos: Some(enriched.receive_time_nanos),
+            decoded_nanos: enriched.decoded_nanos,
+            ..Self::new(enriched.tick.timestamp_nanos)
+        }
+    }
+
+    /// Time since the originating tick's timestamp, clamped at zero for
+    /// clock skew
+    pub fn age_nanos(&self, now_nanos: u128) -> u128 {
+        now_nanos.saturating_sub(self.tick_nanos)
+    }
+
+    /// Per-stage deltas in nanoseconds, clamped at zero for clock skew
+    pub fn stage_deltas(&self) -> Vec<(Stage, u128)> {
+        let stamps = [
+            (Stage::Network, self.recv_nanos),
+            (Stage::Decode, self.decoded_nanos),
+            (Stage::Strategy, self.signal_nanos),
+            (Stage::Order, self.order_nanos),
+            (Stage::Place, self.placed_nanos),
+        ];
+
+        let mut previous = self.tick_nanos;
+        let mut deltas = Vec::new();
+        for (stage, stamp) in stamps {
+            if let Some(stamp) = stamp {
+                deltas.push((stage, stamp.saturating_sub(previous)));
+                previous = stamp;
+            }
+        }
+        deltas
+    }
+
+    /// Deltas summed per component, in pipeline order. Components with no
+    /// stamps of their own are left out.
+    pub fn component_deltas(&self) -> Vec<(Component, u128)> {
+        let mut totals: BTreeMap<Component, u128> = BTreeMap::new();
+        for (stage, delta) in self.stage_deltas() {
+            *totals.entry(stage.component()).or_default() += delta;
+        }
+        totals.into_iter().collect()
+    }
+}
+
+/// Summary of one stage over a reporting window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageSummary {
+    pub stage: Stage,
+    pub count: u64,
+    pub p50_micros: f64,
+    pub p99_micros: f64,
+}
+
+/// Accumulates trace deltas per stage and reports percentiles.
+/// Each stage keeps at most `max_samples` (oldest overwritten).
+#[derive(Debug)]
+pub struct LatencyCollector {
+    max_samples: usize,
+    samples: BTreeMap<Stage, (u64, Vec<u128>)>,
+}
+
+impl LatencyCollector {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            max_samples: max_samples.max(1),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, trace: &LatencyTrace) {
+        for (stage, delta) in trace.stage_deltas() {
+            let (count, values) = self.samples.entry(stage).or_default();
+            if values.len() < self.max_samples {
+                values.push(delta);
+            } else {
+                values[(*count % self.max_samples as u64) as usize] = delta;
+            }
+            *count += 1;
+        }
+    }
+
+    /// Per-stage count, p50 and p99 (nearest rank) in pipeline order
+    pub fn report(&self) -> Vec<StageSummary> {
+        self.samples
+            .iter()
+            .map(|(&stage, (count, values))| {
+                let mut sorted = values.clone();
+                sorted.sort_unstable();
+                StageSummary {
+                    stage,
+                    count: *count,
+                    p50_micros: percentile(&sorted, 0.50) as f64 / 1000.0,
+                    p99_micros: percentile(&sorted, 0.99) as f64 / 1000.0,
+                }
+            })
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+fn percentile(sorted: &[u128], q: f64) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_latency_buckets() {
+        assert_eq!(
+            parse_latency_buckets("0.5, 2,10,100").unwrap(),
+            vec![0.5, 2.0, 10.0, 100.0]
+        );
+        assert!(parse_latency_buckets("1,5,5,10").is_err());
+        assert!(parse_latency_buckets("10,5").is_err());
+        assert!(parse_latency_buckets("1,fast").is_err());
+        assert!(parse_latency_buckets("1,inf").is_err());
+        assert!(parse_latency_buckets("").is_err());
+    }
+
+    #[test]
+    fn test_stage_percentiles() {
+        let mut collector = LatencyCollector::new(1000);
+
+        // Network takes i µs, strategy a constant 5µs, no decode stamp
+        for i in 1..=100u128 {
+            let tick = 1_000_000;
+            let recv = tick + i * 1000;
+            collector.record(&LatencyTrace {
+                tick_nanos: tick,
+                recv_nanos: Some(recv),
+                decoded_nanos: None,
+                signal_nanos: Some(recv + 5000),
+                order_nanos: None,
+                placed_nanos: None,
+            });
+        }
+
+        let report = collector.report();
+        assert_eq!(report.len(), 2);
+
+        assert_eq!(report[0].stage, Stage::Network);
+        assert_eq!(report[0].count, 100);
+        assert_eq!(report[0].p50_micros, 50.0);
+        assert_eq!(report[0].p99_micros, 99.0);
+
+        assert_eq!(report[1].stage, Stage::Strategy);
+        assert_eq!(report[1].p50_micros, 5.0);
+        assert_eq!(report[1].p99_micros, 5.0);
+    }
+
+    #[test]
+    fn test_component_deltas_from_enriched_tick() {
+        let enriched = EnrichedTick {
+            tick: crate::MarketTick::new("BTC/USD".to_string(), 45000.0, 1, 1_000),
+            receive_time_nanos: 4_000,
+            decoded_nanos: Some(4_500),
+            latency_micros: 0.003,
+        };
+        let mut trace = LatencyTrace::from_enriched(&enriched);
+        trace.signal_nanos = Some(6_500);
+        trace.order_nanos = Some(7_000);
+        trace.placed_nanos = Some(9_000);
+
+        assert_eq!(
+            trace.stage_deltas(),
+            vec![
+                (Stage::Network, 3_000),
+                (Stage::Decode, 500),
+                (Stage::Strategy, 2_000),
+                (Stage::Order, 500),
+                (Stage::Place, 2_000),
+            ]
+        );
+        assert_eq!(
+            trace.component_deltas(),
+            vec![
+                (Component::FeedIngest, 3_500),
+                (Component::StrategyDecision, 2_500),
+                (Component::GatewayPlace, 2_000),
+            ]
+        );
+
+        // Without a decode stamp the strategy absorbs the time; without any
+        // strategy stamps the gateway does
+        trace.decoded_nanos = None;
+        trace.signal_nanos = None;
+        trace.order_nanos = None;
+        assert_eq!(
+            trace.component_deltas(),
+            vec![(Component::FeedIngest, 3_000), (Component::GatewayPlace, 5_000)]
+        );
+    }
+
+    #[test]
+    fn test_skewed_stamps_clamp_to_zero() {
+        let mut trace = LatencyTrace::new(1_000);
+        trace.recv_nanos = Some(500);
+        assert_eq!(trace.stage_deltas(), vec![(Stage::Network, 0)]);
+    }
+}
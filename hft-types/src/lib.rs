@@ -1,7 +1,45 @@
+pub mod affinity;
+pub mod arbitrage;
+pub mod audit;
+pub mod backpressure;
+pub mod backtest;
+pub mod cli;
+pub mod clock;
+#[cfg(feature = "arrow")]
+pub mod columnar;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod config;
+pub mod execution;
+pub mod fees;
+pub mod health;
+pub mod heartbeat;
+pub mod l3book;
+pub mod latency;
+pub mod lifecycle;
+pub mod logging;
+pub mod matching;
 pub mod messaging;
+pub mod multicast;
 pub mod orderbook;
+pub mod pacing;
+pub mod performance;
+pub mod portfolio;
+pub mod precision;
+pub mod pushgateway;
+pub mod reconstruct;
 pub mod replay;
+pub mod risk;
+pub mod rotation;
+pub mod shutdown;
+pub mod sizing;
+pub mod snapshot;
 pub mod strategies;
+pub mod sweep;
+pub mod symbol;
+pub mod transport;
+pub mod vwap;
+pub mod wire;
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -13,6 +51,25 @@ pub struct MarketTick {
     pub price: f64,
     pub volume: u64,
     pub timestamp_nanos: u128,
+    /// Per-symbol publisher sequence number, if the source stamps one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    /// Prevailing quote when the trade printed, if the source carries one.
+    /// Only the JSON encoding keeps it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<TopOfBook>,
+    /// Venue the tick came from when one symbol is fed from several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+}
+
+/// Best bid and offer with their sizes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TopOfBook {
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
 }
 
 impl MarketTick {
@@ -22,18 +79,136 @@ impl MarketTick {
             price,
             volume,
             timestamp_nanos,
+            sequence: None,
+            quote: None,
+            venue: None,
+        }
+    }
+
+    /// Like `new`, but rejects ticks that would poison books and strategies
+    pub fn try_new(
+        symbol: String,
+        price: f64,
+        volume: u64,
+        timestamp_nanos: u128,
+    ) -> HftResult<Self> {
+        let tick = Self::new(symbol, price, volume, timestamp_nanos);
+        tick.validate()?;
+        Ok(tick)
+    }
+
+    /// Check the price is finite and positive and the timestamp is set
+    pub fn validate(&self) -> HftResult<()> {
+        if !(self.price.is_finite() && self.price > 0.0) {
+            return Err(HftError::InvalidPrice(self.price));
+        }
+        if self.timestamp_nanos == 0 {
+            return Err(HftError::InvalidTimestamp(self.timestamp_nanos));
+        }
+        Ok(())
+    }
+
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    pub fn with_quote(mut self, quote: TopOfBook) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    pub fn with_venue(mut self, venue: &str) -> Self {
+        self.venue = Some(venue.to_string());
+        self
+    }
+
+    /// The venue's stream of this symbol the tick belongs to
+    pub fn stream(&self) -> StreamKey {
+        StreamKey {
+            symbol: self.symbol.clone(),
+            venue: self.venue.clone(),
         }
     }
 }
 
+/// One venue's stream of a symbol; sequence numbers count per stream. It
+/// reads and serializes as `SYMBOL@VENUE`, or just `SYMBOL` without a
+/// venue, so maps keyed by it stay plain JSON objects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamKey {
+    pub symbol: String,
+    pub venue: Option<String>,
+}
+
+impl StreamKey {
+    pub fn new(symbol: &str, venue: Option<&str>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            venue: venue.map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for StreamKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.venue {
+            Some(venue) => write!(f, "{}@{}", self.symbol, venue),
+            None => write!(f, "{}", self.symbol),
+        }
+    }
+}
+
+impl std::str::FromStr for StreamKey {
+    type Err = HftError;
+
+    fn from_str(s: &str) -> HftResult<Self> {
+        let (symbol, venue) = match s.split_once('@') {
+            Some((symbol, venue)) => (symbol, Some(venue)),
+            None => (s, None),
+        };
+        if symbol.is_empty() || venue.is_some_and(str::is_empty) {
+            return Err(HftError::InvalidConfig(format!("bad stream {:?}, expected SYMBOL[@VENUE]", s)));
+        }
+        Ok(Self::new(symbol, venue))
+    }
+}
+
+impl Serialize for StreamKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Enriched tick with latency information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnrichedTick {
     pub tick: MarketTick,
+    /// Feed handler receive stamp
     pub receive_time_nanos: u128,
+    /// Feed handler stamp once the datagram was decoded and validated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded_nanos: Option<u128>,
     pub latency_micros: f64,
 }
 
+/// Latency between a send and receive timestamp in microseconds.
+/// Returns `None` when the receive clock is behind the sender's (clock skew).
+pub fn latency_micros(sent_nanos: u128, received_nanos: u128) -> Option<f64> {
+    let delta = received_nanos as i128 - sent_nanos as i128;
+    if delta < 0 {
+        None
+    } else {
+        Some(delta as f64 / 1000.0)
+    }
+}
+
 /// Trading order side
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum OrderSide {
@@ -50,6 +225,32 @@ impl fmt::Display for OrderSide {
     }
 }
 
+/// How an order's price is interpreted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OrderType {
+    /// Trade at `price` or better
+    #[default]
+    Limit,
+    /// Trade at whatever the book offers; `price` is ignored
+    Market,
+    /// Becomes a market order once the market trades at or through `trigger`
+    Stop { trigger: f64 },
+}
+
+/// How long an order stays working
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good till cancelled
+    #[default]
+    Gtc,
+    /// Immediate or cancel: fill what crosses now, cancel the rest
+    Ioc,
+    /// Fill or kill: fill completely now or not at all
+    Fok,
+    /// Rest until the end of the trading day
+    Day,
+}
+
 /// Trading order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -59,6 +260,21 @@ pub struct Order {
     pub price: f64,
     pub quantity: f64,
     pub timestamp_nanos: u128,
+    #[serde(default)]
+    pub order_type: OrderType,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    /// Account the order trades for. Two orders with the same client id
+    /// never trade with each other; see `matching::SelfTradePrevention`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Longest the gateway may spend placing the order before giving up
+    /// and rejecting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_processing_micros: Option<u64>,
+    /// Pipeline stage stamps for the tick that led to this order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_trace: Option<latency::LatencyTrace>,
 }
 
 impl Order {
@@ -77,19 +293,65 @@ impl Order {
             price,
             quantity,
             timestamp_nanos,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            client_id: None,
+            max_processing_micros: None,
+            latency_trace: None,
+        }
+    }
+
+    pub fn with_latency_trace(mut self, trace: latency::LatencyTrace) -> Self {
+        self.latency_trace = Some(trace);
+        self
+    }
+
+    pub fn with_order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn with_client_id(mut self, client_id: &str) -> Self {
+        self.client_id = Some(client_id.to_string());
+        self
+    }
+
+    pub fn with_max_processing(mut self, max: std::time::Duration) -> Self {
+        self.max_processing_micros = Some(max.as_micros() as u64);
+        self
+    }
+
+    /// Check the fields the order type and time in force depend on
+    pub fn validate(&self) -> HftResult<()> {
+        if !(self.quantity.is_finite() && self.quantity > 0.0) {
+            return Err(HftError::InvalidQuantity(self.quantity));
+        }
+        match self.order_type {
+            OrderType::Limit if !(self.price.is_finite() && self.price > 0.0) => {
+                Err(HftError::InvalidPrice(self.price))
+            }
+            OrderType::Stop { trigger } if !(trigger.is_finite() && trigger > 0.0) => {
+                Err(HftError::InvalidPrice(trigger))
+            }
+            _ => Ok(()),
         }
     }
 }
 
 /// Order book level
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BookLevel {
     pub price: f64,
     pub quantity: f64,
 }
 
 /// Level 2 Order Book
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderBook {
     pub symbol: String,
     pub bids: Vec<BookLevel>,
@@ -128,6 +390,137 @@ impl OrderBook {
             _ => None,
         }
     }
+
+    /// Spread, rejecting a crossed book (ask below bid) instead of going
+    /// negative. A locked book's zero spread is fine.
+    pub fn spread_checked(&self) -> HftResult<f64> {
+        let (bid, ask) = match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => (bid.price, ask.price),
+            _ => return Err(HftError::OrderBookEmpty(self.symbol.clone())),
+        };
+        if ask < bid {
+            return Err(HftError::CrossedBook {
+                symbol: self.symbol.clone(),
+                bid,
+                ask,
+            });
+        }
+        Ok(ask - bid)
+    }
+
+    /// Spread as basis points of the mid
+    pub fn spread_bps(&self) -> Option<f64> {
+        let mid = self.mid_price().filter(|mid| *mid != 0.0)?;
+        Some(self.spread()? / mid * 10_000.0)
+    }
+
+    /// Mid weighted by the opposite side's top-of-book size, so it leans
+    /// towards the side more likely to trade through. `None` when either
+    /// side is empty or both top sizes are zero.
+    pub fn weighted_mid(&self) -> Option<f64> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        let total_qty = bid.quantity + ask.quantity;
+        if total_qty <= 0.0 {
+            return None;
+        }
+        Some((bid.price * ask.quantity + ask.price * bid.quantity) / total_qty)
+    }
+
+    /// Pressure-adjusted fair value: the mid shifted towards the side with
+    /// less resting size, using the imbalance over the top `depth` levels.
+    pub fn fair_value(&self, depth: usize) -> Option<f64> {
+        let (ask, bid) = (self.best_ask()?, self.best_bid()?);
+        let depth = depth.max(1);
+        let bid_qty: f64 = self.bids.iter().take(depth).map(|l| l.quantity).sum();
+        let ask_qty: f64 = self.asks.iter().take(depth).map(|l| l.quantity).sum();
+
+        let mid = (ask.price + bid.price) / 2.0;
+        if bid_qty + ask_qty <= 0.0 {
+            return Some(mid);
+        }
+
+        // Imbalance in [-1, 1]; +1 means all pressure on the bid
+        let imbalance = (bid_qty - ask_qty) / (bid_qty + ask_qty);
+        Some(mid + imbalance * (ask.price - bid.price) / 2.0)
+    }
+
+    /// CRC32 over the top `levels` of each side, formatted with the default
+    /// `ChecksumFormat`
+    pub fn checksum(&self, levels: usize) -> u32 {
+        self.checksum_with(levels, &ChecksumFormat::default())
+    }
+
+    /// CRC32 over `bid:qty:ask:qty:...` for the top `levels`, interleaving
+    /// bid and ask at each depth and skipping a side once it runs out
+    pub fn checksum_with(&self, levels: usize, format: &ChecksumFormat) -> u32 {
+        let mut fields = Vec::with_capacity(levels * 4);
+        for i in 0..levels {
+            for level in [self.bids.get(i), self.asks.get(i)].into_iter().flatten() {
+                fields.push(format!("{:.*}", format.price_decimals, level.price));
+                fields.push(format!("{:.*}", format.quantity_decimals, level.quantity));
+            }
+        }
+        crc32fast::hash(fields.join(":").as_bytes())
+    }
+
+    /// Insert or update a bid level, keeping bids sorted highest first
+    pub fn upsert_bid(&mut self, price: f64, quantity: f64) {
+        self.upsert_level(OrderSide::Buy, price, quantity);
+    }
+
+    /// Insert or update an ask level, keeping asks sorted lowest first
+    pub fn upsert_ask(&mut self, price: f64, quantity: f64) {
+        self.upsert_level(OrderSide::Sell, price, quantity);
+    }
+
+    /// Remove the level at `price`, returning it if it existed
+    pub fn remove_level(&mut self, side: OrderSide, price: f64) -> Option<BookLevel> {
+        let idx = self.level_index(&side, price).ok()?;
+        Some(self.levels_mut(&side).remove(idx))
+    }
+
+    fn upsert_level(&mut self, side: OrderSide, price: f64, quantity: f64) {
+        let idx = self.level_index(&side, price);
+        let levels = self.levels_mut(&side);
+        match idx {
+            Ok(i) => levels[i].quantity = quantity,
+            Err(i) => levels.insert(i, BookLevel { price, quantity }),
+        }
+    }
+
+    /// Binary search for `price`: `Ok` if the level exists, otherwise
+    /// `Err` with the position that keeps the side sorted
+    fn level_index(&self, side: &OrderSide, price: f64) -> Result<usize, usize> {
+        match side {
+            // Bids are descending, so compare the other way round
+            OrderSide::Buy => self.bids.binary_search_by(|l| price.total_cmp(&l.price)),
+            OrderSide::Sell => self.asks.binary_search_by(|l| l.price.total_cmp(&price)),
+        }
+    }
+
+    fn levels_mut(&mut self, side: &OrderSide) -> &mut Vec<BookLevel> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+}
+
+/// Decimal places used when formatting levels for `OrderBook::checksum`;
+/// set these to match the venue's published precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumFormat {
+    pub price_decimals: usize,
+    pub quantity_decimals: usize,
+}
+
+impl Default for ChecksumFormat {
+    fn default() -> Self {
+        Self {
+            price_decimals: 8,
+            quantity_decimals: 8,
+        }
+    }
 }
 
 /// Trading signal from strategy
@@ -139,6 +532,13 @@ pub struct TradingSignal {
     pub quantity: f64,
     pub signal_type: SignalType,
     pub timestamp_nanos: u128,
+    /// Conviction from 0 to 1; `PositionSizer` scales `quantity` by it
+    #[serde(default = "full_strength")]
+    pub strength: f64,
+}
+
+fn full_strength() -> f64 {
+    1.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,16 +547,113 @@ pub enum SignalType {
     MarketMaking,
     Arbitrage,
     MeanReversion,
+    /// Child slice of a time-sliced parent order
+    Twap,
+    /// Trade priced away from the rolling VWAP
+    Vwap,
 }
 
-/// Configuration for market symbols
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Which way to snap a value that falls between two increments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    Nearest,
+    Down,
+    Up,
+}
+
+/// Snap `value` to a multiple of `increment`. A zero, negative or
+/// non-finite increment leaves the value unchanged.
+pub fn round_to_increment(value: f64, increment: f64, mode: RoundingMode) -> f64 {
+    if !(increment.is_finite() && increment > 0.0) {
+        return value;
+    }
+    let steps = value / increment;
+    let nearest = steps.round();
+    // A value already on a step must not be pushed a whole step by float noise
+    // (100.07 / 0.01 is 10006.999999999998)
+    let steps = if (steps - nearest).abs() < 1e-9 {
+        nearest
+    } else {
+        match mode {
+            RoundingMode::Nearest => nearest,
+            RoundingMode::Down => steps.floor(),
+            RoundingMode::Up => steps.ceil(),
+        }
+    };
+    steps * increment
+}
+
+/// One tradable symbol in the shared universe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SymbolConfig {
     pub symbol: String,
-    pub tick_size: f64,
-    pub lot_size: f64,
-    pub min_price: f64,
-    pub max_price: f64,
+    /// Price the simulator walks around
+    pub base_price: f64,
+    /// Band for the threshold strategy; symbols without one are not traded by it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thresholds: Option<config::ThresholdBand>,
+    /// Minimum price increment; prices are left as is without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<f64>,
+    /// Minimum quantity increment; quantities are left as is without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lot_size: Option<f64>,
+    /// Smallest price × quantity an order may have, for venues that trade
+    /// fractions but not dust
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_notional: Option<f64>,
+    /// How often the simulator ticks this symbol relative to the others;
+    /// 1.0 without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity_weight: Option<f64>,
+}
+
+impl SymbolConfig {
+    /// Snap a price to `tick_size`, if the symbol has one
+    pub fn round_price(&self, price: f64, mode: RoundingMode) -> f64 {
+        self.tick_size
+            .map_or(price, |tick_size| round_to_increment(price, tick_size, mode))
+    }
+
+    /// Snap a quantity to `lot_size`, if the symbol has one
+    pub fn round_quantity(&self, quantity: f64, mode: RoundingMode) -> f64 {
+        self.lot_size
+            .map_or(quantity, |lot_size| round_to_increment(quantity, lot_size, mode))
+    }
+
+    /// Check an order against the symbol's quantity rules: a whole number of
+    /// lots if it has a `lot_size`, and at least `min_notional` if it has
+    /// one. Market orders carry no price, so only their lots are checked.
+    pub fn validate_order(&self, order: &Order) -> HftResult<()> {
+        if let Some(lot_size) = self.lot_size {
+            let lots = round_to_increment(order.quantity, lot_size, RoundingMode::Nearest);
+            if (lots - order.quantity).abs() > 1e-9 * order.quantity.abs().max(1.0) {
+                return Err(HftError::OddLot {
+                    symbol: self.symbol.clone(),
+                    quantity: order.quantity,
+                    lot_size,
+                });
+            }
+        }
+        let price = match order.order_type {
+            OrderType::Limit => Some(order.price),
+            OrderType::Stop { trigger } => Some(trigger),
+            OrderType::Market => None,
+        };
+        if let (Some(min_notional), Some(price)) = (self.min_notional, price) {
+            let notional = price * order.quantity;
+            if notional < min_notional {
+                return Err(HftError::BelowMinNotional {
+                    symbol: self.symbol.clone(),
+                    notional,
+                    min_notional,
+                });
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Error types
@@ -168,17 +665,119 @@ pub enum HftError {
     #[error("Invalid quantity: {0}")]
     InvalidQuantity(f64),
 
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(u128),
+
     #[error("Symbol not found: {0}")]
     SymbolNotFound(String),
 
     #[error("Order book empty for symbol: {0}")]
     OrderBookEmpty(String),
 
+    /// Network failure with no more specific variant, e.g. joining a
+    /// multicast group
     #[error("Network error: {0}")]
     NetworkError(String),
 
+    /// Malformed data with no more specific variant, e.g. an unknown
+    /// datagram header
     #[error("Serialization error: {0}")]
     SerializationError(String),
+
+    #[error("Connection refused: {0}")]
+    ConnectionRefused(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Frame of {len} bytes exceeds the {max} byte limit")]
+    FrameTooLarge { len: usize, max: usize },
+
+    #[error("Frame cut short: expected {expected} bytes")]
+    PartialFrame { expected: usize },
+
+    #[error("Checksum mismatch for {subject}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        subject: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("I/O error: {0}")]
+    Io(#[source] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Order rate limit exceeded for {0}")]
+    RateLimited(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Crossed book for {symbol}: bid {bid} above ask {ask}")]
+    CrossedBook { symbol: String, bid: f64, ask: f64 },
+
+    #[error("Circuit breaker open: {0}")]
+    CircuitOpen(String),
+
+    #[error("No open order with id {0}")]
+    OrderNotFound(u64),
+
+    #[error("Duplicate order id {0}")]
+    DuplicateOrderId(u64),
+
+    #[error("Quantity {quantity} for {symbol} is not a multiple of the lot size {lot_size}")]
+    OddLot {
+        symbol: String,
+        quantity: f64,
+        lot_size: f64,
+    },
+
+    #[error("Notional {notional} for {symbol} is below the minimum of {min_notional}")]
+    BelowMinNotional {
+        symbol: String,
+        notional: f64,
+        min_notional: f64,
+    },
+
+    #[error("Signal is {age_micros}µs old, past the {max_micros}µs limit")]
+    StaleSignal { age_micros: u64, max_micros: u64 },
+
+    #[error("Book delta gap for {symbol}: expected sequence {expected}, got {got}")]
+    SequenceGap { symbol: String, expected: u64, got: u64 },
+
+    #[error("Order {order_id} cannot go from {from} to {to}")]
+    IllegalTransition {
+        order_id: u64,
+        from: lifecycle::OrderStatus,
+        to: lifecycle::OrderStatus,
+    },
+}
+
+impl HftError {
+    /// Whether the same operation might succeed if tried again: the peer
+    /// wasn't listening or was slow, or the stream dropped mid-frame
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            HftError::ConnectionRefused(_) | HftError::Timeout(_) | HftError::PartialFrame { .. }
+        )
+    }
+}
+
+/// Refused connections and timeouts get their own variants so retry logic
+/// can tell them apart from other I/O failures
+impl From<std::io::Error> for HftError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::ConnectionRefused => HftError::ConnectionRefused(e.to_string()),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                HftError::Timeout(e.to_string())
+            }
+            _ => HftError::Io(e),
+        }
+    }
 }
 
 pub type HftResult<T> = Result<T, HftError>;
@@ -0,0 +1,186 @@
+use crate::{HftError, HftResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Where an order is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Accepted, nothing filled yet
+    New,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 5] = [
+        OrderStatus::New,
+        OrderStatus::PartiallyFilled,
+        OrderStatus::Filled,
+        OrderStatus::Cancelled,
+        OrderStatus::Rejected,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::New => "new",
+            OrderStatus::PartiallyFilled => "partially_filled",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Rejected => "rejected",
+        }
+    }
+
+    /// No further transitions are possible
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Execution state of one order. Every change goes through a method that
+/// checks the transition is legal, so e.g. a cancelled order can't fill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderState {
+    pub order_id: u64,
+    pub status: OrderStatus,
+    /// Total quantity ordered, including amendments
+    pub quantity: f64,
+    pub filled_qty: f64,
+    /// Zero until the first fill
+    pub avg_fill_price: f64,
+}
+
+impl OrderState {
+    pub fn new(order_id: u64, quantity: f64) -> Self {
+        Self {
+            order_id,
+            status: OrderStatus::New,
+            quantity,
+            filled_qty: 0.0,
+            avg_fill_price: 0.0,
+        }
+    }
+
+    pub fn remaining(&self) -> f64 {
+        (self.quantity - self.filled_qty).max(0.0)
+    }
+
+    /// Record an execution of `qty` at `price`
+    pub fn fill(&mut self, qty: f64, price: f64) -> HftResult<OrderStatus> {
+        if !(qty.is_finite() && qty > 0.0) || qty > self.remaining() + f64::EPSILON {
+            return Err(HftError::InvalidQuantity(qty));
+        }
+        let filled = self.filled_qty + qty;
+        let to = if filled >= self.quantity - f64::EPSILON {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        self.transition(to)?;
+        self.avg_fill_price = (self.avg_fill_price * self.filled_qty + price * qty) / filled;
+        self.filled_qty = filled;
+        Ok(to)
+    }
+
+    /// Pull whatever is still open
+    pub fn cancel(&mut self) -> HftResult<()> {
+        self.transition(OrderStatus::Cancelled)
+    }
+
+    /// Refuse the order before anything executed
+    pub fn reject(&mut self) -> HftResult<()> {
+        self.transition(OrderStatus::Rejected)
+    }
+
+    /// Change the open quantity of a live order; fills so far still count
+    pub fn amend(&mut self, new_remaining: f64) -> HftResult<()> {
+        if self.status.is_terminal() {
+            return Err(self.illegal(self.status));
+        }
+        self.quantity = self.filled_qty + new_remaining;
+        Ok(())
+    }
+
+    fn transition(&mut self, to: OrderStatus) -> HftResult<()> {
+        use OrderStatus::*;
+        let legal = matches!(
+            (self.status, to),
+            (New, PartiallyFilled | Filled | Cancelled | Rejected)
+                | (PartiallyFilled, PartiallyFilled | Filled | Cancelled)
+        );
+        if !legal {
+            return Err(self.illegal(to));
+        }
+        self.status = to;
+        Ok(())
+    }
+
+    fn illegal(&self, to: OrderStatus) -> HftError {
+        HftError::IllegalTransition {
+            order_id: self.order_id,
+            from: self.status,
+            to,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_lifecycle() {
+        let mut state = OrderState::new(7, 10.0);
+        assert_eq!(state.status, OrderStatus::New);
+
+        assert_eq!(state.fill(4.0, 100.0).unwrap(), OrderStatus::PartiallyFilled);
+        assert_eq!(state.remaining(), 6.0);
+        assert_eq!(state.fill(6.0, 105.0).unwrap(), OrderStatus::Filled);
+        assert_eq!(state.avg_fill_price, 103.0);
+        assert_eq!(state.remaining(), 0.0);
+
+        assert!(matches!(
+            state.cancel(),
+            Err(HftError::IllegalTransition {
+                order_id: 7,
+                from: OrderStatus::Filled,
+                to: OrderStatus::Cancelled,
+            })
+        ));
+        assert!(state.fill(1.0, 100.0).is_err());
+        assert!(state.amend(5.0).is_err());
+        assert_eq!(state.status, OrderStatus::Filled);
+    }
+
+    #[test]
+    fn test_illegal_transitions_leave_state_alone() {
+        let mut state = OrderState::new(1, 5.0);
+        assert!(state.fill(6.0, 100.0).is_err());
+        state.fill(2.0, 100.0).unwrap();
+        // Nothing left to reject once something has traded
+        assert!(state.reject().is_err());
+
+        // An amendment resizes what's still open
+        state.amend(1.0).unwrap();
+        assert_eq!(state.quantity, 3.0);
+        state.cancel().unwrap();
+        assert!(state.fill(1.0, 100.0).is_err());
+        assert_eq!(state.filled_qty, 2.0);
+
+        let mut rejected = OrderState::new(2, 1.0);
+        rejected.reject().unwrap();
+        assert!(rejected.cancel().is_err());
+        assert_eq!(rejected.status.to_string(), "rejected");
+    }
+}
@@ -0,0 +1,165 @@
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Log output style, from `--log-format` or `HFT_LOG_FORMAT`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines for interactive use
+    #[default]
+    Pretty,
+    /// One JSON object per line for log aggregators
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{}'; expected pretty or json", other)),
+        }
+    }
+}
+
+/// Install the global subscriber for `service`, logging at INFO and above
+/// to stdout
+pub fn init(service: &'static str, format: LogFormat) {
+    tracing::subscriber::set_global_default(subscriber(service, format, std::io::stdout))
+        .expect("logging initialised twice");
+}
+
+/// Build the subscriber `init` installs, writing to `writer`
+pub fn subscriber<W>(service: &'static str, format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.event_format(JsonFormat { service }).finish()),
+    }
+}
+
+/// Formats each event as `{"timestamp","level","service","target","message",...fields}`
+struct JsonFormat {
+    service: &'static str,
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(timestamp));
+        line.insert("level".to_string(), Value::String(metadata.level().to_string()));
+        line.insert("service".to_string(), Value::String(self.service.to_string()));
+        line.insert("target".to_string(), Value::String(metadata.target().to_string()));
+        event.record(&mut JsonVisitor(&mut line));
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+/// Copies an event's fields into the JSON line
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), Value::String(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects everything the subscriber writes
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_lines_carry_service() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = subscriber("feed_handler", LogFormat::Json, move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(symbol = "BTC/USD", count = 3, "Ticks received");
+            tracing::debug!("below the level filter");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let line: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(line["service"], "feed_handler");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["message"], "Ticks received");
+        assert_eq!(line["symbol"], "BTC/USD");
+        assert_eq!(line["count"], 3);
+        assert!(line["timestamp"].as_str().is_some_and(|ts| !ts.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("json".parse(), Ok(LogFormat::Json));
+        assert_eq!("pretty".parse(), Ok(LogFormat::Pretty));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
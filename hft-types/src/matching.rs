@@ -0,0 +1,849 @@
+use crate::{MarketTick, Order, OrderBook, OrderSide, OrderType, TimeInForce};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Execution report for one side of a match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub fill_price: f64,
+    pub fill_qty: f64,
+    pub timestamp_nanos: u128,
+    /// The order was resting on the book rather than aggressing
+    #[serde(default)]
+    pub is_maker: bool,
+    /// The `client_id` of the order that filled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+}
+
+/// Outcome of submitting an order to the matching engine
+#[derive(Debug, Clone, Default)]
+pub struct MatchResult {
+    /// Fills for both the incoming order and the resting orders it hit
+    pub fills: Vec<Fill>,
+    /// Quantity of the incoming order executed
+    pub filled_qty: f64,
+    /// Quantity left resting on the book (0 if fully filled)
+    pub resting_qty: f64,
+    /// Quantity cancelled instead of resting: IOC/FOK remainders, unfilled
+    /// market orders, killed FOKs and quantity self-trade prevention took
+    pub cancelled_qty: f64,
+    /// Matches against the same client's resting orders that were stopped
+    pub self_trades: Vec<SelfTrade>,
+}
+
+/// What happens when an order would trade with a resting order from the
+/// same client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTradePrevention {
+    /// Pull the resting order and keep matching
+    #[default]
+    CancelResting,
+    /// Cancel whatever is left of the incoming order
+    CancelIncoming,
+    /// Take the smaller quantity off both without a fill
+    DecrementBoth,
+}
+
+impl FromStr for SelfTradePrevention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cancel_resting" => Ok(SelfTradePrevention::CancelResting),
+            "cancel_incoming" => Ok(SelfTradePrevention::CancelIncoming),
+            "decrement_both" => Ok(SelfTradePrevention::DecrementBoth),
+            other => Err(format!(
+                "unknown self-trade prevention policy '{}'; expected cancel_resting, cancel_incoming or decrement_both",
+                other
+            )),
+        }
+    }
+}
+
+/// A match self-trade prevention stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTrade {
+    pub resting_order_id: u64,
+    /// Quantity the resting order has left; 0 once it is off the book
+    pub resting_remaining: f64,
+    /// The client both orders belong to
+    pub client_id: Option<String>,
+}
+
+/// Both orders belong to the same client
+fn same_client(a: &Order, b: &Order) -> bool {
+    a.client_id.is_some() && a.client_id == b.client_id
+}
+
+/// Resting limit orders for one symbol, best price first then by arrival,
+/// plus stops waiting for their trigger
+#[derive(Debug, Default)]
+struct RestingBook {
+    bids: Vec<Order>,
+    asks: Vec<Order>,
+    stops: Vec<Order>,
+    /// Last market trade price, which stops trigger against
+    last_price: Option<f64>,
+}
+
+/// True once the market has traded at or through a stop's trigger
+fn stop_triggered(order: &Order, last_price: Option<f64>) -> bool {
+    match (order.order_type, last_price) {
+        (OrderType::Stop { trigger }, Some(last)) => match order.side {
+            OrderSide::Buy => last >= trigger,
+            OrderSide::Sell => last <= trigger,
+        },
+        _ => false,
+    }
+}
+
+impl RestingBook {
+    fn insert(&mut self, order: Order) {
+        // Price-time priority: queue behind every order at an equal or better price
+        let (levels, pos) = match order.side {
+            OrderSide::Buy => {
+                let pos = self.bids.partition_point(|o| o.price >= order.price);
+                (&mut self.bids, pos)
+            }
+            OrderSide::Sell => {
+                let pos = self.asks.partition_point(|o| o.price <= order.price);
+                (&mut self.asks, pos)
+            }
+        };
+        levels.insert(pos, order);
+    }
+
+    fn remove(&mut self, order_id: u64) -> Option<Order> {
+        for orders in [&mut self.bids, &mut self.asks, &mut self.stops] {
+            if let Some(pos) = orders.iter().position(|o| o.order_id == order_id) {
+                return Some(orders.remove(pos));
+            }
+        }
+        None
+    }
+
+    /// Does `order` cross a resting order at `resting_price`?
+    fn crosses(order: &Order, resting_price: f64) -> bool {
+        match order.order_type {
+            // A stop only gets here once triggered, at which point it is a market order
+            OrderType::Market | OrderType::Stop { .. } => true,
+            OrderType::Limit => match order.side {
+                OrderSide::Buy => order.price >= resting_price,
+                OrderSide::Sell => order.price <= resting_price,
+            },
+        }
+    }
+
+    /// Match `order` against the opposite side, then rest, cancel or kill
+    /// the remainder according to its type and time in force
+    fn execute(&mut self, mut order: Order, stp: SelfTradePrevention, now_nanos: u128) -> MatchResult {
+        let mut result = MatchResult::default();
+        let opposite = match order.side {
+            OrderSide::Buy => &mut self.asks,
+            OrderSide::Sell => &mut self.bids,
+        };
+
+        if order.time_in_force == TimeInForce::Fok {
+            // Only other clients' orders fill it, and under any policy but
+            // CancelResting its own resting order ends the fill
+            let mut available = 0.0;
+            for resting in opposite.iter().take_while(|resting| Self::crosses(&order, resting.price)) {
+                if !same_client(&order, resting) {
+                    available += resting.quantity;
+                } else if stp != SelfTradePrevention::CancelResting {
+                    break;
+                }
+            }
+            if available < order.quantity {
+                result.cancelled_qty = order.quantity;
+                return result;
+            }
+        }
+
+        while order.quantity > 0.0 {
+            let Some(resting) = opposite.first_mut() else {
+                break;
+            };
+            if !Self::crosses(&order, resting.price) {
+                break;
+            }
+
+            if same_client(&order, resting) {
+                let taken = match stp {
+                    SelfTradePrevention::CancelResting => {
+                        resting.quantity = 0.0;
+                        0.0
+                    }
+                    SelfTradePrevention::CancelIncoming => order.quantity,
+                    SelfTradePrevention::DecrementBoth => {
+                        let qty = order.quantity.min(resting.quantity);
+                        resting.quantity -= qty;
+                        qty
+                    }
+                };
+                order.quantity -= taken;
+                result.cancelled_qty += taken;
+                result.self_trades.push(SelfTrade {
+                    resting_order_id: resting.order_id,
+                    resting_remaining: resting.quantity,
+                    client_id: resting.client_id.clone(),
+                });
+                if resting.quantity <= 0.0 {
+                    opposite.remove(0);
+                }
+                continue;
+            }
+
+            // Trades execute at the resting order's price
+            let qty = order.quantity.min(resting.quantity);
+            let price = resting.price;
+            order.quantity -= qty;
+            resting.quantity -= qty;
+            result.filled_qty += qty;
+
+            for (filled, is_maker) in [(&order, false), (&*resting, true)] {
+                result.fills.push(Fill {
+                    order_id: filled.order_id,
+                    symbol: order.symbol.clone(),
+                    side: filled.side.clone(),
+                    fill_price: price,
+                    fill_qty: qty,
+                    timestamp_nanos: now_nanos,
+                    is_maker,
+                    client_id: filled.client_id.clone(),
+                });
+            }
+
+            if resting.quantity <= 0.0 {
+                opposite.remove(0);
+            }
+        }
+
+        if order.quantity > 0.0 {
+            let rests = order.order_type == OrderType::Limit
+                && matches!(order.time_in_force, TimeInForce::Gtc | TimeInForce::Day);
+            if rests {
+                result.resting_qty = order.quantity;
+                self.insert(order);
+            } else {
+                result.cancelled_qty = order.quantity;
+            }
+        }
+
+        result
+    }
+}
+
+/// Simulated exchange matching incoming orders against resting limit orders
+#[derive(Debug, Default)]
+pub struct MatchingEngine {
+    books: HashMap<String, RestingBook>,
+    stp: SelfTradePrevention,
+}
+
+impl MatchingEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Policy for orders that would trade with their own client's resting orders
+    pub fn with_self_trade_prevention(mut self, stp: SelfTradePrevention) -> Self {
+        self.stp = stp;
+        self
+    }
+
+    /// Match an incoming order. Limit GTC/Day remainders rest; IOC and
+    /// market remainders are cancelled; a FOK that cannot fully fill is
+    /// killed untouched. A stop whose trigger hasn't traded yet is held and
+    /// reports nothing until `on_market_price` releases it.
+    pub fn submit(&mut self, order: Order, now_nanos: u128) -> MatchResult {
+        let book = self.books.entry(order.symbol.clone()).or_default();
+        if matches!(order.order_type, OrderType::Stop { .. })
+            && !stop_triggered(&order, book.last_price)
+        {
+            book.stops.push(order);
+            return MatchResult::default();
+        }
+        book.execute(order, self.stp, now_nanos)
+    }
+
+    /// Record a market trade price, executing any stops it triggers in
+    /// arrival order
+    pub fn on_market_price(&mut self, symbol: &str, price: f64, now_nanos: u128) -> Vec<MatchResult> {
+        let Some(book) = self.books.get_mut(symbol) else {
+            return Vec::new();
+        };
+        book.last_price = Some(price);
+
+        let (triggered, waiting): (Vec<Order>, Vec<Order>) = std::mem::take(&mut book.stops)
+            .into_iter()
+            .partition(|stop| stop_triggered(stop, book.last_price));
+        book.stops = waiting;
+        triggered
+            .into_iter()
+            .map(|stop| book.execute(stop, self.stp, now_nanos))
+            .collect()
+    }
+
+    /// Drop every Day order, resting or waiting on a trigger, at the end of
+    /// the trading day. Returns the expired orders.
+    pub fn expire_day_orders(&mut self) -> Vec<Order> {
+        let mut expired = Vec::new();
+        for book in self.books.values_mut() {
+            for orders in [&mut book.bids, &mut book.asks, &mut book.stops] {
+                let (day, keep): (Vec<Order>, Vec<Order>) = std::mem::take(orders)
+                    .into_iter()
+                    .partition(|o| o.time_in_force == TimeInForce::Day);
+                *orders = keep;
+                expired.extend(day);
+            }
+        }
+        expired
+    }
+
+    /// Pull a resting order, or a stop still waiting for its trigger, off
+    /// the book. `None` if no such order is open.
+    pub fn cancel(&mut self, order_id: u64) -> Option<Order> {
+        self.books.values_mut().find_map(|book| book.remove(order_id))
+    }
+
+    /// Amend a resting limit order. With `keep_priority` and an unchanged
+    /// price it keeps its place in the queue; any price change sends it to
+    /// the back of its new level, where it trades first if it now crosses.
+    /// `None` if no such order is resting.
+    pub fn replace(
+        &mut self,
+        order_id: u64,
+        new_price: f64,
+        new_quantity: f64,
+        keep_priority: bool,
+        now_nanos: u128,
+    ) -> Option<MatchResult> {
+        let book = self
+            .books
+            .values_mut()
+            .find(|book| book.bids.iter().chain(&book.asks).any(|o| o.order_id == order_id))?;
+
+        if keep_priority {
+            let resting = book
+                .bids
+                .iter_mut()
+                .chain(book.asks.iter_mut())
+                .find(|o| o.order_id == order_id && o.price == new_price);
+            if let Some(resting) = resting {
+                resting.quantity = new_quantity;
+                return Some(MatchResult {
+                    resting_qty: new_quantity,
+                    ..MatchResult::default()
+                });
+            }
+        }
+
+        let mut order = book.remove(order_id)?;
+        order.price = new_price;
+        order.quantity = new_quantity;
+        Some(book.execute(order, self.stp, now_nanos))
+    }
+
+    /// Every resting order and waiting stop, in queue order per book
+    pub fn open_orders(&self) -> Vec<Order> {
+        let mut symbols: Vec<&String> = self.books.keys().collect();
+        symbols.sort();
+        symbols
+            .into_iter()
+            .flat_map(|symbol| {
+                let book = &self.books[symbol];
+                book.bids.iter().chain(&book.asks).chain(&book.stops)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Put orders from `open_orders` back on the book without matching
+    /// them. Orders at the same price keep the order they are given in.
+    pub fn restore(&mut self, orders: Vec<Order>) {
+        for order in orders {
+            let book = self.books.entry(order.symbol.clone()).or_default();
+            if matches!(order.order_type, OrderType::Stop { .. }) {
+                book.stops.push(order);
+            } else {
+                book.insert(order);
+            }
+        }
+    }
+
+    /// Number of stop orders waiting for their trigger
+    pub fn pending_stops(&self, symbol: &str) -> usize {
+        self.books.get(symbol).map(|book| book.stops.len()).unwrap_or(0)
+    }
+
+    /// Number of resting orders for a symbol
+    pub fn resting_orders(&self, symbol: &str) -> usize {
+        self.books
+            .get(symbol)
+            .map(|book| book.bids.len() + book.asks.len())
+            .unwrap_or(0)
+    }
+
+    /// Best resting bid and ask prices for a symbol
+    pub fn best_prices(&self, symbol: &str) -> (Option<f64>, Option<f64>) {
+        self.books
+            .get(symbol)
+            .map(|book| {
+                (
+                    book.bids.first().map(|o| o.price),
+                    book.asks.first().map(|o| o.price),
+                )
+            })
+            .unwrap_or((None, None))
+    }
+}
+
+/// Queue model settings for simulating passive fills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueModelConfig {
+    /// Expected fraction of the size ahead cancelled per second
+    pub cancel_rate_per_sec: f64,
+    pub seed: u64,
+}
+
+impl Default for QueueModelConfig {
+    fn default() -> Self {
+        Self {
+            cancel_rate_per_sec: 0.0,
+            seed: 42,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct QueuedOrder {
+    order: Order,
+    size_ahead: f64,
+    last_update_nanos: u128,
+}
+
+/// Queue-position model for resting orders in backtests: an order joins the
+/// back of its price level and only fills once trades at that price have
+/// consumed the size ahead of it. Cancellations ahead are modelled as a
+/// randomised decay, reproducible from the configured seed.
+#[derive(Debug)]
+pub struct QueueModel {
+    config: QueueModelConfig,
+    rng: StdRng,
+    orders: Vec<QueuedOrder>,
+}
+
+impl QueueModel {
+    pub fn new(config: QueueModelConfig) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            config,
+            rng,
+            orders: Vec::new(),
+        }
+    }
+
+    /// Rest an order behind the size currently displayed at its price
+    pub fn place(&mut self, order: Order, book: &OrderBook) {
+        let levels = match order.side {
+            OrderSide::Buy => &book.bids,
+            OrderSide::Sell => &book.asks,
+        };
+        let size_ahead = levels
+            .iter()
+            .filter(|level| (level.price - order.price).abs() < f64::EPSILON)
+            .map(|level| level.quantity)
+            .sum();
+
+        self.orders.push(QueuedOrder {
+            last_update_nanos: book.timestamp_nanos,
+            order,
+            size_ahead,
+        });
+    }
+
+    /// Size still queued ahead of a resting order
+    pub fn size_ahead(&self, order_id: u64) -> Option<f64> {
+        self.orders
+            .iter()
+            .find(|q| q.order.order_id == order_id)
+            .map(|q| q.size_ahead)
+    }
+
+    /// Apply a trade print, returning fills for any resting orders it reached
+    pub fn on_trade(&mut self, trade: &MarketTick) -> Vec<Fill> {
+        let mut fills = Vec::new();
+
+        for queued in self.orders.iter_mut().filter(|q| q.order.symbol == trade.symbol) {
+            // Cancellations ahead of us since the last update
+            let elapsed_secs =
+                trade.timestamp_nanos.saturating_sub(queued.last_update_nanos) as f64 / 1e9;
+            queued.last_update_nanos = trade.timestamp_nanos;
+            if self.config.cancel_rate_per_sec > 0.0 && queued.size_ahead > 0.0 {
+                let jitter: f64 = self.rng.gen_range(0.5..1.5);
+                let cancelled = (self.config.cancel_rate_per_sec * elapsed_secs * jitter).min(1.0);
+                queued.size_ahead *= 1.0 - cancelled;
+            }
+
+            let through = match queued.order.side {
+                OrderSide::Buy => trade.price < queued.order.price,
+                OrderSide::Sell => trade.price > queued.order.price,
+            };
+            let at_price = (trade.price - queued.order.price).abs() < f64::EPSILON;
+
+            let fill_qty = if through {
+                // The market traded through our level, so the whole queue cleared
+                queued.order.quantity
+            } else if at_price {
+                let volume = trade.volume as f64;
+                let consumed_ahead = volume.min(queued.size_ahead);
+                queued.size_ahead -= consumed_ahead;
+                (volume - consumed_ahead).min(queued.order.quantity)
+            } else {
+                0.0
+            };
+
+            if fill_qty > 0.0 {
+                queued.order.quantity -= fill_qty;
+                fills.push(Fill {
+                    order_id: queued.order.order_id,
+                    symbol: queued.order.symbol.clone(),
+                    side: queued.order.side.clone(),
+                    fill_price: queued.order.price,
+                    fill_qty,
+                    timestamp_nanos: trade.timestamp_nanos,
+                    is_maker: true,
+                    client_id: queued.order.client_id.clone(),
+                });
+            }
+        }
+
+        self.orders.retain(|q| q.order.quantity > 0.0);
+        fills
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: u64, side: OrderSide, price: f64, quantity: f64) -> Order {
+        Order::new(id, "BTC/USD".to_string(), side, price, quantity, id as u128)
+    }
+
+    #[test]
+    fn test_full_fill() {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Sell, 45000.0, 2.0), 10);
+
+        let result = engine.submit(order(2, OrderSide::Buy, 45010.0, 2.0), 11);
+
+        assert_eq!(result.filled_qty, 2.0);
+        assert_eq!(result.resting_qty, 0.0);
+        assert_eq!(result.fills.len(), 2);
+        assert!(result
+            .fills
+            .iter()
+            .all(|f| f.fill_price == 45000.0 && f.fill_qty == 2.0));
+        assert_eq!(engine.resting_orders("BTC/USD"), 0);
+    }
+
+    #[test]
+    fn test_partial_fill_rests_remainder() {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Sell, 45000.0, 1.0), 10);
+        engine.submit(order(2, OrderSide::Sell, 45000.0, 1.0), 11);
+        engine.submit(order(3, OrderSide::Sell, 45005.0, 1.0), 12);
+
+        let result = engine.submit(order(4, OrderSide::Buy, 45000.0, 3.0), 13);
+
+        // Both orders at 45000 fill in arrival order; 45005 is out of reach
+        let makers: Vec<u64> = result
+            .fills
+            .iter()
+            .filter(|f| f.order_id != 4)
+            .map(|f| f.order_id)
+            .collect();
+        assert_eq!(makers, vec![1, 2]);
+        assert_eq!(result.filled_qty, 2.0);
+        assert_eq!(result.resting_qty, 1.0);
+        assert_eq!(
+            engine.best_prices("BTC/USD"),
+            (Some(45000.0), Some(45005.0))
+        );
+    }
+
+    #[test]
+    fn test_non_marketable_order_rests() {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Sell, 45010.0, 1.0), 10);
+
+        let result = engine.submit(order(2, OrderSide::Buy, 45000.0, 1.0), 11);
+
+        assert!(result.fills.is_empty());
+        assert_eq!(result.resting_qty, 1.0);
+        assert_eq!(engine.resting_orders("BTC/USD"), 2);
+        assert_eq!(
+            engine.best_prices("BTC/USD"),
+            (Some(45000.0), Some(45010.0))
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_replace() {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Buy, 45000.0, 1.0), 10);
+        engine.submit(order(2, OrderSide::Buy, 45000.0, 1.0), 11);
+        assert_eq!(engine.cancel(1).map(|o| o.quantity), Some(1.0));
+        assert!(engine.cancel(1).is_none());
+        assert_eq!(engine.resting_orders("BTC/USD"), 1);
+
+        // A size change at the same price keeps the queue place
+        engine.submit(order(3, OrderSide::Buy, 45000.0, 1.0), 12);
+        let result = engine.replace(2, 45000.0, 0.5, true, 13).unwrap();
+        assert_eq!(result.resting_qty, 0.5);
+        let fills = engine.submit(order(4, OrderSide::Sell, 45000.0, 0.5), 14).fills;
+        assert!(fills.iter().any(|f| f.order_id == 2));
+
+        // Improving through the ask trades straight away
+        engine.submit(order(5, OrderSide::Sell, 45010.0, 1.0), 15);
+        let result = engine.replace(3, 45010.0, 1.0, false, 16).unwrap();
+        assert_eq!(result.filled_qty, 1.0);
+        assert_eq!(engine.resting_orders("BTC/USD"), 0);
+        assert!(engine.replace(42, 45000.0, 1.0, false, 17).is_none());
+    }
+
+    /// Two 1.0 asks at 45000 and 45005
+    fn two_asks() -> MatchingEngine {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Sell, 45000.0, 1.0), 10);
+        engine.submit(order(2, OrderSide::Sell, 45005.0, 1.0), 11);
+        engine
+    }
+
+    #[test]
+    fn test_market_order_ignores_price_and_never_rests() {
+        let mut engine = two_asks();
+
+        // A zero price would never cross as a limit
+        let market = order(3, OrderSide::Buy, 0.0, 3.0).with_order_type(OrderType::Market);
+        let result = engine.submit(market, 12);
+
+        assert_eq!(result.filled_qty, 2.0);
+        assert_eq!(result.cancelled_qty, 1.0);
+        assert_eq!(result.resting_qty, 0.0);
+        assert_eq!(engine.resting_orders("BTC/USD"), 0);
+    }
+
+    #[test]
+    fn test_ioc_cancels_remainder() {
+        let mut engine = two_asks();
+
+        let ioc = order(3, OrderSide::Buy, 45000.0, 2.0).with_time_in_force(TimeInForce::Ioc);
+        let result = engine.submit(ioc, 12);
+
+        assert_eq!(result.filled_qty, 1.0);
+        assert_eq!(result.cancelled_qty, 1.0);
+        assert_eq!(engine.best_prices("BTC/USD"), (None, Some(45005.0)));
+    }
+
+    #[test]
+    fn test_fok_fills_completely_or_not_at_all() {
+        let mut engine = two_asks();
+
+        // Only 1.0 is available at or below 45000
+        let killed = order(3, OrderSide::Buy, 45000.0, 2.0).with_time_in_force(TimeInForce::Fok);
+        let result = engine.submit(killed, 12);
+        assert!(result.fills.is_empty());
+        assert_eq!(result.cancelled_qty, 2.0);
+        assert_eq!(engine.resting_orders("BTC/USD"), 2);
+
+        let filled = order(4, OrderSide::Buy, 45005.0, 2.0).with_time_in_force(TimeInForce::Fok);
+        let result = engine.submit(filled, 13);
+        assert_eq!(result.filled_qty, 2.0);
+        assert_eq!(result.cancelled_qty, 0.0);
+        assert_eq!(engine.resting_orders("BTC/USD"), 0);
+
+        // Market FOK against an empty book
+        let market = order(5, OrderSide::Buy, 0.0, 1.0)
+            .with_order_type(OrderType::Market)
+            .with_time_in_force(TimeInForce::Fok);
+        assert_eq!(engine.submit(market, 14).cancelled_qty, 1.0);
+    }
+
+    /// Client A bids 1.0 at 45000, client B 1.0 at 44995 behind it
+    fn own_bid_then_other_ask(stp: SelfTradePrevention) -> MatchingEngine {
+        let mut engine = MatchingEngine::new().with_self_trade_prevention(stp);
+        engine.submit(order(1, OrderSide::Buy, 45000.0, 1.0).with_client_id("a"), 10);
+        engine.submit(order(2, OrderSide::Buy, 44995.0, 1.0).with_client_id("b"), 11);
+        engine
+    }
+
+    #[test]
+    fn test_stp_cancel_resting() {
+        let mut engine = own_bid_then_other_ask(SelfTradePrevention::CancelResting);
+
+        let sell = order(3, OrderSide::Sell, 44990.0, 1.5).with_client_id("a");
+        let result = engine.submit(sell, 12);
+
+        // A's bid is pulled and the sell carries on into B's
+        assert_eq!(
+            result.self_trades,
+            vec![SelfTrade {
+                resting_order_id: 1,
+                resting_remaining: 0.0,
+                client_id: Some("a".to_string()),
+            }]
+        );
+        assert_eq!(result.filled_qty, 1.0);
+        assert!(result.fills.iter().all(|f| f.order_id != 1));
+        assert_eq!(result.resting_qty, 0.5);
+        assert_eq!(engine.best_prices("BTC/USD"), (None, Some(44990.0)));
+    }
+
+    #[test]
+    fn test_stp_cancel_incoming() {
+        let mut engine = own_bid_then_other_ask(SelfTradePrevention::CancelIncoming);
+
+        let sell = order(3, OrderSide::Sell, 44990.0, 1.5).with_client_id("a");
+        let result = engine.submit(sell, 12);
+
+        assert!(result.fills.is_empty());
+        assert_eq!(result.cancelled_qty, 1.5);
+        assert_eq!(result.self_trades[0].resting_remaining, 1.0);
+        assert_eq!(engine.best_prices("BTC/USD"), (Some(45000.0), None));
+        assert_eq!(engine.resting_orders("BTC/USD"), 2);
+
+        // Another client's sell still trades with A's bid
+        let result = engine.submit(order(4, OrderSide::Sell, 45000.0, 1.0).with_client_id("b"), 13);
+        assert_eq!(result.filled_qty, 1.0);
+        assert!(result.self_trades.is_empty());
+    }
+
+    #[test]
+    fn test_stp_decrement_both() {
+        let mut engine = own_bid_then_other_ask(SelfTradePrevention::DecrementBoth);
+
+        let sell = order(3, OrderSide::Sell, 44990.0, 0.4).with_client_id("a");
+        let result = engine.submit(sell, 12);
+        assert!(result.fills.is_empty());
+        assert_eq!(result.cancelled_qty, 0.4);
+        assert_eq!(result.self_trades[0].resting_remaining, 0.6);
+        assert_eq!(engine.best_prices("BTC/USD"), (Some(45000.0), None));
+
+        // The larger sell uses up A's remaining 0.6 and fills against B
+        let sell = order(4, OrderSide::Sell, 44990.0, 1.0).with_client_id("a");
+        let result = engine.submit(sell, 13);
+        assert_eq!(result.self_trades[0].resting_remaining, 0.0);
+        assert_eq!(result.cancelled_qty, 0.6);
+        assert!((result.filled_qty - 0.4).abs() < 1e-9);
+        assert_eq!(engine.best_prices("BTC/USD"), (Some(44995.0), None));
+
+        // Orders without a client id never count as self-trades
+        let result = engine.submit(order(5, OrderSide::Sell, 44995.0, 0.6), 14);
+        assert!(result.self_trades.is_empty());
+        assert_eq!(engine.resting_orders("BTC/USD"), 0);
+    }
+
+    #[test]
+    fn test_stop_waits_for_trigger() {
+        let mut engine = two_asks();
+
+        let stop = order(3, OrderSide::Buy, 0.0, 1.5)
+            .with_order_type(OrderType::Stop { trigger: 45100.0 });
+        let result = engine.submit(stop, 12);
+        assert!(result.fills.is_empty());
+        assert_eq!(result.resting_qty, 0.0);
+        assert_eq!(engine.pending_stops("BTC/USD"), 1);
+
+        assert!(engine.on_market_price("BTC/USD", 45050.0, 13).is_empty());
+
+        let results = engine.on_market_price("BTC/USD", 45100.0, 14);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].filled_qty, 1.5);
+        assert_eq!(engine.pending_stops("BTC/USD"), 0);
+        assert_eq!(engine.best_prices("BTC/USD"), (None, Some(45005.0)));
+
+        // Already through the trigger: executes on arrival
+        let sell_stop = order(4, OrderSide::Sell, 0.0, 0.5)
+            .with_order_type(OrderType::Stop { trigger: 45200.0 });
+        engine.submit(order(5, OrderSide::Buy, 44990.0, 1.0), 15);
+        assert_eq!(engine.submit(sell_stop, 16).filled_qty, 0.5);
+    }
+
+    #[test]
+    fn test_day_orders_expire() {
+        let mut engine = MatchingEngine::new();
+        engine.submit(order(1, OrderSide::Buy, 44990.0, 1.0).with_time_in_force(TimeInForce::Day), 10);
+        engine.submit(order(2, OrderSide::Buy, 44980.0, 1.0), 11);
+        let day_stop = order(3, OrderSide::Sell, 0.0, 1.0)
+            .with_order_type(OrderType::Stop { trigger: 44000.0 })
+            .with_time_in_force(TimeInForce::Day);
+        engine.submit(day_stop, 12);
+
+        let expired: Vec<u64> = engine.expire_day_orders().iter().map(|o| o.order_id).collect();
+        assert_eq!(expired, vec![1, 3]);
+        assert_eq!(engine.best_prices("BTC/USD"), (Some(44980.0), None));
+        assert_eq!(engine.pending_stops("BTC/USD"), 0);
+    }
+
+    fn queue_book() -> OrderBook {
+        let mut book = OrderBook::new("BTC/USD".to_string(), 0);
+        book.bids.push(crate::BookLevel {
+            price: 45000.0,
+            quantity: 100.0,
+        });
+        book
+    }
+
+    fn trade(price: f64, volume: u64, ts: u128) -> MarketTick {
+        MarketTick::new("BTC/USD".to_string(), price, volume, ts)
+    }
+
+    #[test]
+    fn test_queue_position_fills_after_size_ahead() {
+        let mut model = QueueModel::new(QueueModelConfig::default());
+        model.place(order(1, OrderSide::Buy, 45000.0, 5.0), &queue_book());
+
+        // 60 of the 100 ahead trade away; nothing reaches us yet
+        assert!(model.on_trade(&trade(45000.0, 60, 1)).is_empty());
+        assert_eq!(model.size_ahead(1), Some(40.0));
+
+        // 42 more: 40 clears the queue, 2 fill us
+        let fills = model.on_trade(&trade(45000.0, 42, 2));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].fill_qty, 2.0);
+
+        // Trading through the level fills the remainder
+        let fills = model.on_trade(&trade(44990.0, 1, 3));
+        assert_eq!(fills[0].fill_qty, 3.0);
+        assert_eq!(model.size_ahead(1), None);
+    }
+
+    #[test]
+    fn test_cancellations_ahead_are_seeded() {
+        let config = QueueModelConfig {
+            cancel_rate_per_sec: 0.1,
+            seed: 7,
+        };
+        let run = || {
+            let mut model = QueueModel::new(config.clone());
+            model.place(order(1, OrderSide::Buy, 45000.0, 1.0), &queue_book());
+            model.on_trade(&trade(45010.0, 1, 2_000_000_000));
+            model.size_ahead(1).unwrap()
+        };
+
+        let ahead = run();
+        assert!(ahead < 100.0);
+        assert_eq!(ahead, run());
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{EnrichedTick, Order, OrderBook, TradingSignal};
+use crate::orderbook::BookDelta;
+use crate::{EnrichedTick, HftError, HftResult, Order, OrderBook, TradingSignal};
 use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
 
 /// Message types for inter-process communication
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,11 +22,55 @@ pub enum Message {
     /// Order book update
     OrderBookUpdate(OrderBook),
 
+    /// Levels changed since the last snapshot or delta for the symbol
+    BookDelta(BookDelta),
+
+    /// Client request for a fresh snapshot after a delta gap
+    BookResync { symbol: String },
+
     /// Heartbeat for connection monitoring
     Heartbeat { sender: String, timestamp: u128 },
 
     /// System control messages
     Shutdown,
+
+    /// Close an open circuit breaker without waiting for its cooldown
+    ResetCircuitBreaker,
+
+    /// Pull a resting order from the gateway's book
+    CancelOrder { order_id: u64 },
+
+    /// Amend a resting order. `keep_priority` holds its queue place when
+    /// the price is unchanged; a new price always goes to the back.
+    ReplaceOrder {
+        order_id: u64,
+        new_price: f64,
+        new_quantity: f64,
+        #[serde(default)]
+        keep_priority: bool,
+    },
+
+    /// Gateway reply: this much of the order left the book unfilled
+    CancelAck { order_id: u64, cancelled_qty: f64 },
+
+    /// Gateway reply: there was nothing open to cancel
+    CancelReject { order_id: u64, reason: CancelRejectReason },
+
+    /// Gateway reply: one of the client's orders traded
+    Fill(crate::matching::Fill),
+
+    /// Gateway reply: the order was refused and never reached the book
+    OrderReject { order_id: u64, reason: String },
+}
+
+/// Why a cancel was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelRejectReason {
+    /// The gateway never placed an order with this id
+    UnknownOrder,
+    /// Placed, but already filled, cancelled or expired
+    NotOpen,
 }
 
 impl Message {
@@ -36,6 +83,47 @@ impl Message {
     }
 }
 
+/// Parse an `IP:PORT` command-line address with a message fit for a usage error
+pub fn parse_socket_addr(s: &str) -> Result<SocketAddr, String> {
+    s.parse().map_err(|_| {
+        format!(
+            "'{}' is not a valid address; expected IP:PORT with a port in 0-65535",
+            s
+        )
+    })
+}
+
+/// Datagram a publisher sends on startup to check the feed is listening
+pub const PROBE_REQUEST: &[u8] = b"HFT_PROBE";
+
+/// Reply from the feed handler to a `PROBE_REQUEST`
+pub const PROBE_ACK: &[u8] = b"HFT_PROBE_ACK";
+
+/// Largest payload a length prefix may announce. Anything bigger is treated
+/// as corrupt or hostile rather than allocated.
+pub const MAX_FRAME_LEN: u32 = 8 * 1024 * 1024;
+
+/// Reject a length prefix above `MAX_FRAME_LEN`; every network decode path
+/// checks this before allocating the payload buffer
+pub fn check_frame_len(length: u32) -> HftResult<usize> {
+    if length > MAX_FRAME_LEN {
+        return Err(HftError::FrameTooLarge {
+            len: length as usize,
+            max: MAX_FRAME_LEN as usize,
+        });
+    }
+    Ok(length as usize)
+}
+
+/// Classify a failure reading an `expected`-byte payload: the stream
+/// ending early is a `PartialFrame`, anything else as for any I/O error
+pub fn payload_read_error(e: std::io::Error, expected: usize) -> HftError {
+    match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => HftError::PartialFrame { expected },
+        _ => HftError::from(e),
+    }
+}
+
 /// TCP message frame with length prefix
 pub struct MessageFrame {
     pub length: u32,
@@ -62,7 +150,120 @@ impl MessageFrame {
         Self { length, payload }
     }
 
+    /// Read the payload announced by an already-read length prefix,
+    /// refusing oversized lengths before allocating anything
+    pub fn try_new_from_prefix<R: Read>(length: u32, reader: &mut R) -> HftResult<Self> {
+        let expected = check_frame_len(length)?;
+        let mut payload = vec![0u8; expected];
+        reader
+            .read_exact(&mut payload)
+            .map_err(|e| payload_read_error(e, expected))?;
+        Ok(Self::from_length_and_payload(length, payload))
+    }
+
     pub fn parse_message(&self) -> Result<Message, serde_json::Error> {
         Message::deserialize(&self.payload)
     }
 }
+
+/// Write a length-prefixed message to a blocking stream
+pub fn write_message<W: Write>(writer: &mut W, message: &Message) -> std::io::Result<()> {
+    let frame = MessageFrame::new(message)?;
+    writer.write_all(&frame.to_bytes())?;
+    writer.flush()
+}
+
+/// Read one length-prefixed frame from a blocking stream
+pub fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<MessageFrame> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf)?;
+    let length = u32::from_be_bytes(len_buf);
+
+    MessageFrame::try_new_from_prefix(length, reader).map_err(|e| {
+        let kind = match e {
+            HftError::Io(e) => return e,
+            HftError::PartialFrame { .. } => std::io::ErrorKind::UnexpectedEof,
+            HftError::Timeout(_) => std::io::ErrorKind::TimedOut,
+            HftError::ConnectionRefused(_) => std::io::ErrorKind::ConnectionRefused,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Order, OrderSide};
+
+    #[test]
+    fn test_frame_round_trip() {
+        let order = Order::new(7, "BTC/USD".to_string(), OrderSide::Buy, 45000.0, 1.0, 1);
+        let mut buf = Vec::new();
+        write_message(&mut buf, &Message::Order(order)).unwrap();
+
+        let frame = read_frame(&mut buf.as_slice()).unwrap();
+        assert_eq!(frame.length as usize, buf.len() - 4);
+        match frame.parse_message().unwrap() {
+            Message::Order(o) => assert_eq!(o.order_id, 7),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    /// Fails the test if the payload is ever read
+    struct Untouchable;
+
+    impl Read for Untouchable {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            panic!("oversized frame should be rejected before reading");
+        }
+    }
+
+    #[test]
+    fn test_oversized_prefix_is_rejected() {
+        assert!(matches!(
+            MessageFrame::try_new_from_prefix(u32::MAX, &mut Untouchable),
+            Err(HftError::FrameTooLarge { len, max })
+                if len == u32::MAX as usize && max == MAX_FRAME_LEN as usize
+        ));
+        assert!(MessageFrame::try_new_from_prefix(MAX_FRAME_LEN + 1, &mut Untouchable).is_err());
+
+        let payload = Message::Shutdown.serialize().unwrap();
+        let frame = MessageFrame::try_new_from_prefix(payload.len() as u32, &mut payload.as_slice()).unwrap();
+        assert!(matches!(frame.parse_message().unwrap(), Message::Shutdown));
+
+        // Same check through the blocking reader
+        let mut stream = u32::MAX.to_be_bytes().to_vec();
+        stream.extend_from_slice(b"junk");
+        let err = read_frame(&mut stream.as_slice()).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_error_kinds_survive_framing() {
+        let payload = Message::Shutdown.serialize().unwrap();
+        let length = payload.len() as u32;
+        let short = &payload[..payload.len() - 2];
+        let err = MessageFrame::try_new_from_prefix(length, &mut &short[..]).err().unwrap();
+        assert!(matches!(err, HftError::PartialFrame { expected } if expected == payload.len()));
+        assert!(err.is_transient());
+
+        let mut stream = length.to_be_bytes().to_vec();
+        stream.extend_from_slice(short);
+        let err = read_frame(&mut stream.as_slice()).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let frame = MessageFrame::from_length_and_payload(3, b"{no".to_vec());
+        let err = HftError::from(frame.parse_message().unwrap_err());
+        assert!(matches!(err, HftError::Json(_)));
+        assert!(!err.is_transient());
+
+        // I/O failures retry logic cares about get their own variants
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(matches!(HftError::from(refused), HftError::ConnectionRefused(_)));
+        let timed_out = std::io::Error::from(std::io::ErrorKind::TimedOut);
+        assert!(matches!(HftError::from(timed_out), HftError::Timeout(_)));
+        let other = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(HftError::from(other), HftError::Io(_)));
+    }
+}
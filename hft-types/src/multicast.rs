@@ -0,0 +1,96 @@
+use crate::{HftError, HftResult};
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+
+/// An IPv4 multicast group to publish to or receive from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MulticastGroup {
+    pub group: Ipv4Addr,
+    pub port: u16,
+    /// Local interface address; `0.0.0.0` lets the kernel pick by route
+    pub interface: Ipv4Addr,
+}
+
+impl MulticastGroup {
+    pub fn new(group: Ipv4Addr, port: u16, interface: Ipv4Addr) -> HftResult<Self> {
+        if !group.is_multicast() {
+            return Err(HftError::InvalidConfig(format!(
+                "{} is not a multicast address (224.0.0.0/4)",
+                group
+            )));
+        }
+        Ok(Self {
+            group,
+            port,
+            interface,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::V4(SocketAddrV4::new(self.group, self.port))
+    }
+
+    /// Bind the group's port and join it. The port is shared (SO_REUSEADDR)
+    /// so several receivers on one host each get every datagram. The socket
+    /// is non-blocking, ready for `tokio::net::UdpSocket::from_std`.
+    pub fn join(&self) -> HftResult<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+            .map_err(|e| network_error("create multicast socket", e))?;
+        socket
+            .set_reuse_address(true)
+            .map_err(|e| network_error("set SO_REUSEADDR", e))?;
+        let bind = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, self.port);
+        socket
+            .bind(&bind.into())
+            .map_err(|e| network_error(&format!("bind {}", bind), e))?;
+        socket.join_multicast_v4(&self.group, &self.interface).map_err(|e| {
+            HftError::NetworkError(format!(
+                "failed to join multicast group {} on interface {}: {} \
+                 (check the interface exists, is up with MULTICAST set, and has a route for 224.0.0.0/4)",
+                self.group, self.interface, e
+            ))
+        })?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| network_error("set non-blocking", e))?;
+        Ok(socket.into())
+    }
+}
+
+/// Set the outgoing interface and TTL for multicast sends on `socket`.
+/// Loopback stays on so receivers on the sending host see the feed too.
+pub fn configure_sender(socket: &UdpSocket, interface: Ipv4Addr, ttl: u32) -> HftResult<()> {
+    let socket = SockRef::from(socket);
+    socket.set_multicast_if_v4(&interface).map_err(|e| {
+        HftError::NetworkError(format!(
+            "cannot send multicast from interface {}: {}",
+            interface, e
+        ))
+    })?;
+    socket
+        .set_multicast_ttl_v4(ttl)
+        .map_err(|e| network_error("set multicast TTL", e))?;
+    socket
+        .set_multicast_loop_v4(true)
+        .map_err(|e| network_error("enable multicast loopback", e))?;
+    Ok(())
+}
+
+fn network_error(action: &str, e: std::io::Error) -> HftError {
+    HftError::NetworkError(format!("failed to {}: {}", action, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_must_be_multicast() {
+        let any = Ipv4Addr::UNSPECIFIED;
+        assert!(MulticastGroup::new(Ipv4Addr::new(239, 255, 0, 1), 9001, any).is_ok());
+        assert!(matches!(
+            MulticastGroup::new(Ipv4Addr::new(127, 0, 0, 1), 9001, any),
+            Err(HftError::InvalidConfig(_))
+        ));
+    }
+}
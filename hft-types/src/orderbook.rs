@@ -1,39 +1,208 @@
-use crate::{BookLevel, OrderBook, MarketTick};
-use std::collections::HashMap;
+use crate::{BookLevel, ChecksumFormat, HftError, HftResult, MarketTick, OrderBook, OrderSide};
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
-/// Order book manager for maintaining level 2 data
+/// Classified trade volume for one symbol
+#[derive(Debug, Default)]
+struct OrderFlow {
+    buy_volume: f64,
+    sell_volume: f64,
+    /// Price and inferred aggressor of the previous trade, for the tick test
+    last_trade: Option<(f64, Option<OrderSide>)>,
+}
+
+/// Best bid and best ask across every venue's book for a symbol, with the
+/// venue each came from (`None` for the book fed without a venue)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsolidatedBbo {
+    pub bid: f64,
+    pub bid_venue: Option<String>,
+    pub ask: f64,
+    pub ask_venue: Option<String>,
+}
+
+impl ConsolidatedBbo {
+    pub fn prices(&self) -> (f64, f64) {
+        (self.bid, self.ask)
+    }
+
+    /// One venue bids at or above another's ask
+    pub fn is_crossed(&self) -> bool {
+        self.bid >= self.ask
+    }
+}
+
+/// Order book manager for maintaining level 2 data. Ticks and snapshots
+/// without a venue share one book per symbol; each venue a symbol is also
+/// fed from keeps a book of its own. `get_book` and the other per-symbol
+/// queries read the venue-less book, or for a symbol only venues feed, the
+/// venue book updated last. `get_bbo` is the best across every book.
 pub struct OrderBookManager {
     books: HashMap<String, OrderBook>,
+    /// Symbol, then venue
+    venue_books: HashMap<String, BTreeMap<String, OrderBook>>,
+    checksum_format: ChecksumFormat,
+    crossed_books: u64,
+    flow: HashMap<String, OrderFlow>,
+    /// Shape of the book `update_from_tick` builds around each trade
+    synthetic_levels: usize,
+    synthetic_spread_bps: f64,
+    level_step_bps: f64,
 }
 
 impl OrderBookManager {
+    /// Synthetic books of 5 levels a side, 10bps wide, 1bp between levels
     pub fn new() -> Self {
         Self {
             books: HashMap::new(),
+            venue_books: HashMap::new(),
+            checksum_format: ChecksumFormat::default(),
+            crossed_books: 0,
+            flow: HashMap::new(),
+            synthetic_levels: 5,
+            synthetic_spread_bps: 10.0,
+            level_step_bps: 1.0,
+        }
+    }
+
+    /// Build synthetic books of `synthetic_levels` a side, with the best
+    /// levels `synthetic_spread_bps` apart and each further level another
+    /// `level_step_bps` out
+    pub fn with_synthetic_depth(
+        synthetic_levels: usize,
+        synthetic_spread_bps: f64,
+        level_step_bps: f64,
+    ) -> HftResult<Self> {
+        if synthetic_levels == 0 {
+            return Err(HftError::InvalidConfig(
+                "synthetic_levels must be at least 1".to_string(),
+            ));
+        }
+        if !(synthetic_spread_bps.is_finite() && synthetic_spread_bps > 0.0) {
+            return Err(HftError::InvalidConfig(format!(
+                "synthetic_spread_bps must be positive, not {}",
+                synthetic_spread_bps
+            )));
+        }
+        if !(level_step_bps.is_finite() && level_step_bps >= 0.0) {
+            return Err(HftError::InvalidConfig(format!(
+                "level_step_bps must not be negative, not {}",
+                level_step_bps
+            )));
+        }
+        Ok(Self {
+            synthetic_levels,
+            synthetic_spread_bps,
+            level_step_bps,
+            ..Self::new()
+        })
+    }
+
+    /// Infer which side initiated a trade from the book it traded against:
+    /// at or above the ask is a buy, at or below the bid a sell. Inside the
+    /// spread the trade is compared with the mid, and one exactly at the mid
+    /// falls back to the tick test against the previous trade. `None` if
+    /// there's nothing to judge by.
+    pub fn classify_trade(&self, tick: &MarketTick) -> Option<OrderSide> {
+        let flow = self.flow.get(&tick.symbol);
+        let bbo = self
+            .venue_book(&tick.symbol, tick.venue.as_deref())
+            .and_then(|book| Some((book.best_bid()?.price, book.best_ask()?.price)));
+        if let Some((bid, ask)) = bbo {
+            if tick.price >= ask {
+                return Some(OrderSide::Buy);
+            }
+            if tick.price <= bid {
+                return Some(OrderSide::Sell);
+            }
+            let mid = (bid + ask) / 2.0;
+            if tick.price > mid {
+                return Some(OrderSide::Buy);
+            }
+            if tick.price < mid {
+                return Some(OrderSide::Sell);
+            }
+        }
+
+        // Tick test: an uptick is a buy, a downtick a sell, and an unchanged
+        // price keeps the previous trade's side
+        let (last_price, last_side) = flow?.last_trade.clone()?;
+        if tick.price > last_price {
+            Some(OrderSide::Buy)
+        } else if tick.price < last_price {
+            Some(OrderSide::Sell)
+        } else {
+            last_side
+        }
+    }
+
+    /// Classify a trade against the current book and add its volume to the
+    /// symbol's order flow. Call before the trade updates the book.
+    pub fn record_trade(&mut self, tick: &MarketTick) -> Option<OrderSide> {
+        let side = self.classify_trade(tick);
+        let flow = self.flow.entry(tick.symbol.clone()).or_default();
+        match side {
+            Some(OrderSide::Buy) => flow.buy_volume += tick.volume as f64,
+            Some(OrderSide::Sell) => flow.sell_volume += tick.volume as f64,
+            None => {}
         }
+        flow.last_trade = Some((tick.price, side.clone()));
+        side
     }
 
-    /// Update order book from market tick (simplified L1 -> L2 conversion)
+    /// Buy minus sell aggressor volume over their sum, from -1 (all sells)
+    /// to 1 (all buys); `None` before any trade has been classified
+    pub fn order_flow_imbalance(&self, symbol: &str) -> Option<f64> {
+        let flow = self.flow.get(symbol)?;
+        let total = flow.buy_volume + flow.sell_volume;
+        (total > 0.0).then(|| (flow.buy_volume - flow.sell_volume) / total)
+    }
+
+    /// Precision the venue formats levels with for its book checksums
+    pub fn set_checksum_format(&mut self, format: ChecksumFormat) {
+        self.checksum_format = format;
+    }
+
+    /// Compare our book against a venue-published checksum over the top
+    /// `levels`. A mismatch (or no book) means we've desynced and should
+    /// resync from a snapshot.
+    pub fn verify_checksum(&self, symbol: &str, levels: usize, expected: u32) -> bool {
+        self.books
+            .get(symbol)
+            .is_some_and(|book| book.checksum_with(levels, &self.checksum_format) == expected)
+    }
+
+    /// Update order book from market tick (simplified L1 -> L2 conversion).
+    /// The trade is first classified against the book it hit.
     pub fn update_from_tick(&mut self, tick: &MarketTick) {
-        let book = self.books
-            .entry(tick.symbol.clone())
-            .or_insert_with(|| OrderBook::new(tick.symbol.clone(), tick.timestamp_nanos));
+        self.record_trade(tick);
+        let new_book = || OrderBook::new(tick.symbol.clone(), tick.timestamp_nanos);
+        let book = match &tick.venue {
+            Some(venue) => self
+                .venue_books
+                .entry(tick.symbol.clone())
+                .or_default()
+                .entry(venue.clone())
+                .or_insert_with(new_book),
+            None => self.books.entry(tick.symbol.clone()).or_insert_with(new_book),
+        };
 
         book.timestamp_nanos = tick.timestamp_nanos;
 
         // Simplified: Create synthetic L2 data from L1 tick
         // In production, this would come from actual exchange order book feed
-        let spread_bps = 10.0; // 10 basis points
-        let spread = tick.price * (spread_bps / 10000.0);
+        let spread = tick.price * (self.synthetic_spread_bps / 10000.0);
+        let step = tick.price * (self.level_step_bps / 10000.0);
 
         // Clear existing levels
         book.bids.clear();
         book.asks.clear();
 
-        // Create 5 levels on each side
-        for i in 0..5 {
-            let bid_price = tick.price - spread / 2.0 - (i as f64 * tick.price * 0.0001);
-            let ask_price = tick.price + spread / 2.0 + (i as f64 * tick.price * 0.0001);
+        for i in 0..self.synthetic_levels {
+            let bid_price = tick.price - spread / 2.0 - (i as f64 * step);
+            let ask_price = tick.price + spread / 2.0 + (i as f64 * step);
 
             book.bids.push(BookLevel {
                 price: bid_price,
@@ -45,11 +214,121 @@ impl OrderBookManager {
                 quantity: tick.volume as f64 / (i + 1) as f64,
             });
         }
+
+        let symbol = book.symbol.clone();
+        self.check_crossed(&symbol, tick.venue.as_deref());
+    }
+
+    /// Replace the book for a symbol with a full snapshot
+    pub fn apply_snapshot(&mut self, book: OrderBook) {
+        let symbol = book.symbol.clone();
+        self.books.insert(symbol.clone(), book);
+        self.check_crossed(&symbol, None);
+    }
+
+    /// Replace one venue's book for a symbol with a full snapshot
+    pub fn apply_venue_snapshot(&mut self, venue: &str, book: OrderBook) {
+        let symbol = book.symbol.clone();
+        self.venue_books
+            .entry(symbol.clone())
+            .or_default()
+            .insert(venue.to_string(), book);
+        self.check_crossed(&symbol, Some(venue));
+    }
+
+    /// Count and log a book left crossed by an update; it points at bad
+    /// feed data rather than anything a strategy should trade on. Venues
+    /// crossing each other are an opportunity, not an error, and aren't counted.
+    fn check_crossed(&mut self, symbol: &str, venue: Option<&str>) {
+        if let Some(Err(HftError::CrossedBook { bid, ask, .. })) =
+            self.venue_book(symbol, venue).map(OrderBook::spread_checked)
+        {
+            self.crossed_books += 1;
+            tracing::warn!(
+                "Crossed book for {}{}: bid {} above ask {}",
+                symbol,
+                venue.map(|venue| format!(" on {}", venue)).unwrap_or_default(),
+                bid,
+                ask
+            );
+        }
+    }
+
+    /// A symbol's book on `venue`, or its venue-less book for `None`
+    pub fn venue_book(&self, symbol: &str, venue: Option<&str>) -> Option<&OrderBook> {
+        match venue {
+            Some(venue) => self.venue_books.get(symbol)?.get(venue),
+            None => self.books.get(symbol),
+        }
+    }
+
+    /// Venues with a book for `symbol`, sorted
+    pub fn venues(&self, symbol: &str) -> Vec<&str> {
+        self.venue_books
+            .get(symbol)
+            .map(|books| books.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// National best bid and offer: the highest bid and lowest ask across
+    /// every book for `symbol`, venue-less included. Ties go to the
+    /// venue-less book, then venues in name order. `None` unless some book
+    /// has a bid and some book an ask.
+    pub fn consolidated_bbo(&self, symbol: &str) -> Option<ConsolidatedBbo> {
+        let books = self
+            .books
+            .get(symbol)
+            .map(|book| (None, book))
+            .into_iter()
+            .chain(
+                self.venue_books
+                    .get(symbol)
+                    .into_iter()
+                    .flatten()
+                    .map(|(venue, book)| (Some(venue), book)),
+            );
+
+        let mut bid: Option<(f64, Option<&String>)> = None;
+        let mut ask: Option<(f64, Option<&String>)> = None;
+        for (venue, book) in books {
+            if let Some(level) = book.best_bid() {
+                if bid.is_none_or(|(best, _)| level.price > best) {
+                    bid = Some((level.price, venue));
+                }
+            }
+            if let Some(level) = book.best_ask() {
+                if ask.is_none_or(|(best, _)| level.price < best) {
+                    ask = Some((level.price, venue));
+                }
+            }
+        }
+        let ((bid, bid_venue), (ask, ask_venue)) = (bid?, ask?);
+        Some(ConsolidatedBbo {
+            bid,
+            bid_venue: bid_venue.cloned(),
+            ask,
+            ask_venue: ask_venue.cloned(),
+        })
+    }
+
+    /// Updates that left a book crossed since creation
+    pub fn crossed_book_count(&self) -> u64 {
+        self.crossed_books
     }
 
     /// Get order book for symbol
     pub fn get_book(&self, symbol: &str) -> Option<&OrderBook> {
-        self.books.get(symbol)
+        self.books.get(symbol).or_else(|| {
+            self.venue_books
+                .get(symbol)?
+                .values()
+                .max_by_key(|book| book.timestamp_nanos)
+        })
+    }
+
+    /// Owned copy of one book, unaffected by later updates
+    pub fn snapshot(&self, symbol: &str) -> Option<OrderBook> {
+        self.get_book(symbol).cloned()
     }
 
     /// Get all books
@@ -57,17 +336,14 @@ impl OrderBookManager {
         &self.books
     }
 
-    /// Get best bid/ask for symbol
+    /// Best bid/ask for symbol across all its books
     pub fn get_bbo(&self, symbol: &str) -> Option<(f64, f64)> {
-        self.books.get(symbol).and_then(|book| {
-            book.best_bid()
-                .and_then(|bid| book.best_ask().map(|ask| (bid.price, ask.price)))
-        })
+        self.consolidated_bbo(symbol).map(|bbo| bbo.prices())
     }
 
     /// Calculate VWAP (Volume Weighted Average Price)
     pub fn calculate_vwap(&self, symbol: &str, side_depth: usize) -> Option<f64> {
-        self.books.get(symbol).map(|book| {
+        self.get_book(symbol).map(|book| {
             let levels = if side_depth > 0 {
                 &book.bids[..side_depth.min(book.bids.len())]
             } else {
@@ -91,16 +367,36 @@ impl OrderBookManager {
 
     /// Check if book is crossed (bid >= ask, indicating arbitrage opportunity)
     pub fn is_crossed(&self, symbol: &str) -> bool {
-        if let Some((bid, ask)) = self.get_bbo(symbol) {
-            bid >= ask
-        } else {
-            false
-        }
+        let venue_books = self.venue_books.get(symbol).into_iter().flat_map(BTreeMap::values);
+        self.books.get(symbol).into_iter().chain(venue_books).any(|book| {
+            matches!((book.best_bid(), book.best_ask()), (Some(bid), Some(ask)) if bid.price >= ask.price)
+        })
+    }
+
+    /// True if the symbol's book was last updated more than `max_age_nanos`
+    /// before `now_nanos`. A symbol with no book at all counts as stale.
+    pub fn is_stale(&self, symbol: &str, now_nanos: u128, max_age_nanos: u128) -> bool {
+        self.get_book(symbol)
+            .is_none_or(|book| now_nanos.saturating_sub(book.timestamp_nanos) > max_age_nanos)
+    }
+
+    /// Every symbol whose book is stale as of `now_nanos`, sorted
+    pub fn stale_symbols(&self, now_nanos: u128, max_age_nanos: u128) -> Vec<String> {
+        let mut stale: Vec<String> = self
+            .books
+            .keys()
+            .chain(self.venue_books.keys())
+            .filter(|symbol| self.is_stale(symbol, now_nanos, max_age_nanos))
+            .cloned()
+            .collect();
+        stale.sort();
+        stale.dedup();
+        stale
     }
 
     /// Get market depth (total quantity at each price level)
     pub fn get_depth(&self, symbol: &str, num_levels: usize) -> Option<(Vec<BookLevel>, Vec<BookLevel>)> {
-        self.books.get(symbol).map(|book| {
+        self.get_book(symbol).map(|book| {
             let bids = book.bids.iter()
                 .take(num_levels)
                 .cloned()
@@ -120,11 +416,343 @@ impl Default for OrderBookManager {
     }
 }
 
+/// Latest published book per symbol for readers on other threads. Writers
+/// swap in a new immutable map per publish and readers load it without
+/// locking, so a slow reader never holds up the feed.
+///
+/// The trade-off is memory: each publish copies the book and the
+/// symbol -> book map (one `Arc` per symbol), and a replaced book stays
+/// alive until the last reader holding it lets go.
+#[derive(Default)]
+pub struct SharedBooks {
+    books: ArcSwap<HashMap<String, Arc<OrderBook>>>,
+}
+
+impl SharedBooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `book` the current book for its symbol
+    pub fn publish(&self, book: OrderBook) {
+        let book = Arc::new(book);
+        self.books.rcu(|books| {
+            let mut books = HashMap::clone(books);
+            books.insert(book.symbol.clone(), book.clone());
+            books
+        });
+    }
+
+    /// Current book for `symbol`; holding it pins that version
+    pub fn load(&self, symbol: &str) -> Option<Arc<OrderBook>> {
+        self.books.load().get(symbol).cloned()
+    }
+
+    /// Symbols with a published book
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.books.load().keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+/// A level change and when it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookUpdate {
+    pub timestamp_nanos: u128,
+    pub change: LevelChange,
+}
+
+impl BookUpdate {
+    fn apply(&self, book: &mut OrderBook) {
+        book.timestamp_nanos = self.timestamp_nanos;
+        self.change.apply(book);
+    }
+}
+
+/// Event-sourced history of one symbol's book. Every update is kept, with a
+/// full snapshot every `snapshot_interval` updates so reconstructing a past
+/// state replays at most that many updates.
+#[derive(Debug)]
+pub struct BookHistory {
+    symbol: String,
+    snapshot_interval: usize,
+    updates: Vec<BookUpdate>,
+    // (number of updates applied, book after applying them)
+    snapshots: Vec<(usize, OrderBook)>,
+    current: OrderBook,
+}
+
+impl BookHistory {
+    pub fn new(symbol: String, snapshot_interval: usize) -> Self {
+        Self {
+            current: OrderBook::new(symbol.clone(), 0),
+            symbol,
+            snapshot_interval: snapshot_interval.max(1),
+            updates: Vec::new(),
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// Record an update. Updates are expected in timestamp order; one stamped
+    /// earlier than the last is treated as happening at the last timestamp.
+    pub fn record(&mut self, mut update: BookUpdate) {
+        if let Some(last) = self.updates.last() {
+            update.timestamp_nanos = update.timestamp_nanos.max(last.timestamp_nanos);
+        }
+        update.apply(&mut self.current);
+        self.updates.push(update);
+
+        if self.updates.len().is_multiple_of(self.snapshot_interval) {
+            self.snapshots.push((self.updates.len(), self.current.clone()));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Latest state of the book
+    pub fn current(&self) -> &OrderBook {
+        &self.current
+    }
+
+    /// The book as of `timestamp_nanos`, including every update stamped at or
+    /// before it. `None` if the history starts after that time.
+    pub fn state_at(&self, timestamp_nanos: u128) -> Option<OrderBook> {
+        let applied = self
+            .updates
+            .partition_point(|u| u.timestamp_nanos <= timestamp_nanos);
+        if applied == 0 {
+            return None;
+        }
+
+        // Latest snapshot that does not go past the target
+        let snapshot = self.snapshots.partition_point(|(count, _)| *count <= applied);
+        let (start, mut book) = match snapshot {
+            0 => (0, OrderBook::new(self.symbol.clone(), 0)),
+            i => (self.snapshots[i - 1].0, self.snapshots[i - 1].1.clone()),
+        };
+
+        for update in &self.updates[start..applied] {
+            update.apply(&mut book);
+        }
+        Some(book)
+    }
+}
+
+/// New quantity at one price level; zero removes the level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelChange {
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+impl LevelChange {
+    fn apply(&self, book: &mut OrderBook) {
+        if self.quantity <= 0.0 {
+            book.remove_level(self.side.clone(), self.price);
+        } else {
+            match self.side {
+                OrderSide::Buy => book.upsert_bid(self.price, self.quantity),
+                OrderSide::Sell => book.upsert_ask(self.price, self.quantity),
+            }
+        }
+    }
+}
+
+/// The levels that changed between two states of a symbol's book, so a
+/// stream can send a snapshot once and only what changed after it.
+/// Deltas are numbered from 1 after each snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub symbol: String,
+    pub sequence: u64,
+    pub timestamp_nanos: u128,
+    pub changes: Vec<LevelChange>,
+}
+
+impl BookDelta {
+    /// The fewest changes that turn `from` into `to`: every level `to` adds
+    /// or resizes, and a zero for every level only `from` has
+    pub fn between(from: &OrderBook, to: &OrderBook, sequence: u64) -> Self {
+        let mut changes = Vec::new();
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let (old, new) = match side {
+                OrderSide::Buy => (&from.bids, &to.bids),
+                OrderSide::Sell => (&from.asks, &to.asks),
+            };
+            for level in new {
+                let unchanged = from
+                    .level_index(&side, level.price)
+                    .is_ok_and(|i| old[i].quantity == level.quantity);
+                if !unchanged {
+                    changes.push(LevelChange {
+                        side: side.clone(),
+                        price: level.price,
+                        quantity: level.quantity,
+                    });
+                }
+            }
+            for level in old {
+                if to.level_index(&side, level.price).is_err() {
+                    changes.push(LevelChange {
+                        side: side.clone(),
+                        price: level.price,
+                        quantity: 0.0,
+                    });
+                }
+            }
+        }
+        Self {
+            symbol: to.symbol.clone(),
+            sequence,
+            timestamp_nanos: to.timestamp_nanos,
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Apply to the book state the delta was taken from
+    pub fn apply(&self, book: &mut OrderBook) {
+        book.timestamp_nanos = self.timestamp_nanos;
+        for change in &self.changes {
+            change.apply(book);
+        }
+    }
+}
+
+/// Receiving end of a snapshot-then-deltas stream for one symbol. A delta
+/// that skips a sequence number drops the book until the next snapshot,
+/// which the client should ask for with `Message::BookResync`.
+#[derive(Debug, Default)]
+pub struct BookSync {
+    book: Option<OrderBook>,
+    sequence: u64,
+}
+
+impl BookSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start over from a full book
+    pub fn on_snapshot(&mut self, book: OrderBook) {
+        self.book = Some(book);
+        self.sequence = 0;
+    }
+
+    /// Apply the next delta and return the updated book. Deltas already
+    /// applied are ignored; a gap, or a delta before any snapshot, is
+    /// `HftError::SequenceGap`.
+    pub fn on_delta(&mut self, delta: &BookDelta) -> HftResult<&OrderBook> {
+        let expected = self.sequence + 1;
+        if self.book.is_none() || delta.sequence > expected {
+            self.book = None;
+            return Err(HftError::SequenceGap {
+                symbol: delta.symbol.clone(),
+                expected,
+                got: delta.sequence,
+            });
+        }
+        let book = self.book.as_mut().expect("checked above");
+        if delta.sequence == expected {
+            delta.apply(book);
+            self.sequence = expected;
+        }
+        Ok(book)
+    }
+
+    /// The book, while in sync
+    pub fn book(&self) -> Option<&OrderBook> {
+        self.book.as_ref()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::time::{SystemTime, UNIX_EPOCH};
 
+    #[test]
+    fn test_crossed_snapshots_are_counted() {
+        let mut manager = OrderBookManager::new();
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 45000.0, 1, 1_000));
+        assert_eq!(manager.crossed_book_count(), 0);
+
+        let mut book = OrderBook::new("ETH/USD".to_string(), 2_000);
+        book.bids.push(BookLevel { price: 2500.0, quantity: 1.0 });
+        book.asks.push(BookLevel { price: 2500.0, quantity: 1.0 });
+        // Locked is not crossed
+        manager.apply_snapshot(book.clone());
+        assert_eq!(manager.crossed_book_count(), 0);
+
+        book.asks[0].price = 2499.0;
+        manager.apply_snapshot(book);
+        assert_eq!(manager.crossed_book_count(), 1);
+        assert!(manager.is_crossed("ETH/USD"));
+    }
+
+    #[test]
+    fn test_snapshots_are_isolated_from_later_updates() {
+        let mut manager = OrderBookManager::new();
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 1));
+
+        let copy = manager.snapshot("BTC/USD").unwrap();
+        let shared = SharedBooks::new();
+        shared.publish(copy.clone());
+        let held = shared.load("BTC/USD").unwrap();
+
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 46000.0, 10, 2));
+        shared.publish(manager.snapshot("BTC/USD").unwrap());
+
+        // Readers keep the version they took
+        assert_eq!(copy.timestamp_nanos, 1);
+        assert_eq!(held.timestamp_nanos, 1);
+        assert!(held.mid_price().unwrap() < 45100.0);
+        // New readers see the update
+        assert_eq!(shared.load("BTC/USD").unwrap().timestamp_nanos, 2);
+        assert!(shared.load("ETH/USD").is_none());
+        assert!(manager.snapshot("ETH/USD").is_none());
+        assert_eq!(shared.symbols(), vec!["BTC/USD".to_string()]);
+    }
+
+    #[test]
+    fn test_trade_classification() {
+        let mut manager = OrderBookManager::new();
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel { price: 99.0, quantity: 5.0 });
+        book.asks.push(BookLevel { price: 101.0, quantity: 5.0 });
+        manager.apply_snapshot(book);
+        let trade = |price, volume| MarketTick::new("BTC/USD".to_string(), price, volume, 2);
+
+        assert_eq!(manager.order_flow_imbalance("BTC/USD"), None);
+        assert_eq!(manager.record_trade(&trade(101.0, 3)), Some(OrderSide::Buy));
+        assert_eq!(manager.record_trade(&trade(99.0, 1)), Some(OrderSide::Sell));
+        // Inside the spread: above the mid lifts, below it hits
+        assert_eq!(manager.classify_trade(&trade(100.5, 1)), Some(OrderSide::Buy));
+        assert_eq!(manager.classify_trade(&trade(99.5, 1)), Some(OrderSide::Sell));
+        // At the mid, a downtick from the last trade at 99 would be a sell,
+        // but 100 is an uptick
+        assert_eq!(manager.record_trade(&trade(100.0, 2)), Some(OrderSide::Buy));
+        // Zero tick: same side as before
+        assert_eq!(manager.classify_trade(&trade(100.0, 1)), Some(OrderSide::Buy));
+
+        // 5 bought, 1 sold
+        assert!((manager.order_flow_imbalance("BTC/USD").unwrap() - 4.0 / 6.0).abs() < 1e-12);
+
+        // No book and no history: nothing to go on
+        assert_eq!(manager.classify_trade(&MarketTick::new("ETH/USD".to_string(), 1.0, 1, 1)), None);
+    }
+
     #[test]
     fn test_orderbook_manager() {
         let mut manager = OrderBookManager::new();
@@ -149,4 +777,244 @@ mod tests {
         let vwap = manager.calculate_vwap("BTC/USD", 3).unwrap();
         assert!(vwap > 0.0);
     }
+
+    #[test]
+    fn test_synthetic_depth_is_configurable() {
+        let mut manager = OrderBookManager::with_synthetic_depth(10, 4.0, 1.0).unwrap();
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 10_000.0, 100, 1));
+
+        let (bids, asks) = manager.get_depth("BTC/USD", 20).unwrap();
+        assert_eq!((bids.len(), asks.len()), (10, 10));
+        let (bid, ask) = manager.get_bbo("BTC/USD").unwrap();
+        assert!((bid - 9_998.0).abs() < 1e-9);
+        assert!((ask - 10_002.0).abs() < 1e-9);
+        // 1bp further out per level
+        assert!((bids[9].price - 9_989.0).abs() < 1e-9);
+        assert!((asks[9].price - 10_011.0).abs() < 1e-9);
+        assert!((asks[9].quantity - 10.0).abs() < 1e-9);
+
+        assert!(OrderBookManager::with_synthetic_depth(0, 4.0, 1.0).is_err());
+        assert!(OrderBookManager::with_synthetic_depth(10, 0.0, 1.0).is_err());
+    }
+
+    fn one_level(symbol: &str, bid: f64, ask: f64) -> OrderBook {
+        let mut book = OrderBook::new(symbol.to_string(), 1_000);
+        book.bids.push(BookLevel { price: bid, quantity: 1.0 });
+        book.asks.push(BookLevel { price: ask, quantity: 1.0 });
+        book
+    }
+
+    #[test]
+    fn test_consolidated_bbo_across_venues() {
+        let mut manager = OrderBookManager::new();
+        manager.apply_venue_snapshot("coinbase", one_level("BTC/USD", 45000.0, 45010.0));
+        manager.apply_venue_snapshot("kraken", one_level("BTC/USD", 44995.0, 45005.0));
+
+        // Each venue keeps its own book
+        assert_eq!(manager.venues("BTC/USD"), vec!["coinbase", "kraken"]);
+        let kraken = manager.venue_book("BTC/USD", Some("kraken")).unwrap();
+        assert_eq!(kraken.best_bid().unwrap().price, 44995.0);
+        // With no venue-less book, the venue book updated last stands in
+        assert_eq!(manager.get_book("BTC/USD").unwrap().best_bid().unwrap().price, 44995.0);
+
+        // Best bid from one venue, best ask from the other
+        let nbbo = manager.consolidated_bbo("BTC/USD").unwrap();
+        assert_eq!(nbbo.prices(), (45000.0, 45005.0));
+        assert_eq!(nbbo.bid_venue.as_deref(), Some("coinbase"));
+        assert_eq!(nbbo.ask_venue.as_deref(), Some("kraken"));
+        assert!(!nbbo.is_crossed());
+
+        // Kraken's ask drops under Coinbase's bid: crossed across venues,
+        // though neither book is crossed on its own
+        manager.apply_venue_snapshot("kraken", one_level("BTC/USD", 44990.0, 44998.0));
+        let nbbo = manager.consolidated_bbo("BTC/USD").unwrap();
+        assert!(nbbo.is_crossed());
+        assert_eq!(manager.crossed_book_count(), 0);
+        assert!(!manager.is_crossed("BTC/USD"));
+        assert_eq!(manager.get_bbo("BTC/USD"), Some((45000.0, 44998.0)));
+
+        // Venue ticks update their venue's book; venue-less ticks the shared one
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 46000.0, 5, 2_000).with_venue("coinbase"));
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 44000.0, 5, 2_000));
+        let nbbo = manager.consolidated_bbo("BTC/USD").unwrap();
+        assert_eq!(nbbo.bid_venue.as_deref(), Some("coinbase"));
+        assert_eq!(nbbo.ask_venue, None);
+        assert_eq!(manager.get_bbo("BTC/USD"), Some(nbbo.prices()));
+        assert!(manager.get_book("BTC/USD").unwrap().best_bid().unwrap().price < 44000.0);
+        assert!(manager.consolidated_bbo("ETH/USD").is_none());
+    }
+
+    #[test]
+    fn test_stale_books() {
+        let mut manager = OrderBookManager::new();
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 45000.0, 1, 1_000));
+        manager.update_from_tick(&MarketTick::new("ETH/USD".to_string(), 2500.0, 1, 5_000));
+
+        // Fresh
+        assert!(!manager.is_stale("ETH/USD", 5_100, 500));
+        // Exactly max_age old is still fresh; one nanosecond more is stale
+        assert!(!manager.is_stale("BTC/USD", 1_500, 500));
+        assert!(manager.is_stale("BTC/USD", 1_501, 500));
+        // Never seen
+        assert!(manager.is_stale("SOL/USD", 1_501, 500));
+
+        assert_eq!(manager.stale_symbols(5_100, 500), vec!["BTC/USD".to_string()]);
+        assert_eq!(manager.stale_symbols(10_000, 500), vec!["BTC/USD".to_string(), "ETH/USD".to_string()]);
+        assert!(manager.stale_symbols(5_100, 10_000).is_empty());
+    }
+
+    #[test]
+    fn test_book_checksum() {
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.upsert_bid(100.5, 2.0);
+        book.upsert_bid(100.0, 1.5);
+        book.upsert_ask(101.0, 3.0);
+
+        let one_decimal = ChecksumFormat {
+            price_decimals: 1,
+            quantity_decimals: 1,
+        };
+        // crc32("100.5:2.0:101.0:3.0:100.0:1.5")
+        assert_eq!(book.checksum_with(2, &one_decimal), 738317551);
+        // crc32("100.50:2.000:101.00:3.000:100.00:1.500")
+        let venue = ChecksumFormat {
+            price_decimals: 2,
+            quantity_decimals: 3,
+        };
+        assert_eq!(book.checksum_with(5, &venue), 842190618);
+
+        let mut manager = OrderBookManager::new();
+        manager.set_checksum_format(one_decimal);
+        manager.apply_snapshot(book);
+        assert!(manager.verify_checksum("BTC/USD", 2, 738317551));
+
+        // A missed update leaves our book out of step with the venue's
+        let mut desynced = manager.get_book("BTC/USD").unwrap().clone();
+        desynced.upsert_ask(101.0, 2.5);
+        manager.apply_snapshot(desynced);
+        assert!(!manager.verify_checksum("BTC/USD", 2, 738317551));
+        assert!(!manager.verify_checksum("ETH/USD", 2, 738317551));
+    }
+
+    fn update(ts: u128, side: OrderSide, price: f64, quantity: f64) -> BookUpdate {
+        BookUpdate {
+            timestamp_nanos: ts,
+            change: LevelChange { side, price, quantity },
+        }
+    }
+
+    #[test]
+    fn test_book_history_time_travel() {
+        let mut history = BookHistory::new("BTC/USD".to_string(), 3);
+        let updates = [
+            update(10, OrderSide::Buy, 100.0, 1.0),
+            update(20, OrderSide::Sell, 102.0, 1.0),
+            update(30, OrderSide::Buy, 101.0, 2.0),
+            update(40, OrderSide::Sell, 101.5, 1.0),
+            update(50, OrderSide::Buy, 101.0, 0.0),
+            update(60, OrderSide::Sell, 101.5, 0.0),
+            update(70, OrderSide::Buy, 99.0, 5.0),
+        ];
+        for u in updates {
+            history.record(u);
+        }
+        assert_eq!(history.len(), 7);
+
+        let bbo = |t: u128| {
+            history.state_at(t).map(|book| {
+                (
+                    book.best_bid().map(|l| l.price),
+                    book.best_ask().map(|l| l.price),
+                )
+            })
+        };
+
+        assert_eq!(bbo(5), None);
+        assert_eq!(bbo(10), Some((Some(100.0), None)));
+        assert_eq!(bbo(25), Some((Some(100.0), Some(102.0))));
+        assert_eq!(bbo(45), Some((Some(101.0), Some(101.5))));
+        assert_eq!(bbo(55), Some((Some(100.0), Some(101.5))));
+        assert_eq!(bbo(65), Some((Some(100.0), Some(102.0))));
+
+        let latest = history.state_at(u128::MAX).unwrap();
+        assert_eq!(latest.bids.len(), 2);
+        assert_eq!(latest.timestamp_nanos, 70);
+        assert_eq!(latest.bids[0].price, history.current().bids[0].price);
+    }
+
+    fn book_with(bids: &[(f64, f64)], asks: &[(f64, f64)], timestamp_nanos: u128) -> OrderBook {
+        let mut book = OrderBook::new("BTC/USD".to_string(), timestamp_nanos);
+        for &(price, quantity) in bids {
+            book.upsert_bid(price, quantity);
+        }
+        for &(price, quantity) in asks {
+            book.upsert_ask(price, quantity);
+        }
+        book
+    }
+
+    #[test]
+    fn test_delta_holds_only_changed_levels_and_rebuilds_target() {
+        let from = book_with(&[(100.0, 1.0), (99.0, 2.0), (98.0, 3.0)], &[(101.0, 1.0), (102.0, 2.0)], 1);
+        // One bid resized, one ask gone, one new ask; the rest untouched
+        let to = book_with(&[(100.0, 1.0), (99.0, 5.0), (98.0, 3.0)], &[(102.0, 2.0), (103.0, 4.0)], 2);
+
+        let delta = BookDelta::between(&from, &to, 1);
+        let change = |side, price, quantity| LevelChange { side, price, quantity };
+        assert_eq!(
+            delta.changes,
+            vec![
+                change(OrderSide::Buy, 99.0, 5.0),
+                change(OrderSide::Sell, 103.0, 4.0),
+                change(OrderSide::Sell, 101.0, 0.0),
+            ]
+        );
+
+        let mut rebuilt = from.clone();
+        delta.apply(&mut rebuilt);
+        assert_eq!(rebuilt, to);
+
+        // Identical books need nothing sent, and the delta survives the wire
+        assert!(BookDelta::between(&to, &to, 2).is_empty());
+        let json = serde_json::to_string(&crate::messaging::Message::BookDelta(delta.clone())).unwrap();
+        match serde_json::from_str(&json).unwrap() {
+            crate::messaging::Message::BookDelta(decoded) => assert_eq!(decoded, delta),
+            other => panic!("expected a delta, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_book_sync_rejects_deltas_after_a_gap() {
+        let books = [
+            book_with(&[(100.0, 1.0)], &[(101.0, 1.0)], 1),
+            book_with(&[(100.0, 2.0)], &[(101.0, 1.0)], 2),
+            book_with(&[(100.0, 2.0)], &[(101.5, 1.0)], 3),
+            book_with(&[(99.5, 1.0)], &[(101.5, 1.0)], 4),
+        ];
+        let deltas: Vec<BookDelta> = books
+            .windows(2)
+            .zip(1..)
+            .map(|(pair, sequence)| BookDelta::between(&pair[0], &pair[1], sequence))
+            .collect();
+
+        let mut sync = BookSync::new();
+        assert!(matches!(
+            sync.on_delta(&deltas[0]),
+            Err(HftError::SequenceGap { expected: 1, got: 1, .. })
+        ));
+        sync.on_snapshot(books[0].clone());
+        assert_eq!(sync.on_delta(&deltas[0]).unwrap(), &books[1]);
+        // A repeat is ignored
+        assert_eq!(sync.on_delta(&deltas[0]).unwrap(), &books[1]);
+
+        // Missing delta 2: out of sync until the next snapshot
+        assert!(matches!(
+            sync.on_delta(&deltas[2]),
+            Err(HftError::SequenceGap { expected: 2, got: 3, .. })
+        ));
+        assert!(sync.book().is_none());
+        sync.on_snapshot(books[2].clone());
+        let resynced = BookDelta { sequence: 1, ..deltas[2].clone() };
+        assert_eq!(sync.on_delta(&resynced).unwrap(), &books[3]);
+    }
 }
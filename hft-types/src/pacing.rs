@@ -0,0 +1,224 @@
+use crate::MarketTick;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Commands a paced replay takes between ticks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayCommand {
+    /// Stop emitting until `Resume` or `Step`
+    Pause,
+    Resume,
+    /// Multiple of recorded time to play at; `f64::INFINITY` for no pacing.
+    /// Anything not above zero is ignored.
+    SetSpeed(f64),
+    /// While paused, emit the next n ticks straight away
+    Step(u64),
+}
+
+/// Steers a running `replay_with_pacing` from another thread. Once every
+/// controller is dropped the replay plays on at its last speed.
+#[derive(Debug, Clone)]
+pub struct ReplayController {
+    tx: Sender<ReplayCommand>,
+}
+
+impl ReplayController {
+    /// Queue a command, returning false once the replay has finished
+    pub fn send(&self, command: ReplayCommand) -> bool {
+        self.tx.send(command).is_ok()
+    }
+
+    pub fn pause(&self) -> bool {
+        self.send(ReplayCommand::Pause)
+    }
+
+    pub fn resume(&self) -> bool {
+        self.send(ReplayCommand::Resume)
+    }
+
+    pub fn set_speed(&self, speed: f64) -> bool {
+        self.send(ReplayCommand::SetSpeed(speed))
+    }
+
+    pub fn step(&self, ticks: u64) -> bool {
+        self.send(ReplayCommand::Step(ticks))
+    }
+}
+
+/// Spaces replayed ticks out by their recorded gaps, scaled by a speed,
+/// and applies controller commands as they arrive. Waiting, paused or not,
+/// blocks on the command channel rather than spinning.
+#[derive(Debug)]
+pub struct ReplayPacer {
+    commands: Receiver<ReplayCommand>,
+    connected: bool,
+    speed: f64,
+    paused: bool,
+    steps: u64,
+    /// Recorded time and wall time of the last tick emitted
+    last: Option<(u128, Instant)>,
+}
+
+impl ReplayPacer {
+    pub fn new(speed: f64) -> (Self, ReplayController) {
+        let (tx, commands) = channel();
+        let pacer = Self {
+            commands,
+            connected: true,
+            speed: if speed > 0.0 { speed } else { 1.0 },
+            paused: false,
+            steps: 0,
+            last: None,
+        };
+        (pacer, ReplayController { tx })
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn apply(&mut self, command: ReplayCommand) {
+        match command {
+            ReplayCommand::Pause => self.paused = true,
+            ReplayCommand::Resume => {
+                self.paused = false;
+                self.steps = 0;
+                // Pick up from now rather than rushing to catch up
+                self.last = self.last.map(|(recorded, _)| (recorded, Instant::now()));
+            }
+            ReplayCommand::SetSpeed(speed) if speed > 0.0 => self.speed = speed,
+            ReplayCommand::SetSpeed(_) => {}
+            ReplayCommand::Step(ticks) if self.paused => self.steps += ticks,
+            ReplayCommand::Step(_) => {}
+        }
+    }
+
+    /// When a tick recorded at `timestamp_nanos` is due at the current speed
+    fn due(&self, timestamp_nanos: u128) -> Option<Instant> {
+        let (recorded, emitted) = self.last?;
+        let gap_secs = timestamp_nanos.saturating_sub(recorded) as f64 / 1e9;
+        emitted.checked_add(Duration::try_from_secs_f64(gap_secs / self.speed).ok()?)
+    }
+
+    /// Block until `tick` should be emitted
+    pub fn wait_for(&mut self, tick: &MarketTick) {
+        loop {
+            while self.connected {
+                match self.commands.try_recv() {
+                    Ok(command) => self.apply(command),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => self.connected = false,
+                }
+            }
+            if self.paused {
+                if self.steps > 0 {
+                    self.steps -= 1;
+                    break;
+                }
+                match self.commands.recv() {
+                    Ok(command) => self.apply(command),
+                    // Nobody is left to resume it
+                    Err(_) => {
+                        self.connected = false;
+                        self.paused = false;
+                    }
+                }
+                continue;
+            }
+
+            let Some(due) = self.due(tick.timestamp_nanos) else {
+                break;
+            };
+            let now = Instant::now();
+            if now >= due {
+                break;
+            }
+            if !self.connected {
+                std::thread::sleep(due - now);
+                break;
+            }
+            match self.commands.recv_timeout(due - now) {
+                Ok(command) => self.apply(command),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => self.connected = false,
+            }
+        }
+        self.last = Some((tick.timestamp_nanos, Instant::now()));
+    }
+}
+
+/// Play ticks from `next_tick` into `emit` at recorded pace under `pacer`.
+/// Returns the number of ticks emitted.
+pub fn replay_with_pacing(
+    mut next_tick: impl FnMut() -> std::io::Result<Option<MarketTick>>,
+    mut pacer: ReplayPacer,
+    mut emit: impl FnMut(MarketTick),
+) -> std::io::Result<u64> {
+    let mut emitted = 0;
+    while let Some(tick) = next_tick()? {
+        pacer.wait_for(&tick);
+        emit(tick);
+        emitted += 1;
+    }
+    Ok(emitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(count: u128) -> impl FnMut() -> std::io::Result<Option<MarketTick>> {
+        let mut i = 0;
+        move || {
+            i += 1;
+            // A millisecond apart in recorded time
+            Ok((i <= count)
+                .then(|| MarketTick::new("BTC/USD".to_string(), 45000.0, 1, i * 1_000_000)))
+        }
+    }
+
+    #[test]
+    fn test_step_emits_exactly_n_ticks_while_paused() {
+        let (pacer, controller) = ReplayPacer::new(1.0);
+        controller.pause();
+        controller.step(3);
+
+        let (tx, rx) = channel();
+        let replay = std::thread::spawn(move || {
+            replay_with_pacing(ticks(10), pacer, |tick| {
+                tx.send(tick.timestamp_nanos).unwrap()
+            })
+        });
+
+        let stepped: Vec<u128> = (0..3)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap() / 1_000_000)
+            .collect();
+        assert_eq!(stepped, vec![1, 2, 3]);
+        // Still paused: nothing more comes out
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(100)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        controller.set_speed(f64::INFINITY);
+        controller.step(1);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 4_000_000);
+        controller.resume();
+        assert_eq!(replay.join().unwrap().unwrap(), 10);
+        assert_eq!(rx.iter().count(), 6);
+        assert!(!controller.pause());
+    }
+
+    #[test]
+    fn test_speed_scales_recorded_gaps() {
+        // 9 gaps of 1ms at 0.1x is 90ms of wall time
+        let (pacer, _controller) = ReplayPacer::new(0.1);
+        let start = Instant::now();
+        assert_eq!(replay_with_pacing(ticks(10), pacer, |_| {}).unwrap(), 10);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
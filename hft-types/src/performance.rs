@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+/// Risk-adjusted performance of an equity curve: drawdown, time under
+/// water, and a Sharpe ratio over the most recent per-period returns.
+/// Every figure is finite; with too little data they read as zero.
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceTracker {
+    /// Returns kept for the Sharpe ratio; `None` keeps them all
+    window: Option<usize>,
+    returns: VecDeque<f64>,
+    /// Latest equity and when it was seen
+    last: Option<(f64, u128)>,
+    /// Highest equity so far and when it was first reached
+    peak: Option<(f64, u128)>,
+    max_drawdown: f64,
+    max_drawdown_nanos: u128,
+}
+
+impl PerformanceTracker {
+    /// Sharpe ratio over every return seen
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sharpe ratio over the last `window` returns
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            window: Some(window.max(2)),
+            ..Self::default()
+        }
+    }
+
+    /// Add a PnL increment to the curve, which starts from zero
+    pub fn record_pnl(&mut self, increment: f64, now_nanos: u128) {
+        if self.last.is_none() {
+            self.record_equity(0.0, now_nanos);
+        }
+        self.record_equity(self.equity() + increment, now_nanos);
+    }
+
+    /// Add an equity snapshot. The first one only sets the baseline.
+    pub fn record_equity(&mut self, equity: f64, now_nanos: u128) {
+        if !equity.is_finite() {
+            return;
+        }
+        let was_under_water = self.drawdown() > 0.0;
+        if let Some((last_equity, _)) = self.last {
+            self.returns.push_back(equity - last_equity);
+            if self.window.is_some_and(|window| self.returns.len() > window) {
+                self.returns.pop_front();
+            }
+        }
+        self.last = Some((equity, now_nanos));
+
+        match self.peak {
+            Some((peak, _)) if equity < peak => {
+                self.max_drawdown = self.max_drawdown.max(peak - equity);
+                self.max_drawdown_nanos = self.max_drawdown_nanos.max(self.drawdown_nanos());
+            }
+            _ => {
+                // A new high ends any drawdown; its length counts up to here
+                if let (true, Some((_, peaked_at))) = (was_under_water, self.peak) {
+                    let under_water = now_nanos.saturating_sub(peaked_at);
+                    self.max_drawdown_nanos = self.max_drawdown_nanos.max(under_water);
+                }
+                self.peak = Some((equity, now_nanos));
+            }
+        }
+    }
+
+    pub fn equity(&self) -> f64 {
+        self.last.map_or(0.0, |(equity, _)| equity)
+    }
+
+    /// Largest peak-to-trough fall so far
+    pub fn max_drawdown(&self) -> f64 {
+        self.max_drawdown
+    }
+
+    /// How far equity currently sits below its peak
+    pub fn drawdown(&self) -> f64 {
+        match (self.peak, self.last) {
+            (Some((peak, _)), Some((equity, _))) => (peak - equity).max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Time since the last peak; zero while equity is at a high
+    pub fn drawdown_nanos(&self) -> u128 {
+        match (self.peak, self.last) {
+            (Some((peak, peaked_at)), Some((equity, now))) if equity < peak => {
+                now.saturating_sub(peaked_at)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Longest stretch spent below a previous peak
+    pub fn max_drawdown_nanos(&self) -> u128 {
+        self.max_drawdown_nanos.max(self.drawdown_nanos())
+    }
+
+    /// Mean over population standard deviation of the windowed returns;
+    /// not annualised. Zero with fewer than two returns or no variance.
+    pub fn sharpe(&self) -> f64 {
+        if self.returns.len() < 2 {
+            return 0.0;
+        }
+        let n = self.returns.len() as f64;
+        let mean = self.returns.iter().sum::<f64>() / n;
+        let variance = self.returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+        if variance > f64::EPSILON {
+            mean / variance.sqrt()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_known_equity_curve() {
+        let mut tracker = PerformanceTracker::new();
+        let mut rolling = PerformanceTracker::with_window(3);
+        for (i, equity) in [100.0, 110.0, 105.0, 120.0, 90.0, 95.0, 130.0].into_iter().enumerate() {
+            tracker.record_equity(equity, (i as u128 + 1) * SEC);
+            rolling.record_equity(equity, (i as u128 + 1) * SEC);
+        }
+
+        // Peak 120 at t=4 down to 90; under water from t=4 until 130 at t=7
+        assert_eq!(tracker.max_drawdown(), 30.0);
+        assert_eq!(tracker.max_drawdown_nanos(), 3 * SEC);
+        assert_eq!(tracker.drawdown(), 0.0);
+        assert_eq!(tracker.drawdown_nanos(), 0);
+
+        // Returns 10, -5, 15, -30, 5, 35: mean 5, variance 2350 / 6
+        let expected = 5.0 / (2350.0f64 / 6.0).sqrt();
+        assert!((tracker.sharpe() - expected).abs() < 1e-12);
+
+        // Last three returns -30, 5, 35: mean 10 / 3, mean square 2150 / 3
+        let mean = 10.0 / 3.0;
+        let expected = mean / (2150.0f64 / 3.0 - mean * mean).sqrt();
+        assert!((rolling.sharpe() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_degenerate_curves_stay_finite() {
+        let mut tracker = PerformanceTracker::new();
+        assert_eq!(tracker.sharpe(), 0.0);
+        assert_eq!(tracker.max_drawdown_nanos(), 0);
+
+        tracker.record_pnl(5.0, SEC);
+        assert_eq!(tracker.equity(), 5.0);
+        assert_eq!(tracker.sharpe(), 0.0);
+
+        // Identical returns have no variance
+        tracker.record_pnl(5.0, 2 * SEC);
+        tracker.record_pnl(5.0, 3 * SEC);
+        assert_eq!(tracker.sharpe(), 0.0);
+
+        // Still under water: the open drawdown counts
+        tracker.record_pnl(-4.0, 5 * SEC);
+        tracker.record_equity(f64::NAN, 6 * SEC);
+        assert_eq!(tracker.drawdown(), 4.0);
+        assert_eq!(tracker.max_drawdown_nanos(), 2 * SEC);
+        assert!(tracker.sharpe().is_finite());
+    }
+}
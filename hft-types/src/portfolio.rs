@@ -0,0 +1,356 @@
+use crate::fees::FeeSchedule;
+use crate::matching::Fill;
+use crate::{MarketTick, Order, OrderSide};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+/// Net position in one symbol; positive quantity is long
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    pub avg_price: f64,
+    /// Net of fees
+    pub realized_pnl: f64,
+    /// Fees paid on fills in this symbol
+    #[serde(default)]
+    pub fees: f64,
+    pub last_price: f64,
+    pub opened_at_nanos: u128,
+    /// A flattening order has been emitted and not yet filled
+    pub flattening: bool,
+}
+
+impl Position {
+    fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            quantity: 0.0,
+            avg_price: 0.0,
+            realized_pnl: 0.0,
+            fees: 0.0,
+            last_price: 0.0,
+            opened_at_nanos: 0,
+            flattening: false,
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.quantity.abs() < f64::EPSILON
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        (self.last_price - self.avg_price) * self.quantity
+    }
+}
+
+/// What happens to a position's open time when it is added to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingClock {
+    /// Keep the time the position was first opened
+    #[default]
+    KeepOriginal,
+    /// Restart the clock whenever the position grows
+    ResetOnAdd,
+}
+
+impl FromStr for HoldingClock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep_original" => Ok(HoldingClock::KeepOriginal),
+            "reset_on_add" => Ok(HoldingClock::ResetOnAdd),
+            other => Err(format!(
+                "unknown holding clock '{}'; expected keep_original or reset_on_add",
+                other
+            )),
+        }
+    }
+}
+
+/// Orders remembered as having paid their flat fee
+const CHARGED_ORDER_WINDOW: usize = 100_000;
+
+/// Tracks positions and PnL from fills, optionally flattening
+/// positions held longer than `max_holding_nanos`. Fees from the schedule
+/// come straight off realized PnL.
+#[derive(Debug, Clone, Default)]
+pub struct PositionTracker {
+    positions: HashMap<String, Position>,
+    max_holding_nanos: Option<u128>,
+    holding_clock: HoldingClock,
+    fees: FeeSchedule,
+    /// Orders already charged the schedule's `per_order` fee, oldest first
+    charged: VecDeque<u64>,
+    charged_ids: HashSet<u64>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_holding(max_holding_nanos: u128, holding_clock: HoldingClock) -> Self {
+        Self {
+            max_holding_nanos: Some(max_holding_nanos),
+            holding_clock,
+            ..Self::default()
+        }
+    }
+
+    /// Charge fills according to `fees`
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.realized_pnl).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.unrealized_pnl()).sum()
+    }
+
+    /// Total fees paid, already taken out of `realized_pnl`
+    pub fn fees(&self) -> f64 {
+        self.positions.values().map(|p| p.fees).sum()
+    }
+
+    /// Replace tracked positions with ones saved from `positions`
+    pub fn restore(&mut self, positions: Vec<Position>) {
+        self.positions = positions
+            .into_iter()
+            .map(|position| (position.symbol.clone(), position))
+            .collect();
+    }
+
+    /// Apply an execution to the position for its symbol
+    pub fn apply_fill(&mut self, fill: &Fill) {
+        let position = self
+            .positions
+            .entry(fill.symbol.clone())
+            .or_insert_with(|| Position::new(fill.symbol.clone()));
+
+        let signed_qty = match fill.side {
+            OrderSide::Buy => fill.fill_qty,
+            OrderSide::Sell => -fill.fill_qty,
+        };
+        position.last_price = fill.fill_price;
+        position.flattening = false;
+
+        let first_fill = self.charged_ids.insert(fill.order_id);
+        if first_fill {
+            self.charged.push_back(fill.order_id);
+            if self.charged.len() > CHARGED_ORDER_WINDOW {
+                if let Some(oldest) = self.charged.pop_front() {
+                    self.charged_ids.remove(&oldest);
+                }
+            }
+        }
+        let fee = self.fees.fee(
+            &fill.symbol,
+            fill.fill_price * fill.fill_qty,
+            fill.is_maker,
+            first_fill,
+        );
+        position.fees += fee;
+        position.realized_pnl -= fee;
+
+        if position.is_flat() {
+            position.quantity = signed_qty;
+            position.avg_price = fill.fill_price;
+            position.opened_at_nanos = fill.timestamp_nanos;
+        } else if position.quantity.signum() == signed_qty.signum() {
+            // Adding to the position
+            let total = position.quantity + signed_qty;
+            position.avg_price =
+                (position.avg_price * position.quantity + fill.fill_price * signed_qty) / total;
+            position.quantity = total;
+            if self.holding_clock == HoldingClock::ResetOnAdd {
+                position.opened_at_nanos = fill.timestamp_nanos;
+            }
+        } else {
+            // Reducing, closing, or flipping the position
+            let closed = signed_qty.abs().min(position.quantity.abs());
+            position.realized_pnl +=
+                closed * (fill.fill_price - position.avg_price) * position.quantity.signum();
+            position.quantity += signed_qty;
+
+            if position.is_flat() {
+                position.quantity = 0.0;
+            } else if position.quantity.signum() == signed_qty.signum() {
+                // Flipped through zero: the remainder is a fresh position
+                position.avg_price = fill.fill_price;
+                position.opened_at_nanos = fill.timestamp_nanos;
+            }
+        }
+    }
+
+    /// The flattening order for `symbol` was never placed, so the next
+    /// tick past the limit may try again
+    pub fn cancel_flatten(&mut self, symbol: &str) {
+        if let Some(position) = self.positions.get_mut(symbol) {
+            position.flattening = false;
+        }
+    }
+
+    /// Mark positions to the tick and return flattening orders for any
+    /// position held past the limit. The returned orders carry `order_id` 0
+    /// for the caller to assign.
+    pub fn on_tick(&mut self, tick: &MarketTick) -> Vec<Order> {
+        if let Some(position) = self.positions.get_mut(&tick.symbol) {
+            position.last_price = tick.price;
+        }
+
+        let Some(max_holding) = self.max_holding_nanos else {
+            return Vec::new();
+        };
+
+        self.positions
+            .values_mut()
+            .filter(|p| !p.is_flat() && !p.flattening)
+            .filter(|p| tick.timestamp_nanos.saturating_sub(p.opened_at_nanos) >= max_holding)
+            .map(|p| {
+                p.flattening = true;
+                let side = if p.quantity > 0.0 {
+                    OrderSide::Sell
+                } else {
+                    OrderSide::Buy
+                };
+                Order::new(
+                    0,
+                    p.symbol.clone(),
+                    side,
+                    p.last_price,
+                    p.quantity.abs(),
+                    tick.timestamp_nanos,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fees::FeeRates;
+
+    fn fill(side: OrderSide, price: f64, qty: f64, ts: u128) -> Fill {
+        Fill {
+            order_id: 1,
+            symbol: "BTC/USD".to_string(),
+            side,
+            fill_price: price,
+            fill_qty: qty,
+            timestamp_nanos: ts,
+            is_maker: false,
+            client_id: None,
+        }
+    }
+
+    fn tick(price: f64, ts: u128) -> MarketTick {
+        MarketTick::new("BTC/USD".to_string(), price, 10, ts)
+    }
+
+    #[test]
+    fn test_realized_pnl_on_close() {
+        let mut tracker = PositionTracker::new();
+        tracker.apply_fill(&fill(OrderSide::Buy, 100.0, 2.0, 1));
+        tracker.apply_fill(&fill(OrderSide::Sell, 110.0, 2.0, 2));
+
+        let position = tracker.position("BTC/USD").unwrap();
+        assert!(position.is_flat());
+        assert_eq!(tracker.realized_pnl(), 20.0);
+    }
+
+    #[test]
+    fn test_holding_time_flatten() {
+        let mut tracker = PositionTracker::with_max_holding(1_000, HoldingClock::KeepOriginal);
+        tracker.apply_fill(&fill(OrderSide::Buy, 100.0, 3.0, 0));
+
+        assert!(tracker.on_tick(&tick(101.0, 999)).is_empty());
+
+        let orders = tracker.on_tick(&tick(102.0, 1_000));
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].side, OrderSide::Sell);
+        assert_eq!(orders[0].quantity, 3.0);
+        assert_eq!(orders[0].price, 102.0);
+
+        // Only emitted once while the flatten is outstanding
+        assert!(tracker.on_tick(&tick(102.0, 2_000)).is_empty());
+
+        // Unless it never made it to the book
+        tracker.cancel_flatten("BTC/USD");
+        assert_eq!(tracker.on_tick(&tick(102.0, 3_000)).len(), 1);
+    }
+
+    #[test]
+    fn test_adding_to_position_reset_vs_keep() {
+        let mut keep = PositionTracker::with_max_holding(1_000, HoldingClock::KeepOriginal);
+        let mut reset = PositionTracker::with_max_holding(1_000, HoldingClock::ResetOnAdd);
+        for tracker in [&mut keep, &mut reset] {
+            tracker.apply_fill(&fill(OrderSide::Buy, 100.0, 1.0, 0));
+            tracker.apply_fill(&fill(OrderSide::Buy, 100.0, 1.0, 800));
+        }
+
+        assert_eq!(keep.on_tick(&tick(100.0, 1_200)).len(), 1);
+        assert!(reset.on_tick(&tick(100.0, 1_200)).is_empty());
+        assert_eq!(reset.on_tick(&tick(100.0, 1_800)).len(), 1);
+    }
+
+    #[test]
+    fn test_fees_come_out_of_realized_pnl() {
+        let rates = FeeRates {
+            maker_bps: 1.0,
+            taker_bps: 10.0,
+            per_order: 0.0,
+        };
+        let mut maker = PositionTracker::new().with_fees(FeeSchedule::new(rates));
+        let mut taker = PositionTracker::new().with_fees(FeeSchedule::new(rates));
+        for (tracker, is_maker) in [(&mut maker, true), (&mut taker, false)] {
+            for (side, price, ts) in [(OrderSide::Buy, 100.0, 1), (OrderSide::Sell, 110.0, 2)] {
+                tracker.apply_fill(&Fill {
+                    is_maker,
+                    ..fill(side, price, 2.0, ts)
+                });
+            }
+        }
+
+        // 420 of notional traded either way
+        assert!((maker.fees() - 0.042).abs() < 1e-9);
+        assert!((taker.fees() - 0.42).abs() < 1e-9);
+        assert!((maker.realized_pnl() - (20.0 - 0.042)).abs() < 1e-9);
+        assert!((taker.realized_pnl() - (20.0 - 0.42)).abs() < 1e-9);
+
+        // Opening a position already costs its fee
+        let mut open = PositionTracker::new().with_fees(FeeSchedule::new(rates));
+        open.apply_fill(&fill(OrderSide::Buy, 100.0, 2.0, 1));
+        assert!((open.realized_pnl() + 0.2).abs() < 1e-9);
+
+        // The flat fee comes once per order, whatever the number of fills
+        let flat = FeeRates {
+            per_order: 1.0,
+            ..FeeRates::default()
+        };
+        let mut tracker = PositionTracker::new().with_fees(FeeSchedule::new(flat));
+        for order_id in [1, 1, 1, 2] {
+            tracker.apply_fill(&Fill {
+                order_id,
+                ..fill(OrderSide::Buy, 100.0, 1.0, 1)
+            });
+        }
+        assert_eq!(tracker.fees(), 2.0);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::config::SymbolUniverse;
+use crate::{MarketTick, TopOfBook};
+use std::collections::BTreeMap;
+
+/// Finest increment searched for when deriving decimals from a tick size
+const MAX_DECIMALS: u32 = 12;
+
+/// Largest magnitude an f64 holds every integer up to; past it, scaling to
+/// round would lose more than it saves
+const EXACT_INTEGER_LIMIT: f64 = 9_007_199_254_740_992.0;
+
+/// Decimal places prices are written with: per symbol, else a default,
+/// else in full. Only what a `MarketRecorder` given one writes is rounded;
+/// prices in memory and on the wire keep full precision.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PricePrecision {
+    default: Option<u32>,
+    symbols: BTreeMap<String, u32>,
+}
+
+impl PricePrecision {
+    pub const fn new() -> Self {
+        Self {
+            default: None,
+            symbols: BTreeMap::new(),
+        }
+    }
+
+    /// Decimals for symbols without their own
+    pub fn with_default(mut self, decimals: u32) -> Self {
+        self.default = Some(decimals);
+        self
+    }
+
+    pub fn with_symbol(mut self, symbol: &str, decimals: u32) -> Self {
+        self.symbols.insert(symbol.to_string(), decimals);
+        self
+    }
+
+    /// Decimals implied by each symbol's `tick_size`. Symbols without one,
+    /// or with one finer than 1e-12, are left to the default.
+    pub fn from_universe(universe: &SymbolUniverse) -> Self {
+        let mut precision = Self::new();
+        for config in universe.symbols() {
+            if let Some(decimals) = config.tick_size.and_then(decimals_for_increment) {
+                precision.symbols.insert(config.symbol.clone(), decimals);
+            }
+        }
+        precision
+    }
+
+    pub fn decimals(&self, symbol: &str) -> Option<u32> {
+        self.symbols.get(symbol).copied().or(self.default)
+    }
+
+    /// `price` as it should be written for `symbol`
+    pub fn round(&self, symbol: &str, price: f64) -> f64 {
+        match self.decimals(symbol) {
+            Some(decimals) => round_to_decimals(price, decimals),
+            None => price,
+        }
+    }
+
+    /// A copy of `tick` with its trade and quote prices rounded for writing
+    pub fn round_tick(&self, tick: &MarketTick) -> MarketTick {
+        let round = |price| self.round(&tick.symbol, price);
+        MarketTick {
+            price: round(tick.price),
+            quote: tick.quote.map(|quote| TopOfBook {
+                bid: round(quote.bid),
+                ask: round(quote.ask),
+                ..quote
+            }),
+            ..tick.clone()
+        }
+    }
+}
+
+/// Fewest decimal places that write every multiple of `increment` exactly,
+/// e.g. 2 for 0.05 and 0 for 5
+pub fn decimals_for_increment(increment: f64) -> Option<u32> {
+    if !(increment.is_finite() && increment > 0.0) {
+        return None;
+    }
+    (0..=MAX_DECIMALS).find(|&decimals| {
+        let scaled = increment * 10f64.powi(decimals as i32);
+        (scaled - scaled.round()).abs() < 1e-9 * scaled.max(1.0)
+    })
+}
+
+fn round_to_decimals(price: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    let scaled = price * scale;
+    if !scaled.is_finite() || scaled.abs() >= EXACT_INTEGER_LIMIT {
+        return price;
+    }
+    scaled.round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{MarketRecorder, MarketReplayer};
+    use crate::{round_to_increment, RoundingMode};
+
+    #[test]
+    fn test_decimals_follow_tick_size() {
+        assert_eq!(decimals_for_increment(0.5), Some(1));
+        assert_eq!(decimals_for_increment(0.05), Some(2));
+        assert_eq!(decimals_for_increment(0.01), Some(2));
+        assert_eq!(decimals_for_increment(5.0), Some(0));
+        assert_eq!(decimals_for_increment(0.0), None);
+
+        let universe =
+            SymbolUniverse::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/../config.toml"))
+                .unwrap();
+        let precision = PricePrecision::from_universe(&universe).with_default(4);
+        assert_eq!(precision.decimals("BTC/USD"), Some(1));
+        assert_eq!(precision.decimals("ETH/USD"), Some(2));
+        assert_eq!(precision.decimals("DOGE/USD"), Some(4));
+        assert_eq!(PricePrecision::new().round("BTC/USD", 0.1 + 0.2), 0.1 + 0.2);
+    }
+
+    #[test]
+    fn test_recorded_price_is_rounded_and_round_trips() {
+        let path = "/tmp/hft_test_precision.jsonl";
+        let precision = PricePrecision::new().with_symbol("BTC/USD", 2);
+
+        let price = 45000.1 + 1.2;
+        assert_eq!(price.to_string(), "45001.299999999996");
+        let quoted = |symbol: &str| {
+            MarketTick::new(symbol.to_string(), price, 3, 7).with_quote(TopOfBook {
+                bid: price - 0.01,
+                bid_size: 1.0,
+                ask: price + 0.01,
+                ask_size: 1.0,
+            })
+        };
+        let tick = quoted("BTC/USD");
+        {
+            let mut recorder = MarketRecorder::new(path).unwrap();
+            recorder.set_precision(precision);
+            recorder.record_tick(&tick).unwrap();
+            // Other symbols are written in full
+            recorder.record_tick(&quoted("ETH/USD")).unwrap();
+        }
+
+        let lines = std::fs::read_to_string(path).unwrap();
+        let (rounded, full) = lines.split_once('\n').unwrap();
+        assert!(rounded.contains("\"price\":45001.3,"), "{}", rounded);
+        assert!(rounded.contains("\"bid\":45001.29,"), "{}", rounded);
+        assert!(rounded.contains("\"ask\":45001.31,"), "{}", rounded);
+        assert!(full.contains("\"price\":45001.299999999996,"), "{}", full);
+
+        let mut replayer = MarketReplayer::new(path).unwrap();
+        let decoded = replayer.next_tick().unwrap().unwrap();
+        assert_eq!(
+            decoded.price,
+            round_to_increment(price, 0.01, RoundingMode::Nearest)
+        );
+        assert_eq!(decoded.price, 45001.3);
+        std::fs::remove_file(path).ok();
+
+        // Memory and every other serialization keep full precision
+        assert_eq!(tick.price, price);
+        let json = serde_json::to_string(&tick).unwrap();
+        assert!(json.contains("\"price\":45001.299999999996,"), "{}", json);
+    }
+}
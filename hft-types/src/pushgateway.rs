@@ -0,0 +1,210 @@
+use crate::{HftError, HftResult};
+use prometheus::Registry;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Pushgateway base URL, e.g. `http://localhost:9091`; pushing is off when unset
+pub const PUSHGATEWAY_URL_ENV: &str = "HFT_PUSHGATEWAY_URL";
+/// Job label to push under; defaults to the service name
+pub const PUSHGATEWAY_JOB_ENV: &str = "HFT_PUSHGATEWAY_JOB";
+/// Milliseconds between pushes
+pub const PUSHGATEWAY_INTERVAL_ENV: &str = "HFT_PUSHGATEWAY_INTERVAL_MS";
+
+pub const DEFAULT_PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Where and how often to push
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConfig {
+    pub url: String,
+    pub job: String,
+    pub interval: Duration,
+}
+
+impl PushConfig {
+    /// Read the `HFT_PUSHGATEWAY_*` variables; `None` unless a URL is set
+    pub fn from_env(default_job: &str) -> HftResult<Option<Self>> {
+        let Some(url) = std::env::var(PUSHGATEWAY_URL_ENV).ok().filter(|url| !url.is_empty()) else {
+            return Ok(None);
+        };
+        let interval = match std::env::var(PUSHGATEWAY_INTERVAL_ENV) {
+            Ok(ms) => match ms.parse::<u64>() {
+                Ok(ms) if ms > 0 => Duration::from_millis(ms),
+                _ => {
+                    return Err(HftError::InvalidConfig(format!(
+                        "{} must be a positive number of milliseconds, got {}",
+                        PUSHGATEWAY_INTERVAL_ENV, ms
+                    )))
+                }
+            },
+            Err(_) => DEFAULT_PUSH_INTERVAL,
+        };
+        Ok(Some(Self {
+            url,
+            job: std::env::var(PUSHGATEWAY_JOB_ENV).unwrap_or_else(|_| default_job.to_string()),
+            interval,
+        }))
+    }
+}
+
+/// Replace everything previously pushed under the job with `registry`'s
+/// current values. Blocks on the HTTP request.
+pub fn push(registry: &Registry, config: &PushConfig) -> HftResult<()> {
+    prometheus::push_metrics(
+        &config.job,
+        HashMap::new(),
+        &config.url,
+        registry.gather(),
+        None,
+    )
+    .map_err(|e| HftError::NetworkError(format!("push to {}: {}", config.url, e)))
+}
+
+/// Pushes a registry on a background thread every interval, and once more
+/// when stopped or dropped, so a run that exits between scrapes still
+/// reports its final values
+pub struct MetricsPusher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsPusher {
+    pub fn start(registry: Registry, config: PushConfig) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        tracing::info!(
+            "Pushing metrics to {} as job {} every {:?}",
+            config.url,
+            config.job,
+            config.interval
+        );
+        let thread = std::thread::spawn(move || loop {
+            let last = !matches!(
+                stopped.recv_timeout(config.interval),
+                Err(RecvTimeoutError::Timeout)
+            );
+            if let Err(e) = push(&registry, &config) {
+                tracing::warn!("Metrics push failed: {}", e);
+            }
+            if last {
+                break;
+            }
+        });
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Start pushing per the environment; `None` when no gateway is configured
+    pub fn from_env(default_job: &str, registry: &Registry) -> HftResult<Option<Self>> {
+        Ok(PushConfig::from_env(default_job)?.map(|config| Self::start(registry.clone(), config)))
+    }
+
+    /// Make the final push and wait for it
+    pub fn finish(mut self) {
+        self.push_final();
+    }
+
+    fn push_final(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MetricsPusher {
+    fn drop(&mut self) {
+        self.push_final();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::IntCounter;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    type Requests = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// Answer every request with 200, keeping (request line, body) for each
+    fn mock_gateway() -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+        let received = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                received
+                    .lock()
+                    .unwrap()
+                    .push((request_line.trim().to_string(), body));
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn test_push_sends_job_and_metrics() {
+        let registry = Registry::new();
+        let counter = IntCounter::new("backtest_ticks_total", "Ticks replayed").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.inc_by(42);
+
+        let (url, requests) = mock_gateway();
+        let pusher = MetricsPusher::start(
+            registry,
+            PushConfig {
+                url,
+                job: "backtest".to_string(),
+                interval: Duration::from_millis(50),
+            },
+        );
+        std::thread::sleep(Duration::from_millis(200));
+        let interval_pushes = requests.lock().unwrap().len();
+        assert!(interval_pushes >= 1);
+
+        // Finishing waits for one last push
+        pusher.finish();
+        let requests = requests.lock().unwrap();
+        assert!(requests.len() > interval_pushes);
+        for (request_line, body) in requests.iter() {
+            assert!(
+                request_line.starts_with("PUT /metrics/job/backtest "),
+                "{}",
+                request_line
+            );
+            assert!(contains(body, b"backtest_ticks_total"));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::orderbook::OrderBookManager;
+use crate::{BookLevel, MarketTick, OrderBook};
+use std::collections::HashSet;
+
+/// Best-effort books for a replay. A tick carrying a quote sets the top of
+/// its symbol's book; once a symbol has been quoted, trade-only ticks leave
+/// that quote in place. Symbols never quoted fall back to the synthetic
+/// book `OrderBookManager::update_from_tick` builds around each trade.
+///
+/// Strategies read the books through `books()`, the same `OrderBookManager`
+/// they get live.
+#[derive(Default)]
+pub struct BookReconstructor {
+    books: OrderBookManager,
+    quoted: HashSet<String>,
+    synthetic_updates: u64,
+}
+
+impl BookReconstructor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold one replayed tick into its symbol's book
+    pub fn on_tick(&mut self, tick: &MarketTick) {
+        let Some(quote) = tick.quote else {
+            if self.quoted.contains(&tick.symbol) {
+                self.books.record_trade(tick);
+            } else {
+                self.synthetic_updates += 1;
+                self.books.update_from_tick(tick);
+            }
+            return;
+        };
+
+        // Classify the trade against the book it printed into before requoting
+        self.books.record_trade(tick);
+        let mut book = OrderBook::new(tick.symbol.clone(), tick.timestamp_nanos);
+        book.bids.push(BookLevel {
+            price: quote.bid,
+            quantity: quote.bid_size,
+        });
+        book.asks.push(BookLevel {
+            price: quote.ask,
+            quantity: quote.ask_size,
+        });
+        self.books.apply_snapshot(book);
+        self.quoted.insert(tick.symbol.clone());
+    }
+
+    pub fn books(&self) -> &OrderBookManager {
+        &self.books
+    }
+
+    /// Whether `symbol`'s book comes from quotes rather than the synthetic model
+    pub fn is_quoted(&self, symbol: &str) -> bool {
+        self.quoted.contains(symbol)
+    }
+
+    /// Ticks that fell back to a synthetic book
+    pub fn synthetic_updates(&self) -> u64 {
+        self.synthetic_updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{MarketRecorder, MarketReplayer};
+    use crate::TopOfBook;
+
+    fn quoted(price: f64, bid: f64, ask: f64, timestamp: u128) -> MarketTick {
+        MarketTick::new("BTC/USD".to_string(), price, 1, timestamp).with_quote(TopOfBook {
+            bid,
+            bid_size: 2.0,
+            ask,
+            ask_size: 3.0,
+        })
+    }
+
+    #[test]
+    fn test_replayed_quotes_rebuild_bbo() {
+        let path = "/tmp/hft_test_reconstruct.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(path).unwrap();
+            for tick in [
+                quoted(45000.0, 44999.0, 45001.0, 1),
+                MarketTick::new("ETH/USD".to_string(), 2500.0, 4, 2),
+                quoted(45001.0, 45000.0, 45002.0, 3),
+                // Trade with no quote: the last quote stands
+                MarketTick::new("BTC/USD".to_string(), 45002.0, 1, 4),
+                quoted(44998.0, 44997.5, 44999.0, 5),
+            ] {
+                recorder.record_tick(&tick).unwrap();
+            }
+        }
+
+        let mut replayer = MarketReplayer::new(path).unwrap();
+        let mut reconstructor = BookReconstructor::new();
+        let mut bbos = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            reconstructor.on_tick(&tick);
+            if tick.symbol == "BTC/USD" {
+                bbos.push(reconstructor.books().get_bbo("BTC/USD").unwrap());
+            }
+        }
+        assert_eq!(
+            bbos,
+            vec![
+                (44999.0, 45001.0),
+                (45000.0, 45002.0),
+                (45000.0, 45002.0),
+                (44997.5, 44999.0),
+            ]
+        );
+        let book = reconstructor.books().get_book("BTC/USD").unwrap();
+        assert_eq!(book.best_bid().unwrap().quantity, 2.0);
+        assert_eq!(book.best_ask().unwrap().quantity, 3.0);
+        assert_eq!(book.timestamp_nanos, 5);
+
+        // ETH never carried a quote, so it gets the synthetic book
+        assert!(reconstructor.is_quoted("BTC/USD"));
+        assert!(!reconstructor.is_quoted("ETH/USD"));
+        assert_eq!(reconstructor.synthetic_updates(), 1);
+        let (bid, ask) = reconstructor.books().get_bbo("ETH/USD").unwrap();
+        assert!(bid < 2500.0 && ask > 2500.0);
+        assert_eq!(reconstructor.books().get_depth("ETH/USD", 10).unwrap().0.len(), 5);
+
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -1,14 +1,117 @@
-use crate::MarketTick;
+use crate::precision::PricePrecision;
+use crate::rotation::MergedReplayer;
+use crate::{HftError, MarketTick};
+use flate2::read::GzDecoder;
+use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// How the recorder treats a tick whose price matches the previous one
+/// for the same symbol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compaction {
+    /// Record every tick
+    #[default]
+    None,
+    /// Drop unchanged ticks; they are not recoverable on replay
+    SkipUnchanged,
+    /// Record the first tick of a run and a repeat count when the run ends.
+    /// Replay expands the run, stamping repeats with the run's last timestamp.
+    /// Any tick written ends every open run first, so a run only spans
+    /// repeats that nothing else was recorded between, and the expanded
+    /// recording stays in timestamp order.
+    RunLength,
+}
+
+/// Marker closing a run of unchanged ticks under `Compaction::RunLength`
+#[derive(Debug, Serialize, Deserialize)]
+struct RepeatMarker {
+    symbol: String,
+    repeat: u64,
+    last_timestamp_nanos: u128,
+}
+
+/// One line of a recording
+#[derive(Debug)]
+enum RecordLine {
+    Tick(MarketTick),
+    Repeat(RepeatMarker),
+}
+
+impl RecordLine {
+    // Not `#[serde(untagged)]`: untagged enums cannot buffer u128 timestamps
+    fn parse(line: &[u8]) -> std::io::Result<Self> {
+        serde_json::from_slice(line)
+            .map(RecordLine::Tick)
+            .or_else(|e| serde_json::from_slice(line).map(RecordLine::Repeat).map_err(|_| e))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Expands run-length repeat markers back into the ticks they stand for
+#[derive(Debug, Default)]
+struct RunExpander {
+    last_ticks: HashMap<String, MarketTick>,
+    // Expanded copies still to emit for the current repeat marker
+    pending: Option<(MarketTick, u64)>,
+}
+
+impl RunExpander {
+    /// Next copy owed by the last repeat marker
+    fn next_pending(&mut self) -> Option<MarketTick> {
+        let (tick, remaining) = self.pending.as_mut()?;
+        let tick = tick.clone();
+        *remaining -= 1;
+        if *remaining == 0 {
+            self.pending = None;
+        }
+        Some(tick)
+    }
+
+    /// The tick a recording line stands for. A repeat marker queues its
+    /// copies for `next_pending` and yields nothing itself.
+    fn on_record(&mut self, record: RecordLine) -> std::io::Result<Option<MarketTick>> {
+        match record {
+            RecordLine::Tick(tick) => {
+                self.last_ticks.insert(tick.symbol.clone(), tick.clone());
+                Ok(Some(tick))
+            }
+            RecordLine::Repeat(marker) => {
+                let Some(last) = self.last_ticks.get(&marker.symbol) else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("repeat marker for {} before any tick", marker.symbol),
+                    ));
+                };
+                let mut tick = last.clone();
+                tick.timestamp_nanos = marker.last_timestamp_nanos;
+                if marker.repeat > 0 {
+                    self.pending = Some((tick, marker.repeat));
+                }
+                Ok(None)
+            }
+        }
+    }
+}
 
 /// Market data recorder for backtesting
 #[derive(Debug)]
 pub struct MarketRecorder {
     file: File,
     tick_count: u64,
+    manifest: Option<ManifestBuilder>,
+    compaction: Compaction,
+    last_prices: HashMap<String, f64>,
+    // Open runs per symbol: (repeats, last timestamp)
+    runs: BTreeMap<String, (u64, u128)>,
+    bytes_written: u64,
+    /// Rounds prices as they are written; full precision without one
+    precision: Option<PricePrecision>,
 }
 
 impl MarketRecorder {
@@ -16,63 +119,462 @@ impl MarketRecorder {
         let file = File::create(path)?;
         Ok(Self {
             file,
+            bytes_written: 0,
             tick_count: 0,
+            manifest: None,
+            compaction: Compaction::None,
+            last_prices: HashMap::new(),
+            runs: BTreeMap::new(),
+            precision: None,
         })
     }
 
+    pub fn set_compaction(&mut self, compaction: Compaction) {
+        self.compaction = compaction;
+    }
+
+    /// Write prices with `precision`'s decimals from now on
+    pub fn set_precision(&mut self, precision: PricePrecision) {
+        self.precision = Some(precision);
+    }
+
+    /// Create a recorder that also maintains a sidecar manifest
+    /// (see [`manifest_path`]), rewritten on every flush and on drop.
+    pub fn with_manifest<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let mut recorder = Self::new(&path)?;
+        recorder.manifest = Some(ManifestBuilder::new(path.as_ref()));
+        Ok(recorder)
+    }
+
     pub fn record_tick(&mut self, tick: &MarketTick) -> std::io::Result<()> {
-        let json = serde_json::to_string(tick)?;
-        writeln!(self.file, "{}", json)?;
         self.tick_count += 1;
+
+        if self.compaction != Compaction::None {
+            let unchanged = self.last_prices.get(&tick.symbol) == Some(&tick.price);
+            match (self.compaction, unchanged) {
+                (Compaction::SkipUnchanged, true) => return Ok(()),
+                (Compaction::RunLength, true) => {
+                    let run = self.runs.entry(tick.symbol.clone()).or_default();
+                    run.0 += 1;
+                    run.1 = tick.timestamp_nanos;
+                    return Ok(());
+                }
+                (Compaction::RunLength, false) => self.close_runs()?,
+                _ => {}
+            }
+            self.last_prices.insert(tick.symbol.clone(), tick.price);
+        }
+
+        let mut json = match &self.precision {
+            Some(precision) => serde_json::to_string(&precision.round_tick(tick))?,
+            None => serde_json::to_string(tick)?,
+        };
+        json.push('\n');
+        self.file.write_all(json.as_bytes())?;
+        self.bytes_written += json.len() as u64;
+
+        if let Some(manifest) = self.manifest.as_mut() {
+            manifest.observe(tick, json.as_bytes());
+        }
+        Ok(())
+    }
+
+    /// Write the repeat markers for all open runs, oldest last repeat first
+    fn close_runs(&mut self) -> std::io::Result<()> {
+        let mut markers: Vec<RepeatMarker> = std::mem::take(&mut self.runs)
+            .into_iter()
+            .map(|(symbol, (repeat, last_timestamp_nanos))| RepeatMarker {
+                symbol,
+                repeat,
+                last_timestamp_nanos,
+            })
+            .collect();
+        markers.sort_by_key(|marker| marker.last_timestamp_nanos);
+        for marker in markers {
+            self.write_marker(marker)?;
+        }
         Ok(())
     }
 
+    fn write_marker(&mut self, marker: RepeatMarker) -> std::io::Result<()> {
+        let mut json = serde_json::to_string(&marker)?;
+        json.push('\n');
+        self.file.write_all(json.as_bytes())?;
+        self.bytes_written += json.len() as u64;
+
+        if let Some(manifest) = self.manifest.as_mut() {
+            manifest.observe_repeat(&marker, json.as_bytes());
+        }
+        Ok(())
+    }
+
+    /// Ticks offered to the recorder, including any compacted away
     pub fn tick_count(&self) -> u64 {
         self.tick_count
     }
 
+    /// Bytes written to the recording so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
     pub fn flush(&mut self) -> std::io::Result<()> {
-        self.file.flush()
+        self.close_runs()?;
+        self.file.flush()?;
+        if let Some(manifest) = &self.manifest {
+            manifest.write(self.tick_count)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MarketRecorder {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Sidecar manifest describing a recording, used to detect tampering
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingManifest {
+    pub data_file: String,
+    pub tick_count: u64,
+    pub start_timestamp: u128,
+    pub end_timestamp: u128,
+    pub symbols: Vec<String>,
+    pub sha256: String,
+}
+
+/// Sidecar manifest location for a recording: `capture.jsonl` -> `capture.manifest.json`
+pub fn manifest_path<P: AsRef<Path>>(recording: P) -> PathBuf {
+    recording.as_ref().with_extension("manifest.json")
+}
+
+/// Recompute the checksum of a recording and compare it against its manifest.
+/// Returns the manifest on success and an `InvalidData` error wrapping
+/// `HftError::ChecksumMismatch` on mismatch.
+pub fn verify_manifest<P: AsRef<Path>>(recording: P) -> std::io::Result<RecordingManifest> {
+    let manifest_file = File::open(manifest_path(&recording))?;
+    let manifest: RecordingManifest = serde_json::from_reader(BufReader::new(manifest_file))?;
+
+    let mut hasher = Sha256::new();
+    let mut reader = BufReader::new(File::open(&recording)?);
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    let actual = to_hex(&hasher.finalize());
+    if actual != manifest.sha256 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            HftError::ChecksumMismatch {
+                subject: recording.as_ref().display().to_string(),
+                expected: manifest.sha256,
+                actual,
+            },
+        ));
     }
+
+    Ok(manifest)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
-/// Market data replayer for backtesting
+/// Running manifest state, updated as ticks are written
+#[derive(Debug)]
+struct ManifestBuilder {
+    data_path: PathBuf,
+    hasher: Sha256,
+    start_timestamp: Option<u128>,
+    end_timestamp: u128,
+    symbols: BTreeSet<String>,
+}
+
+impl ManifestBuilder {
+    fn new(data_path: &Path) -> Self {
+        Self {
+            data_path: data_path.to_path_buf(),
+            hasher: Sha256::new(),
+            start_timestamp: None,
+            end_timestamp: 0,
+            symbols: BTreeSet::new(),
+        }
+    }
+
+    fn observe(&mut self, tick: &MarketTick, bytes: &[u8]) {
+        self.hasher.update(bytes);
+        self.start_timestamp.get_or_insert(tick.timestamp_nanos);
+        self.end_timestamp = tick.timestamp_nanos;
+        if !self.symbols.contains(&tick.symbol) {
+            self.symbols.insert(tick.symbol.clone());
+        }
+    }
+
+    fn observe_repeat(&mut self, marker: &RepeatMarker, bytes: &[u8]) {
+        self.hasher.update(bytes);
+        self.end_timestamp = self.end_timestamp.max(marker.last_timestamp_nanos);
+    }
+
+    fn write(&self, tick_count: u64) -> std::io::Result<()> {
+        let manifest = RecordingManifest {
+            data_file: self
+                .data_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            tick_count,
+            start_timestamp: self.start_timestamp.unwrap_or(0),
+            end_timestamp: self.end_timestamp,
+            symbols: self.symbols.iter().cloned().collect(),
+            sha256: to_hex(&self.hasher.clone().finalize()),
+        };
+
+        let file = File::create(manifest_path(&self.data_path))?;
+        serde_json::to_writer_pretty(file, &manifest)?;
+        Ok(())
+    }
+}
+
+/// Recording file, decompressed on the fly when gzipped
+#[derive(Debug)]
+enum RecordingSource {
+    Plain(File),
+    Gzip(GzDecoder<File>),
+}
+
+impl Read for RecordingSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            RecordingSource::Plain(file) => file.read(buf),
+            RecordingSource::Gzip(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+/// Market data replayer for backtesting. Run-length compacted
+/// recordings are expanded back into individual ticks, and `.gz`
+/// recordings are decompressed as they are read.
 #[derive(Debug)]
 pub struct MarketReplayer {
-    reader: BufReader<File>,
+    reader: BufReader<RecordingSource>,
     tick_count: u64,
+    runs: RunExpander,
+    // Tick read ahead by a seek, returned before anything else
+    peeked: Option<MarketTick>,
 }
 
 impl MarketReplayer {
     pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        let file = File::open(&path)?;
+        let source = if path.as_ref().extension().is_some_and(|ext| ext == "gz") {
+            RecordingSource::Gzip(GzDecoder::new(file))
+        } else {
+            RecordingSource::Plain(file)
+        };
         Ok(Self {
-            reader,
+            reader: BufReader::new(source),
             tick_count: 0,
+            runs: RunExpander::default(),
+            peeked: None,
         })
     }
 
     pub fn next_tick(&mut self) -> std::io::Result<Option<MarketTick>> {
-        let mut line = String::new();
-        let bytes_read = self.reader.read_line(&mut line)?;
+        loop {
+            if let Some(tick) = self.peeked.take().or_else(|| self.runs.next_pending()) {
+                self.tick_count += 1;
+                return Ok(Some(tick));
+            }
+
+            let mut line = String::new();
+            let bytes_read = self.reader.read_line(&mut line)?;
+
+            if bytes_read == 0 {
+                return Ok(None);
+            }
+
+            if let Some(tick) = self.runs.on_record(RecordLine::parse(line.as_bytes())?)? {
+                self.tick_count += 1;
+                return Ok(Some(tick));
+            }
+        }
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// Hand `tick` back so the next `next_tick` returns it again
+    fn unread(&mut self, tick: MarketTick) {
+        self.tick_count -= 1;
+        self.peeked = Some(tick);
+    }
+
+    /// Skip forward so the next `next_tick` returns the first tick with
+    /// `timestamp_nanos >= target_nanos`, or `None` if there is none.
+    /// This is a linear scan from the current position and assumes the
+    /// recording's timestamps never go backwards.
+    pub fn seek_to_timestamp(&mut self, target_nanos: u128) -> std::io::Result<()> {
+        while let Some(tick) = self.next_tick()? {
+            if tick.timestamp_nanos >= target_nanos {
+                self.unread(tick);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ticks with `start_nanos <= timestamp_nanos < end_nanos`. Seeks to
+    /// `start_nanos` first, so the same ordering assumption applies; the
+    /// first tick at or after `end_nanos` is left for `next_tick`.
+    pub fn take_range(
+        &mut self,
+        start_nanos: u128,
+        end_nanos: u128,
+    ) -> impl Iterator<Item = std::io::Result<MarketTick>> + '_ {
+        let mut seek = Some(self.seek_to_timestamp(start_nanos));
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if let Some(Err(e)) = seek.take() {
+                done = true;
+                return Some(Err(e));
+            }
+            if done {
+                return None;
+            }
+            match self.next_tick() {
+                Ok(Some(tick)) if tick.timestamp_nanos < end_nanos => Some(Ok(tick)),
+                Ok(Some(tick)) => {
+                    self.unread(tick);
+                    done = true;
+                    None
+                }
+                Ok(None) => {
+                    done = true;
+                    None
+                }
+                Err(e) => {
+                    done = true;
+                    Some(Err(e))
+                }
+            }
+        })
+    }
+}
+
+/// Replayer over a memory-mapped recording, for multi-GB files scanned
+/// repeatedly (e.g. parameter sweeps). Lines are sliced straight out of
+/// the mapping rather than copied into a per-line `String`, and each is
+/// only parsed when its tick is asked for. Yields the same ticks as
+/// `MarketReplayer`, run-length markers included; gzipped recordings
+/// can't be mapped and are refused.
+#[derive(Debug)]
+pub struct MmapReplayer {
+    mmap: Mmap,
+    /// Offset of the next unread line
+    pos: usize,
+    tick_count: u64,
+    runs: RunExpander,
+}
 
-        if bytes_read == 0 {
-            return Ok(None);
+impl MmapReplayer {
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        if path.as_ref().extension().is_some_and(|ext| ext == "gz") {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "gzipped recordings can't be memory-mapped; use MarketReplayer",
+            ));
         }
+        let file = File::open(path)?;
+        // SAFETY: recordings are written once and not modified while replayed
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            mmap,
+            pos: 0,
+            tick_count: 0,
+            runs: RunExpander::default(),
+        })
+    }
 
-        match serde_json::from_str(&line) {
-            Ok(tick) => {
+    /// The next raw line, without its newline. The last line needs no
+    /// trailing newline.
+    pub fn next_line(&mut self) -> Option<&[u8]> {
+        let rest = self.mmap.get(self.pos..).filter(|rest| !rest.is_empty())?;
+        let (line, advance) = match memchr::memchr(b'\n', rest) {
+            Some(end) => (&rest[..end], end + 1),
+            None => (rest, rest.len()),
+        };
+        self.pos += advance;
+        Some(line)
+    }
+
+    pub fn next_tick(&mut self) -> std::io::Result<Option<MarketTick>> {
+        loop {
+            if let Some(tick) = self.runs.next_pending() {
                 self.tick_count += 1;
-                Ok(Some(tick))
+                return Ok(Some(tick));
+            }
+            let Some(line) = self.next_line() else {
+                return Ok(None);
+            };
+            let record = RecordLine::parse(line)?;
+            if let Some(tick) = self.runs.on_record(record)? {
+                self.tick_count += 1;
+                return Ok(Some(tick));
             }
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
         }
     }
 
     pub fn tick_count(&self) -> u64 {
         self.tick_count
     }
+
+    /// Start over from the first line, e.g. for the next run of a sweep
+    pub fn rewind(&mut self) {
+        self.pos = 0;
+        self.tick_count = 0;
+        self.runs = RunExpander::default();
+    }
+}
+
+/// Replay statistics for one symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolStats {
+    pub tick_count: u64,
+    pub first_timestamp: u128,
+    pub last_timestamp: u128,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub mean_price: f64,
+}
+
+impl SymbolStats {
+    fn new(tick: &MarketTick) -> Self {
+        Self {
+            tick_count: 1,
+            first_timestamp: tick.timestamp_nanos,
+            last_timestamp: tick.timestamp_nanos,
+            min_price: tick.price,
+            max_price: tick.price,
+            mean_price: tick.price,
+        }
+    }
+
+    fn observe(&mut self, tick: &MarketTick) {
+        self.tick_count += 1;
+        self.last_timestamp = tick.timestamp_nanos;
+        self.min_price = self.min_price.min(tick.price);
+        self.max_price = self.max_price.max(tick.price);
+        // Running mean, so a long file can't accumulate a huge sum
+        self.mean_price += (tick.price - self.mean_price) / self.tick_count as f64;
+    }
 }
 
 /// Replay statistics
@@ -83,22 +585,41 @@ pub struct ReplayStats {
     pub end_timestamp: u128,
     pub duration_ms: u64,
     pub symbols: Vec<String>,
+    #[serde(default)]
+    pub per_symbol: HashMap<String, SymbolStats>,
 }
 
 impl ReplayStats {
     pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
         let mut replayer = MarketReplayer::new(path)?;
+        Self::from_ticks(|| replayer.next_tick())
+    }
+
+    /// Stats over a directory of rotated segments, replayed in order
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> std::io::Result<Self> {
+        let mut replayer = MergedReplayer::from_dir(dir)?;
+        Self::from_ticks(|| replayer.next_tick())
+    }
+
+    fn from_ticks(
+        mut next_tick: impl FnMut() -> std::io::Result<Option<MarketTick>>,
+    ) -> std::io::Result<Self> {
         let mut total_ticks = 0u64;
         let mut start_timestamp = 0u128;
         let mut end_timestamp = 0u128;
-        let mut symbols = std::collections::HashSet::new();
+        let mut per_symbol: HashMap<String, SymbolStats> = HashMap::new();
 
-        while let Some(tick) = replayer.next_tick()? {
+        while let Some(tick) = next_tick()? {
             if total_ticks == 0 {
                 start_timestamp = tick.timestamp_nanos;
             }
             end_timestamp = tick.timestamp_nanos;
-            symbols.insert(tick.symbol);
+            match per_symbol.get_mut(&tick.symbol) {
+                Some(stats) => stats.observe(&tick),
+                None => {
+                    per_symbol.insert(tick.symbol.clone(), SymbolStats::new(&tick));
+                }
+            }
             total_ticks += 1;
         }
 
@@ -109,7 +630,8 @@ impl ReplayStats {
             start_timestamp,
             end_timestamp,
             duration_ms,
-            symbols: symbols.into_iter().collect(),
+            symbols: per_symbol.keys().cloned().collect(),
+            per_symbol,
         })
     }
 }
@@ -161,4 +683,282 @@ mod tests {
         // Cleanup
         std::fs::remove_file(temp_file).unwrap();
     }
+
+    #[test]
+    fn test_manifest_verification() {
+        let temp_file = "/tmp/hft_test_manifest.jsonl";
+
+        {
+            let mut recorder = MarketRecorder::with_manifest(temp_file).unwrap();
+            for (i, symbol) in ["BTC/USD", "ETH/USD", "BTC/USD"].iter().enumerate() {
+                let tick = MarketTick::new(symbol.to_string(), 100.0 + i as f64, 10, 1_000 + i as u128);
+                recorder.record_tick(&tick).unwrap();
+            }
+            recorder.flush().unwrap();
+        }
+
+        // Unmodified recording verifies
+        let manifest = verify_manifest(temp_file).unwrap();
+        assert_eq!(manifest.tick_count, 3);
+        assert_eq!(manifest.start_timestamp, 1_000);
+        assert_eq!(manifest.end_timestamp, 1_002);
+        assert_eq!(manifest.symbols, vec!["BTC/USD".to_string(), "ETH/USD".to_string()]);
+
+        // Tampered recording fails
+        let contents = std::fs::read_to_string(temp_file).unwrap();
+        std::fs::write(temp_file, contents.replace("101.0", "109.0")).unwrap();
+        let err = verify_manifest(temp_file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref::<HftError>()),
+            Some(HftError::ChecksumMismatch { .. })
+        ));
+
+        std::fs::remove_file(temp_file).unwrap();
+        std::fs::remove_file(manifest_path(temp_file)).unwrap();
+    }
+
+    #[test]
+    fn test_run_length_compaction_round_trip() {
+        let temp_file = "/tmp/hft_test_compaction.jsonl";
+        let prices = [100.0, 100.0, 100.0, 100.0, 101.0, 101.0, 100.0];
+
+        let ticks: Vec<MarketTick> = prices
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &price)| {
+                [
+                    MarketTick::new("QUIET".to_string(), price, 1, i as u128 * 10),
+                    MarketTick::new("BUSY".to_string(), 50.0 + i as f64, 1, i as u128 * 10 + 5),
+                ]
+            })
+            .collect();
+
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            recorder.set_compaction(Compaction::RunLength);
+            for tick in &ticks {
+                recorder.record_tick(tick).unwrap();
+            }
+            recorder.flush().unwrap();
+            assert_eq!(recorder.tick_count(), 14);
+        }
+
+        // 7 BUSY ticks, 3 distinct QUIET runs, and a repeat marker before
+        // each BUSY tick that interrupts a QUIET run
+        let lines = std::fs::read_to_string(temp_file).unwrap().lines().count();
+        assert_eq!(lines, 14);
+
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        let mut quiet = Vec::new();
+        let mut busy = 0;
+        let mut timestamps = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            timestamps.push(tick.timestamp_nanos);
+            if tick.symbol == "QUIET" {
+                quiet.push((tick.price, tick.timestamp_nanos));
+            } else {
+                busy += 1;
+            }
+        }
+        assert_eq!(busy, 7);
+        assert_eq!(replayer.tick_count(), 14);
+        assert_eq!(
+            quiet,
+            vec![
+                (100.0, 0),
+                (100.0, 10),
+                (100.0, 20),
+                (100.0, 30),
+                (101.0, 40),
+                (101.0, 50),
+                (100.0, 60),
+            ]
+        );
+        assert!(timestamps.is_sorted(), "{:?}", timestamps);
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
+
+    #[test]
+    fn test_interleaved_runs_replay_in_timestamp_order() {
+        let temp_file = "/tmp/hft_test_interleaved_runs.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            recorder.set_compaction(Compaction::RunLength);
+            // Both symbols repeat until ZED moves, with ZED's run ending first
+            for (i, (symbol, price)) in [("ZED", 1.0), ("ABC", 2.0), ("ZED", 1.0), ("ABC", 2.0), ("ZED", 3.0)]
+                .into_iter()
+                .enumerate()
+            {
+                recorder
+                    .record_tick(&MarketTick::new(symbol.to_string(), price, 1, i as u128))
+                    .unwrap();
+            }
+        }
+
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        let mut replayed = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            replayed.push((tick.symbol, tick.timestamp_nanos));
+        }
+        let expected = [("ZED", 0), ("ABC", 1), ("ZED", 2), ("ABC", 3), ("ZED", 4)];
+        assert_eq!(
+            replayed,
+            expected.map(|(symbol, timestamp)| (symbol.to_string(), timestamp))
+        );
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_replay_matches_buffered_replay() {
+        let temp_file = "/tmp/hft_test_mmap_replay.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            recorder.set_compaction(Compaction::RunLength);
+            for (i, price) in [100.0, 100.0, 100.0, 101.0, 99.5, 99.5].into_iter().enumerate() {
+                let tick = MarketTick::new("BTC/USD".to_string(), price, i as u64, i as u128).with_sequence(i as u64);
+                recorder.record_tick(&tick).unwrap();
+            }
+        }
+        // A line far longer than any read buffer, and no newline at the end
+        let long_symbol = "X".repeat(100_000);
+        let last = MarketTick::new(long_symbol.clone(), 7.0, 1, 99);
+        let mut file = std::fs::OpenOptions::new().append(true).open(temp_file).unwrap();
+        file.write_all(serde_json::to_string(&last).unwrap().as_bytes()).unwrap();
+        drop(file);
+
+        let key = |tick: MarketTick| (tick.symbol, tick.price, tick.volume, tick.timestamp_nanos, tick.sequence);
+        let mut buffered = MarketReplayer::new(temp_file).unwrap();
+        let mut expected = Vec::new();
+        while let Some(tick) = buffered.next_tick().unwrap() {
+            expected.push(key(tick));
+        }
+        assert_eq!(expected.len(), 7);
+        assert_eq!(expected[6].0, long_symbol);
+
+        let mut mapped = MmapReplayer::new(temp_file).unwrap();
+        for _ in 0..2 {
+            let mut replayed = Vec::new();
+            while let Some(tick) = mapped.next_tick().unwrap() {
+                replayed.push(key(tick));
+            }
+            assert_eq!(replayed, expected);
+            assert_eq!(mapped.tick_count(), buffered.tick_count());
+            mapped.rewind();
+        }
+
+        std::fs::write(temp_file, "").unwrap();
+        assert!(MmapReplayer::new(temp_file).unwrap().next_tick().unwrap().is_none());
+        let gz = MmapReplayer::new("/tmp/hft_test_mmap_replay.jsonl.gz").unwrap_err();
+        assert_eq!(gz.kind(), std::io::ErrorKind::InvalidInput);
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
+
+    #[test]
+    fn test_skip_unchanged_compaction() {
+        let temp_file = "/tmp/hft_test_skip_unchanged.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            recorder.set_compaction(Compaction::SkipUnchanged);
+            for (i, price) in [100.0, 100.0, 100.0, 101.0].iter().enumerate() {
+                let tick = MarketTick::new("QUIET".to_string(), *price, 1, i as u128);
+                recorder.record_tick(&tick).unwrap();
+            }
+        }
+
+        let stats = ReplayStats::from_file(temp_file).unwrap();
+        assert_eq!(stats.total_ticks, 2);
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
+
+    #[test]
+    fn test_per_symbol_stats() {
+        let temp_file = "/tmp/hft_test_per_symbol_stats.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            let ticks = [
+                ("BTC/USD", 45000.0, 10),
+                ("ETH/USD", 2500.0, 20),
+                ("BTC/USD", 45300.0, 30),
+                ("ETH/USD", 2490.0, 40),
+                ("BTC/USD", 44700.0, 50),
+            ];
+            for (symbol, price, ts) in ticks {
+                recorder
+                    .record_tick(&MarketTick::new(symbol.to_string(), price, 1, ts))
+                    .unwrap();
+            }
+        }
+
+        let stats = ReplayStats::from_file(temp_file).unwrap();
+        assert_eq!(stats.total_ticks, 5);
+        assert_eq!((stats.start_timestamp, stats.end_timestamp), (10, 50));
+        assert_eq!(stats.symbols.len(), 2);
+
+        assert_eq!(
+            stats.per_symbol["BTC/USD"],
+            SymbolStats {
+                tick_count: 3,
+                first_timestamp: 10,
+                last_timestamp: 50,
+                min_price: 44700.0,
+                max_price: 45300.0,
+                mean_price: 45000.0,
+            }
+        );
+        let eth = &stats.per_symbol["ETH/USD"];
+        assert_eq!(eth.tick_count, 2);
+        assert_eq!((eth.first_timestamp, eth.last_timestamp), (20, 40));
+        assert_eq!((eth.min_price, eth.max_price), (2490.0, 2500.0));
+        assert!((eth.mean_price - 2495.0).abs() < 1e-9);
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
+
+    #[test]
+    fn test_seek_and_take_range() {
+        let temp_file = "/tmp/hft_test_seek.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            // 100 ticks 10ns apart
+            for i in 0..100u128 {
+                let price = 100.0 + (i / 4) as f64;
+                recorder
+                    .record_tick(&MarketTick::new("BTC/USD".to_string(), price, 1, i * 10))
+                    .unwrap();
+            }
+        }
+
+        // Between ticks: land on the next one
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        replayer.seek_to_timestamp(455).unwrap();
+        let tick = replayer.next_tick().unwrap().unwrap();
+        assert_eq!(tick.timestamp_nanos, 460);
+        assert_eq!(tick.price, 111.0);
+
+        // Exactly on a tick: that tick is returned
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        replayer.seek_to_timestamp(500).unwrap();
+        assert_eq!(replayer.next_tick().unwrap().unwrap().timestamp_nanos, 500);
+
+        // Past the end
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        replayer.seek_to_timestamp(10_000).unwrap();
+        assert!(replayer.next_tick().unwrap().is_none());
+
+        let mut replayer = MarketReplayer::new(temp_file).unwrap();
+        let window: Vec<u128> = replayer
+            .take_range(205, 250)
+            .map(|tick| tick.unwrap().timestamp_nanos)
+            .collect();
+        assert_eq!(window, vec![210, 220, 230, 240]);
+        // Replay carries on from the end of the window
+        assert_eq!(replayer.next_tick().unwrap().unwrap().timestamp_nanos, 250);
+
+        std::fs::remove_file(temp_file).unwrap();
+    }
 }
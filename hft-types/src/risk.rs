@@ -0,0 +1,349 @@
+use crate::{HftError, MarketTick, OrderBook, OrderSide, StreamKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Reference price used when judging how aggressive an order is
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceBasis {
+    /// Raw BBO midpoint
+    Bbo,
+    /// Imbalance-adjusted fair value over the top `depth` levels
+    FairValue { depth: usize },
+}
+
+/// Pre-trade check flagging orders priced too far through the reference price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggressionCheck {
+    pub basis: PriceBasis,
+    pub max_through_bps: f64,
+}
+
+impl AggressionCheck {
+    pub fn new(basis: PriceBasis, max_through_bps: f64) -> Self {
+        Self {
+            basis,
+            max_through_bps,
+        }
+    }
+
+    /// Reference price for the configured basis
+    pub fn reference_price(&self, book: &OrderBook) -> Option<f64> {
+        match self.basis {
+            PriceBasis::Bbo => book.mid_price(),
+            PriceBasis::FairValue { depth } => book.fair_value(depth),
+        }
+    }
+
+    /// Returns true if a buy pays (or a sell gives up) more than
+    /// `max_through_bps` relative to the reference price.
+    /// Books without both sides never flag.
+    pub fn is_aggressive(&self, side: &OrderSide, price: f64, book: &OrderBook) -> bool {
+        let Some(reference) = self.reference_price(book) else {
+            return false;
+        };
+        if reference <= 0.0 {
+            return false;
+        }
+
+        let through = match side {
+            OrderSide::Buy => price - reference,
+            OrderSide::Sell => reference - price,
+        };
+        through / reference * 10000.0 > self.max_through_bps
+    }
+}
+
+impl Default for AggressionCheck {
+    fn default() -> Self {
+        Self::new(PriceBasis::Bbo, 5.0)
+    }
+}
+
+/// Ticks per feed gap rate sample
+pub const GAP_RATE_WINDOW: u64 = 1_000;
+
+/// Limits that trip a `CircuitBreaker`; unset limits never trip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub max_p99_latency_micros: Option<f64>,
+    /// Sequence gaps per tick, sampled every `GAP_RATE_WINDOW` ticks
+    pub max_gap_rate: Option<f64>,
+    /// How long a book may stay crossed
+    pub max_crossed_secs: Option<f64>,
+    /// Realized loss, as a positive amount
+    pub max_realized_loss: Option<f64>,
+    /// Time since the last trigger before the breaker closes by itself
+    pub cooldown_secs: f64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_p99_latency_micros: None,
+            max_gap_rate: None,
+            max_crossed_secs: None,
+            max_realized_loss: None,
+            cooldown_secs: 30.0,
+        }
+    }
+}
+
+/// Why a `CircuitBreaker` opened
+#[derive(Debug, Clone, PartialEq)]
+pub enum TripReason {
+    Latency { p99_micros: f64 },
+    GapRate { rate: f64 },
+    CrossedBook { symbol: String },
+    RealizedLoss { loss: f64 },
+    Manual,
+}
+
+impl fmt::Display for TripReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TripReason::Latency { p99_micros } => write!(f, "p99 latency {:.1}µs", p99_micros),
+            TripReason::GapRate { rate } => write!(f, "feed gap rate {:.4}", rate),
+            TripReason::CrossedBook { symbol } => write!(f, "{} book stayed crossed", symbol),
+            TripReason::RealizedLoss { loss } => write!(f, "realized loss {:.2}", loss),
+            TripReason::Manual => write!(f, "manual trip"),
+        }
+    }
+}
+
+/// Halts new orders while the pipeline looks unhealthy. Callers feed it
+/// observations; once one breaches its limit the breaker opens and stays
+/// open until `cooldown_secs` pass without another breach, or `reset`.
+/// Times are in nanoseconds on whatever clock the caller uses throughout.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    /// Latest trigger and when it fired
+    open: Option<(TripReason, u128)>,
+    /// Per venue's stream of a symbol
+    last_sequences: HashMap<StreamKey, u64>,
+    window_ticks: u64,
+    window_gaps: u64,
+    crossed_since: HashMap<String, u128>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            open: None,
+            last_sequences: HashMap::new(),
+            window_ticks: 0,
+            window_gaps: 0,
+            crossed_since: HashMap::new(),
+        }
+    }
+
+    /// Whether orders are blocked at `now_nanos`; closes the breaker once
+    /// the cooldown has passed
+    pub fn is_open(&mut self, now_nanos: u128) -> bool {
+        let cooldown_nanos = (self.config.cooldown_secs * 1e9) as u128;
+        match &self.open {
+            Some((reason, tripped)) if now_nanos.saturating_sub(*tripped) >= cooldown_nanos => {
+                tracing::info!("Circuit breaker closed after cooldown ({})", reason);
+                self.open = None;
+                false
+            }
+            open => open.is_some(),
+        }
+    }
+
+    /// What opened the breaker, if it is open
+    pub fn reason(&self) -> Option<&TripReason> {
+        self.open.as_ref().map(|(reason, _)| reason)
+    }
+
+    /// Open the breaker, or restart the cooldown if already open
+    pub fn trip(&mut self, reason: TripReason, now_nanos: u128) {
+        if self.open.is_none() {
+            tracing::warn!("Circuit breaker opened: {}", reason);
+        }
+        self.open = Some((reason, now_nanos));
+    }
+
+    /// Close the breaker immediately
+    pub fn reset(&mut self) {
+        if let Some((reason, _)) = self.open.take() {
+            tracing::info!("Circuit breaker reset (was open for {})", reason);
+        }
+    }
+
+    pub fn on_latency_p99(&mut self, p99_micros: f64, now_nanos: u128) {
+        if self.config.max_p99_latency_micros.is_some_and(|max| p99_micros > max) {
+            self.trip(TripReason::Latency { p99_micros }, now_nanos);
+        }
+    }
+
+    /// Count sequence gaps; ticks without a sequence are ignored
+    pub fn on_tick(&mut self, tick: &MarketTick, now_nanos: u128) {
+        let Some(sequence) = tick.sequence else {
+            return;
+        };
+        if let Some(last) = self.last_sequences.insert(tick.stream(), sequence) {
+            self.window_gaps += sequence.saturating_sub(last).saturating_sub(1);
+        }
+        self.window_ticks += 1;
+        if self.window_ticks < GAP_RATE_WINDOW {
+            return;
+        }
+
+        let rate = self.window_gaps as f64 / self.window_ticks as f64;
+        self.window_ticks = 0;
+        self.window_gaps = 0;
+        if self.config.max_gap_rate.is_some_and(|max| rate > max) {
+            self.trip(TripReason::GapRate { rate }, now_nanos);
+        }
+    }
+
+    /// Track how long `book` has been crossed
+    pub fn on_book(&mut self, book: &OrderBook, now_nanos: u128) {
+        if !matches!(book.spread_checked(), Err(HftError::CrossedBook { .. })) {
+            self.crossed_since.remove(&book.symbol);
+            return;
+        }
+        let since = *self.crossed_since.entry(book.symbol.clone()).or_insert(now_nanos);
+        let crossed_secs = now_nanos.saturating_sub(since) as f64 / 1e9;
+        if self.config.max_crossed_secs.is_some_and(|max| crossed_secs >= max) {
+            self.trip(
+                TripReason::CrossedBook {
+                    symbol: book.symbol.clone(),
+                },
+                now_nanos,
+            );
+        }
+    }
+
+    pub fn on_realized_pnl(&mut self, realized_pnl: f64, now_nanos: u128) {
+        let loss = -realized_pnl;
+        if self.config.max_realized_loss.is_some_and(|max| loss > max) {
+            self.trip(TripReason::RealizedLoss { loss }, now_nanos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BookLevel;
+
+    const SEC: u128 = 1_000_000_000;
+
+    fn bid_heavy_book() -> OrderBook {
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel {
+            price: 100.00,
+            quantity: 9.0,
+        });
+        book.asks.push(BookLevel {
+            price: 100.10,
+            quantity: 1.0,
+        });
+        book
+    }
+
+    #[test]
+    fn test_fair_value_basis_vs_bbo_basis() {
+        let book = bid_heavy_book();
+
+        // Mid is 100.05, bid pressure pushes fair value up to 100.09
+        assert!((book.fair_value(1).unwrap() - 100.09).abs() < 1e-9);
+
+        let bbo = AggressionCheck::new(PriceBasis::Bbo, 3.0);
+        let fair = AggressionCheck::new(PriceBasis::FairValue { depth: 1 }, 3.0);
+
+        // Lifting the offer is ~5bps through mid but only ~1bp through fair value
+        assert!(bbo.is_aggressive(&OrderSide::Buy, 100.10, &book));
+        assert!(!fair.is_aggressive(&OrderSide::Buy, 100.10, &book));
+
+        // Selling below fair value is the other way round
+        assert!(!bbo.is_aggressive(&OrderSide::Sell, 100.03, &book));
+        assert!(fair.is_aggressive(&OrderSide::Sell, 100.03, &book));
+    }
+
+    #[test]
+    fn test_one_sided_book_never_flags() {
+        let mut book = bid_heavy_book();
+        book.asks.clear();
+
+        let check = AggressionCheck::new(PriceBasis::FairValue { depth: 5 }, 0.0);
+        assert!(!check.is_aggressive(&OrderSide::Buy, 1_000_000.0, &book));
+    }
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            max_p99_latency_micros: Some(500.0),
+            max_gap_rate: Some(0.01),
+            max_crossed_secs: Some(1.0),
+            max_realized_loss: Some(100.0),
+            cooldown_secs: 10.0,
+        })
+    }
+
+    #[test]
+    fn test_each_trigger_opens_the_breaker() {
+        let mut latency = breaker();
+        latency.on_latency_p99(400.0, SEC);
+        assert!(!latency.is_open(SEC));
+        latency.on_latency_p99(600.0, SEC);
+        assert!(latency.is_open(SEC));
+        assert_eq!(latency.reason(), Some(&TripReason::Latency { p99_micros: 600.0 }));
+
+        // Every 50th tick skips a sequence number: a 2% gap rate
+        let mut gaps = breaker();
+        let mut sequence = 0;
+        for i in 0..GAP_RATE_WINDOW {
+            sequence += if i % 50 == 49 { 2 } else { 1 };
+            let tick = MarketTick::new("BTC/USD".to_string(), 45000.0, 1, 1).with_sequence(sequence);
+            gaps.on_tick(&tick, SEC);
+        }
+        assert!(matches!(gaps.reason(), Some(TripReason::GapRate { .. })));
+
+        // A crossed book only trips once it has persisted
+        let mut crossed = breaker();
+        let mut book = bid_heavy_book();
+        book.asks[0].price = 99.0;
+        crossed.on_book(&book, SEC);
+        assert!(!crossed.is_open(SEC));
+        crossed.on_book(&book, 2 * SEC);
+        assert!(crossed.is_open(2 * SEC));
+
+        let mut loss = breaker();
+        loss.on_realized_pnl(-50.0, SEC);
+        assert!(!loss.is_open(SEC));
+        loss.on_realized_pnl(-150.0, SEC);
+        assert_eq!(loss.reason(), Some(&TripReason::RealizedLoss { loss: 150.0 }));
+
+        let mut manual = breaker();
+        manual.trip(TripReason::Manual, SEC);
+        assert!(manual.is_open(SEC));
+        manual.reset();
+        assert!(!manual.is_open(SEC));
+    }
+
+    #[test]
+    fn test_breaker_closes_after_quiet_cooldown() {
+        let mut breaker = breaker();
+        breaker.on_latency_p99(600.0, SEC);
+
+        // A new breach restarts the cooldown
+        breaker.on_latency_p99(700.0, 5 * SEC);
+        assert!(breaker.is_open(14 * SEC));
+        assert!(!breaker.is_open(15 * SEC));
+        assert_eq!(breaker.reason(), None);
+
+        // A book that uncrosses in time never trips
+        let mut book = bid_heavy_book();
+        book.asks[0].price = 99.0;
+        breaker.on_book(&book, 20 * SEC);
+        breaker.on_book(&bid_heavy_book(), 20 * SEC + SEC / 2);
+        breaker.on_book(&book, 21 * SEC);
+        assert!(!breaker.is_open(21 * SEC));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::replay::{Compaction, MarketRecorder, MarketReplayer};
+use crate::MarketTick;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// When a `RotatingRecorder` closes its segment and starts a new one.
+/// Unset limits never trigger.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RotationPolicy {
+    /// Roll once the segment holds at least this many bytes
+    pub max_bytes: Option<u64>,
+    /// Roll once a tick is this much newer than the segment's first tick.
+    /// Measured on tick timestamps, so a replayed capture rotates the same way.
+    pub max_age: Option<Duration>,
+    /// Gzip each segment once it is closed
+    pub gzip: bool,
+}
+
+/// Segment being written
+struct Segment {
+    recorder: MarketRecorder,
+    path: PathBuf,
+    first_timestamp: u128,
+}
+
+/// Records into `<dir>/<prefix>-<first tick timestamp>-<index>.jsonl`
+/// segments, rolling to a new one per the `RotationPolicy`. A segment is
+/// opened by the tick that triggers the roll, so every tick lands in
+/// exactly one segment. Segment names sort in recording order.
+pub struct RotatingRecorder {
+    dir: PathBuf,
+    prefix: String,
+    policy: RotationPolicy,
+    compaction: Compaction,
+    current: Option<Segment>,
+    next_index: u64,
+    closed: Vec<PathBuf>,
+}
+
+impl RotatingRecorder {
+    pub fn new<P: AsRef<Path>>(
+        dir: P,
+        prefix: &str,
+        policy: RotationPolicy,
+    ) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            policy,
+            compaction: Compaction::None,
+            current: None,
+            next_index: 0,
+            closed: Vec::new(),
+        })
+    }
+
+    /// Compaction for segments opened from now on
+    pub fn set_compaction(&mut self, compaction: Compaction) {
+        self.compaction = compaction;
+    }
+
+    pub fn record_tick(&mut self, tick: &MarketTick) -> std::io::Result<()> {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|segment| self.is_due(segment, tick))
+        {
+            self.close_segment()?;
+        }
+        if self.current.is_none() {
+            self.open_segment(tick.timestamp_nanos)?;
+        }
+        match self.current.as_mut() {
+            Some(segment) => segment.recorder.record_tick(tick),
+            None => unreachable!("a segment was just opened"),
+        }
+    }
+
+    fn is_due(&self, segment: &Segment, tick: &MarketTick) -> bool {
+        let full = self
+            .policy
+            .max_bytes
+            .is_some_and(|max| segment.recorder.bytes_written() >= max);
+        let old = self.policy.max_age.is_some_and(|max| {
+            tick.timestamp_nanos.saturating_sub(segment.first_timestamp) >= max.as_nanos()
+        });
+        full || old
+    }
+
+    fn open_segment(&mut self, first_timestamp: u128) -> std::io::Result<()> {
+        let path = self.dir.join(format!(
+            "{}-{:020}-{:06}.jsonl",
+            self.prefix, first_timestamp, self.next_index
+        ));
+        self.next_index += 1;
+        let mut recorder = MarketRecorder::new(&path)?;
+        recorder.set_compaction(self.compaction);
+        self.current = Some(Segment {
+            recorder,
+            path,
+            first_timestamp,
+        });
+        Ok(())
+    }
+
+    fn close_segment(&mut self) -> std::io::Result<()> {
+        let Some(mut segment) = self.current.take() else {
+            return Ok(());
+        };
+        segment.recorder.flush()?;
+        drop(segment.recorder);
+
+        let path = if self.policy.gzip {
+            gzip_file(&segment.path)?
+        } else {
+            segment.path
+        };
+        self.closed.push(path);
+        Ok(())
+    }
+
+    /// Flush the open segment without closing it
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match self.current.as_mut() {
+            Some(segment) => segment.recorder.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Segments closed so far, oldest first
+    pub fn closed_segments(&self) -> &[PathBuf] {
+        &self.closed
+    }
+
+    /// Close the open segment (gzipping it if configured) and return every
+    /// segment written, oldest first
+    pub fn finish(mut self) -> std::io::Result<Vec<PathBuf>> {
+        self.close_segment()?;
+        Ok(std::mem::take(&mut self.closed))
+    }
+}
+
+/// Compress `path` to `path.gz` and remove the original
+fn gzip_file(path: &Path) -> std::io::Result<PathBuf> {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+    let gz_path = PathBuf::from(gz_name);
+
+    let mut encoder = GzEncoder::new(File::create(&gz_path)?, Compression::default());
+    std::io::copy(&mut BufReader::new(File::open(path)?), &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    std::fs::remove_file(path)?;
+    Ok(gz_path)
+}
+
+/// Replays a sequence of recordings back to back, e.g. the segments of a
+/// `RotatingRecorder`
+#[derive(Debug)]
+pub struct MergedReplayer {
+    segments: VecDeque<PathBuf>,
+    current: Option<MarketReplayer>,
+    tick_count: u64,
+}
+
+impl MergedReplayer {
+    pub fn new(segments: Vec<PathBuf>) -> Self {
+        Self {
+            segments: segments.into(),
+            current: None,
+            tick_count: 0,
+        }
+    }
+
+    /// Every `.jsonl` and `.jsonl.gz` recording in `dir`, in file name order
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> std::io::Result<Self> {
+        let mut segments = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            if name.is_some_and(|name| name.ends_with(".jsonl") || name.ends_with(".jsonl.gz")) {
+                segments.push(path);
+            }
+        }
+        segments.sort();
+        Ok(Self::new(segments))
+    }
+
+    pub fn next_tick(&mut self) -> std::io::Result<Option<MarketTick>> {
+        loop {
+            if let Some(replayer) = self.current.as_mut() {
+                if let Some(tick) = replayer.next_tick()? {
+                    self.tick_count += 1;
+                    return Ok(Some(tick));
+                }
+            }
+            match self.segments.pop_front() {
+                Some(path) => self.current = Some(MarketReplayer::new(path)?),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::ReplayStats;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn ticks(count: u128) -> Vec<MarketTick> {
+        (1..=count)
+            .map(|i| MarketTick::new("BTC/USD".to_string(), 45000.0 + i as f64, 10, i * 1_000))
+            .collect()
+    }
+
+    #[test]
+    fn test_rotation_by_size_keeps_every_tick() {
+        let dir = temp_dir("hft_test_rotation_size");
+        let policy = RotationPolicy {
+            max_bytes: Some(300),
+            ..RotationPolicy::default()
+        };
+        let mut recorder = RotatingRecorder::new(&dir, "capture", policy).unwrap();
+        for tick in ticks(20) {
+            recorder.record_tick(&tick).unwrap();
+        }
+        let segments = recorder.finish().unwrap();
+        assert!(segments.len() >= 2, "{:?}", segments);
+
+        let mut replayer = MergedReplayer::from_dir(&dir).unwrap();
+        let mut timestamps = Vec::new();
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            timestamps.push(tick.timestamp_nanos);
+        }
+        assert_eq!(
+            timestamps,
+            (1..=20).map(|i| i * 1_000).collect::<Vec<u128>>()
+        );
+
+        let stats = ReplayStats::from_dir(&dir).unwrap();
+        assert_eq!(stats.total_ticks, 20);
+        assert_eq!(stats.start_timestamp, 1_000);
+        assert_eq!(stats.end_timestamp, 20_000);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rotation_by_age_with_gzip() {
+        let dir = temp_dir("hft_test_rotation_age");
+        let policy = RotationPolicy {
+            max_age: Some(Duration::from_micros(5)),
+            gzip: true,
+            ..RotationPolicy::default()
+        };
+        let mut recorder = RotatingRecorder::new(&dir, "capture", policy).unwrap();
+        for tick in ticks(12) {
+            recorder.record_tick(&tick).unwrap();
+        }
+        // Ticks 1-5, 6-10 closed; 11-12 still open
+        assert_eq!(recorder.closed_segments().len(), 2);
+        let segments = recorder.finish().unwrap();
+        assert_eq!(segments.len(), 3);
+        assert!(segments
+            .iter()
+            .all(|path| path.extension().is_some_and(|ext| ext == "gz")));
+
+        let mut replayer = MergedReplayer::new(segments);
+        let mut count = 0;
+        while let Some(tick) = replayer.next_tick().unwrap() {
+            count += 1;
+            assert_eq!(tick.timestamp_nanos, count * 1_000);
+        }
+        assert_eq!(count, 12);
+        assert_eq!(replayer.tick_count(), 12);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
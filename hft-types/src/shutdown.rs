@@ -0,0 +1,39 @@
+use std::io;
+use std::thread::JoinHandle;
+
+/// Resolves once the process is asked to stop: Ctrl-C, or SIGTERM on Unix
+pub async fn signal() -> io::Result<()> {
+    #[cfg(unix)]
+    let received = {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|()| "Ctrl-C")?,
+            _ = terminate.recv() => "SIGTERM",
+        }
+    };
+    #[cfg(not(unix))]
+    let received = tokio::signal::ctrl_c().await.map(|()| "Ctrl-C")?;
+
+    tracing::info!("{} received; shutting down", received);
+    Ok(())
+}
+
+/// Call `on_signal` from a background thread once the process is asked to
+/// stop, for services that don't run on a tokio runtime
+pub fn spawn_listener<F>(on_signal: F) -> io::Result<JoinHandle<()>>
+where
+    F: FnOnce() + Send + 'static,
+{
+    std::thread::Builder::new()
+        .name("shutdown-signal".to_string())
+        .spawn(move || {
+            let received = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .and_then(|runtime| runtime.block_on(signal()));
+            match received {
+                Ok(()) => on_signal(),
+                Err(e) => tracing::warn!("Can't listen for shutdown signals: {}", e),
+            }
+        })
+}
@@ -0,0 +1,237 @@
+use crate::matching::Fill;
+use crate::{round_to_increment, OrderSide, RoundingMode, TradingSignal};
+use std::collections::HashMap;
+
+/// Turns a signal into an order quantity: the signal's `quantity` scaled by
+/// its `strength`, cut to what's left of the per-symbol position budget and
+/// rounded down to the lot size. Positions move only on fills; quantity
+/// still working at the gateway counts against the budget as if it would
+/// fill, until it fills, is cancelled or is rejected.
+#[derive(Debug, Clone)]
+pub struct PositionSizer {
+    /// Largest absolute position per symbol
+    max_position: f64,
+    /// Signed: long positive, short negative
+    positions: HashMap<String, f64>,
+    /// Orders sent and not yet filled, cancelled or rejected, by id
+    working: HashMap<u64, WorkingOrder>,
+}
+
+#[derive(Debug, Clone)]
+struct WorkingOrder {
+    symbol: String,
+    side: OrderSide,
+    remaining: f64,
+}
+
+impl Default for PositionSizer {
+    /// No position cap; quantities are only scaled and rounded
+    fn default() -> Self {
+        Self::new(f64::INFINITY)
+    }
+}
+
+impl PositionSizer {
+    pub fn new(max_position: f64) -> Self {
+        Self {
+            max_position: max_position.max(0.0),
+            positions: HashMap::new(),
+            working: HashMap::new(),
+        }
+    }
+
+    pub fn position(&self, symbol: &str) -> f64 {
+        self.positions.get(symbol).copied().unwrap_or(0.0)
+    }
+
+    /// Signed position per symbol
+    pub fn positions(&self) -> &HashMap<String, f64> {
+        &self.positions
+    }
+
+    /// Pick up positions saved from `positions`
+    pub fn restore(&mut self, positions: HashMap<String, f64>) {
+        self.positions = positions;
+    }
+
+    /// Quantity of `side` in `symbol` sent and still working
+    pub fn working(&self, symbol: &str, side: &OrderSide) -> f64 {
+        self.working
+            .values()
+            .filter(|order| order.symbol == symbol && order.side == *side)
+            .map(|order| order.remaining)
+            .sum()
+    }
+
+    /// Whether `order_id` was sent and may still fill
+    pub fn is_working(&self, order_id: u64) -> bool {
+        self.working.contains_key(&order_id)
+    }
+
+    /// Room left to trade `side` in `symbol` before hitting the cap, were
+    /// everything still working to fill
+    pub fn budget(&self, symbol: &str, side: &OrderSide) -> f64 {
+        let position = self.position(symbol);
+        let room = match side {
+            OrderSide::Buy => self.max_position - position,
+            OrderSide::Sell => self.max_position + position,
+        };
+        (room - self.working(symbol, side)).max(0.0)
+    }
+
+    /// Quantity to trade for `signal`, in whole multiples of `lot_size` if
+    /// given. Zero means the signal is too weak or the budget is used up.
+    pub fn size(&self, signal: &TradingSignal, lot_size: Option<f64>) -> f64 {
+        let strength = if signal.strength.is_nan() {
+            0.0
+        } else {
+            signal.strength.clamp(0.0, 1.0)
+        };
+        let quantity = (signal.quantity * strength).min(self.budget(&signal.symbol, &signal.side));
+        let quantity = match lot_size {
+            Some(lot_size) => round_to_increment(quantity, lot_size, RoundingMode::Down),
+            None => quantity,
+        };
+        quantity.max(0.0)
+    }
+
+    /// Count a sent order against the budget until it's done
+    pub fn on_order(&mut self, order_id: u64, symbol: &str, side: &OrderSide, quantity: f64) {
+        self.working.insert(
+            order_id,
+            WorkingOrder {
+                symbol: symbol.to_string(),
+                side: side.clone(),
+                remaining: quantity,
+            },
+        );
+    }
+
+    /// Move the position by a fill. Fills of orders this sizer never saw,
+    /// such as ones placed by the gateway itself, still count.
+    pub fn on_fill(&mut self, fill: &Fill) {
+        let signed = match fill.side {
+            OrderSide::Buy => fill.fill_qty,
+            OrderSide::Sell => -fill.fill_qty,
+        };
+        *self.positions.entry(fill.symbol.clone()).or_default() += signed;
+        self.reduce(fill.order_id, fill.fill_qty);
+    }
+
+    /// `quantity` of an order left the book without filling
+    pub fn on_cancel(&mut self, order_id: u64, quantity: f64) {
+        self.reduce(order_id, quantity);
+    }
+
+    /// An order never reached the book
+    pub fn on_reject(&mut self, order_id: u64) {
+        self.working.remove(&order_id);
+    }
+
+    fn reduce(&mut self, order_id: u64, quantity: f64) {
+        if let Some(order) = self.working.get_mut(&order_id) {
+            order.remaining -= quantity;
+            if order.remaining <= 1e-9 {
+                self.working.remove(&order_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SignalType;
+
+    fn signal(side: OrderSide, quantity: f64, strength: f64) -> TradingSignal {
+        TradingSignal {
+            symbol: "BTC/USD".to_string(),
+            side,
+            price: 45000.0,
+            quantity,
+            signal_type: SignalType::MeanReversion,
+            timestamp_nanos: 0,
+            strength,
+        }
+    }
+
+    #[test]
+    fn test_stronger_signal_trades_more() {
+        let sizer = PositionSizer::new(10.0);
+        let weak = sizer.size(&signal(OrderSide::Buy, 4.0, 0.3), Some(0.5));
+        let strong = sizer.size(&signal(OrderSide::Buy, 4.0, 0.9), Some(0.5));
+        assert_eq!(weak, 1.0);
+        assert_eq!(strong, 3.5);
+
+        // Strength is a fraction; anything outside 0..1 is clamped
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, 3.0), None), 4.0);
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, -1.0), None), 0.0);
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, f64::NAN), None), 0.0);
+        // Too weak for a single lot
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, 0.1), Some(0.5)), 0.0);
+    }
+
+    fn fill(order_id: u64, side: OrderSide, fill_qty: f64) -> Fill {
+        Fill {
+            order_id,
+            symbol: "BTC/USD".to_string(),
+            side,
+            fill_price: 45000.0,
+            fill_qty,
+            timestamp_nanos: 0,
+            is_maker: false,
+            client_id: None,
+        }
+    }
+
+    #[test]
+    fn test_clamped_to_lot_size_and_position_cap() {
+        let mut sizer = PositionSizer::new(5.0);
+        sizer.on_order(1, "BTC/USD", &OrderSide::Buy, 3.3);
+        sizer.on_fill(&fill(1, OrderSide::Buy, 3.3));
+        assert!((sizer.budget("BTC/USD", &OrderSide::Buy) - 1.7).abs() < 1e-9);
+
+        // 1.7 left to buy, in whole 0.5 lots
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, 1.0), Some(0.5)), 1.5);
+        // Selling can flip the position as far as the cap on the other side
+        assert_eq!(sizer.size(&signal(OrderSide::Sell, 20.0, 1.0), Some(0.1)), 8.3);
+
+        sizer.on_order(2, "BTC/USD", &OrderSide::Buy, 1.7);
+        sizer.on_fill(&fill(2, OrderSide::Buy, 1.7));
+        assert_eq!(sizer.size(&signal(OrderSide::Buy, 4.0, 1.0), None), 0.0);
+        assert_eq!(sizer.position("BTC/USD"), 5.0);
+        assert_eq!(sizer.position("ETH/USD"), 0.0);
+    }
+
+    #[test]
+    fn test_position_moves_on_fills_not_orders() {
+        let mut sizer = PositionSizer::new(5.0);
+        sizer.on_order(1, "BTC/USD", &OrderSide::Buy, 4.0);
+
+        // Working quantity uses up the budget but isn't a position yet
+        assert_eq!(sizer.position("BTC/USD"), 0.0);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Buy), 1.0);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Sell), 5.0);
+
+        sizer.on_fill(&fill(1, OrderSide::Buy, 1.5));
+        assert_eq!(sizer.position("BTC/USD"), 1.5);
+        assert_eq!(sizer.working("BTC/USD", &OrderSide::Buy), 2.5);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Buy), 1.0);
+
+        // The unfilled rest comes off the book and frees the budget
+        sizer.on_cancel(1, 2.5);
+        assert_eq!(sizer.working("BTC/USD", &OrderSide::Buy), 0.0);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Buy), 3.5);
+        assert_eq!(sizer.position("BTC/USD"), 1.5);
+
+        // A rejected order never counted
+        sizer.on_order(2, "BTC/USD", &OrderSide::Sell, 6.0);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Sell), 0.5);
+        sizer.on_reject(2);
+        assert_eq!(sizer.budget("BTC/USD", &OrderSide::Sell), 6.5);
+
+        // Fills of orders it never sent still move the position
+        sizer.on_fill(&fill(99, OrderSide::Sell, 1.5));
+        assert_eq!(sizer.position("BTC/USD"), 0.0);
+    }
+}
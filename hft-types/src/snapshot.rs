@@ -0,0 +1,118 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Write `state` to `path` as JSON without ever leaving a partial file:
+/// it goes to a temp file alongside, is synced, then renamed over `path`
+pub fn save<T: Serialize, P: AsRef<Path>>(path: P, state: &T) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    serde_json::to_writer(&mut writer, state)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    drop(writer);
+    std::fs::rename(&tmp, path)
+}
+
+/// State saved at `path`, or `None` if nothing has been saved there yet
+pub fn load<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> std::io::Result<Option<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let state = serde_json::from_reader(BufReader::new(file))?;
+    Ok(Some(state))
+}
+
+/// Saves a service's state to one file at most once per `interval`, so a
+/// restart can pick up where the last snapshot left off
+#[derive(Debug)]
+pub struct Snapshotter {
+    path: PathBuf,
+    interval: Duration,
+    last_save: Instant,
+}
+
+impl Snapshotter {
+    pub fn new<P: AsRef<Path>>(path: P, interval: Duration) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            interval,
+            last_save: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The last snapshot written, if any
+    pub fn load<T: DeserializeOwned>(&self) -> std::io::Result<Option<T>> {
+        load(&self.path)
+    }
+
+    pub fn save<T: Serialize>(&mut self, state: &T) -> std::io::Result<()> {
+        self.last_save = Instant::now();
+        save(&self.path, state)
+    }
+
+    /// Save the state `state` builds if `interval` has passed since the last
+    /// save. Returns whether it saved.
+    pub fn save_if_due<T: Serialize>(
+        &mut self,
+        state: impl FnOnce() -> T,
+    ) -> std::io::Result<bool> {
+        if self.last_save.elapsed() < self.interval {
+            return Ok(false);
+        }
+        self.save(&state())?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_save_replaces_whole_file() {
+        let path = "/tmp/hft_test_snapshot.json";
+        std::fs::remove_file(path).ok();
+        assert_eq!(load::<HashMap<String, f64>, _>(path).unwrap(), None);
+
+        let first = HashMap::from([("BTC/USD".to_string(), 1.5), ("ETH/USD".to_string(), -2.0)]);
+        save(path, &first).unwrap();
+        let second = HashMap::from([("SOL/USD".to_string(), 3.0)]);
+        save(path, &second).unwrap();
+        assert_eq!(load::<HashMap<String, f64>, _>(path).unwrap(), Some(second));
+        // Nothing left behind but the snapshot itself
+        assert!(!Path::new("/tmp/hft_test_snapshot.json.tmp").exists());
+
+        std::fs::write(path, "{\"BTC/USD\":").unwrap();
+        assert!(load::<HashMap<String, f64>, _>(path).is_err());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_saves_only_when_due() {
+        let path = "/tmp/hft_test_snapshotter.json";
+        std::fs::remove_file(path).ok();
+        let mut snapshotter = Snapshotter::new(path, Duration::from_secs(3600));
+        assert!(!snapshotter.save_if_due(|| 1u64).unwrap());
+        assert_eq!(snapshotter.load::<u64>().unwrap(), None);
+
+        let mut snapshotter = Snapshotter::new(path, Duration::ZERO);
+        assert!(snapshotter.save_if_due(|| 7u64).unwrap());
+        assert_eq!(snapshotter.load::<u64>().unwrap(), Some(7));
+        std::fs::remove_file(path).ok();
+    }
+}
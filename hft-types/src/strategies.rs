@@ -1,21 +1,361 @@
-use crate::{EnrichedTick, OrderSide, TradingSignal, SignalType};
-use std::collections::HashMap;
+use crate::clock::{SharedClock, SystemClock};
+use crate::orderbook::OrderBookManager;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use crate::vwap::VwapTracker;
+use crate::{EnrichedTick, HftError, HftResult, OrderSide, TradingSignal, SignalType};
+use prometheus::IntCounter;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// `Strategy::set_clock` for strategies that stamp their signals from a
+/// `clock` field
+macro_rules! set_clock_field {
+    () => {
+        fn set_clock(&mut self, clock: SharedClock) {
+            self.clock = clock;
+        }
+    };
+}
 
 /// Base strategy trait
 pub trait Strategy: Send {
     fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal>;
     fn name(&self) -> &str;
+
+    /// Process a tick with access to current order books. Book-aware
+    /// strategies override this; the default defers to `process_tick`.
+    fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        _books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        self.process_tick(tick).into_iter().collect()
+    }
+
+    /// Whether enough history has been seen for `symbol` to act on it.
+    /// Strategies without a warmup are always ready.
+    fn is_ready(&self, _symbol: &str) -> bool {
+        true
+    }
+
+    /// Forget per-symbol warmup history, e.g. after a feed outage has made
+    /// it stale. Strategies without history have nothing to forget.
+    fn reset_history(&mut self) {}
+
+    /// Start over as if newly built: history goes, as does any other state
+    /// such as tracked positions, so warmup begins again
+    fn reset(&mut self) {
+        self.reset_history();
+    }
+
+    /// Signals that bring each position the strategy tracks back to zero,
+    /// e.g. at session end or on a kill switch. Strategies that don't track
+    /// positions have nothing to close.
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        Vec::new()
+    }
+
+    /// Read time from `clock` instead of the system clock, e.g. a
+    /// `MockClock` in tests or one following tick timestamps in a backtest
+    fn set_clock(&mut self, _clock: SharedClock) {}
+
+    /// The runner's signed position in `symbol` changed. Inventory-aware
+    /// strategies override this; the rest ignore it.
+    fn on_position(&mut self, _symbol: &str, _position: f64) {}
+}
+
+impl Strategy for Box<dyn Strategy> {
+    fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+        (**self).process_tick(tick)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        (**self).process_tick_with_book(tick, books)
+    }
+
+    fn is_ready(&self, symbol: &str) -> bool {
+        (**self).is_ready(symbol)
+    }
+
+    fn reset_history(&mut self) {
+        (**self).reset_history()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        (**self).flatten()
+    }
+
+    fn set_clock(&mut self, clock: SharedClock) {
+        (**self).set_clock(clock)
+    }
+
+    fn on_position(&mut self, symbol: &str, position: f64) {
+        (**self).on_position(symbol, position)
+    }
+}
+
+/// Strategy that can await I/O, such as fetching a reference price or a
+/// remote risk check, while handling a tick. Synchronous strategies run
+/// through `SyncAdapter`.
+#[async_trait]
+pub trait AsyncStrategy: Send {
+    async fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal>;
+    fn name(&self) -> &str;
+
+    /// Book-aware counterpart of `process_tick`, as on `Strategy`
+    async fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        _books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        self.process_tick(tick).await.into_iter().collect()
+    }
+
+    fn is_ready(&self, _symbol: &str) -> bool {
+        true
+    }
+
+    /// As on `Strategy`
+    fn reset_history(&mut self) {}
+
+    /// As on `Strategy`
+    fn reset(&mut self) {
+        self.reset_history();
+    }
+
+    /// As on `Strategy`
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        Vec::new()
+    }
+
+    /// As on `Strategy`
+    fn set_clock(&mut self, _clock: SharedClock) {}
+
+    /// As on `Strategy`
+    fn on_position(&mut self, _symbol: &str, _position: f64) {}
+}
+
+/// Runs a synchronous `Strategy` as an `AsyncStrategy`; every call
+/// completes without yielding
+pub struct SyncAdapter<S>(pub S);
+
+#[async_trait]
+impl<S: Strategy> AsyncStrategy for SyncAdapter<S> {
+    async fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+        self.0.process_tick(tick)
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    async fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        self.0.process_tick_with_book(tick, books)
+    }
+
+    fn is_ready(&self, symbol: &str) -> bool {
+        self.0.is_ready(symbol)
+    }
+
+    fn reset_history(&mut self) {
+        self.0.reset_history()
+    }
+
+    fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        self.0.flatten()
+    }
+
+    fn set_clock(&mut self, clock: SharedClock) {
+        self.0.set_clock(clock)
+    }
+
+    fn on_position(&mut self, symbol: &str, position: f64) {
+        self.0.on_position(symbol, position)
+    }
+}
+
+lazy_static! {
+    /// NaN or infinite values caught on their way out of a strategy. Process
+    /// wide, since strategies are built in many places; services export it
+    /// by registering it with their own registry.
+    pub static ref NONFINITE_VALUES: IntCounter = IntCounter::new(
+        "nonfinite_values_total",
+        "NaN or infinite values caught in strategy output"
+    )
+    .unwrap();
+}
+
+/// What `sanitize_signal` does with a signal carrying a NaN or infinity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonFinitePolicy {
+    /// Drop the signal
+    #[default]
+    Suppress,
+    /// Clamp a non-finite strength into [0, 1], NaN to 0, and keep the
+    /// signal. A non-finite price or quantity has nothing sensible to clamp
+    /// to, so those signals are dropped all the same.
+    Clamp,
+}
+
+impl FromStr for NonFinitePolicy {
+    type Err = HftError;
+
+    fn from_str(s: &str) -> HftResult<Self> {
+        match s {
+            "suppress" => Ok(NonFinitePolicy::Suppress),
+            "clamp" => Ok(NonFinitePolicy::Clamp),
+            _ => Err(HftError::InvalidConfig(format!(
+                "unknown non-finite policy {:?}; expected suppress or clamp",
+                s
+            ))),
+        }
+    }
+}
+
+/// `value` if it is finite; otherwise counted in `nonfinite_values_total`
+/// and dropped
+pub fn sanitize_value(value: f64) -> Option<f64> {
+    if value.is_finite() {
+        Some(value)
+    } else {
+        NONFINITE_VALUES.inc();
+        None
+    }
+}
+
+/// `signal` if its price, quantity and strength are all finite; otherwise
+/// counted in `nonfinite_values_total` and handled per `policy`
+pub fn sanitize_signal(mut signal: TradingSignal, policy: NonFinitePolicy) -> Option<TradingSignal> {
+    if !signal.strength.is_finite() {
+        NONFINITE_VALUES.inc();
+        match policy {
+            NonFinitePolicy::Suppress => return None,
+            NonFinitePolicy::Clamp if signal.strength.is_nan() => signal.strength = 0.0,
+            NonFinitePolicy::Clamp => signal.strength = signal.strength.clamp(0.0, 1.0),
+        }
+    }
+    sanitize_value(signal.price)?;
+    sanitize_value(signal.quantity)?;
+    Some(signal)
+}
+
+/// Passes every signal a strategy emits through `sanitize_signal`, so no
+/// NaN or infinity reaches an order. `config::build_strategy` wraps each
+/// strategy it builds in one.
+pub struct Guarded<S> {
+    inner: S,
+    policy: NonFinitePolicy,
+}
+
+impl<S: Strategy> Guarded<S> {
+    pub fn new(inner: S, policy: NonFinitePolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<S: Strategy> Strategy for Guarded<S> {
+    fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+        let signal = self.inner.process_tick(tick)?;
+        sanitize_signal(signal, self.policy)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        self.inner
+            .process_tick_with_book(tick, books)
+            .into_iter()
+            .filter_map(|signal| sanitize_signal(signal, self.policy))
+            .collect()
+    }
+
+    fn is_ready(&self, symbol: &str) -> bool {
+        self.inner.is_ready(symbol)
+    }
+
+    fn reset_history(&mut self) {
+        self.inner.reset_history()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        self.inner
+            .flatten()
+            .into_iter()
+            .filter_map(|signal| sanitize_signal(signal, self.policy))
+            .collect()
+    }
+
+    fn set_clock(&mut self, clock: SharedClock) {
+        self.inner.set_clock(clock)
+    }
+
+    fn on_position(&mut self, symbol: &str, position: f64) {
+        self.inner.on_position(symbol, position)
+    }
+}
+
+/// Mean reversion conviction: half strength at the entry threshold, full
+/// strength at twice it
+fn z_score_strength(z_score: f64, threshold: f64) -> f64 {
+    if threshold > 0.0 {
+        (z_score.abs() / (2.0 * threshold)).min(1.0)
+    } else {
+        1.0
+    }
 }
 
 /// Simple threshold-based strategy
 pub struct ThresholdStrategy {
     thresholds: HashMap<String, (f64, f64)>,
     order_size: f64,
+    /// Stamps signals
+    clock: SharedClock,
 }
 
 impl ThresholdStrategy {
     pub fn new(thresholds: HashMap<String, (f64, f64)>, order_size: f64) -> Self {
-        Self { thresholds, order_size }
+        Self {
+            thresholds,
+            order_size,
+            clock: SystemClock::shared(),
+        }
     }
 }
 
@@ -32,16 +372,16 @@ impl Strategy for ThresholdStrategy {
                 None
             };
 
+            // Half strength at the band edge, full half a band width past it
+            let beyond = (low - tick.price).max(tick.price - high);
             side.map(|s| TradingSignal {
                 symbol: tick.symbol.clone(),
                 side: s,
                 price: tick.price,
                 quantity: self.order_size,
                 signal_type: SignalType::Threshold,
-                timestamp_nanos: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos(),
+                timestamp_nanos: self.clock.now_nanos(),
+                strength: (0.5 + beyond / (high - low)).min(1.0),
             })
         } else {
             None
@@ -51,21 +391,110 @@ impl Strategy for ThresholdStrategy {
     fn name(&self) -> &str {
         "ThresholdStrategy"
     }
+
+    set_clock_field!();
+}
+
+/// Ticks of returns in the market maker's volatility estimate
+const MM_VOL_WINDOW: usize = 20;
+
+/// Bid and ask the market maker would quote
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    pub bid: f64,
+    pub ask: f64,
+}
+
+impl Quote {
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    /// Width from bid to ask in basis points of the mid
+    pub fn spread_bps(&self) -> f64 {
+        (self.ask - self.bid) / self.mid() * 10_000.0
+    }
 }
 
-/// Market making strategy
+/// Market making strategy. Each quote sits `spread_bps` plus
+/// `vol_multiplier` times the recent realized volatility (in bps per tick)
+/// away from the mid, and the mid leans `inventory_skew_bps` per unit of
+/// inventory against the position. With both at zero the spread is constant.
 pub struct MarketMakingStrategy {
     spread_bps: f64, // Spread in basis points
+    vol_multiplier: f64,
+    inventory_skew_bps: f64,
     order_size: f64,
     last_prices: HashMap<String, f64>,
+    /// Last `MM_VOL_WINDOW` tick-to-tick returns per symbol, in bps
+    returns: HashMap<String, VecDeque<f64>>,
+    /// Signed position per symbol, as reported through `on_position`
+    inventory: HashMap<String, f64>,
+    /// Stamps signals
+    clock: SharedClock,
 }
 
 impl MarketMakingStrategy {
     pub fn new(spread_bps: f64, order_size: f64) -> Self {
         Self {
             spread_bps,
+            vol_multiplier: 0.0,
+            inventory_skew_bps: 0.0,
             order_size,
             last_prices: HashMap::new(),
+            returns: HashMap::new(),
+            inventory: HashMap::new(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Widen each side by `vol_multiplier` bps per bp of realized volatility
+    pub fn with_vol_multiplier(mut self, vol_multiplier: f64) -> Self {
+        self.vol_multiplier = vol_multiplier;
+        self
+    }
+
+    /// Shift the mid `inventory_skew_bps` per unit held, down when long and
+    /// up when short, and quote the side that works the position off
+    pub fn with_inventory_skew(mut self, inventory_skew_bps: f64) -> Self {
+        self.inventory_skew_bps = inventory_skew_bps;
+        self
+    }
+
+    /// Standard deviation of recent tick-to-tick returns, in bps
+    pub fn volatility_bps(&self, symbol: &str) -> f64 {
+        match self.returns.get(symbol) {
+            Some(returns) if returns.len() >= 2 => {
+                let n = returns.len() as f64;
+                let mean = returns.iter().sum::<f64>() / n;
+                let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+                variance.sqrt()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Quotes around the last price seen for `symbol`
+    pub fn quote(&self, symbol: &str) -> Option<Quote> {
+        let price = *self.last_prices.get(symbol)?;
+        let inventory = self.inventory.get(symbol).copied().unwrap_or(0.0);
+        let mid = price * (1.0 - inventory * self.inventory_skew_bps / 10_000.0);
+        let half_spread_bps = self.spread_bps + self.vol_multiplier * self.volatility_bps(symbol);
+        Some(Quote {
+            bid: mid * (1.0 - half_spread_bps / 10_000.0),
+            ask: mid * (1.0 + half_spread_bps / 10_000.0),
+        })
+    }
+
+    fn record_price(&mut self, symbol: &str, price: f64) {
+        if let Some(last) = self.last_prices.insert(symbol.to_string(), price) {
+            if last > 0.0 {
+                let returns = self.returns.entry(symbol.to_string()).or_default();
+                if returns.len() == MM_VOL_WINDOW {
+                    returns.pop_front();
+                }
+                returns.push_back((price / last - 1.0) * 10_000.0);
+            }
         }
     }
 }
@@ -73,27 +502,73 @@ impl MarketMakingStrategy {
 impl Strategy for MarketMakingStrategy {
     fn process_tick(&mut self, enriched: &EnrichedTick) -> Option<TradingSignal> {
         let tick = &enriched.tick;
-        self.last_prices.insert(tick.symbol.clone(), tick.price);
+        self.record_price(&tick.symbol, tick.price);
+        let quote = self.quote(&tick.symbol)?;
 
-        // Simplified: Place both bid and ask orders (return buy signal for demo)
-        let half_spread = tick.price * (self.spread_bps / 10000.0);
+        // Simplified: one side per tick. Flat or short it bids; long with a
+        // skew it offers, to work the position down.
+        let inventory = self.inventory.get(&tick.symbol).copied().unwrap_or(0.0);
+        let (side, price) = if self.inventory_skew_bps > 0.0 && inventory > 0.0 {
+            (OrderSide::Sell, quote.ask)
+        } else {
+            (OrderSide::Buy, quote.bid)
+        };
 
         Some(TradingSignal {
             symbol: tick.symbol.clone(),
-            side: OrderSide::Buy,
-            price: tick.price - half_spread,
+            side,
+            price,
             quantity: self.order_size,
             signal_type: SignalType::MarketMaking,
-            timestamp_nanos: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_nanos(),
+            timestamp_nanos: self.clock.now_nanos(),
+            strength: 1.0,
         })
     }
 
     fn name(&self) -> &str {
         "MarketMakingStrategy"
     }
+
+    /// Volatility history only; inventory is a position, not history
+    fn reset_history(&mut self) {
+        self.returns.clear();
+        self.last_prices.clear();
+    }
+
+    fn reset(&mut self) {
+        self.reset_history();
+        self.inventory.clear();
+    }
+
+    /// Offsets each inventory position at the last price seen for it.
+    /// Inventory itself changes only through `on_position`.
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        let timestamp_nanos = self.clock.now_nanos();
+        let mut signals: Vec<TradingSignal> = self
+            .inventory
+            .iter()
+            .filter(|(_, position)| position.abs() > 1e-9)
+            .filter_map(|(symbol, &position)| {
+                Some(TradingSignal {
+                    symbol: symbol.clone(),
+                    side: if position > 0.0 { OrderSide::Sell } else { OrderSide::Buy },
+                    price: *self.last_prices.get(symbol)?,
+                    quantity: position.abs(),
+                    signal_type: SignalType::MarketMaking,
+                    timestamp_nanos,
+                    strength: 1.0,
+                })
+            })
+            .collect();
+        signals.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        signals
+    }
+
+    set_clock_field!();
+
+    fn on_position(&mut self, symbol: &str, position: f64) {
+        self.inventory.insert(symbol.to_string(), position);
+    }
 }
 
 /// Mean reversion strategy
@@ -102,6 +577,8 @@ pub struct MeanReversionStrategy {
     std_dev_threshold: f64,
     order_size: f64,
     price_history: HashMap<String, Vec<f64>>,
+    /// Stamps signals
+    clock: SharedClock,
 }
 
 impl MeanReversionStrategy {
@@ -111,7 +588,46 @@ impl MeanReversionStrategy {
             std_dev_threshold,
             order_size,
             price_history: HashMap::new(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Persist per-symbol price history so a restart resumes warm
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let state = MeanReversionState {
+            version: MeanReversionState::VERSION,
+            window_size: self.window_size,
+            price_history: self.price_history.clone(),
+        };
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, &state)?;
+        Ok(())
+    }
+
+    /// Restore state written by `save_state`. Files from another schema
+    /// version are rejected; histories longer than the current window are trimmed.
+    pub fn load_state<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let reader = BufReader::new(File::open(path)?);
+        let state: MeanReversionState = serde_json::from_reader(reader)?;
+
+        if state.version != MeanReversionState::VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "unsupported strategy state version {} (expected {})",
+                    state.version,
+                    MeanReversionState::VERSION
+                ),
+            ));
+        }
+
+        self.price_history = state.price_history;
+        for history in self.price_history.values_mut() {
+            if history.len() > self.window_size {
+                history.drain(..history.len() - self.window_size);
+            }
         }
+        Ok(())
     }
 
     fn calculate_mean(&self, prices: &[f64]) -> f64 {
@@ -126,12 +642,24 @@ impl MeanReversionStrategy {
     }
 }
 
+/// On-disk representation of `MeanReversionStrategy` state
+#[derive(Debug, Serialize, Deserialize)]
+struct MeanReversionState {
+    version: u32,
+    window_size: usize,
+    price_history: HashMap<String, Vec<f64>>,
+}
+
+impl MeanReversionState {
+    const VERSION: u32 = 1;
+}
+
 impl Strategy for MeanReversionStrategy {
     fn process_tick(&mut self, enriched: &EnrichedTick) -> Option<TradingSignal> {
         let tick = &enriched.tick;
         let history = self.price_history
             .entry(tick.symbol.clone())
-            .or_insert_with(Vec::new);
+            .or_default();
 
         history.push(tick.price);
         if history.len() > self.window_size {
@@ -145,6 +673,10 @@ impl Strategy for MeanReversionStrategy {
         let history_clone = history.clone();
         let mean = self.calculate_mean(&history_clone);
         let std_dev = self.calculate_std_dev(&history_clone, mean);
+        // A flat window has no spread to score against
+        if std_dev <= 0.0 {
+            return None;
+        }
         let z_score = (tick.price - mean) / std_dev;
 
         if z_score.abs() > self.std_dev_threshold {
@@ -160,10 +692,8 @@ impl Strategy for MeanReversionStrategy {
                 price: tick.price,
                 quantity: self.order_size,
                 signal_type: SignalType::MeanReversion,
-                timestamp_nanos: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos(),
+                timestamp_nanos: self.clock.now_nanos(),
+                strength: z_score_strength(z_score, self.std_dev_threshold),
             })
         } else {
             None
@@ -173,77 +703,1283 @@ impl Strategy for MeanReversionStrategy {
     fn name(&self) -> &str {
         "MeanReversionStrategy"
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::MarketTick;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    set_clock_field!();
 
-    #[test]
-    fn test_threshold_strategy() {
-        let mut thresholds = HashMap::new();
-        thresholds.insert("BTC/USD".to_string(), (44000.0, 46000.0));
+    fn is_ready(&self, symbol: &str) -> bool {
+        self.price_history
+            .get(symbol)
+            .is_some_and(|history| history.len() >= self.window_size)
+    }
 
-        let mut strategy = ThresholdStrategy::new(thresholds, 1.0);
+    fn reset_history(&mut self) {
+        self.price_history.clear();
+    }
+}
 
-        let tick = MarketTick::new(
-            "BTC/USD".to_string(),
-            43500.0,
-            100,
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
-        );
+/// Exponentially-weighted mean and variance for one symbol
+#[derive(Debug, Clone, Copy)]
+struct EwmaStats {
+    mean: f64,
+    variance: f64,
+    count: usize,
+}
 
-        let enriched = EnrichedTick {
-            tick,
-            receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
-            latency_micros: 10.0,
-        };
+/// Mean reversion against an exponentially-weighted mean. Keeps O(1) state
+/// per symbol and reacts faster to level changes than the flat SMA window.
+pub struct EwmaReversionStrategy {
+    alpha: f64,
+    std_dev_threshold: f64,
+    order_size: f64,
+    warmup_ticks: usize,
+    stats: HashMap<String, EwmaStats>,
+    /// Stamps signals
+    clock: SharedClock,
+}
 
-        let signal = strategy.process_tick(&enriched);
-        assert!(signal.is_some());
-        assert_eq!(signal.unwrap().side, OrderSide::Buy);
+impl EwmaReversionStrategy {
+    /// `alpha` in (0, 1]: weight given to each new price
+    pub fn new(alpha: f64, std_dev_threshold: f64, order_size: f64) -> Self {
+        let alpha = alpha.clamp(f64::EPSILON, 1.0);
+        Self {
+            alpha,
+            std_dev_threshold,
+            order_size,
+            // Roughly one effective window before trusting the variance
+            warmup_ticks: (1.0 / alpha).ceil() as usize,
+            stats: HashMap::new(),
+            clock: SystemClock::shared(),
+        }
     }
 
-    #[test]
-    fn test_mean_reversion_strategy() {
-        let mut strategy = MeanReversionStrategy::new(5, 1.5, 1.0);
+    /// Weight halves every `half_life` ticks
+    pub fn with_half_life(half_life: f64, std_dev_threshold: f64, order_size: f64) -> Self {
+        Self::new(1.0 - 0.5f64.powf(1.0 / half_life), std_dev_threshold, order_size)
+    }
 
-        // Add some prices to build history
-        for price in [45000.0, 45100.0, 45000.0, 45050.0, 45000.0] {
-            let tick = MarketTick::new(
-                "BTC/USD".to_string(),
-                price,
-                100,
-                SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+    pub fn mean(&self, symbol: &str) -> Option<f64> {
+        self.stats.get(symbol).map(|s| s.mean)
+    }
+
+    pub fn std_dev(&self, symbol: &str) -> Option<f64> {
+        self.stats.get(symbol).map(|s| s.variance.sqrt())
+    }
+}
+
+impl Strategy for EwmaReversionStrategy {
+    fn process_tick(&mut self, enriched: &EnrichedTick) -> Option<TradingSignal> {
+        let tick = &enriched.tick;
+        let Some(stats) = self.stats.get_mut(&tick.symbol) else {
+            self.stats.insert(
+                tick.symbol.clone(),
+                EwmaStats { mean: tick.price, variance: 0.0, count: 1 },
             );
+            return None;
+        };
 
-            let enriched = EnrichedTick {
-                tick,
-                receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
-                latency_micros: 10.0,
+        // Score against the estimate before this price is folded in
+        let std_dev = stats.variance.sqrt();
+        let z_score = if stats.count >= self.warmup_ticks && std_dev > 0.0 {
+            (tick.price - stats.mean) / std_dev
+        } else {
+            0.0
+        };
+
+        let diff = tick.price - stats.mean;
+        let increment = self.alpha * diff;
+        stats.mean += increment;
+        stats.variance = (1.0 - self.alpha) * (stats.variance + diff * increment);
+        stats.count += 1;
+
+        if z_score.abs() > self.std_dev_threshold {
+            let side = if z_score > 0.0 {
+                OrderSide::Sell
+            } else {
+                OrderSide::Buy
             };
 
-            let _ = strategy.process_tick(&enriched);
+            Some(TradingSignal {
+                symbol: tick.symbol.clone(),
+                side,
+                price: tick.price,
+                quantity: self.order_size,
+                signal_type: SignalType::MeanReversion,
+                timestamp_nanos: self.clock.now_nanos(),
+                strength: z_score_strength(z_score, self.std_dev_threshold),
+            })
+        } else {
+            None
         }
+    }
 
-        // Now add an outlier
-        let tick = MarketTick::new(
-            "BTC/USD".to_string(),
-            50000.0, // Much higher outlier
-            100,
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
-        );
+    fn name(&self) -> &str {
+        "EwmaReversionStrategy"
+    }
 
-        let enriched = EnrichedTick {
-            tick,
-            receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
-            latency_micros: 10.0,
+    set_clock_field!();
+
+    fn is_ready(&self, symbol: &str) -> bool {
+        self.stats
+            .get(symbol)
+            .is_some_and(|stats| stats.count >= self.warmup_ticks)
+    }
+
+    fn reset_history(&mut self) {
+        self.stats.clear();
+    }
+}
+
+/// Large order to be worked over time by an execution strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentOrder {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub total_quantity: f64,
+    pub duration_nanos: u128,
+}
+
+/// Time-weighted execution: splits a parent order into `slices` equal child
+/// orders spaced evenly over its duration. Pacing follows the wall clock
+/// (tick receive time), so a gap in ticks is caught up in a single larger
+/// child rather than drifting the schedule.
+pub struct TwapStrategy {
+    parent: ParentOrder,
+    slices: usize,
+    slices_sent: usize,
+    scheduled_qty: f64,
+    start_nanos: Option<u128>,
+    /// Stamps signals
+    clock: SharedClock,
+}
+
+impl TwapStrategy {
+    pub fn new(parent: ParentOrder, slices: usize) -> Self {
+        Self {
+            parent,
+            slices: slices.max(1),
+            slices_sent: 0,
+            scheduled_qty: 0.0,
+            start_nanos: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn scheduled_qty(&self) -> f64 {
+        self.scheduled_qty
+    }
+
+    pub fn remaining_qty(&self) -> f64 {
+        self.parent.total_quantity - self.scheduled_qty
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.slices_sent >= self.slices
+    }
+
+    /// Slices that should have been released by `now_nanos`; the first is
+    /// due immediately and the last at the end of the duration
+    fn slices_due(&self, start_nanos: u128, now_nanos: u128) -> usize {
+        if self.slices == 1 {
+            return 1;
+        }
+        let interval = self.parent.duration_nanos / (self.slices as u128 - 1);
+        if interval == 0 {
+            return self.slices;
+        }
+        let elapsed = now_nanos.saturating_sub(start_nanos);
+        ((elapsed / interval) as usize + 1).min(self.slices)
+    }
+}
+
+impl Strategy for TwapStrategy {
+    fn process_tick(&mut self, enriched: &EnrichedTick) -> Option<TradingSignal> {
+        let tick = &enriched.tick;
+        if tick.symbol != self.parent.symbol || self.is_complete() {
+            return None;
+        }
+
+        let now = enriched.receive_time_nanos;
+        let start = *self.start_nanos.get_or_insert(now);
+        let due = self.slices_due(start, now);
+        if due <= self.slices_sent {
+            return None;
+        }
+
+        // The final slice takes whatever is left so rounding never over- or under-fills
+        let quantity = if due == self.slices {
+            self.remaining_qty()
+        } else {
+            self.parent.total_quantity / self.slices as f64 * (due - self.slices_sent) as f64
+        };
+        self.slices_sent = due;
+        self.scheduled_qty += quantity;
+
+        Some(TradingSignal {
+            symbol: tick.symbol.clone(),
+            side: self.parent.side.clone(),
+            price: tick.price,
+            quantity,
+            signal_type: SignalType::Twap,
+            timestamp_nanos: self.clock.now_nanos(),
+            strength: 1.0,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "TwapStrategy"
+    }
+
+    set_clock_field!();
+}
+
+/// Buys when a trade prints more than `threshold_bps` below the rolling
+/// VWAP of the trades before it
+pub struct VwapExecutionStrategy {
+    tracker: VwapTracker,
+    threshold_bps: f64,
+    order_size: f64,
+    /// Stamps signals
+    clock: SharedClock,
+}
+
+impl VwapExecutionStrategy {
+    pub fn new(window: Duration, threshold_bps: f64, order_size: f64) -> Self {
+        Self {
+            tracker: VwapTracker::new(window),
+            threshold_bps,
+            order_size,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+
+impl Strategy for VwapExecutionStrategy {
+    fn process_tick(&mut self, enriched: &EnrichedTick) -> Option<TradingSignal> {
+        let tick = &enriched.tick;
+        // Benchmark against the window before this trade joins it
+        let vwap = self.tracker.vwap(&tick.symbol);
+        self.tracker.on_tick(tick);
+
+        let vwap = vwap?;
+        if tick.price >= vwap * (1.0 - self.threshold_bps / 10000.0) {
+            return None;
+        }
+
+        Some(TradingSignal {
+            symbol: tick.symbol.clone(),
+            side: OrderSide::Buy,
+            price: tick.price,
+            quantity: self.order_size,
+            signal_type: SignalType::Vwap,
+            timestamp_nanos: self.clock.now_nanos(),
+            strength: 1.0,
+        })
+    }
+
+    fn name(&self) -> &str {
+        "VwapExecutionStrategy"
+    }
+
+    set_clock_field!();
+}
+
+/// Parameters for `PairsStrategy`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairsConfig {
+    pub symbol_a: String,
+    pub symbol_b: String,
+    /// Units of B per unit of A; the spread is `price_a - hedge_ratio * price_b`
+    pub hedge_ratio: f64,
+    /// Spread observations in the rolling z-score
+    pub window_size: usize,
+    /// Open when |z| exceeds this
+    pub entry_z: f64,
+    /// Close when |z| falls back below this
+    pub exit_z: f64,
+    /// Quantity of A per trade; B trades `order_size * hedge_ratio`
+    pub order_size: f64,
+}
+
+/// Which way the pair is currently positioned
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PairPosition {
+    Flat,
+    /// Long A, short B: entered when the spread was too low
+    LongSpread,
+    /// Short A, long B: entered when the spread was too high
+    ShortSpread,
+}
+
+/// Spread trading across two symbols: opens opposing legs when the spread's
+/// rolling z-score breaches `entry_z` and unwinds them once it reverts
+/// inside `exit_z`
+pub struct PairsStrategy {
+    config: PairsConfig,
+    price_a: Option<f64>,
+    price_b: Option<f64>,
+    spreads: Vec<f64>,
+    position: PairPosition,
+    /// Stamps signals
+    clock: SharedClock,
+}
+
+impl PairsStrategy {
+    pub fn new(config: PairsConfig) -> Self {
+        Self {
+            config,
+            price_a: None,
+            price_b: None,
+            spreads: Vec::new(),
+            position: PairPosition::Flat,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Z-score of `spread` against the spreads seen before it
+    fn z_score(&self, spread: f64) -> Option<f64> {
+        if self.spreads.len() < self.config.window_size.max(2) {
+            return None;
+        }
+        let n = self.spreads.len() as f64;
+        let mean = self.spreads.iter().sum::<f64>() / n;
+        let variance = self.spreads.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        (variance > 0.0).then(|| (spread - mean) / variance.sqrt())
+    }
+
+    /// Signals for both legs; `a_side` is the side for symbol A
+    fn legs(&self, a_side: OrderSide, price_a: f64, price_b: f64) -> Vec<TradingSignal> {
+        let b_side = match a_side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let timestamp_nanos = self.clock.now_nanos();
+        vec![
+            TradingSignal {
+                symbol: self.config.symbol_a.clone(),
+                side: a_side,
+                price: price_a,
+                quantity: self.config.order_size,
+                signal_type: SignalType::Arbitrage,
+                timestamp_nanos,
+                strength: 1.0,
+            },
+            TradingSignal {
+                symbol: self.config.symbol_b.clone(),
+                side: b_side,
+                price: price_b,
+                quantity: self.config.order_size * self.config.hedge_ratio,
+                signal_type: SignalType::Arbitrage,
+                timestamp_nanos,
+                strength: 1.0,
+            },
+        ]
+    }
+}
+
+impl Strategy for PairsStrategy {
+    fn process_tick(&mut self, _tick: &EnrichedTick) -> Option<TradingSignal> {
+        // Trades two legs at once; see process_tick_with_book
+        None
+    }
+
+    fn name(&self) -> &str {
+        "PairsStrategy"
+    }
+
+    set_clock_field!();
+
+    /// Ready for either leg once the spread window has filled
+    fn is_ready(&self, symbol: &str) -> bool {
+        (symbol == self.config.symbol_a || symbol == self.config.symbol_b)
+            && self.spreads.len() >= self.config.window_size.max(2)
+    }
+
+    /// Drops the spread window and last prices; an open position is kept
+    fn reset_history(&mut self) {
+        self.price_a = None;
+        self.price_b = None;
+        self.spreads.clear();
+    }
+
+    fn reset(&mut self) {
+        self.reset_history();
+        self.position = PairPosition::Flat;
+    }
+
+    /// Unwinds both legs of an open position at the last prices seen
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        let (Some(price_a), Some(price_b)) = (self.price_a, self.price_b) else {
+            return Vec::new();
+        };
+        let a_side = match self.position {
+            PairPosition::Flat => return Vec::new(),
+            PairPosition::LongSpread => OrderSide::Sell,
+            PairPosition::ShortSpread => OrderSide::Buy,
+        };
+        self.position = PairPosition::Flat;
+        self.legs(a_side, price_a, price_b)
+    }
+
+    fn process_tick_with_book(
+        &mut self,
+        enriched: &EnrichedTick,
+        _books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        let tick = &enriched.tick;
+        if tick.symbol == self.config.symbol_a {
+            self.price_a = Some(tick.price);
+        } else if tick.symbol == self.config.symbol_b {
+            self.price_b = Some(tick.price);
+        } else {
+            return Vec::new();
+        }
+        let (Some(price_a), Some(price_b)) = (self.price_a, self.price_b) else {
+            return Vec::new();
+        };
+
+        let spread = price_a - self.config.hedge_ratio * price_b;
+        let z_score = self.z_score(spread);
+        self.spreads.push(spread);
+        if self.spreads.len() > self.config.window_size {
+            self.spreads.remove(0);
+        }
+        let Some(z) = z_score else {
+            return Vec::new();
+        };
+
+        match self.position {
+            PairPosition::Flat if z > self.config.entry_z => {
+                self.position = PairPosition::ShortSpread;
+                self.legs(OrderSide::Sell, price_a, price_b)
+            }
+            PairPosition::Flat if z < -self.config.entry_z => {
+                self.position = PairPosition::LongSpread;
+                self.legs(OrderSide::Buy, price_a, price_b)
+            }
+            PairPosition::ShortSpread if z.abs() < self.config.exit_z => {
+                self.position = PairPosition::Flat;
+                self.legs(OrderSide::Buy, price_a, price_b)
+            }
+            PairPosition::LongSpread if z.abs() < self.config.exit_z => {
+                self.position = PairPosition::Flat;
+                self.legs(OrderSide::Sell, price_a, price_b)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Crossed-book arbitrage: buys the ask and sells the bid when bid >= ask
+pub struct CrossBookArbStrategy {
+    min_edge_bps: f64,
+    /// Stamps signals
+    clock: SharedClock,
+}
+
+impl CrossBookArbStrategy {
+    pub fn new(min_edge_bps: f64) -> Self {
+        Self {
+            min_edge_bps,
+            clock: SystemClock::shared(),
+        }
+    }
+}
+
+impl Strategy for CrossBookArbStrategy {
+    fn process_tick(&mut self, _tick: &EnrichedTick) -> Option<TradingSignal> {
+        // Needs the book; see process_tick_with_book
+        None
+    }
+
+    fn name(&self) -> &str {
+        "CrossBookArbStrategy"
+    }
+
+    set_clock_field!();
+
+    fn process_tick_with_book(
+        &mut self,
+        enriched: &EnrichedTick,
+        books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        let symbol = &enriched.tick.symbol;
+        if !books.is_crossed(symbol) {
+            return Vec::new();
+        }
+        let Some(book) = books.get_book(symbol) else {
+            return Vec::new();
+        };
+        let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask()) else {
+            return Vec::new();
+        };
+
+        let mid = (bid.price + ask.price) / 2.0;
+        let Some(edge_bps) = sanitize_value((bid.price - ask.price) / mid * 10000.0) else {
+            return Vec::new();
+        };
+        if edge_bps < self.min_edge_bps {
+            return Vec::new();
+        }
+
+        let quantity = bid.quantity.min(ask.quantity);
+        let timestamp_nanos = self.clock.now_nanos();
+        vec![
+            TradingSignal {
+                symbol: symbol.clone(),
+                side: OrderSide::Buy,
+                price: ask.price,
+                quantity,
+                signal_type: SignalType::Arbitrage,
+                timestamp_nanos,
+                strength: 1.0,
+            },
+            TradingSignal {
+                symbol: symbol.clone(),
+                side: OrderSide::Sell,
+                price: bid.price,
+                quantity,
+                signal_type: SignalType::Arbitrage,
+                timestamp_nanos,
+                strength: 1.0,
+            },
+        ]
+    }
+}
+
+/// How a `StrategyEnsemble` settles its members' signals for one symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnsemblePolicy {
+    /// The earliest member to signal decides
+    FirstWins,
+    /// Buys and sells offset; the remainder trades on the larger side
+    Net,
+    /// Trade only when more than half of all members signal the same side.
+    /// Members that abstain count against.
+    Majority,
+}
+
+/// Runs several strategies on every tick and merges their signals into at
+/// most one per symbol
+pub struct StrategyEnsemble {
+    strategies: Vec<Box<dyn Strategy>>,
+    policy: EnsemblePolicy,
+}
+
+impl StrategyEnsemble {
+    pub fn new(strategies: Vec<Box<dyn Strategy>>, policy: EnsemblePolicy) -> Self {
+        Self { strategies, policy }
+    }
+
+    /// One signal per symbol, in the order symbols first appear
+    fn aggregate(&self, signals: Vec<TradingSignal>) -> Vec<TradingSignal> {
+        let mut by_symbol: Vec<(String, Vec<TradingSignal>)> = Vec::new();
+        for signal in signals {
+            match by_symbol.iter_mut().find(|(symbol, _)| *symbol == signal.symbol) {
+                Some((_, group)) => group.push(signal),
+                None => by_symbol.push((signal.symbol.clone(), vec![signal])),
+            }
+        }
+        by_symbol
+            .into_iter()
+            .filter_map(|(_, group)| self.resolve(group))
+            .collect()
+    }
+
+    fn resolve(&self, group: Vec<TradingSignal>) -> Option<TradingSignal> {
+        match self.policy {
+            EnsemblePolicy::FirstWins => group.into_iter().next(),
+            EnsemblePolicy::Net => {
+                let net: f64 = group
+                    .iter()
+                    .map(|signal| match signal.side {
+                        OrderSide::Buy => signal.quantity,
+                        OrderSide::Sell => -signal.quantity,
+                    })
+                    .sum();
+                if net.abs() < 1e-9 {
+                    return None;
+                }
+                let side = if net > 0.0 { OrderSide::Buy } else { OrderSide::Sell };
+                // Priced like the first signal on the winning side
+                let mut signal = group.into_iter().find(|signal| signal.side == side)?;
+                signal.quantity = net.abs();
+                Some(signal)
+            }
+            EnsemblePolicy::Majority => {
+                let buys = group.iter().filter(|signal| signal.side == OrderSide::Buy).count();
+                let side = if buys * 2 > self.strategies.len() {
+                    OrderSide::Buy
+                } else if (group.len() - buys) * 2 > self.strategies.len() {
+                    OrderSide::Sell
+                } else {
+                    return None;
+                };
+                group.into_iter().find(|signal| signal.side == side)
+            }
+        }
+    }
+}
+
+impl Strategy for StrategyEnsemble {
+    fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+        let signals = self
+            .strategies
+            .iter_mut()
+            .filter_map(|strategy| strategy.process_tick(tick))
+            .collect();
+        self.aggregate(signals).into_iter().next()
+    }
+
+    fn name(&self) -> &str {
+        "StrategyEnsemble"
+    }
+
+    fn process_tick_with_book(
+        &mut self,
+        tick: &EnrichedTick,
+        books: &OrderBookManager,
+    ) -> Vec<TradingSignal> {
+        let signals = self
+            .strategies
+            .iter_mut()
+            .flat_map(|strategy| strategy.process_tick_with_book(tick, books))
+            .collect();
+        self.aggregate(signals)
+    }
+
+    /// Ready once any member can act
+    fn is_ready(&self, symbol: &str) -> bool {
+        self.strategies.iter().any(|strategy| strategy.is_ready(symbol))
+    }
+
+    fn reset_history(&mut self) {
+        for strategy in &mut self.strategies {
+            strategy.reset_history();
+        }
+    }
+
+    fn reset(&mut self) {
+        for strategy in &mut self.strategies {
+            strategy.reset();
+        }
+    }
+
+    /// Members all see the same positions, so the first member to close a
+    /// symbol closes it for the ensemble
+    fn flatten(&mut self) -> Vec<TradingSignal> {
+        let mut signals: Vec<TradingSignal> = Vec::new();
+        for signal in self.strategies.iter_mut().flat_map(|strategy| strategy.flatten()) {
+            if !signals.iter().any(|closing| closing.symbol == signal.symbol) {
+                signals.push(signal);
+            }
+        }
+        signals
+    }
+
+    fn set_clock(&mut self, clock: SharedClock) {
+        for strategy in &mut self.strategies {
+            strategy.set_clock(clock.clone());
+        }
+    }
+
+    fn on_position(&mut self, symbol: &str, position: f64) {
+        for strategy in &mut self.strategies {
+            strategy.on_position(symbol, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::MarketTick;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_threshold_strategy() {
+        let mut thresholds = HashMap::new();
+        thresholds.insert("BTC/USD".to_string(), (44000.0, 46000.0));
+
+        let mut strategy = ThresholdStrategy::new(thresholds, 1.0);
+
+        let tick = MarketTick::new(
+            "BTC/USD".to_string(),
+            43500.0,
+            100,
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+        );
+
+        let enriched = EnrichedTick {
+            tick,
+            receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+            decoded_nanos: None,
+            latency_micros: 10.0,
         };
 
-        let signal = strategy.process_tick(&enriched);
-        assert!(signal.is_some());
-        assert_eq!(signal.unwrap().side, OrderSide::Sell);
+        let signal = strategy.process_tick(&enriched).unwrap();
+        assert_eq!(signal.side, OrderSide::Buy);
+        // A quarter band width below the band
+        assert_eq!(signal.strength, 0.75);
+    }
+
+    /// Buys below a reference price it has to "fetch" first
+    struct RemoteReferenceStrategy {
+        reference: f64,
+    }
+
+    #[async_trait]
+    impl AsyncStrategy for RemoteReferenceStrategy {
+        async fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+            tokio::task::yield_now().await;
+            (tick.tick.price < self.reference).then(|| TradingSignal {
+                symbol: tick.tick.symbol.clone(),
+                side: OrderSide::Buy,
+                price: tick.tick.price,
+                quantity: 1.0,
+                signal_type: SignalType::Threshold,
+                timestamp_nanos: tick.tick.timestamp_nanos,
+                strength: 1.0,
+            })
+        }
+
+        fn name(&self) -> &str {
+            "RemoteReferenceStrategy"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_strategy_and_sync_adapter() {
+        let enriched = |price| EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), price, 1, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 0.0,
+        };
+        let books = OrderBookManager::new();
+
+        let mut remote: Box<dyn AsyncStrategy> = Box::new(RemoteReferenceStrategy { reference: 45000.0 });
+        assert!(remote.process_tick(&enriched(45500.0)).await.is_none());
+        let signals = remote.process_tick_with_book(&enriched(44500.0), &books).await;
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].side, OrderSide::Buy);
+        assert_eq!(signals[0].price, 44500.0);
+
+        // A synchronous strategy behaves the same through the adapter
+        let thresholds = HashMap::from([("BTC/USD".to_string(), (44000.0, 46000.0))]);
+        let boxed: Box<dyn Strategy> = Box::new(ThresholdStrategy::new(thresholds, 1.0));
+        let mut adapted: Box<dyn AsyncStrategy> = Box::new(SyncAdapter(boxed));
+        assert_eq!(adapted.name(), "ThresholdStrategy");
+        let signal = adapted.process_tick(&enriched(46500.0)).await.unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+    }
+
+    /// Signals `side` on every tick, or never
+    struct Fixed(Option<OrderSide>, f64);
+
+    impl Strategy for Fixed {
+        fn process_tick(&mut self, tick: &EnrichedTick) -> Option<TradingSignal> {
+            Some(TradingSignal {
+                symbol: tick.tick.symbol.clone(),
+                side: self.0.clone()?,
+                price: tick.tick.price,
+                quantity: self.1,
+                signal_type: SignalType::Threshold,
+                timestamp_nanos: tick.tick.timestamp_nanos,
+                strength: 1.0,
+            })
+        }
+
+        fn name(&self) -> &str {
+            "Fixed"
+        }
+    }
+
+    fn ensemble(members: &[(Option<OrderSide>, f64)], policy: EnsemblePolicy) -> StrategyEnsemble {
+        StrategyEnsemble::new(
+            members
+                .iter()
+                .map(|(side, quantity)| Box::new(Fixed(side.clone(), *quantity)) as Box<dyn Strategy>)
+                .collect(),
+            policy,
+        )
+    }
+
+    fn ensemble_signal(
+        members: &[(Option<OrderSide>, f64)],
+        policy: EnsemblePolicy,
+    ) -> Option<(OrderSide, f64)> {
+        let enriched = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+        let signals = ensemble(members, policy).process_tick_with_book(&enriched, &OrderBookManager::new());
+        assert!(signals.len() <= 1);
+        signals.into_iter().next().map(|signal| (signal.side, signal.quantity))
+    }
+
+    #[test]
+    fn test_ensemble_agreement_passes_through() {
+        let agree = [(Some(OrderSide::Buy), 1.0), (Some(OrderSide::Buy), 2.0)];
+        assert_eq!(ensemble_signal(&agree, EnsemblePolicy::FirstWins), Some((OrderSide::Buy, 1.0)));
+        assert_eq!(ensemble_signal(&agree, EnsemblePolicy::Net), Some((OrderSide::Buy, 3.0)));
+        assert_eq!(ensemble_signal(&agree, EnsemblePolicy::Majority), Some((OrderSide::Buy, 1.0)));
+    }
+
+    #[test]
+    fn test_ensemble_disagreement_nets_or_suppresses() {
+        let disagree = [(Some(OrderSide::Buy), 1.0), (Some(OrderSide::Sell), 3.0)];
+        assert_eq!(ensemble_signal(&disagree, EnsemblePolicy::FirstWins), Some((OrderSide::Buy, 1.0)));
+        assert_eq!(ensemble_signal(&disagree, EnsemblePolicy::Net), Some((OrderSide::Sell, 2.0)));
+        assert_eq!(ensemble_signal(&disagree, EnsemblePolicy::Majority), None);
+
+        // Equal and opposite cancel out
+        let offset = [(Some(OrderSide::Buy), 2.0), (Some(OrderSide::Sell), 2.0)];
+        assert_eq!(ensemble_signal(&offset, EnsemblePolicy::Net), None);
+    }
+
+    #[test]
+    fn test_ensemble_with_abstaining_member() {
+        let abstain = [(None, 1.0), (Some(OrderSide::Sell), 2.0)];
+        assert_eq!(ensemble_signal(&abstain, EnsemblePolicy::FirstWins), Some((OrderSide::Sell, 2.0)));
+        assert_eq!(ensemble_signal(&abstain, EnsemblePolicy::Net), Some((OrderSide::Sell, 2.0)));
+        // One vote of two is not a majority
+        assert_eq!(ensemble_signal(&abstain, EnsemblePolicy::Majority), None);
+
+        let three = [(None, 1.0), (Some(OrderSide::Sell), 2.0), (Some(OrderSide::Sell), 1.0)];
+        assert_eq!(ensemble_signal(&three, EnsemblePolicy::Majority), Some((OrderSide::Sell, 2.0)));
+
+        let none = [(None, 1.0), (None, 1.0)];
+        assert_eq!(ensemble_signal(&none, EnsemblePolicy::FirstWins), None);
+    }
+
+    #[test]
+    fn test_signals_are_stamped_by_clock() {
+        let clock = MockClock::new(5_000);
+        let mut strategy: Box<dyn Strategy> = Box::new(ThresholdStrategy::new(
+            HashMap::from([("BTC/USD".to_string(), (44000.0, 46000.0))]),
+            1.0,
+        ));
+        strategy.set_clock(clock.shared());
+
+        let enriched = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 43000.0, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+        assert_eq!(strategy.process_tick(&enriched).unwrap().timestamp_nanos, 5_000);
+        clock.advance(Duration::from_nanos(10));
+        assert_eq!(strategy.process_tick(&enriched).unwrap().timestamp_nanos, 5_010);
+    }
+
+    #[test]
+    fn test_mean_reversion_strategy() {
+        let mut strategy = MeanReversionStrategy::new(5, 1.5, 1.0);
+
+        // Add some prices to build history
+        for price in [45000.0, 45100.0, 45000.0, 45050.0, 45000.0] {
+            let tick = MarketTick::new(
+                "BTC/USD".to_string(),
+                price,
+                100,
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+            );
+
+            let enriched = EnrichedTick {
+                tick,
+                receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+                decoded_nanos: None,
+                latency_micros: 10.0,
+            };
+
+            let _ = strategy.process_tick(&enriched);
+        }
+
+        // Now add an outlier
+        let tick = MarketTick::new(
+            "BTC/USD".to_string(),
+            50000.0, // Much higher outlier
+            100,
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+        );
+
+        let enriched = EnrichedTick {
+            tick,
+            receive_time_nanos: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos(),
+            decoded_nanos: None,
+            latency_micros: 10.0,
+        };
+
+        let signal = strategy.process_tick(&enriched).unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+        // Past the 1.5 threshold, short of twice it
+        assert!(signal.strength > 0.5 && signal.strength < 1.0, "{}", signal.strength);
+    }
+
+    #[test]
+    fn test_mean_reversion_is_ready_after_window() {
+        let make_tick = |symbol: &str, price: f64| EnrichedTick {
+            tick: MarketTick::new(symbol.to_string(), price, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+
+        let mut strategy = MeanReversionStrategy::new(3, 1.5, 1.0);
+        assert!(!strategy.is_ready("BTC/USD"));
+
+        for price in [45000.0, 45100.0] {
+            strategy.process_tick(&make_tick("BTC/USD", price));
+            assert!(!strategy.is_ready("BTC/USD"));
+        }
+        strategy.process_tick(&make_tick("BTC/USD", 45050.0));
+        assert!(strategy.is_ready("BTC/USD"));
+
+        // Readiness is tracked per symbol
+        strategy.process_tick(&make_tick("ETH/USD", 2500.0));
+        assert!(!strategy.is_ready("ETH/USD"));
+
+        // Stale history has to be rebuilt
+        strategy.reset_history();
+        assert!(!strategy.is_ready("BTC/USD"));
+
+        // Strategies without a warmup are always ready
+        let threshold = ThresholdStrategy::new(HashMap::new(), 1.0);
+        assert!(threshold.is_ready("BTC/USD"));
+    }
+
+    #[test]
+    fn test_mean_reversion_state_round_trip() {
+        let state_file = "/tmp/hft_test_mean_reversion_state.json";
+        let make_tick = |price: f64| EnrichedTick {
+            tick: MarketTick::new("ETH/USD".to_string(), price, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+
+        let mut uninterrupted = MeanReversionStrategy::new(5, 1.5, 1.0);
+        for price in [2500.0, 2510.0, 2495.0, 2505.0, 2500.0] {
+            uninterrupted.process_tick(&make_tick(price));
+        }
+        uninterrupted.save_state(state_file).unwrap();
+
+        let mut restarted = MeanReversionStrategy::new(5, 1.5, 1.0);
+        restarted.load_state(state_file).unwrap();
+
+        let expected = uninterrupted.process_tick(&make_tick(2400.0)).unwrap();
+        let resumed = restarted.process_tick(&make_tick(2400.0)).unwrap();
+        assert_eq!(resumed.side, expected.side);
+        assert_eq!(resumed.price, expected.price);
+
+        // A file from a different schema version is rejected
+        std::fs::write(state_file, r#"{"version":99,"window_size":5,"price_history":{}}"#).unwrap();
+        let err = restarted.load_state(state_file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(state_file).unwrap();
+    }
+
+    #[test]
+    fn test_cross_book_arbitrage() {
+        use crate::{BookLevel, OrderBook};
+
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel { price: 45010.0, quantity: 2.0 });
+        book.asks.push(BookLevel { price: 45000.0, quantity: 0.5 });
+        let mut books = OrderBookManager::new();
+        books.apply_snapshot(book);
+
+        let enriched = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 45005.0, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+
+        // ~2.2bps of edge clears a 1bp minimum
+        let mut strategy = CrossBookArbStrategy::new(1.0);
+        let signals = strategy.process_tick_with_book(&enriched, &books);
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].side, OrderSide::Buy);
+        assert_eq!(signals[0].price, 45000.0);
+        assert_eq!(signals[1].side, OrderSide::Sell);
+        assert_eq!(signals[1].price, 45010.0);
+        assert!(signals.iter().all(|s| s.quantity == 0.5));
+        assert!(signals.iter().all(|s| matches!(s.signal_type, SignalType::Arbitrage)));
+
+        // ...but not a 5bp minimum
+        let mut picky = CrossBookArbStrategy::new(5.0);
+        assert!(picky.process_tick_with_book(&enriched, &books).is_empty());
+    }
+
+    #[test]
+    fn test_no_nonfinite_signal_escapes() {
+        use crate::{BookLevel, OrderBook};
+
+        let finite = |signal: &TradingSignal| {
+            signal.price.is_finite() && signal.quantity.is_finite() && signal.strength.is_finite()
+        };
+
+        // Constant prices: zero std-dev, nothing to score against
+        let mut mean_reversion = Guarded::new(MeanReversionStrategy::new(5, 2.0, 1.0), NonFinitePolicy::Suppress);
+        for _ in 0..20 {
+            assert!(mean_reversion.process_tick(&btc_tick(100.0)).is_none());
+        }
+
+        // A zero-priced, zero-quantity crossed book has no mid to measure
+        // edge against
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel { price: 0.0, quantity: 0.0 });
+        book.asks.push(BookLevel { price: 0.0, quantity: 0.0 });
+        let mut books = OrderBookManager::new();
+        books.apply_snapshot(book);
+        let mut arb = Guarded::new(CrossBookArbStrategy::new(1.0), NonFinitePolicy::Suppress);
+        assert!(arb.process_tick_with_book(&btc_tick(0.0), &books).is_empty());
+
+        // A poisoned tick makes NaN quotes, which are dropped on the way out
+        let mut market_maker = Guarded::new(MarketMakingStrategy::new(10.0, 1.0), NonFinitePolicy::Suppress);
+        assert!(market_maker.process_tick(&btc_tick(100.0)).is_some_and(|s| finite(&s)));
+        assert!(market_maker.process_tick(&btc_tick(f64::NAN)).is_none());
+
+        // Clamping keeps a signal whose only flaw is its strength
+        let signal = TradingSignal {
+            strength: f64::INFINITY,
+            ..market_maker.process_tick(&btc_tick(100.0)).unwrap()
+        };
+        assert!(sanitize_signal(signal.clone(), NonFinitePolicy::Suppress).is_none());
+        let clamped = sanitize_signal(signal.clone(), NonFinitePolicy::Clamp).unwrap();
+        assert!(finite(&clamped));
+        assert_eq!(clamped.strength, 1.0);
+        // ...but not one with nothing sensible to clamp a NaN price to
+        let poisoned = TradingSignal { price: f64::NAN, ..signal };
+        assert!(sanitize_signal(poisoned, NonFinitePolicy::Clamp).is_none());
+    }
+
+    fn btc_tick(price: f64) -> EnrichedTick {
+        EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), price, 10, 1),
+            receive_time_nanos: 1,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_ewma_tracks_step_faster_than_sma() {
+        let mut sma = MeanReversionStrategy::new(20, 3.0, 1.0);
+        let mut ewma = EwmaReversionStrategy::with_half_life(2.0, 3.0, 1.0);
+
+        let prices = std::iter::repeat_n(100.0, 20).chain(std::iter::repeat_n(110.0, 5));
+        for price in prices {
+            sma.process_tick(&btc_tick(price));
+            ewma.process_tick(&btc_tick(price));
+        }
+
+        let sma_mean = sma.calculate_mean(&sma.price_history["BTC/USD"]);
+        let ewma_mean = ewma.mean("BTC/USD").unwrap();
+        assert_eq!(sma_mean, 102.5);
+        assert!(ewma_mean > 108.0, "ewma mean {}", ewma_mean);
+    }
+
+    #[test]
+    fn test_ewma_quiet_series_no_signal() {
+        let mut strategy = EwmaReversionStrategy::new(0.1, 3.0, 1.0);
+        for i in 0..200 {
+            let price = if i % 2 == 0 { 45000.0 } else { 45001.0 };
+            assert!(strategy.process_tick(&btc_tick(price)).is_none());
+        }
+
+        // A jump far outside the band does signal
+        let signal = strategy.process_tick(&btc_tick(45100.0)).unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+        assert!(matches!(signal.signal_type, SignalType::MeanReversion));
+    }
+
+    #[test]
+    fn test_market_maker_widens_with_volatility() {
+        let mut calm = MarketMakingStrategy::new(5.0, 1.0).with_vol_multiplier(1.0);
+        let mut choppy = MarketMakingStrategy::new(5.0, 1.0).with_vol_multiplier(1.0);
+        let mut constant = MarketMakingStrategy::new(5.0, 1.0);
+        for i in 0..30 {
+            let swing = if i % 2 == 0 { 1.0 } else { -1.0 };
+            calm.process_tick(&btc_tick(45000.0 + swing));
+            choppy.process_tick(&btc_tick(45000.0 + 100.0 * swing));
+            constant.process_tick(&btc_tick(45000.0 + 100.0 * swing));
+        }
+
+        let calm_width = calm.quote("BTC/USD").unwrap().spread_bps();
+        let choppy_width = choppy.quote("BTC/USD").unwrap().spread_bps();
+        // 5bp a side plus about 0.4bp of volatility
+        assert!((calm_width - 10.9).abs() < 0.1, "calm spread {}", calm_width);
+        // About 44bp a side of volatility on top
+        assert!(choppy_width > 90.0, "choppy spread {}", choppy_width);
+        // Without a multiplier, volatility is ignored
+        let constant_width = constant.quote("BTC/USD").unwrap().spread_bps();
+        assert!((constant_width - 10.0).abs() < 1e-9);
+
+        let signal = choppy.process_tick(&btc_tick(44900.0)).unwrap();
+        assert_eq!(signal.side, OrderSide::Buy);
+        assert_eq!(signal.price, choppy.quote("BTC/USD").unwrap().bid);
+    }
+
+    #[test]
+    fn test_market_maker_leans_against_inventory() {
+        let mut strategy = MarketMakingStrategy::new(5.0, 1.0).with_inventory_skew(2.0);
+        strategy.process_tick(&btc_tick(45000.0));
+        let flat = strategy.quote("BTC/USD").unwrap();
+        assert!((flat.mid() - 45000.0).abs() < 1e-9);
+
+        // Long 5: mid 10bp lower, and it offers instead of bidding
+        strategy.on_position("BTC/USD", 5.0);
+        let long = strategy.quote("BTC/USD").unwrap();
+        assert!((long.mid() - 44955.0).abs() < 1e-9);
+        assert!((long.spread_bps() - flat.spread_bps()).abs() < 1e-9);
+        let signal = strategy.process_tick(&btc_tick(45000.0)).unwrap();
+        assert_eq!(signal.side, OrderSide::Sell);
+        assert_eq!(signal.price, long.ask);
+
+        strategy.on_position("BTC/USD", -5.0);
+        let short = strategy.quote("BTC/USD").unwrap();
+        assert!((short.mid() - 45045.0).abs() < 1e-9);
+        assert_eq!(strategy.process_tick(&btc_tick(45000.0)).unwrap().side, OrderSide::Buy);
+    }
+
+    #[test]
+    fn test_flatten_closes_long_position_and_reset_restarts_warmup() {
+        let mut strategy = MarketMakingStrategy::new(5.0, 1.0);
+        strategy.process_tick(&btc_tick(45000.0));
+        assert!(strategy.flatten().is_empty());
+
+        strategy.on_position("BTC/USD", 2.5);
+        let signals = strategy.flatten();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].symbol, "BTC/USD");
+        assert_eq!(signals[0].side, OrderSide::Sell);
+        assert_eq!(signals[0].quantity, 2.5);
+        assert_eq!(signals[0].price, 45000.0);
+
+        strategy.reset();
+        assert!(strategy.quote("BTC/USD").is_none());
+        assert!(strategy.flatten().is_empty());
+
+        let mut ensemble = StrategyEnsemble::new(
+            vec![
+                Box::new(MarketMakingStrategy::new(5.0, 1.0)),
+                Box::new(MarketMakingStrategy::new(8.0, 1.0)),
+            ],
+            EnsemblePolicy::Net,
+        );
+        ensemble.process_tick(&btc_tick(45000.0));
+        ensemble.on_position("BTC/USD", -3.0);
+        let signals = ensemble.flatten();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].side, OrderSide::Buy);
+        assert_eq!(signals[0].quantity, 3.0);
+    }
+
+    #[test]
+    fn test_twap_slices_sum_to_parent() {
+        const MS: u128 = 1_000_000;
+        let mut twap = TwapStrategy::new(
+            ParentOrder {
+                symbol: "BTC/USD".to_string(),
+                side: OrderSide::Buy,
+                total_quantity: 10.0,
+                duration_nanos: 300 * MS,
+            },
+            4,
+        );
+
+        // Irregular arrivals, including a gap spanning two slice intervals
+        let arrivals = [0, 20, 100, 105, 310, 400, 500];
+        let mut slices = Vec::new();
+        for ms in arrivals {
+            let mut enriched = btc_tick(45000.0);
+            enriched.receive_time_nanos = ms * MS;
+            if let Some(signal) = twap.process_tick(&enriched) {
+                assert_eq!(signal.side, OrderSide::Buy);
+                assert!(matches!(signal.signal_type, SignalType::Twap));
+                slices.push((ms, signal.quantity));
+            }
+
+            // Other symbols never advance the schedule
+            let other = EnrichedTick {
+                tick: MarketTick::new("ETH/USD".to_string(), 2500.0, 1, 1),
+                receive_time_nanos: ms * MS,
+                decoded_nanos: None,
+                latency_micros: 0.0,
+            };
+            assert!(twap.process_tick(&other).is_none());
+        }
+
+        assert_eq!(slices, vec![(0, 2.5), (100, 2.5), (310, 5.0)]);
+        assert_eq!(twap.scheduled_qty(), 10.0);
+        assert_eq!(twap.remaining_qty(), 0.0);
+        assert!(twap.is_complete());
+    }
+
+    #[test]
+    fn test_vwap_execution_buys_below_vwap() {
+        let mut strategy = VwapExecutionStrategy::new(Duration::from_secs(60), 10.0, 1.0);
+
+        // First trade has no benchmark yet
+        assert!(strategy.process_tick(&btc_tick(45000.0)).is_none());
+        // 5bps below VWAP: inside the 10bp threshold
+        assert!(strategy.process_tick(&btc_tick(44977.5)).is_none());
+
+        let signal = strategy.process_tick(&btc_tick(44900.0)).unwrap();
+        assert_eq!(signal.side, OrderSide::Buy);
+        assert!(matches!(signal.signal_type, SignalType::Vwap));
+    }
+
+    #[test]
+    fn test_pairs_entry_and_exit() {
+        let mut strategy = PairsStrategy::new(PairsConfig {
+            symbol_a: "BTC/USD".to_string(),
+            symbol_b: "ETH/USD".to_string(),
+            hedge_ratio: 18.0,
+            window_size: 10,
+            entry_z: 2.0,
+            exit_z: 0.5,
+            order_size: 1.0,
+        });
+        let books = OrderBookManager::new();
+        let mut feed = |symbol: &str, price: f64| {
+            let enriched = EnrichedTick {
+                tick: MarketTick::new(symbol.to_string(), price, 1, 1),
+                receive_time_nanos: 1,
+                decoded_nanos: None,
+                latency_micros: 0.0,
+            };
+            strategy.process_tick_with_book(&enriched, &books)
+        };
+
+        // ETH fixed at 2500, so the spread is BTC - 45000
+        assert!(feed("ETH/USD", 2500.0).is_empty());
+        for i in 0..10 {
+            let btc = if i % 2 == 0 { 45000.0 } else { 45010.0 };
+            assert!(feed("BTC/USD", btc).is_empty());
+        }
+
+        // Diverge: spread far above its mean, so short A / long B
+        let entry = feed("BTC/USD", 45100.0);
+        assert_eq!(entry.len(), 2);
+        assert_eq!((entry[0].symbol.as_str(), &entry[0].side), ("BTC/USD", &OrderSide::Sell));
+        assert_eq!((entry[1].symbol.as_str(), &entry[1].side), ("ETH/USD", &OrderSide::Buy));
+        assert_eq!(entry[1].quantity, 18.0);
+
+        // Still wide: hold
+        assert!(feed("BTC/USD", 45090.0).is_empty());
+
+        // Converge back to the mean: unwind both legs
+        let exit = feed("BTC/USD", 45020.0);
+        assert_eq!(exit.len(), 2);
+        assert_eq!(exit[0].side, OrderSide::Buy);
+        assert_eq!(exit[1].side, OrderSide::Sell);
     }
 }
@@ -0,0 +1,282 @@
+use crate::backtest::{BacktestReport, Backtester};
+use crate::config::{build_strategy, StrategyConfig};
+use crate::fees::FeeSchedule;
+use crate::{HftError, HftResult};
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Parameter values by `StrategyConfig` field name, e.g. `window_size`
+pub type Parameters = BTreeMap<String, f64>;
+
+/// What a sweep ranks its runs by, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    NetPnl,
+    Sharpe,
+}
+
+impl Objective {
+    pub fn score(self, report: &BacktestReport) -> f64 {
+        match self {
+            Objective::NetPnl => report.net_pnl,
+            Objective::Sharpe => report.sharpe,
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = HftError;
+
+    fn from_str(s: &str) -> HftResult<Self> {
+        match s {
+            "net_pnl" => Ok(Objective::NetPnl),
+            "sharpe" => Ok(Objective::Sharpe),
+            _ => Err(HftError::InvalidConfig(format!(
+                "unknown objective {:?}; expected net_pnl or sharpe",
+                s
+            ))),
+        }
+    }
+}
+
+/// One backtest of a sweep
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepResult {
+    pub parameters: Parameters,
+    pub config: StrategyConfig,
+    pub report: BacktestReport,
+}
+
+/// Runs the `Backtester` over one recording for every combination of a
+/// parameter grid, in parallel, and ranks the reports by an `Objective`.
+/// Each combination is `base` with the grid's fields overridden, so any
+/// numeric field of any strategy can be swept.
+pub struct ParameterSweep {
+    base: StrategyConfig,
+    grid: BTreeMap<String, Vec<f64>>,
+    recording: PathBuf,
+    fees: FeeSchedule,
+    objective: Objective,
+    /// Run only this many combinations, drawn with the seed
+    sample: Option<(usize, u64)>,
+}
+
+impl ParameterSweep {
+    pub fn new<P: AsRef<Path>>(base: StrategyConfig, recording: P) -> Self {
+        Self {
+            base,
+            grid: BTreeMap::new(),
+            recording: recording.as_ref().to_path_buf(),
+            fees: FeeSchedule::default(),
+            objective: Objective::NetPnl,
+            sample: None,
+        }
+    }
+
+    /// Try each of `values` for the field `name`
+    pub fn with_parameter(mut self, name: &str, values: &[f64]) -> Self {
+        self.grid.insert(name.to_string(), values.to_vec());
+        self
+    }
+
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Run a random `count` of the combinations instead of all of them,
+    /// picked the same way every time for the same `seed`
+    pub fn with_sample(mut self, count: usize, seed: u64) -> Self {
+        self.sample = Some((count, seed));
+        self
+    }
+
+    /// Every combination of the grid, varying the last parameter (by name)
+    /// fastest
+    pub fn combinations(&self) -> Vec<Parameters> {
+        let mut combinations = vec![Parameters::new()];
+        for (name, values) in &self.grid {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|parameters| {
+                    values.iter().map(move |&value| {
+                        let mut parameters = parameters.clone();
+                        parameters.insert(name.clone(), value);
+                        parameters
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+
+    /// The base config with `parameters` substituted. Integer fields only
+    /// take whole values.
+    pub fn config_for(&self, parameters: &Parameters) -> HftResult<StrategyConfig> {
+        let mut config = serde_json::to_value(&self.base)?;
+        let fields = config
+            .as_object_mut()
+            .ok_or_else(|| HftError::InvalidConfig("strategy config is not a table".to_string()))?;
+        for (name, &value) in parameters {
+            let field = fields.get_mut(name).ok_or_else(|| {
+                HftError::InvalidConfig(format!("strategy has no parameter {}", name))
+            })?;
+            *field = match field {
+                Value::Number(current) if current.is_u64() => {
+                    if value.fract() != 0.0 || value < 0.0 {
+                        return Err(HftError::InvalidConfig(format!(
+                            "{} takes whole numbers, not {}",
+                            name, value
+                        )));
+                    }
+                    Value::from(value as u64)
+                }
+                Value::Number(_) => {
+                    Number::from_f64(value).map(Value::Number).ok_or_else(|| {
+                        HftError::InvalidConfig(format!("{} of {} is not finite", name, value))
+                    })?
+                }
+                _ => {
+                    return Err(HftError::InvalidConfig(format!(
+                        "strategy parameter {} is not numeric",
+                        name
+                    )))
+                }
+            };
+        }
+        Ok(serde_json::from_value(config)?)
+    }
+
+    /// Backtest each combination and return the results best first. Ties
+    /// keep grid order, so the table is the same from run to run.
+    pub fn run(&self) -> HftResult<Vec<SweepResult>> {
+        let mut combinations = self.combinations();
+        if let Some((count, seed)) = self.sample {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut picked =
+                sample(&mut rng, combinations.len(), count.min(combinations.len())).into_vec();
+            picked.sort_unstable();
+            combinations = picked
+                .into_iter()
+                .map(|i| combinations[i].clone())
+                .collect();
+        }
+        let configs = combinations
+            .into_iter()
+            .map(|parameters| Ok((self.config_for(&parameters)?, parameters)))
+            .collect::<HftResult<Vec<_>>>()?;
+
+        let mut results = configs
+            .into_par_iter()
+            .map(|(config, parameters)| {
+                let report = Backtester::new(build_strategy(&config), &self.recording)
+                    .with_fees(self.fees.clone())
+                    .run()?;
+                Ok(SweepResult {
+                    parameters,
+                    config,
+                    report,
+                })
+            })
+            .collect::<HftResult<Vec<_>>>()?;
+        results.sort_by(|a, b| {
+            self.objective
+                .score(&b.report)
+                .total_cmp(&self.objective.score(&a.report))
+        });
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThresholdBand;
+    use crate::replay::MarketRecorder;
+    use crate::MarketTick;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_sweep_ranks_each_combination() {
+        let temp_file = "/tmp/hft_test_sweep.jsonl";
+        {
+            let mut recorder = MarketRecorder::new(temp_file).unwrap();
+            // Buys the dip to 94, sells the pop to 106
+            for (i, price) in [100.0, 94.0, 100.0, 106.0, 100.0].into_iter().enumerate() {
+                let tick =
+                    MarketTick::new("BTC/USD".to_string(), price, 10, (i as u128 + 1) * 1_000);
+                recorder.record_tick(&tick).unwrap();
+            }
+            recorder.flush().unwrap();
+        }
+        let base = StrategyConfig::Threshold {
+            order_size: 1.0,
+            thresholds: HashMap::from([(
+                "BTC/USD".to_string(),
+                ThresholdBand {
+                    low: 95.0,
+                    high: 105.0,
+                },
+            )]),
+        };
+
+        let sweep =
+            ParameterSweep::new(base.clone(), temp_file).with_parameter("order_size", &[1.0, 3.0]);
+        let results = sweep.run().unwrap();
+        assert_eq!(results.len(), 2);
+        // Same round trip, three times the size
+        assert_eq!(results[0].parameters["order_size"], 3.0);
+        assert!((results[0].report.net_pnl - 36.0).abs() < 1e-9);
+        assert!((results[1].report.net_pnl - 12.0).abs() < 1e-9);
+        assert!(
+            matches!(results[0].config, StrategyConfig::Threshold { order_size, .. } if order_size == 3.0)
+        );
+
+        // A seeded sample picks the same combinations every time
+        let sampled = |seed| {
+            ParameterSweep::new(base.clone(), temp_file)
+                .with_parameter("order_size", &[1.0, 2.0, 3.0, 4.0])
+                .with_sample(2, seed)
+                .run()
+                .unwrap()
+        };
+        assert_eq!(sampled(7), sampled(7));
+        assert_eq!(sampled(7).len(), 2);
+
+        // Only fields the strategy has, and whole numbers for integer ones
+        let unknown = ParameterSweep::new(base, temp_file).with_parameter("window_size", &[10.0]);
+        assert!(unknown.run().is_err());
+        let mean_reversion = StrategyConfig::MeanReversion {
+            window_size: 20,
+            std_dev_threshold: 2.0,
+            order_size: 1.0,
+        };
+        let sweep = ParameterSweep::new(mean_reversion, temp_file)
+            .with_parameter("window_size", &[10.0, 20.0, 50.0])
+            .with_parameter("std_dev_threshold", &[1.5, 2.0]);
+        assert_eq!(sweep.combinations().len(), 6);
+        let config = sweep.config_for(&sweep.combinations()[5]).unwrap();
+        assert!(matches!(
+            config,
+            StrategyConfig::MeanReversion { window_size: 50, std_dev_threshold, .. } if std_dev_threshold == 2.0
+        ));
+        assert!(sweep
+            .config_for(&Parameters::from([("window_size".to_string(), 12.5)]))
+            .is_err());
+
+        std::fs::remove_file(temp_file).ok();
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{HftError, HftResult, MarketTick, TopOfBook};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Interned symbol: a small copyable handle into a `SymbolTable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SymbolId(pub u16);
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Interns symbol strings once so hot-path structures can carry a
+/// `SymbolId` instead of cloning a `String` per tick. Lookups by `&str`
+/// don't allocate; only the first sighting of a symbol does. The table
+/// holds at most `capacity` symbols and ids are never reused.
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    names: Vec<String>,
+    ids: HashMap<String, SymbolId>,
+    capacity: usize,
+}
+
+impl SymbolTable {
+    /// Table for up to `capacity` symbols, capped at what a `SymbolId` holds
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.min(u16::MAX as usize + 1);
+        Self {
+            names: Vec::with_capacity(capacity),
+            ids: HashMap::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Id for `symbol`, adding it on first sight. Fails once the table is full.
+    pub fn intern(&mut self, symbol: &str) -> HftResult<SymbolId> {
+        if let Some(id) = self.get(symbol) {
+            return Ok(id);
+        }
+        if self.names.len() >= self.capacity {
+            return Err(HftError::InvalidConfig(format!(
+                "symbol table is full ({} symbols); cannot add {}",
+                self.capacity, symbol
+            )));
+        }
+        let id = SymbolId(self.names.len() as u16);
+        self.names.push(symbol.to_string());
+        self.ids.insert(symbol.to_string(), id);
+        Ok(id)
+    }
+
+    /// Id for an already interned symbol
+    pub fn get(&self, symbol: &str) -> Option<SymbolId> {
+        self.ids.get(symbol).copied()
+    }
+
+    /// The string an id was interned from
+    pub fn resolve(&self, id: SymbolId) -> Option<&str> {
+        self.names.get(id.0 as usize).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// `MarketTick` with its symbol interned; `Copy`, so passing it around
+/// never allocates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InternedTick {
+    pub symbol: SymbolId,
+    pub price: f64,
+    pub volume: u64,
+    pub timestamp_nanos: u128,
+    pub sequence: Option<u64>,
+    pub quote: Option<TopOfBook>,
+    /// Venue, interned in the same table as the symbol
+    pub venue: Option<SymbolId>,
+}
+
+impl InternedTick {
+    /// Back to a string-keyed tick, e.g. for logging or the wire
+    pub fn to_tick(&self, symbols: &SymbolTable) -> HftResult<MarketTick> {
+        let symbol = symbols
+            .resolve(self.symbol)
+            .ok_or_else(|| HftError::SymbolNotFound(self.symbol.to_string()))?;
+        let venue = self
+            .venue
+            .map(|venue| {
+                symbols
+                    .resolve(venue)
+                    .map(str::to_string)
+                    .ok_or_else(|| HftError::SymbolNotFound(venue.to_string()))
+            })
+            .transpose()?;
+        Ok(MarketTick {
+            symbol: symbol.to_string(),
+            price: self.price,
+            volume: self.volume,
+            timestamp_nanos: self.timestamp_nanos,
+            sequence: self.sequence,
+            quote: self.quote,
+            venue,
+        })
+    }
+}
+
+impl MarketTick {
+    /// Intern this tick's symbol into `symbols`
+    pub fn intern(&self, symbols: &mut SymbolTable) -> HftResult<InternedTick> {
+        Ok(InternedTick {
+            symbol: symbols.intern(&self.symbol)?,
+            price: self.price,
+            volume: self.volume,
+            timestamp_nanos: self.timestamp_nanos,
+            sequence: self.sequence,
+            quote: self.quote,
+            venue: self.venue.as_deref().map(|venue| symbols.intern(venue)).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_is_stable_and_round_trips() {
+        let mut symbols = SymbolTable::with_capacity(2);
+        let btc = symbols.intern("BTC/USD").unwrap();
+        let eth = symbols.intern("ETH/USD").unwrap();
+        assert_ne!(btc, eth);
+        assert_eq!(symbols.intern("BTC/USD").unwrap(), btc);
+        assert_eq!(symbols.get("ETH/USD"), Some(eth));
+        assert_eq!(symbols.resolve(btc), Some("BTC/USD"));
+        assert_eq!(symbols.resolve(eth), Some("ETH/USD"));
+        assert_eq!(symbols.len(), 2);
+
+        // Full: known symbols still resolve, new ones are refused
+        assert!(symbols.intern("SOL/USD").is_err());
+        assert_eq!(symbols.intern("ETH/USD").unwrap(), eth);
+        assert_eq!(symbols.resolve(SymbolId(7)), None);
+
+        let tick = MarketTick::new("ETH/USD".to_string(), 2500.0, 3, 9).with_sequence(4);
+        let interned = tick.intern(&mut symbols).unwrap();
+        assert_eq!(interned.symbol, eth);
+        let back = interned.to_tick(&symbols).unwrap();
+        assert_eq!(back.symbol, tick.symbol);
+        assert_eq!((back.price, back.sequence), (2500.0, Some(4)));
+        assert!(InternedTick { symbol: SymbolId(7), ..interned }.to_tick(&symbols).is_err());
+
+        let mut symbols = SymbolTable::with_capacity(2);
+        let quote = TopOfBook {
+            bid: 0.9,
+            bid_size: 2.0,
+            ask: 1.1,
+            ask_size: 3.0,
+        };
+        let tick = MarketTick::new("BTC/USD".to_string(), 1.0, 1, 1)
+            .with_venue("kraken")
+            .with_quote(quote);
+        let back = tick.intern(&mut symbols).unwrap().to_tick(&symbols).unwrap();
+        assert_eq!(back.venue.as_deref(), Some("kraken"));
+        assert_eq!(back.quote, Some(quote));
+    }
+}
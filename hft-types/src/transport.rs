@@ -0,0 +1,202 @@
+use crate::messaging::{read_frame, Message, MessageFrame};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Largest datagram a `UdpTransport` will receive
+const MAX_DATAGRAM: usize = 65_536;
+
+/// Moves whole frames between two pipeline stages. Services talk over UDP
+/// and TCP; tests wire the same stages together in memory.
+pub trait Transport: Send {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()>;
+
+    /// Block for the next frame; `None` once the other end has gone away
+    fn recv(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    fn send_message(&mut self, message: &Message) -> io::Result<()> {
+        self.send(&message.serialize()?)
+    }
+
+    fn recv_message(&mut self) -> io::Result<Option<Message>> {
+        match self.recv()? {
+            Some(frame) => Ok(Some(Message::deserialize(&frame)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// One frame per datagram to a fixed peer
+#[derive(Debug)]
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    pub fn connect<A: ToSocketAddrs, B: ToSocketAddrs>(bind: A, peer: B) -> io::Result<Self> {
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(peer)?;
+        Ok(Self { socket })
+    }
+
+    /// Send to and receive from whatever `socket` is connected to
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self { socket }
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.socket.send(frame).map(|_| ())
+    }
+
+    /// UDP has no end of stream, so this only ever returns a frame or an error
+    fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = vec![0u8; MAX_DATAGRAM];
+        let len = self.socket.recv(&mut buf)?;
+        buf.truncate(len);
+        Ok(Some(buf))
+    }
+}
+
+/// Length-prefixed frames over a stream, as `write_message` and
+/// `read_frame` lay them out
+#[derive(Debug)]
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    pub fn new(stream: TcpStream) -> Self {
+        Self { stream }
+    }
+
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Another handle on the same connection, e.g. to read on one thread
+    /// while writing on another
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self::new(self.stream.try_clone()?))
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        let frame = MessageFrame::from_length_and_payload(frame.len() as u32, frame.to_vec());
+        self.stream.write_all(&frame.to_bytes())?;
+        self.stream.flush()
+    }
+
+    fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match read_frame(&mut self.stream) {
+            Ok(frame) => Ok(Some(frame.payload)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// In-process transport for driving stages from tests without sockets
+#[derive(Debug)]
+pub struct ChannelTransport {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    /// Two connected ends: what one sends the other receives
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        (Self { tx: a_tx, rx: a_rx }, Self { tx: b_tx, rx: b_rx })
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.tx
+            .send(frame.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "channel peer dropped"))
+    }
+
+    fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.rx.recv().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Order, OrderSide};
+    use std::net::TcpListener;
+
+    fn order(id: u64) -> Message {
+        Message::Order(Order::new(
+            id,
+            "BTC/USD".to_string(),
+            OrderSide::Buy,
+            45000.0,
+            1.0,
+            1,
+        ))
+    }
+
+    fn order_id(message: Option<Message>) -> u64 {
+        match message {
+            Some(Message::Order(order)) => order.order_id,
+            other => panic!("expected an order, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_channel_ends_after_peer_drops() {
+        let (mut a, mut b) = ChannelTransport::pair();
+        a.send_message(&order(1)).unwrap();
+        b.send_message(&order(2)).unwrap();
+        assert_eq!(order_id(b.recv_message().unwrap()), 1);
+        assert_eq!(order_id(a.recv_message().unwrap()), 2);
+
+        drop(a);
+        assert!(b.recv().unwrap().is_none());
+        assert_eq!(b.send(b"x").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_tcp_matches_message_framing() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let sender = std::thread::spawn(move || {
+            let mut transport = TcpTransport::connect(addr).unwrap();
+            transport.send_message(&order(3)).unwrap();
+            crate::messaging::write_message(&mut transport.stream, &order(4)).unwrap();
+        });
+
+        let mut transport = TcpTransport::new(listener.accept().unwrap().0);
+        assert_eq!(order_id(transport.recv_message().unwrap()), 3);
+        assert_eq!(order_id(transport.recv_message().unwrap()), 4);
+        sender.join().unwrap();
+        assert!(transport.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_udp_sends_one_frame_per_datagram() {
+        let mut a = UdpTransport::connect("127.0.0.1:0", "127.0.0.1:9").unwrap();
+        let mut b = UdpTransport::connect("127.0.0.1:0", a.socket().local_addr().unwrap()).unwrap();
+        a.socket()
+            .connect(b.socket().local_addr().unwrap())
+            .unwrap();
+
+        a.send(b"first").unwrap();
+        a.send(b"second").unwrap();
+        assert_eq!(b.recv().unwrap().unwrap(), b"first");
+        assert_eq!(b.recv().unwrap().unwrap(), b"second");
+    }
+}
@@ -0,0 +1,106 @@
+use crate::MarketTick;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// Trades inside one symbol's window with running sums
+#[derive(Debug, Default)]
+struct VwapWindow {
+    trades: VecDeque<(u128, f64, f64)>, // (timestamp, price, volume)
+    notional: f64,
+    volume: f64,
+}
+
+impl VwapWindow {
+    /// Drop trades at least `window_nanos` older than `now_nanos`
+    fn expire(&mut self, now_nanos: u128, window_nanos: u128) {
+        while let Some(&(ts, price, volume)) = self.trades.front() {
+            if now_nanos.saturating_sub(ts) < window_nanos {
+                break;
+            }
+            self.trades.pop_front();
+            self.notional -= price * volume;
+            self.volume -= volume;
+        }
+        if self.trades.is_empty() {
+            // Don't carry float drift into the next run of trades
+            self.notional = 0.0;
+            self.volume = 0.0;
+        }
+    }
+}
+
+/// Rolling time-windowed VWAP per symbol from the trade stream. A trade
+/// stays in the window while it is less than `window` older than the
+/// newest trade (or the time passed to `expire`).
+#[derive(Debug)]
+pub struct VwapTracker {
+    window_nanos: u128,
+    windows: HashMap<String, VwapWindow>,
+}
+
+impl VwapTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_nanos: window.as_nanos(),
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn on_tick(&mut self, tick: &MarketTick) {
+        let window = self.windows.entry(tick.symbol.clone()).or_default();
+        let volume = tick.volume as f64;
+        window.trades.push_back((tick.timestamp_nanos, tick.price, volume));
+        window.notional += tick.price * volume;
+        window.volume += volume;
+        window.expire(tick.timestamp_nanos, self.window_nanos);
+    }
+
+    /// Evict trades that have aged out as of `now_nanos` for every symbol,
+    /// including ones that have stopped trading
+    pub fn expire(&mut self, now_nanos: u128) {
+        for window in self.windows.values_mut() {
+            window.expire(now_nanos, self.window_nanos);
+        }
+    }
+
+    /// VWAP over the window, or `None` if no volume traded in it
+    pub fn vwap(&self, symbol: &str) -> Option<f64> {
+        self.windows
+            .get(symbol)
+            .filter(|w| w.volume > 0.0)
+            .map(|w| w.notional / w.volume)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u128 = 1_000_000_000;
+
+    fn trade(price: f64, volume: u64, secs: u128) -> MarketTick {
+        MarketTick::new("BTC/USD".to_string(), price, volume, secs * SEC)
+    }
+
+    #[test]
+    fn test_vwap_rolls_with_window() {
+        let mut tracker = VwapTracker::new(Duration::from_secs(10));
+        assert_eq!(tracker.vwap("BTC/USD"), None);
+
+        tracker.on_tick(&trade(100.0, 1, 0));
+        tracker.on_tick(&trade(110.0, 3, 5));
+        assert_eq!(tracker.vwap("BTC/USD"), Some(107.5));
+
+        // The t=0 trade ages out once a trade lands 10s later
+        tracker.on_tick(&trade(120.0, 1, 10));
+        assert_eq!(tracker.vwap("BTC/USD"), Some(112.5));
+
+        // Nothing trades; the window empties out
+        tracker.expire(30 * SEC);
+        assert_eq!(tracker.vwap("BTC/USD"), None);
+
+        tracker.on_tick(&trade(90.0, 2, 31));
+        assert_eq!(tracker.vwap("BTC/USD"), Some(90.0));
+        assert_eq!(tracker.vwap("ETH/USD"), None);
+    }
+}
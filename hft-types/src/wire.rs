@@ -0,0 +1,344 @@
+use crate::{HftError, HftResult, MarketTick};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Encoded size of a binary tick:
+/// `symbol_id u16 | flags u8 | price f64 | volume u64 | timestamp u128 | sequence u64`,
+/// all little-endian. A tick with a venue is followed by `venue_len u8 |
+/// venue`, the venue name in UTF-8.
+pub const BINARY_TICK_LEN: usize = 2 + 1 + 8 + 8 + 16 + 8;
+
+/// `flags` bit set when the sequence field is meaningful
+const HAS_SEQUENCE: u8 = 0x01;
+/// `flags` bit set when the venue follows the fixed fields
+const HAS_VENUE: u8 = 0x02;
+
+/// Header in front of every binary tick datagram. 0xFE never starts valid
+/// UTF-8, so it can't be mistaken for JSON; the second byte is the layout
+/// version.
+pub const BINARY_MAGIC: [u8; 2] = [0xFE, 0x01];
+
+/// How every JSON tick starts. JSON datagrams carry no header, so senders
+/// that predate the binary format keep working.
+pub const JSON_PREFIX: [u8; 2] = *b"{\"";
+
+/// Tell a datagram's encoding from its first two bytes; `None` for anything
+/// unrecognised
+pub fn detect_format(data: &[u8]) -> Option<WireFormat> {
+    match data.get(..2)? {
+        prefix if prefix == JSON_PREFIX => Some(WireFormat::Json),
+        prefix if prefix == BINARY_MAGIC => Some(WireFormat::Binary),
+        _ => None,
+    }
+}
+
+/// Symbol ids agreed out of band between publisher and subscribers. An id
+/// is the symbol's position in the list, so both ends must be configured
+/// with the same symbols in the same order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WireSymbols {
+    symbols: Vec<String>,
+    ids: HashMap<String, u16>,
+}
+
+impl WireSymbols {
+    pub fn new<I, S>(symbols: I) -> HftResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let symbols: Vec<String> = symbols.into_iter().map(Into::into).collect();
+        if symbols.len() > u16::MAX as usize + 1 {
+            return Err(HftError::InvalidConfig(format!(
+                "{} symbols do not fit a u16 symbol id",
+                symbols.len()
+            )));
+        }
+        let mut ids = HashMap::with_capacity(symbols.len());
+        for (id, symbol) in symbols.iter().enumerate() {
+            if ids.insert(symbol.clone(), id as u16).is_some() {
+                return Err(HftError::InvalidConfig(format!(
+                    "duplicate wire symbol {}",
+                    symbol
+                )));
+            }
+        }
+        Ok(Self { symbols, ids })
+    }
+
+    pub fn id(&self, symbol: &str) -> Option<u16> {
+        self.ids.get(symbol).copied()
+    }
+
+    pub fn symbol(&self, id: u16) -> Option<&str> {
+        self.symbols.get(id as usize).map(String::as_str)
+    }
+}
+
+impl MarketTick {
+    /// Fixed-layout binary encoding, plus the venue if there is one; fails
+    /// for symbols missing from `symbols` and venues over 255 bytes
+    pub fn encode_binary(&self, symbols: &WireSymbols) -> HftResult<Vec<u8>> {
+        let id = symbols
+            .id(&self.symbol)
+            .ok_or_else(|| HftError::SymbolNotFound(self.symbol.clone()))?;
+        let venue = self.venue.as_deref().unwrap_or_default().as_bytes();
+        let venue_len = u8::try_from(venue.len())
+            .map_err(|_| HftError::InvalidConfig(format!("venue of {} bytes does not fit the wire", venue.len())))?;
+
+        let mut buf = vec![0u8; BINARY_TICK_LEN];
+        buf[0..2].copy_from_slice(&id.to_le_bytes());
+        buf[2] = if self.sequence.is_some() { HAS_SEQUENCE } else { 0 };
+        buf[3..11].copy_from_slice(&self.price.to_le_bytes());
+        buf[11..19].copy_from_slice(&self.volume.to_le_bytes());
+        buf[19..35].copy_from_slice(&self.timestamp_nanos.to_le_bytes());
+        buf[35..43].copy_from_slice(&self.sequence.unwrap_or(0).to_le_bytes());
+        if self.venue.is_some() {
+            buf[2] |= HAS_VENUE;
+            buf.push(venue_len);
+            buf.extend_from_slice(venue);
+        }
+        Ok(buf)
+    }
+
+    pub fn decode_binary(data: &[u8], symbols: &WireSymbols) -> HftResult<Self> {
+        if data.len() < BINARY_TICK_LEN {
+            return Err(HftError::PartialFrame { expected: BINARY_TICK_LEN });
+        }
+        let (data, tail) = data.split_at(BINARY_TICK_LEN);
+        let venue = match (data[2] & HAS_VENUE != 0, tail) {
+            (false, []) => None,
+            (true, [len, venue @ ..]) if venue.len() == *len as usize => Some(
+                std::str::from_utf8(venue)
+                    .map_err(|e| HftError::SerializationError(format!("venue is not UTF-8: {}", e)))?
+                    .to_string(),
+            ),
+            (true, [len, venue @ ..]) if venue.len() < *len as usize => {
+                return Err(HftError::PartialFrame {
+                    expected: BINARY_TICK_LEN + 1 + *len as usize,
+                })
+            }
+            (true, []) => return Err(HftError::PartialFrame { expected: BINARY_TICK_LEN + 1 }),
+            (has_venue, _) => {
+                let venue_len = if has_venue { 1 + tail[0] as usize } else { 0 };
+                return Err(HftError::FrameTooLarge {
+                    len: BINARY_TICK_LEN + tail.len(),
+                    max: BINARY_TICK_LEN + venue_len,
+                });
+            }
+        };
+
+        let id = u16::from_le_bytes([data[0], data[1]]);
+        let symbol = symbols
+            .symbol(id)
+            .ok_or_else(|| HftError::SymbolNotFound(format!("wire symbol id {}", id)))?;
+        // The slices below have fixed lengths, so the conversions can't fail
+        let sequence = u64::from_le_bytes(data[35..43].try_into().unwrap());
+        Ok(Self {
+            symbol: symbol.to_string(),
+            price: f64::from_le_bytes(data[3..11].try_into().unwrap()),
+            volume: u64::from_le_bytes(data[11..19].try_into().unwrap()),
+            timestamp_nanos: u128::from_le_bytes(data[19..35].try_into().unwrap()),
+            sequence: (data[2] & HAS_SEQUENCE != 0).then_some(sequence),
+            quote: None,
+            venue,
+        })
+    }
+}
+
+/// Tick encoding selected on the command line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Binary,
+}
+
+impl FromStr for WireFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(WireFormat::Json),
+            "binary" => Ok(WireFormat::Binary),
+            other => Err(format!("unknown wire format: {} (expected json or binary)", other)),
+        }
+    }
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireFormat::Json => write!(f, "json"),
+            WireFormat::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// Encoder/decoder for ticks on the UDP feed. Encoding uses the selected
+/// format; decoding goes by each datagram's header, so a binary receiver
+/// still accepts JSON.
+#[derive(Debug, Clone, Default)]
+pub enum TickWire {
+    #[default]
+    Json,
+    Binary(WireSymbols),
+}
+
+impl TickWire {
+    pub fn format(&self) -> WireFormat {
+        match self {
+            TickWire::Json => WireFormat::Json,
+            TickWire::Binary(_) => WireFormat::Binary,
+        }
+    }
+
+    pub fn encode(&self, tick: &MarketTick) -> HftResult<Vec<u8>> {
+        match self {
+            TickWire::Json => Ok(serde_json::to_vec(tick)?),
+            TickWire::Binary(symbols) => {
+                let mut datagram = Vec::with_capacity(BINARY_MAGIC.len() + BINARY_TICK_LEN);
+                datagram.extend_from_slice(&BINARY_MAGIC);
+                datagram.extend_from_slice(&tick.encode_binary(symbols)?);
+                Ok(datagram)
+            }
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> HftResult<MarketTick> {
+        match (detect_format(data), self) {
+            (Some(WireFormat::Json), _) => Ok(serde_json::from_slice(data)?),
+            (Some(WireFormat::Binary), TickWire::Binary(symbols)) => {
+                MarketTick::decode_binary(&data[BINARY_MAGIC.len()..], symbols)
+            }
+            (Some(WireFormat::Binary), TickWire::Json) => Err(HftError::SerializationError(
+                "binary tick received without wire symbols".to_string(),
+            )),
+            (None, _) => Err(HftError::SerializationError(format!(
+                "unknown datagram header {:02x?}",
+                data.get(..2).unwrap_or(data)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols() -> WireSymbols {
+        WireSymbols::new(["BTC/USD", "ETH/USD", "SOL/USD"]).unwrap()
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let symbols = symbols();
+        let ticks = [
+            MarketTick::new("BTC/USD".to_string(), 45000.25, 100, 1_700_000_000_000_000_001),
+            MarketTick::new("ETH/USD".to_string(), 2500.5, 1, 1).with_sequence(42),
+            MarketTick::new("SOL/USD".to_string(), 0.000_001, u64::MAX, u128::MAX)
+                .with_sequence(0),
+            MarketTick::new("BTC/USD".to_string(), 45000.0, 1, 1)
+                .with_sequence(7)
+                .with_venue("kraken"),
+        ];
+
+        for tick in &ticks {
+            let encoded = tick.encode_binary(&symbols).unwrap();
+            let decoded = MarketTick::decode_binary(&encoded, &symbols).unwrap();
+            assert_eq!(decoded.symbol, tick.symbol);
+            assert_eq!(decoded.price, tick.price);
+            assert_eq!(decoded.volume, tick.volume);
+            assert_eq!(decoded.timestamp_nanos, tick.timestamp_nanos);
+            assert_eq!(decoded.sequence, tick.sequence);
+            assert_eq!(decoded.venue, tick.venue);
+        }
+    }
+
+    #[test]
+    fn test_binary_matches_json() {
+        let tick = MarketTick::new("ETH/USD".to_string(), 2499.75, 7, 1_700_000_000_123_456_789)
+            .with_sequence(9);
+        let binary = TickWire::Binary(symbols());
+
+        let from_binary = binary.decode(&binary.encode(&tick).unwrap()).unwrap();
+        let from_json = TickWire::Json.decode(&TickWire::Json.encode(&tick).unwrap()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_binary).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+        assert!(binary.encode(&tick).unwrap().len() < TickWire::Json.encode(&tick).unwrap().len());
+    }
+
+    #[test]
+    fn test_decode_dispatches_on_header() {
+        let tick = MarketTick::new("SOL/USD".to_string(), 101.5, 3, 7);
+        let binary = TickWire::Binary(symbols());
+
+        // A legacy sender's plain JSON, no header
+        let legacy = serde_json::to_vec(&tick).unwrap();
+        assert_eq!(detect_format(&legacy), Some(WireFormat::Json));
+        assert_eq!(binary.decode(&legacy).unwrap().price, 101.5);
+
+        let headered = binary.encode(&tick).unwrap();
+        assert_eq!(headered[..2], BINARY_MAGIC);
+        assert_eq!(detect_format(&headered), Some(WireFormat::Binary));
+        assert_eq!(binary.decode(&headered).unwrap().symbol, "SOL/USD");
+        // Binary can't be decoded without the symbol table
+        assert!(TickWire::Json.decode(&headered).is_err());
+
+        // Truncated JSON is a parse failure, not a framing one
+        let truncated = &legacy[..legacy.len() - 1];
+        assert!(matches!(TickWire::Json.decode(truncated), Err(HftError::Json(_))));
+
+        for unknown in [&b"\x00\x01garbage"[..], b"x", b""] {
+            assert_eq!(detect_format(unknown), None);
+            assert!(binary.decode(unknown).is_err());
+        }
+    }
+
+    #[test]
+    fn test_binary_rejects_unknown_symbols_and_bad_lengths() {
+        let symbols = symbols();
+        let tick = MarketTick::new("DOGE/USD".to_string(), 0.1, 1, 1);
+        assert!(matches!(tick.encode_binary(&symbols), Err(HftError::SymbolNotFound(_))));
+
+        let mut encoded = MarketTick::new("BTC/USD".to_string(), 1.0, 1, 1)
+            .encode_binary(&symbols)
+            .unwrap();
+        assert!(matches!(
+            MarketTick::decode_binary(&encoded[..10], &symbols),
+            Err(HftError::PartialFrame { expected: BINARY_TICK_LEN })
+        ));
+        let mut padded = encoded.clone();
+        padded.push(0);
+        assert!(matches!(
+            MarketTick::decode_binary(&padded, &symbols),
+            Err(HftError::FrameTooLarge { len, max: BINARY_TICK_LEN }) if len == BINARY_TICK_LEN + 1
+        ));
+        encoded[0] = 99;
+        assert!(matches!(
+            MarketTick::decode_binary(&encoded, &symbols),
+            Err(HftError::SymbolNotFound(_))
+        ));
+
+        // The venue must be there in full, and nothing after it
+        let venued = MarketTick::new("BTC/USD".to_string(), 1.0, 1, 1)
+            .with_venue("kraken")
+            .encode_binary(&symbols)
+            .unwrap();
+        assert!(matches!(
+            MarketTick::decode_binary(&venued[..venued.len() - 1], &symbols),
+            Err(HftError::PartialFrame { .. })
+        ));
+        let mut padded = venued.clone();
+        padded.push(0);
+        assert!(matches!(
+            MarketTick::decode_binary(&padded, &symbols),
+            Err(HftError::FrameTooLarge { .. })
+        ));
+
+        assert!(WireSymbols::new(["BTC/USD", "BTC/USD"]).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use hft_types::{MarketTick, Order, OrderSide, OrderBook, BookLevel};
+use hft_types::{HftError, MarketTick, Order, OrderSide, OrderBook, OrderType, BookLevel, RoundingMode, SymbolConfig, TimeInForce};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[test]
@@ -16,6 +16,23 @@ fn test_market_tick_creation() {
     assert_eq!(tick.timestamp_nanos, timestamp);
 }
 
+#[test]
+fn test_market_tick_try_new() {
+    let tick = MarketTick::try_new("BTC/USD".to_string(), 45000.0, 100, 1).unwrap();
+    assert_eq!(tick.price, 45000.0);
+
+    for price in [f64::NAN, f64::INFINITY, -1.0, 0.0] {
+        assert!(matches!(
+            MarketTick::try_new("BTC/USD".to_string(), price, 100, 1),
+            Err(HftError::InvalidPrice(_))
+        ));
+    }
+    assert!(matches!(
+        MarketTick::try_new("BTC/USD".to_string(), 45000.0, 100, 0),
+        Err(HftError::InvalidTimestamp(0))
+    ));
+}
+
 #[test]
 fn test_order_serialization() {
     let timestamp = SystemTime::now()
@@ -40,6 +57,33 @@ fn test_order_serialization() {
     assert_eq!(order.price, deserialized.price);
 }
 
+#[test]
+fn test_order_type_defaults_and_validation() {
+    // Orders serialized before order types existed
+    let legacy = r#"{"order_id":1,"symbol":"ETH/USD","side":"Buy","price":2500.0,"quantity":1.0,"timestamp_nanos":0}"#;
+    let order: Order = serde_json::from_str(legacy).unwrap();
+    assert_eq!(order.order_type, OrderType::Limit);
+    assert_eq!(order.time_in_force, TimeInForce::Gtc);
+    assert!(order.validate().is_ok());
+
+    let stop = Order::new(2, "ETH/USD".to_string(), OrderSide::Sell, 0.0, 1.0, 0)
+        .with_order_type(OrderType::Stop { trigger: 2400.0 })
+        .with_time_in_force(TimeInForce::Day);
+    let round_trip: Order = serde_json::from_str(&serde_json::to_string(&stop).unwrap()).unwrap();
+    assert_eq!(round_trip.order_type, OrderType::Stop { trigger: 2400.0 });
+    assert_eq!(round_trip.time_in_force, TimeInForce::Day);
+
+    // Price only matters for limits
+    assert!(stop.validate().is_ok());
+    let market = Order::new(3, "ETH/USD".to_string(), OrderSide::Buy, 0.0, 1.0, 0)
+        .with_order_type(OrderType::Market)
+        .with_time_in_force(TimeInForce::Ioc);
+    assert!(market.validate().is_ok());
+    assert!(Order::new(4, "ETH/USD".to_string(), OrderSide::Buy, 0.0, 1.0, 0).validate().is_err());
+    assert!(market.clone().with_order_type(OrderType::Stop { trigger: -1.0 }).validate().is_err());
+    assert!(Order::new(5, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 0.0, 0).validate().is_err());
+}
+
 #[test]
 fn test_order_book_operations() {
     let timestamp = SystemTime::now()
@@ -63,6 +107,86 @@ fn test_order_book_operations() {
     assert_eq!(book.mid_price().unwrap(), 45000.0);
 }
 
+#[test]
+fn test_spread_bps_and_weighted_mid() {
+    let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+    assert_eq!(book.spread_bps(), None);
+    assert_eq!(book.weighted_mid(), None);
+
+    book.bids.push(BookLevel { price: 99.0, quantity: 3.0 });
+    // One side only
+    assert_eq!(book.spread_bps(), None);
+    assert_eq!(book.weighted_mid(), None);
+
+    book.asks.push(BookLevel { price: 101.0, quantity: 1.0 });
+    // Spread 2 on a mid of 100
+    assert!((book.spread_bps().unwrap() - 200.0).abs() < 1e-9);
+    // (99 * 1 + 101 * 3) / 4: heavy bid pulls it towards the ask
+    assert!((book.weighted_mid().unwrap() - 100.5).abs() < 1e-9);
+
+    // Zero mid
+    let mut zero = OrderBook::new("ZERO".to_string(), 1);
+    zero.bids.push(BookLevel { price: 0.0, quantity: 1.0 });
+    zero.asks.push(BookLevel { price: 0.0, quantity: 1.0 });
+    assert_eq!(zero.spread_bps(), None);
+}
+
+#[test]
+fn test_spread_checked() {
+    let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+    assert!(matches!(book.spread_checked(), Err(HftError::OrderBookEmpty(_))));
+
+    book.bids.push(BookLevel { price: 99.0, quantity: 1.0 });
+    book.asks.push(BookLevel { price: 101.0, quantity: 1.0 });
+    assert_eq!(book.spread_checked().unwrap(), 2.0);
+
+    // Locked
+    book.asks[0].price = 99.0;
+    assert_eq!(book.spread_checked().unwrap(), 0.0);
+
+    // Crossed
+    book.asks[0].price = 98.5;
+    assert!(book.spread().unwrap() < 0.0);
+    match book.spread_checked() {
+        Err(HftError::CrossedBook { symbol, bid, ask }) => {
+            assert_eq!(symbol, "BTC/USD");
+            assert_eq!((bid, ask), (99.0, 98.5));
+        }
+        other => panic!("expected a crossed book, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_symbol_config_rounding() {
+    let config = SymbolConfig {
+        symbol: "BTC/USD".to_string(),
+        base_price: 45000.0,
+        thresholds: None,
+        tick_size: Some(0.5),
+        lot_size: Some(0.001),
+        min_notional: None,
+        activity_weight: None,
+    };
+    assert_eq!(config.round_price(45001.37, RoundingMode::Nearest), 45001.5);
+    assert_eq!(config.round_price(45001.37, RoundingMode::Down), 45001.0);
+    assert_eq!(config.round_price(45001.37, RoundingMode::Up), 45001.5);
+    // Already on a tick
+    assert_eq!(config.round_price(45001.5, RoundingMode::Up), 45001.5);
+
+    assert_eq!(config.round_quantity(1.2345, RoundingMode::Down), 1.234);
+    assert_eq!(config.round_quantity(1.2345, RoundingMode::Up), 1.235);
+
+    // Float noise does not push a price off the tick it is on
+    let cents = SymbolConfig { tick_size: Some(0.01), ..config.clone() };
+    assert!((cents.round_price(100.07, RoundingMode::Down) - 100.07).abs() < 1e-9);
+
+    // A zero tick size leaves prices alone, as does having none
+    let zero = SymbolConfig { tick_size: Some(0.0), ..config.clone() };
+    assert_eq!(zero.round_price(45001.37, RoundingMode::Nearest), 45001.37);
+    let unset = SymbolConfig { tick_size: None, ..config };
+    assert_eq!(unset.round_price(45001.37, RoundingMode::Nearest), 45001.37);
+}
+
 #[test]
 fn test_tick_latency_calculation() {
     let send_time = SystemTime::now()
@@ -88,3 +212,33 @@ fn test_order_side_display() {
     assert_eq!(format!("{}", OrderSide::Buy), "BUY");
     assert_eq!(format!("{}", OrderSide::Sell), "SELL");
 }
+
+#[test]
+fn test_order_book_upsert_out_of_order() {
+    let mut book = OrderBook::new("BTC/USD".to_string(), 0);
+
+    for (price, qty) in [(44800.0, 2.0), (44900.0, 1.0), (44700.0, 4.0), (44850.0, 3.0)] {
+        book.upsert_bid(price, qty);
+    }
+    for (price, qty) in [(45200.0, 3.0), (45100.0, 1.5), (45300.0, 1.0), (45150.0, 2.0)] {
+        book.upsert_ask(price, qty);
+    }
+
+    let bids: Vec<f64> = book.bids.iter().map(|l| l.price).collect();
+    let asks: Vec<f64> = book.asks.iter().map(|l| l.price).collect();
+    assert_eq!(bids, vec![44900.0, 44850.0, 44800.0, 44700.0]);
+    assert_eq!(asks, vec![45100.0, 45150.0, 45200.0, 45300.0]);
+    assert_eq!(book.best_bid().unwrap().price, 44900.0);
+    assert_eq!(book.best_ask().unwrap().price, 45100.0);
+
+    // Updating an existing level changes size without adding a level
+    book.upsert_bid(44850.0, 7.0);
+    assert_eq!(book.bids.len(), 4);
+    assert_eq!(book.bids[1].quantity, 7.0);
+
+    // Removing the top of book promotes the next level
+    let removed = book.remove_level(OrderSide::Sell, 45100.0).unwrap();
+    assert_eq!(removed.quantity, 1.5);
+    assert_eq!(book.best_ask().unwrap().price, 45150.0);
+    assert!(book.remove_level(OrderSide::Buy, 12345.0).is_none());
+}
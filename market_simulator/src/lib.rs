@@ -0,0 +1,790 @@
+use anyhow::{bail, Result};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use hft_types::cli;
+use hft_types::config::SymbolUniverse;
+use hft_types::health::Readiness;
+use hft_types::messaging::{parse_socket_addr, PROBE_ACK, PROBE_REQUEST};
+use hft_types::multicast;
+use hft_types::replay::MarketReplayer;
+use hft_types::transport::{Transport, UdpTransport};
+use hft_types::wire::{TickWire, WireFormat, WireSymbols};
+use hft_types::MarketTick;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Symbols simulated by default, with their base prices
+const DEFAULT_SYMBOLS: [(&str, f64); 4] = [
+    ("BTC/USD", 45000.0),
+    ("ETH/USD", 2500.0),
+    ("SOL/USD", 100.0),
+    ("AVAX/USD", 25.0),
+];
+
+/// Parse `SYMBOL` (one of the defaults) or `SYMBOL=BASE_PRICE`
+fn parse_symbol(s: &str) -> Result<(String, f64), String> {
+    match s.split_once('=') {
+        Some((symbol, price)) => match price.parse::<f64>() {
+            Ok(price) if price > 0.0 => Ok((symbol.to_string(), price)),
+            _ => Err(format!("invalid base price '{}' for {}", price, symbol)),
+        },
+        None => DEFAULT_SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == s)
+            .map(|&(symbol, price)| (symbol.to_string(), price))
+            .ok_or_else(|| format!("no default base price for {}; use {}=PRICE", s, s)),
+    }
+}
+
+/// Parse a probability in [0, 1]
+fn parse_probability(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!("'{}' is not a probability between 0 and 1", s)),
+    }
+}
+
+fn cli() -> Command {
+    Command::new("market_simulator")
+        .about("Publishes synthetic market ticks over UDP")
+        .arg(
+            Arg::new("bind")
+                .long("bind")
+                .value_name("ADDR")
+                .help("Local address to send from")
+                .value_parser(parse_socket_addr)
+                .default_value("0.0.0.0:0"),
+        )
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .value_name("ADDR")
+                .help("Feed handler address, or a multicast group to publish to")
+                .value_parser(parse_socket_addr)
+                .default_value("127.0.0.1:9001"),
+        )
+        .arg(
+            Arg::new("multicast-interface")
+                .long("multicast-interface")
+                .value_name("IP")
+                .help("Local interface to publish multicast from")
+                .value_parser(value_parser!(Ipv4Addr))
+                .default_value("0.0.0.0"),
+        )
+        .arg(
+            Arg::new("multicast-ttl")
+                .long("multicast-ttl")
+                .value_name("HOPS")
+                .help("Multicast TTL; 1 keeps the feed on the local subnet")
+                .value_parser(value_parser!(u32).range(0..=255))
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("ticks-per-second")
+                .long("ticks-per-second")
+                .value_name("N")
+                .value_parser(value_parser!(u64).range(1..=1_000_000))
+                .default_value("10000"),
+        )
+        .arg(
+            Arg::new("symbols")
+                .long("symbols")
+                .value_name("SYMBOL[=PRICE],...")
+                .help("Symbols to simulate; custom symbols need a base price")
+                .value_delimiter(',')
+                .value_parser(parse_symbol)
+                .default_value("BTC/USD,ETH/USD,SOL/USD,AVAX/USD"),
+        )
+        .arg(
+            cli::universe("Simulate the [[symbols]] of a shared config file instead of --symbols")
+                .conflicts_with("symbols"),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("PATH")
+                .help("Publish a recorded session at --ticks-per-second instead of a random walk, then exit"),
+        )
+        .arg(
+            Arg::new("wire")
+                .long("wire")
+                .value_name("FORMAT")
+                .help("Tick encoding: json, or binary with symbol ids in --symbols order")
+                .value_parser(|s: &str| s.parse::<WireFormat>())
+                .default_value("json"),
+        )
+        .arg(
+            Arg::new("jitter-us")
+                .long("jitter-us")
+                .value_name("MICROS")
+                .help("Vary each send interval uniformly by up to this much either way")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("drop-probability")
+                .long("drop-probability")
+                .value_name("P")
+                .help("Fraction of ticks to skip sending; their sequence numbers still advance")
+                .value_parser(parse_probability)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("duplicate-probability")
+                .long("duplicate-probability")
+                .value_name("P")
+                .help("Fraction of ticks to send twice")
+                .value_parser(parse_probability)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("N")
+                .help("Seed the random walk and the chaos options for a reproducible run")
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("require-target")
+                .long("require-target")
+                .help("Refuse to start unless the feed handler acknowledges a probe")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(cli::health_listen("0.0.0.0:9201"))
+        .arg(cli::log_format())
+}
+
+struct Args {
+    bind: SocketAddr,
+    target: SocketAddr,
+    multicast_interface: Ipv4Addr,
+    multicast_ttl: u32,
+    ticks_per_second: u64,
+    symbols: Vec<(String, f64)>,
+    /// Relative tick rate per symbol, in `symbols` order
+    activity_weights: Vec<f64>,
+    replay: Option<String>,
+    wire: WireFormat,
+    chaos: ChaosOptions,
+    seed: Option<u64>,
+    require_target: bool,
+    health_listen: SocketAddr,
+}
+
+impl Args {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let universe = matches.get_one::<SymbolUniverse>("universe");
+        Self {
+            bind: *matches.get_one("bind").unwrap(),
+            target: *matches.get_one("target").unwrap(),
+            multicast_interface: *matches.get_one("multicast-interface").unwrap(),
+            multicast_ttl: *matches.get_one("multicast-ttl").unwrap(),
+            ticks_per_second: *matches.get_one("ticks-per-second").unwrap(),
+            symbols: match universe {
+                Some(universe) => universe
+                    .symbols()
+                    .iter()
+                    .map(|config| (config.symbol.clone(), config.base_price))
+                    .collect(),
+                None => matches
+                    .get_many::<(String, f64)>("symbols")
+                    .unwrap()
+                    .cloned()
+                    .collect(),
+            },
+            activity_weights: match universe {
+                Some(universe) => universe
+                    .symbols()
+                    .iter()
+                    .map(|config| config.activity_weight.unwrap_or(1.0))
+                    .collect(),
+                None => vec![1.0; matches.get_many::<(String, f64)>("symbols").unwrap().len()],
+            },
+            replay: matches.get_one("replay").cloned(),
+            wire: *matches.get_one("wire").unwrap(),
+            chaos: ChaosOptions {
+                jitter: Duration::from_micros(*matches.get_one("jitter-us").unwrap()),
+                drop_probability: *matches.get_one("drop-probability").unwrap(),
+                duplicate_probability: *matches.get_one("duplicate-probability").unwrap(),
+            },
+            seed: matches.get_one("seed").copied(),
+            require_target: matches.get_flag("require-target"),
+            health_listen: *matches.get_one("health-listen").unwrap(),
+        }
+    }
+}
+
+/// Outgoing multicast settings, used when the target is a multicast group
+struct MulticastOptions {
+    interface: Ipv4Addr,
+    ttl: u32,
+}
+
+/// Network imperfections injected on send, for exercising receivers'
+/// gap, dedup and latency handling
+#[derive(Debug, Clone, Default)]
+pub struct ChaosOptions {
+    /// Largest deviation from the nominal send interval, either way
+    pub jitter: Duration,
+    pub drop_probability: f64,
+    pub duplicate_probability: f64,
+}
+
+impl ChaosOptions {
+    fn is_enabled(&self) -> bool {
+        !self.jitter.is_zero() || self.drop_probability > 0.0 || self.duplicate_probability > 0.0
+    }
+}
+
+/// Publishes ticks to `out`: a random walk over its symbols, or a recorded
+/// session given `with_replay`
+pub struct MarketSimulator<T = UdpTransport> {
+    out: T,
+    multicast: bool,
+    symbols: Vec<String>,
+    base_prices: Vec<f64>,
+    /// Picks which symbol ticks next, so liquid symbols tick more often
+    activity: WeightedIndex<f64>,
+    /// Next sequence number per symbol, so receivers can spot duplicates
+    sequences: Vec<u64>,
+    wire: TickWire,
+    chaos: ChaosOptions,
+    /// Drives the random walk and chaos; seeded for reproducible runs
+    rng: StdRng,
+    /// Recorded ticks published in place of the random walk
+    replay: Option<MarketReplayer>,
+}
+
+impl MarketSimulator<UdpTransport> {
+    fn new(
+        bind_addr: SocketAddr,
+        target_addr: SocketAddr,
+        symbols: Vec<(String, f64)>,
+        multicast_options: MulticastOptions,
+    ) -> Result<Self> {
+        let multicast = matches!(target_addr.ip(), IpAddr::V4(ip) if ip.is_multicast());
+        let socket = std::net::UdpSocket::bind(bind_addr)?;
+        if multicast {
+            multicast::configure_sender(&socket, multicast_options.interface, multicast_options.ttl)?;
+        }
+        socket.connect(target_addr)?;
+
+        if multicast {
+            info!(
+                "Market simulator bound to {} → multicast {} (interface {}, ttl {})",
+                bind_addr, target_addr, multicast_options.interface, multicast_options.ttl
+            );
+        } else {
+            info!("Market simulator bound to {} → {}", bind_addr, target_addr);
+        }
+
+        let mut simulator = MarketSimulator::with_transport(UdpTransport::from_socket(socket), symbols)?;
+        simulator.multicast = multicast;
+        Ok(simulator)
+    }
+
+    /// Send a probe to the target and wait for the feed handler's ack.
+    /// UDP `connect` succeeds whether or not anything is listening, so this
+    /// is the only way to catch a misconfigured target before ticks vanish.
+    async fn probe_target(&self, attempts: u32, timeout: Duration) -> bool {
+        let socket = match self.out.socket().try_clone() {
+            Ok(socket) => socket,
+            Err(e) => {
+                warn!("Failed to probe the feed handler: {}", e);
+                return false;
+            }
+        };
+        tokio::task::spawn_blocking(move || probe(&socket, attempts, timeout))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Probe the target, warning if it is silent or refusing to start if
+    /// `require_target` is set
+    async fn check_target(&self, require_target: bool) -> Result<()> {
+        if self.multicast {
+            // Acks would come from each subscriber's own address, which the
+            // group-connected socket filters out; there's no single target to check
+            if require_target {
+                bail!("--require-target cannot be used with a multicast target");
+            }
+            info!("Publishing to a multicast group; skipping the feed handler probe");
+            return Ok(());
+        }
+        let target = self.out.socket().peer_addr()?;
+        if self.probe_target(3, Duration::from_millis(200)).await {
+            info!("Feed handler at {} acknowledged probe", target);
+            return Ok(());
+        }
+
+        if require_target {
+            bail!("feed handler at {} did not acknowledge probe", target);
+        }
+        warn!(
+            "Feed handler at {} did not acknowledge probe; ticks may be going nowhere",
+            target
+        );
+        Ok(())
+    }
+}
+
+/// Probe the peer `socket` is connected to, waiting `timeout` per attempt
+fn probe(socket: &std::net::UdpSocket, attempts: u32, timeout: Duration) -> bool {
+    if let Err(e) = socket.set_read_timeout(Some(timeout)) {
+        warn!("Failed to probe the feed handler: {}", e);
+        return false;
+    }
+    let mut buf = [0u8; 64];
+    for _ in 0..attempts {
+        if let Err(e) = socket.send(PROBE_REQUEST) {
+            tracing::debug!("Probe send failed: {}", e);
+            continue;
+        }
+        match socket.recv(&mut buf) {
+            Ok(n) if &buf[..n] == PROBE_ACK => return true,
+            Ok(_) => continue,
+            // ICMP port unreachable surfaces here as ConnectionRefused
+            Err(e) => tracing::debug!("Probe receive failed: {}", e),
+        }
+    }
+    false
+}
+
+impl<T: Transport> MarketSimulator<T> {
+    /// Publish `symbols`, each with its base price, to `out`
+    pub fn with_transport(out: T, symbols: Vec<(String, f64)>) -> Result<Self> {
+        let (symbols, base_prices): (Vec<String>, Vec<f64>) = symbols.into_iter().unzip();
+        // Start from the clock in microseconds so a restarted simulator keeps
+        // counting upwards instead of replaying sequences receivers have seen
+        let first_sequence = SystemTime::now().duration_since(UNIX_EPOCH)?.as_micros() as u64;
+        Ok(Self {
+            sequences: vec![first_sequence; symbols.len()],
+            activity: WeightedIndex::new(vec![1.0; symbols.len()])?,
+            out,
+            multicast: false,
+            symbols,
+            base_prices,
+            wire: TickWire::Json,
+            chaos: ChaosOptions::default(),
+            rng: StdRng::from_entropy(),
+            replay: None,
+        })
+    }
+
+    /// Publish the ticks of `replayer` as recorded, sequence numbers and
+    /// all, instead of a random walk; `run` returns once they run out
+    pub fn with_replay(mut self, replayer: MarketReplayer) -> Self {
+        self.replay = Some(replayer);
+        self
+    }
+
+    /// Inject `chaos` into the stream, drawing from a generator seeded with
+    /// `seed` if given
+    pub fn with_chaos(mut self, chaos: ChaosOptions, seed: Option<u64>) -> Self {
+        if chaos.is_enabled() {
+            info!(
+                "Injecting chaos: jitter ±{:?}, drop {:.1}%, duplicate {:.1}%",
+                chaos.jitter,
+                chaos.drop_probability * 100.0,
+                chaos.duplicate_probability * 100.0
+            );
+        }
+        if let Some(seed) = seed {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+        self.chaos = chaos;
+        self
+    }
+
+    /// Tick each symbol in proportion to its entry in `weights`, given in
+    /// the order of the simulated symbols
+    fn with_activity_weights(mut self, weights: &[f64]) -> Result<Self> {
+        if weights.len() != self.symbols.len() {
+            bail!(
+                "{} activity weights for {} symbols",
+                weights.len(),
+                self.symbols.len()
+            );
+        }
+        self.activity = WeightedIndex::new(weights)?;
+        Ok(self)
+    }
+
+    /// Encode ticks as `format`. Binary symbol ids follow the order of the
+    /// simulated symbols, which subscribers must be given in the same order.
+    fn with_wire(mut self, format: WireFormat) -> Result<Self> {
+        self.wire = match format {
+            WireFormat::Json => TickWire::Json,
+            WireFormat::Binary => TickWire::Binary(WireSymbols::new(self.symbols.clone())?),
+        };
+        info!("Encoding ticks as {}", format);
+        Ok(self)
+    }
+
+    /// Publish `ticks_per_second` ticks until sending fails or, replaying,
+    /// the recording runs out. Tick n is due `n` periods after the start,
+    /// so time spent sending and waking up never adds up into a lower rate.
+    pub async fn run(&mut self, ticks_per_second: u64) -> Result<()> {
+        let period = Duration::from_micros(1_000_000 / ticks_per_second);
+        let mut slot = Instant::now();
+
+        info!("Generating {} ticks/second", ticks_per_second);
+
+        loop {
+            tokio::time::sleep_until(self.jittered(slot)).await;
+            slot += period;
+            let tick = match &mut self.replay {
+                Some(replayer) => match replayer.next_tick()? {
+                    Some(tick) => tick,
+                    None => {
+                        info!("Recording finished");
+                        return Ok(());
+                    }
+                },
+                None => self.next_tick()?,
+            };
+            self.publish(&tick)?;
+        }
+    }
+
+    /// `slot` moved by a uniform draw from ±jitter. Each tick is jittered
+    /// around its own slot, so the offsets never accumulate.
+    fn jittered(&mut self, slot: Instant) -> Instant {
+        if self.chaos.jitter.is_zero() {
+            return slot;
+        }
+        let jitter = self.chaos.jitter.as_nanos() as u64;
+        let offset = self.rng.gen_range(0..=2 * jitter);
+        (slot + Duration::from_nanos(offset))
+            .checked_sub(self.chaos.jitter)
+            .unwrap_or(slot)
+    }
+
+    /// Random walk one step for a symbol drawn by activity weight
+    fn next_tick(&mut self) -> Result<MarketTick> {
+        let idx = self.activity.sample(&mut self.rng);
+        let symbol = self.symbols[idx].clone();
+        let base_price = self.base_prices[idx];
+
+        let price_delta = self.rng.gen_range(-0.01..0.01);
+        let price = base_price * (1.0 + price_delta);
+        let volume = self.rng.gen_range(1..100);
+
+        let timestamp_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_nanos();
+
+        self.sequences[idx] += 1;
+        Ok(MarketTick::new(symbol, price, volume, timestamp_nanos).with_sequence(self.sequences[idx]))
+    }
+
+    /// Send a tick, subject to the chaos options; returns the number of
+    /// datagrams put on the wire
+    fn publish(&mut self, tick: &MarketTick) -> Result<usize> {
+        if self.rng.gen_bool(self.chaos.drop_probability) {
+            tracing::debug!("Dropping {:?}", tick);
+            return Ok(0);
+        }
+        let copies = if self.rng.gen_bool(self.chaos.duplicate_probability) { 2 } else { 1 };
+        let payload = self.wire.encode(tick)?;
+
+        let mut sent = 0;
+        for _ in 0..copies {
+            match self.out.send(&payload) {
+                Ok(()) => {
+                    tracing::debug!("Sent {} bytes: {:?}", payload.len(), tick);
+                    sent += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to send tick: {}", e);
+                }
+            }
+        }
+        Ok(sent)
+    }
+}
+
+/// Entry point of the `market_simulator` binary
+pub async fn run() -> Result<()> {
+    let matches = cli::parse_args(cli());
+    hft_types::logging::init("market_simulator", *matches.get_one("log-format").unwrap());
+    let args = Args::from_matches(&matches);
+
+    hft_types::heartbeat::spawn_emitter(
+        "market_simulator",
+        hft_types::heartbeat::DEFAULT_HEARTBEAT_ADDR,
+        Duration::from_secs(1),
+    )?;
+    // Ready once the feed handler has been probed
+    let readiness = Readiness::new();
+    readiness.require("target");
+    hft_types::health::spawn_server(args.health_listen, readiness.clone(), &Default::default())?;
+
+    let multicast_options = MulticastOptions {
+        interface: args.multicast_interface,
+        ttl: args.multicast_ttl,
+    };
+    let mut simulator = MarketSimulator::new(args.bind, args.target, args.symbols, multicast_options)?
+        .with_activity_weights(&args.activity_weights)?
+        .with_wire(args.wire)?
+        .with_chaos(args.chaos, args.seed);
+    if let Some(path) = &args.replay {
+        info!("Replaying {}", path);
+        simulator = simulator.with_replay(MarketReplayer::new(path)?);
+    }
+    simulator.check_target(args.require_target).await?;
+    readiness.set("target", true);
+    simulator.run(args.ticks_per_second).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::net::UdpSocket;
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        for args in [
+            &["--target", "127.0.0.1:70000"][..],
+            &["--ticks-per-second", "fast"],
+            &["--no-such-flag"],
+        ] {
+            let result = cli().try_get_matches_from(std::iter::once(&"market_simulator").chain(args));
+            assert!(result.is_err(), "{:?} should fail", args);
+        }
+    }
+
+    fn simulator_to(target: SocketAddr) -> MarketSimulator {
+        let args = Args::from_matches(&cli().get_matches_from(["market_simulator", "--bind", "127.0.0.1:0"]));
+        let multicast_options = MulticastOptions {
+            interface: args.multicast_interface,
+            ttl: args.multicast_ttl,
+        };
+        MarketSimulator::new(args.bind, target, args.symbols, multicast_options).unwrap()
+    }
+
+    #[test]
+    fn test_symbols_argument() {
+        let matches = cli().get_matches_from(["market_simulator", "--symbols", "ETH/USD,DOGE/USD=0.1"]);
+        let args = Args::from_matches(&matches);
+        assert_eq!(
+            args.symbols,
+            vec![("ETH/USD".to_string(), 2500.0), ("DOGE/USD".to_string(), 0.1)]
+        );
+        assert_eq!(args.target, "127.0.0.1:9001".parse().unwrap());
+        assert_eq!(args.ticks_per_second, 10_000);
+
+        assert!(cli().try_get_matches_from(["market_simulator", "--symbols", "DOGE/USD"]).is_err());
+        assert!(cli().try_get_matches_from(["market_simulator", "--ticks-per-second", "0"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_universe_limits_simulated_symbols() {
+        let path = "/tmp/hft_test_simulator_universe.toml";
+        std::fs::write(
+            path,
+            "[[symbols]]\nsymbol = \"ETH/USD\"\nbase_price = 2500.0\n\n\
+             [[symbols]]\nsymbol = \"DOGE/USD\"\nbase_price = 0.1\n",
+        )
+        .unwrap();
+        let matches = cli().get_matches_from(["market_simulator", "--bind", "127.0.0.1:0", "--universe", path]);
+        let args = Args::from_matches(&matches);
+        assert_eq!(
+            args.symbols,
+            vec![("ETH/USD".to_string(), 2500.0), ("DOGE/USD".to_string(), 0.1)]
+        );
+        assert!(cli()
+            .try_get_matches_from(["market_simulator", "--universe", path, "--symbols", "BTC/USD"])
+            .is_err());
+
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let multicast_options = MulticastOptions {
+            interface: args.multicast_interface,
+            ttl: args.multicast_ttl,
+        };
+        let mut simulator =
+            MarketSimulator::new(args.bind, feed.local_addr().unwrap(), args.symbols, multicast_options).unwrap();
+        let receive = async {
+            let mut buf = [0u8; 512];
+            for _ in 0..50 {
+                let n = feed.recv(&mut buf).await.unwrap();
+                let tick: MarketTick = serde_json::from_slice(&buf[..n]).unwrap();
+                assert!(["ETH/USD", "DOGE/USD"].contains(&tick.symbol.as_str()), "{}", tick.symbol);
+            }
+        };
+        tokio::select! {
+            result = simulator.run(10_000) => panic!("simulator stopped: {:?}", result),
+            _ = receive => {}
+        }
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_symbols_tick_in_proportion_to_activity_weight() {
+        let path = "/tmp/hft_test_simulator_weights.toml";
+        std::fs::write(
+            path,
+            "[[symbols]]\nsymbol = \"BTC/USD\"\nbase_price = 45000.0\nactivity_weight = 6.0\n\n\
+             [[symbols]]\nsymbol = \"ETH/USD\"\nbase_price = 2500.0\nactivity_weight = 3.0\n\n\
+             [[symbols]]\nsymbol = \"SOL/USD\"\nbase_price = 100.0\n",
+        )
+        .unwrap();
+        let matches = cli().get_matches_from(["market_simulator", "--bind", "127.0.0.1:0", "--universe", path]);
+        let args = Args::from_matches(&matches);
+        std::fs::remove_file(path).ok();
+        assert_eq!(args.activity_weights, vec![6.0, 3.0, 1.0]);
+
+        let target = UdpSocket::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let mut simulator = simulator_to(target)
+            .with_activity_weights(&[6.0, 3.0, 1.0, 0.0])
+            .unwrap()
+            .with_chaos(ChaosOptions::default(), Some(11));
+        let ticks = 100_000;
+        let mut counts = HashMap::new();
+        for _ in 0..ticks {
+            *counts.entry(simulator.next_tick().unwrap().symbol).or_insert(0) += 1;
+        }
+        for (symbol, expected) in [("BTC/USD", 0.6), ("ETH/USD", 0.3), ("SOL/USD", 0.1)] {
+            let observed = counts[symbol] as f64 / ticks as f64;
+            assert!((observed - expected).abs() < 0.01, "{}: {}", symbol, observed);
+        }
+        // A zero weight never ticks
+        assert!(!counts.contains_key("AVAX/USD"));
+
+        assert!(simulator_to(target).with_activity_weights(&[1.0]).is_err());
+        assert!(simulator_to(target).with_activity_weights(&[0.0; 4]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chaos_drops_and_duplicates_ticks() {
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = feed.local_addr().unwrap();
+        let mut buf = [0u8; 512];
+
+        let drop_all = ChaosOptions {
+            drop_probability: 1.0,
+            ..ChaosOptions::default()
+        };
+        let mut simulator = simulator_to(target).with_chaos(drop_all, Some(7));
+        for _ in 0..10 {
+            let tick = simulator.next_tick().unwrap();
+            assert_eq!(simulator.publish(&tick).unwrap(), 0);
+        }
+        assert!(tokio::time::timeout(Duration::from_millis(50), feed.recv(&mut buf))
+            .await
+            .is_err());
+
+        let duplicate_all = ChaosOptions {
+            duplicate_probability: 1.0,
+            ..ChaosOptions::default()
+        };
+        let mut simulator = simulator_to(target).with_chaos(duplicate_all, Some(7));
+        for _ in 0..5 {
+            let tick = simulator.next_tick().unwrap();
+            assert_eq!(simulator.publish(&tick).unwrap(), 2);
+            for _ in 0..2 {
+                let n = feed.recv(&mut buf).await.unwrap();
+                let received: MarketTick = serde_json::from_slice(&buf[..n]).unwrap();
+                assert_eq!(received.sequence, tick.sequence);
+            }
+        }
+
+        // Same seed, same walk
+        let mut a = simulator_to(target).with_chaos(ChaosOptions::default(), Some(42));
+        let mut b = simulator_to(target).with_chaos(ChaosOptions::default(), Some(42));
+        for _ in 0..5 {
+            let (a, b) = (a.next_tick().unwrap(), b.next_tick().unwrap());
+            assert_eq!((a.symbol, a.price), (b.symbol, b.price));
+        }
+        assert!(cli()
+            .try_get_matches_from(["market_simulator", "--drop-probability", "1.5"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds_of_each_slot() {
+        let target = "127.0.0.1:9".parse().unwrap();
+        let jitter = Duration::from_micros(50);
+        let chaos = ChaosOptions {
+            jitter,
+            ..ChaosOptions::default()
+        };
+        let mut simulator = simulator_to(target).with_chaos(chaos, Some(3));
+
+        let start = Instant::now() + Duration::from_secs(1);
+        let period = Duration::from_micros(100);
+        let (mut early, mut late) = (false, false);
+        for n in 0..1_000 {
+            let slot = start + period * n;
+            let due = simulator.jittered(slot);
+            assert!(due >= slot - jitter && due <= slot + jitter, "tick {}", n);
+            early |= due < slot;
+            late |= due > slot;
+        }
+        assert!(early && late);
+
+        // Without jitter every tick is due on its slot
+        let mut steady = simulator_to(target);
+        assert_eq!(steady.jittered(start), start);
+    }
+
+    #[tokio::test]
+    async fn test_probe_with_responding_feed_handler() {
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = feed.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            loop {
+                let (n, from) = feed.recv_from(&mut buf).await.unwrap();
+                if &buf[..n] == PROBE_REQUEST {
+                    feed.send_to(PROBE_ACK, from).await.unwrap();
+                }
+            }
+        });
+
+        let simulator = simulator_to(target);
+        assert!(simulator.check_target(true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_probe_without_feed_handler() {
+        // Bound but never answers, like a host that swallows datagrams
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let target = silent.local_addr().unwrap();
+
+        let simulator = simulator_to(target);
+        assert!(!simulator.probe_target(1, Duration::from_millis(50)).await);
+        assert!(simulator.check_target(false).await.is_ok());
+        assert!(simulator.check_target(true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multicast_target_skips_probe() {
+        let group: SocketAddr = "239.255.77.2:9001".parse().unwrap();
+        let simulator = match MarketSimulator::new(
+            "127.0.0.1:0".parse().unwrap(),
+            group,
+            vec![("BTC/USD".to_string(), 45000.0)],
+            MulticastOptions {
+                interface: Ipv4Addr::LOCALHOST,
+                ttl: 0,
+            },
+        ) {
+            Ok(simulator) => simulator,
+            Err(e) => {
+                eprintln!("skipping: multicast unavailable here: {}", e);
+                return;
+            }
+        };
+        assert!(simulator.multicast);
+        assert!(simulator.check_target(false).await.is_ok());
+        assert!(simulator.check_target(true).await.is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use hft_types::matching::Fill;
+use hft_types::performance::PerformanceTracker;
+use hft_types::portfolio::{Position, PositionTracker};
+use hft_types::{MarketTick, Order};
+use std::collections::BTreeMap;
+
+/// Realized PnL changes in each account's rolling Sharpe ratio
+const SHARPE_WINDOW: usize = 100;
+
+/// One client's positions and the performance of its realized PnL
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub positions: PositionTracker,
+    pub performance: PerformanceTracker,
+}
+
+/// Positions kept per client, keyed by `Order::client_id`. Orders without
+/// one share the unnamed `""` account. Keeping clients apart means both
+/// sides of a match are each booked against their own position instead of
+/// netting to flat.
+#[derive(Debug)]
+pub struct Accounts {
+    /// Cloned for each new client, carrying the holding limit and fees
+    template: PositionTracker,
+    accounts: BTreeMap<String, Account>,
+}
+
+impl Accounts {
+    pub fn new(template: PositionTracker) -> Self {
+        Self {
+            template,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    pub fn get_mut(&mut self, client: &str) -> Option<&mut Account> {
+        self.accounts.get_mut(client)
+    }
+
+    fn entry(&mut self, client: &str) -> &mut Account {
+        if !self.accounts.contains_key(client) {
+            let account = Account {
+                positions: self.template.clone(),
+                performance: PerformanceTracker::with_window(SHARPE_WINDOW),
+            };
+            self.accounts.insert(client.to_string(), account);
+        }
+        self.accounts.get_mut(client).unwrap()
+    }
+
+    /// Book `fill` against its client's account, returning the client
+    pub fn apply_fill(&mut self, fill: &Fill) -> &str {
+        let client = fill.client_id.as_deref().unwrap_or_default();
+        self.entry(client).positions.apply_fill(fill);
+        self.accounts.get_key_value(client).unwrap().0
+    }
+
+    /// Flattening orders for positions held too long, stamped with the
+    /// client whose position they close
+    pub fn on_tick(&mut self, tick: &MarketTick) -> Vec<Order> {
+        let mut orders = Vec::new();
+        for (client, account) in &mut self.accounts {
+            for mut order in account.positions.on_tick(tick) {
+                order.client_id = (!client.is_empty()).then(|| client.clone());
+                orders.push(order);
+            }
+        }
+        orders
+    }
+
+    /// A flattening order for `client` was rejected; let it try again
+    pub fn cancel_flatten(&mut self, client: Option<&str>, symbol: &str) {
+        if let Some(account) = self.accounts.get_mut(client.unwrap_or_default()) {
+            account.positions.cancel_flatten(symbol);
+        }
+    }
+
+    /// Fees paid across every account
+    pub fn fees(&self) -> f64 {
+        self.accounts.values().map(|account| account.positions.fees()).sum()
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, Vec<Position>> {
+        self.accounts
+            .iter()
+            .map(|(client, account)| (client.clone(), account.positions.positions().cloned().collect()))
+            .collect()
+    }
+
+    pub fn restore(&mut self, positions: BTreeMap<String, Vec<Position>>) {
+        for (client, positions) in positions {
+            self.entry(&client).positions.restore(positions);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// What the gateway reported back for a placed order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderAck {
+    pub order_id: u64,
+    pub filled_qty: f64,
+    pub resting_qty: f64,
+    pub cancelled_qty: f64,
+}
+
+/// Acks for the most recently used order ids, so a retransmitted order is
+/// answered with its original ack instead of being placed again. Once
+/// `capacity` ids are held the least recently used is forgotten.
+pub struct PlacedOrders {
+    capacity: usize,
+    /// Order id -> ack and the generation of its latest use
+    acks: HashMap<u64, (OrderAck, u64)>,
+    /// Uses oldest first; entries superseded by a later use are skipped on eviction
+    order: VecDeque<(u64, u64)>,
+    generation: u64,
+}
+
+impl PlacedOrders {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            acks: HashMap::new(),
+            order: VecDeque::new(),
+            generation: 0,
+        }
+    }
+
+    /// The ack for `order_id` if it was placed within the window
+    pub fn get(&mut self, order_id: u64) -> Option<OrderAck> {
+        self.generation += 1;
+        let (ack, generation) = self.acks.get_mut(&order_id)?;
+        *generation = self.generation;
+        self.order.push_back((order_id, self.generation));
+        Some(ack.clone())
+    }
+
+    /// Whether `order_id` was placed within the window, without counting
+    /// as a use
+    pub fn contains(&self, order_id: u64) -> bool {
+        self.acks.contains_key(&order_id)
+    }
+
+    /// Every remembered ack, least recently used first, so that inserting
+    /// them in order rebuilds the same window
+    pub fn snapshot(&self) -> Vec<OrderAck> {
+        let mut acks: Vec<&(OrderAck, u64)> = self.acks.values().collect();
+        acks.sort_by_key(|(_, generation)| *generation);
+        acks.into_iter().map(|(ack, _)| ack.clone()).collect()
+    }
+
+    pub fn insert(&mut self, ack: OrderAck) {
+        self.generation += 1;
+        self.order.push_back((ack.order_id, self.generation));
+        self.acks.insert(ack.order_id, (ack, self.generation));
+
+        while self.acks.len() > self.capacity {
+            let Some((order_id, generation)) = self.order.pop_front() else {
+                break;
+            };
+            if self.acks.get(&order_id).is_some_and(|&(_, latest)| latest == generation) {
+                self.acks.remove(&order_id);
+            }
+        }
+        // Stale use records pile up under repeated lookups; compact them
+        if self.order.len() > self.capacity * 2 {
+            let acks = &self.acks;
+            self.order.retain(|(order_id, generation)| {
+                acks.get(order_id).is_some_and(|&(_, latest)| latest == *generation)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(order_id: u64) -> OrderAck {
+        OrderAck {
+            order_id,
+            filled_qty: 0.0,
+            resting_qty: 1.0,
+            cancelled_qty: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_id_is_forgotten() {
+        let mut placed = PlacedOrders::new(2);
+        placed.insert(ack(1));
+        placed.insert(ack(2));
+
+        // Touching 1 makes 2 the eviction candidate
+        assert_eq!(placed.get(1), Some(ack(1)));
+        placed.insert(ack(3));
+        assert_eq!(placed.get(2), None);
+        assert_eq!(placed.get(1), Some(ack(1)));
+        assert_eq!(placed.get(3), Some(ack(3)));
+    }
+
+    #[test]
+    fn test_snapshot_rebuilds_the_same_window() {
+        let mut placed = PlacedOrders::new(2);
+        placed.insert(ack(1));
+        placed.insert(ack(2));
+        placed.get(1);
+
+        let mut restored = PlacedOrders::new(2);
+        for ack in placed.snapshot() {
+            restored.insert(ack);
+        }
+        assert_eq!(restored.snapshot(), vec![ack(2), ack(1)]);
+        // 2 is still the next to go
+        restored.insert(ack(3));
+        assert!(!restored.contains(2));
+        assert!(restored.contains(1));
+    }
+}
@@ -0,0 +1,2153 @@
+mod accounts;
+mod idempotency;
+mod matcher;
+mod order_states;
+mod rate_limit;
+
+use accounts::Accounts;
+use anyhow::Result;
+use idempotency::{OrderAck, PlacedOrders};
+use matcher::{MatchStep, Matcher};
+use order_states::OrderStates;
+use clap::{value_parser, Arg, Command};
+use hft_types::audit::{AuditEvent, AuditLog};
+use hft_types::cli;
+use hft_types::config::{read_config_file, SymbolUniverse};
+use hft_types::clock::{SharedClock, SystemClock};
+use hft_types::fees::FeeSchedule;
+use hft_types::health::Readiness;
+use hft_types::latency::{latency_buckets, monotonic_nanos, Component, LatencyTrace};
+use hft_types::lifecycle::OrderState;
+use hft_types::matching::{MatchResult, MatchingEngine, SelfTradePrevention};
+use hft_types::messaging::{parse_socket_addr, CancelRejectReason, Message};
+use hft_types::orderbook::OrderBookManager;
+use hft_types::portfolio::{HoldingClock, Position, PositionTracker};
+use hft_types::snapshot::Snapshotter;
+use hft_types::risk::{AggressionCheck, CircuitBreaker, CircuitBreakerConfig};
+use hft_types::transport::{TcpTransport, Transport};
+use hft_types::{HftError, HftResult, MarketTick, Order, OrderType};
+use lazy_static::lazy_static;
+use prometheus::{
+    Gauge, GaugeVec, Histogram, HistogramOpts, IntCounter, IntGauge, IntGaugeVec, Opts, Registry,
+};
+use rate_limit::{RateLimitConfig, RateLimiter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+    pub static ref ORDERS_PLACED: IntCounter = IntCounter::new(
+        "gateway_orders_placed_total",
+        "Total number of orders placed"
+    )
+    .unwrap();
+    pub static ref AGGRESSIVE_ORDERS: IntCounter = IntCounter::new(
+        "gateway_aggressive_orders_total",
+        "Total number of orders flagged as too aggressive versus the reference price"
+    )
+    .unwrap();
+    pub static ref ORDER_QUEUE_DEPTH: IntGauge = IntGauge::new(
+        "gateway_order_queue_depth",
+        "Number of decoded messages waiting to be processed by the gateway"
+    )
+    .unwrap();
+    pub static ref DROPPED_FRAMES: IntCounter = IntCounter::new(
+        "gateway_dropped_frames_total",
+        "Total number of malformed frames dropped"
+    )
+    .unwrap();
+    pub static ref FILLS: IntCounter = IntCounter::new(
+        "gateway_fills_total",
+        "Total number of fill events produced by the matching engine"
+    )
+    .unwrap();
+    pub static ref FILL_RATIO: Gauge = Gauge::new(
+        "fill_ratio",
+        "Filled quantity as a fraction of submitted quantity"
+    )
+    .unwrap();
+    pub static ref ORDERS_THROTTLED: IntCounter = IntCounter::new(
+        "orders_throttled_total",
+        "Total number of orders rejected by the rate limiter"
+    )
+    .unwrap();
+    pub static ref FEED_INGEST_LATENCY: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "gateway_feed_ingest_micros",
+            "Exchange timestamp to decoded tick in the feed handler, per placed order"
+        )
+        .buckets(latency_buckets())
+    )
+    .unwrap();
+    pub static ref STRATEGY_DECISION_LATENCY: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "gateway_strategy_decision_micros",
+            "Decoded tick to order sent by the strategy engine, per placed order"
+        )
+        .buckets(latency_buckets())
+    )
+    .unwrap();
+    pub static ref GATEWAY_PLACE_LATENCY: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "gateway_place_micros",
+            "Order sent by the strategy to placed by the gateway"
+        )
+        .buckets(latency_buckets())
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_OPEN: IntGauge = IntGauge::new(
+        "circuit_breaker_open",
+        "1 while the circuit breaker is blocking new orders"
+    )
+    .unwrap();
+    pub static ref DUPLICATE_ORDERS: IntCounter = IntCounter::new(
+        "gateway_duplicate_orders_total",
+        "Total number of resubmitted order ids answered with their original ack"
+    )
+    .unwrap();
+    pub static ref ORDERS_CANCELLED: IntCounter = IntCounter::new(
+        "orders_cancelled_total",
+        "Total number of resting orders cancelled on request"
+    )
+    .unwrap();
+    pub static ref ORDERS_REPLACED: IntCounter = IntCounter::new(
+        "orders_replaced_total",
+        "Total number of resting orders amended on request"
+    )
+    .unwrap();
+    pub static ref ORDERS_TIMED_OUT: IntCounter = IntCounter::new(
+        "orders_timed_out_total",
+        "Total number of orders rejected for running past their processing deadline"
+    )
+    .unwrap();
+    pub static ref ORDERS_REJECTED_STALE: IntCounter = IntCounter::new(
+        "orders_rejected_stale_total",
+        "Total number of orders rejected because their originating tick was too old"
+    )
+    .unwrap();
+    pub static ref ORDERS_REJECTED_SIZE: IntCounter = IntCounter::new(
+        "orders_rejected_size_total",
+        "Total number of orders rejected for an odd lot or a notional under the symbol's minimum"
+    )
+    .unwrap();
+    pub static ref SELF_TRADES_PREVENTED: IntCounter = IntCounter::new(
+        "self_trades_prevented_total",
+        "Total number of matches between one client's own orders that were stopped"
+    )
+    .unwrap();
+    pub static ref MAX_DRAWDOWN: GaugeVec = GaugeVec::new(
+        Opts::new(
+            "gateway_max_drawdown",
+            "Largest peak-to-trough fall in realized PnL per client"
+        ),
+        &["client"]
+    )
+    .unwrap();
+    pub static ref DRAWDOWN_DURATION: GaugeVec = GaugeVec::new(
+        Opts::new(
+            "gateway_drawdown_duration_seconds",
+            "Time each client's realized PnL has spent below its last peak"
+        ),
+        &["client"]
+    )
+    .unwrap();
+    pub static ref ORDERS_BY_STATUS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "gateway_orders_by_status",
+            "Live and recently finished orders by lifecycle status"
+        ),
+        &["status"]
+    )
+    .unwrap();
+    pub static ref FEES_PAID: Gauge = Gauge::new(
+        "gateway_fees_paid",
+        "Fees charged on simulated fills, net of maker rebates"
+    )
+    .unwrap();
+    pub static ref ROLLING_SHARPE: GaugeVec = GaugeVec::new(
+        Opts::new(
+            "gateway_rolling_sharpe",
+            "Sharpe ratio of each client's recent per-fill realized PnL changes"
+        ),
+        &["client"]
+    )
+    .unwrap();
+}
+
+pub fn init_metrics() {
+    REGISTRY
+        .register(Box::new(ORDERS_PLACED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(AGGRESSIVE_ORDERS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDER_QUEUE_DEPTH.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(DROPPED_FRAMES.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(FILLS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(FILL_RATIO.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_THROTTLED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(DUPLICATE_ORDERS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_CANCELLED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_REPLACED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(CIRCUIT_BREAKER_OPEN.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(FEED_INGEST_LATENCY.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(STRATEGY_DECISION_LATENCY.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(GATEWAY_PLACE_LATENCY.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(MAX_DRAWDOWN.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(DRAWDOWN_DURATION.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ROLLING_SHARPE.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_BY_STATUS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(FEES_PAID.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(SELF_TRADES_PREVENTED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_TIMED_OUT.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_REJECTED_STALE.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_REJECTED_SIZE.clone()))
+        .unwrap();
+}
+
+/// Record where the time went for an order's tick, per owning service
+fn observe_component_latencies(trace: &LatencyTrace) {
+    for (component, delta_nanos) in trace.component_deltas() {
+        let histogram: &Histogram = match component {
+            Component::FeedIngest => &FEED_INGEST_LATENCY,
+            Component::StrategyDecision => &STRATEGY_DECISION_LATENCY,
+            Component::GatewayPlace => &GATEWAY_PLACE_LATENCY,
+        };
+        histogram.observe(delta_nanos as f64 / 1000.0);
+    }
+}
+
+/// Gateway settings, read from the `[gateway]` section of config.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GatewayConfig {
+    aggression: AggressionCheck,
+    rate_limit: RateLimitConfig,
+    /// Flatten positions held longer than this; unset disables auto-flatten
+    max_holding_secs: Option<f64>,
+    holding_clock: HoldingClock,
+    /// Charged on every fill and taken out of realized PnL
+    fees: FeeSchedule,
+    /// What to do when an order would trade with its own client's resting order
+    self_trade_prevention: SelfTradePrevention,
+    /// Processing deadline for orders that don't carry their own
+    order_timeout_micros: Option<u64>,
+    /// Reject orders whose originating tick is older than this when they
+    /// arrive; orders without a latency trace are never rejected for age
+    max_signal_age_micros: Option<u64>,
+    /// Loaded from the top-level `[circuit_breaker]` section
+    #[serde(skip)]
+    circuit_breaker: CircuitBreakerConfig,
+}
+
+/// A config file; only `[gateway]` is read here, so the shared config.toml
+/// can be passed as-is
+#[derive(Debug, Deserialize)]
+struct GatewayFile {
+    #[serde(default)]
+    gateway: GatewayConfig,
+}
+
+impl GatewayConfig {
+    /// Load the `[gateway]` section of a `.json` or TOML file, with the
+    /// circuit breaker from its top-level `[circuit_breaker]` section
+    fn from_file<P: AsRef<Path>>(path: P) -> HftResult<Self> {
+        let file: GatewayFile = read_config_file(path.as_ref())?;
+        if file.gateway.max_holding_secs.is_some_and(|secs| !(secs.is_finite() && secs > 0.0)) {
+            return Err(HftError::InvalidConfig(
+                "gateway max_holding_secs must be positive".to_string(),
+            ));
+        }
+        Ok(GatewayConfig {
+            circuit_breaker: CircuitBreakerConfig::from_file(path)?,
+            ..file.gateway
+        })
+    }
+}
+
+/// Day orders expire when a tick's timestamp crosses a UTC midnight
+const NANOS_PER_DAY: u128 = 86_400 * 1_000_000_000;
+
+/// Order ids remembered for duplicate detection
+const ORDER_ID_WINDOW: usize = 100_000;
+
+/// Ids for orders the gateway originates itself start here, clear of the
+/// ids strategies assign
+const INTERNAL_ORDER_ID_BASE: u64 = 1 << 63;
+
+/// Wait between attempts to reach the feed handler
+const FEED_RETRY: Duration = Duration::from_secs(1);
+
+/// What the gateway needs to pick up after a restart: positions per client,
+/// the book of open orders and their lifecycle states, the acks that answer
+/// retransmitted orders, and its own counters
+#[derive(Debug, Serialize, Deserialize)]
+struct GatewaySnapshot {
+    order_id: u64,
+    trading_day: Option<u128>,
+    positions: BTreeMap<String, Vec<Position>>,
+    open_orders: Vec<Order>,
+    order_states: Vec<OrderState>,
+    /// Least recently used first; missing from snapshots of older gateways
+    #[serde(default)]
+    placed: Vec<OrderAck>,
+}
+
+/// A message from a strategy connection and the way back to it
+pub struct Inbound {
+    pub message: Message,
+    /// Takes fills, cancels and rejects of the connection's orders
+    pub reply: std::sync::mpsc::Sender<Message>,
+}
+
+/// Places, matches and tracks the orders strategies send in
+pub struct OrderGateway {
+    /// Last id assigned to a gateway-originated order
+    order_id: u64,
+    placed: PlacedOrders,
+    orders: OrderStates,
+    trading_day: Option<u128>,
+    books: OrderBookManager,
+    aggression: AggressionCheck,
+    matcher: Matcher,
+    match_step: MatchStep,
+    order_timeout_micros: Option<u64>,
+    max_signal_age_micros: Option<u64>,
+    limiter: RateLimiter,
+    breaker: CircuitBreaker,
+    /// Positions and performance per client
+    accounts: Accounts,
+    submitted_qty: f64,
+    filled_qty: f64,
+    /// Drives rate limits, the circuit breaker and matching; latency
+    /// stamps always use `monotonic_nanos`
+    clock: SharedClock,
+    /// Where placements, fills, cancels and rejects are recorded
+    audit: Option<AuditLog>,
+    /// Lot and minimum notional rules per symbol; symbols outside it, or
+    /// every symbol without one, are not checked
+    universe: Option<SymbolUniverse>,
+    snapshotter: Option<Snapshotter>,
+    /// Where each client hears about its orders: the connection its last
+    /// order came in on
+    clients: HashMap<Option<String>, std::sync::mpsc::Sender<Message>>,
+}
+
+impl OrderGateway {
+    pub fn new(config: GatewayConfig) -> Self {
+        Self {
+            order_id: INTERNAL_ORDER_ID_BASE,
+            placed: PlacedOrders::new(ORDER_ID_WINDOW),
+            orders: OrderStates::new(ORDER_ID_WINDOW),
+            trading_day: None,
+            books: OrderBookManager::new(),
+            aggression: config.aggression,
+            matcher: Matcher::new(MatchingEngine::new().with_self_trade_prevention(config.self_trade_prevention)),
+            match_step: MatchingEngine::submit,
+            order_timeout_micros: config.order_timeout_micros,
+            max_signal_age_micros: config.max_signal_age_micros,
+            limiter: RateLimiter::new(config.rate_limit),
+            breaker: CircuitBreaker::new(config.circuit_breaker),
+            accounts: Accounts::new(
+                match config.max_holding_secs {
+                    Some(secs) => PositionTracker::with_max_holding(
+                        (secs * 1_000_000_000.0) as u128,
+                        config.holding_clock,
+                    ),
+                    None => PositionTracker::new(),
+                }
+                .with_fees(config.fees),
+            ),
+            submitted_qty: 0.0,
+            filled_qty: 0.0,
+            clock: SystemClock::shared(),
+            audit: None,
+            universe: None,
+            snapshotter: None,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Hold orders to the lot and minimum notional rules of `universe`
+    fn with_universe(mut self, universe: SymbolUniverse) -> Self {
+        self.universe = Some(universe);
+        self
+    }
+
+    /// Record order activity to `log`
+    fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Save state with `snapshotter` while running
+    fn with_snapshotter(mut self, snapshotter: Snapshotter) -> Self {
+        self.snapshotter = Some(snapshotter);
+        self
+    }
+
+    /// Tell `client` about one of its orders, forgetting it once its
+    /// connection has gone
+    fn notify(&mut self, client: &Option<String>, message: Message) {
+        let gone = self
+            .clients
+            .get(client)
+            .is_some_and(|reply| reply.send(message).is_err());
+        if gone {
+            self.clients.remove(client);
+        }
+    }
+
+    /// Append to the audit log, if one is kept
+    fn audit(&self, event: impl FnOnce() -> AuditEvent) {
+        if let Some(log) = &self.audit {
+            log.record(event());
+        }
+    }
+
+    fn on_tick(&mut self, tick: &MarketTick) {
+        self.settle_late_matches(false);
+        self.books.update_from_tick(tick);
+        self.observe_book(&tick.symbol);
+
+        let day = tick.timestamp_nanos / NANOS_PER_DAY;
+        if self.trading_day.is_some_and(|current| day > current) {
+            let expired = self.matcher.engine().expire_day_orders();
+            for order in expired {
+                info!("DAY EXPIRED [{}]: {} {} x {}", order.order_id, order.side, order.quantity, order.symbol);
+                self.update_state(order.order_id, OrderState::cancel);
+                self.audit(|| AuditEvent::Cancel {
+                    order_id: order.order_id,
+                    cancelled_qty: order.quantity,
+                    reason: "day order expired".to_string(),
+                });
+                self.notify(
+                    &order.client_id,
+                    Message::CancelAck {
+                        order_id: order.order_id,
+                        cancelled_qty: order.quantity,
+                    },
+                );
+            }
+        }
+        self.trading_day = Some(self.trading_day.map_or(day, |current| current.max(day)));
+
+        let results = self.matcher.engine().on_market_price(&tick.symbol, tick.price, tick.timestamp_nanos);
+        for result in results {
+            self.record_result(result);
+        }
+
+        for mut order in self.accounts.on_tick(tick) {
+            self.order_id += 1;
+            order.order_id = self.order_id;
+            info!(
+                "Holding limit reached: flattening {} {} x {}",
+                order.symbol, order.side, order.quantity
+            );
+            let (client, symbol) = (order.client_id.clone(), order.symbol.clone());
+            if let Err(e) = self.place_order(order) {
+                warn!("Flatten order rejected: {}", e);
+                self.accounts.cancel_flatten(client.as_deref(), &symbol);
+            }
+        }
+    }
+
+    /// Let the circuit breaker see the symbol's latest book
+    fn observe_book(&mut self, symbol: &str) {
+        if let Some(book) = self.books.get_book(symbol) {
+            self.breaker.on_book(book, self.clock.now_nanos());
+        }
+        self.circuit_open();
+    }
+
+    /// Whether the circuit breaker is blocking orders, keeping its gauge current
+    fn circuit_open(&mut self) -> bool {
+        let open = self.breaker.is_open(self.clock.now_nanos());
+        CIRCUIT_BREAKER_OPEN.set(open as i64);
+        open
+    }
+
+    /// Check an order against the configured reference price.
+    /// Orders without a reference book, and orders whose price isn't
+    /// used for crossing (market and stop), are never flagged.
+    fn is_aggressive(&self, order: &Order) -> bool {
+        if order.order_type != OrderType::Limit {
+            return false;
+        }
+        self.books
+            .get_book(&order.symbol)
+            .map(|book| self.aggression.is_aggressive(&order.side, order.price, book))
+            .unwrap_or(false)
+    }
+
+    /// Handle messages from strategy connections until every connection
+    /// has gone, saving snapshots as they come due
+    pub async fn run(&mut self, mut rx: mpsc::Receiver<Inbound>) {
+        while let Some(inbound) = rx.recv().await {
+            ORDER_QUEUE_DEPTH.dec();
+            self.on_inbound(inbound);
+            self.save_snapshot(false);
+        }
+    }
+
+    /// Handle a message, replying to its connection about any order it
+    /// places or cancels
+    fn on_inbound(&mut self, Inbound { message, reply }: Inbound) {
+        match message {
+            Message::CancelOrder { order_id } => {
+                let answer = self.cancel_order(order_id);
+                match &answer {
+                    Message::CancelReject { reason, .. } => {
+                        warn!("CANCEL REJECTED [{}]: {:?}", order_id, reason)
+                    }
+                    answer => tracing::debug!("{:?}", answer),
+                }
+                // The connection may already be gone
+                let _ = reply.send(answer);
+            }
+            message => {
+                if let Message::Order(order) = &message {
+                    self.clients.insert(order.client_id.clone(), reply);
+                }
+                self.handle_message(message);
+            }
+        }
+    }
+
+    /// Save the last of the state and write out the audit log before the
+    /// process exits
+    pub fn shutdown(&mut self) {
+        self.save_snapshot(true);
+        if let Some(log) = &self.audit {
+            log.flush();
+        }
+    }
+
+    /// The book orders are matched on
+    pub fn engine(&self) -> MutexGuard<'_, MatchingEngine> {
+        self.matcher.engine()
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Order(order) => {
+                let (order_id, client) = (order.order_id, order.client_id.clone());
+                match self.place_order(order) {
+                    Ok(ack) => tracing::debug!(
+                        "ACK [{}]: filled {} resting {} cancelled {}",
+                        ack.order_id, ack.filled_qty, ack.resting_qty, ack.cancelled_qty
+                    ),
+                    Err(e) => {
+                        warn!("Order rejected: {}", e);
+                        let reason = e.to_string();
+                        self.notify(&client, Message::OrderReject { order_id, reason });
+                    }
+                }
+            }
+            Message::Tick(tick) => self.on_tick(&tick),
+            Message::OrderBookUpdate(book) => {
+                let symbol = book.symbol.clone();
+                self.books.apply_snapshot(book);
+                self.observe_book(&symbol);
+            }
+            Message::ResetCircuitBreaker => {
+                self.breaker.reset();
+                self.circuit_open();
+            }
+            Message::ReplaceOrder { order_id, new_price, new_quantity, keep_priority } => {
+                match self.replace_order(order_id, new_price, new_quantity, keep_priority) {
+                    Ok(ack) => tracing::debug!(
+                        "REPLACE ACK [{}]: filled {} resting {}",
+                        ack.order_id, ack.filled_qty, ack.resting_qty
+                    ),
+                    Err(e) => warn!("Replace rejected: {}", e),
+                }
+            }
+            other => tracing::debug!("Ignoring message: {:?}", other),
+        }
+    }
+
+    /// Place an order under the caller's `order_id`. Resubmitting an id
+    /// placed within the window returns the original ack without placing
+    /// it again; rejected orders aren't remembered and can be retried.
+    /// A timed-out order that filled in part is remembered with its fills.
+    ///
+    /// An order whose processing deadline passes before the engine
+    /// returns is rejected with `HftError::Timeout` at the deadline. The
+    /// match runs on the matcher's worker, so a hung match can't hold the
+    /// gateway; when it does come back whatever rests is pulled, so
+    /// nothing is left working that the caller was told timed out.
+    fn place_order(&mut self, order: Order) -> HftResult<OrderAck> {
+        let deadline = order
+            .max_processing_micros
+            .or(self.order_timeout_micros)
+            .map(|max| monotonic_nanos() + max as u128 * 1_000);
+        self.settle_late_matches(false);
+        if let Some(ack) = self.placed.get(order.order_id) {
+            DUPLICATE_ORDERS.inc();
+            info!("DUPLICATE [{}]: already placed, returning original ack", order.order_id);
+            return Ok(ack);
+        }
+        if self.matcher.is_late(order.order_id) {
+            return Err(HftError::Timeout(format!(
+                "order {} is still matching past its deadline",
+                order.order_id
+            )));
+        }
+        self.orders.open(order.order_id, order.quantity);
+        if let Err(e) = self.admit(&order) {
+            self.update_state(order.order_id, OrderState::reject);
+            self.audit(|| AuditEvent::Reject {
+                order_id: order.order_id,
+                reason: e.to_string(),
+            });
+            return Err(e);
+        }
+
+        if self.is_aggressive(&order) {
+            AGGRESSIVE_ORDERS.inc();
+            warn!(
+                "Aggressive order [{}]: {} {} @ {} vs {:?} reference",
+                order.order_id, order.side, order.symbol, order.price, self.aggression.basis
+            );
+        }
+
+        let placed_time = monotonic_nanos();
+        if let Some(trace) = &order.latency_trace {
+            let mut trace = trace.clone();
+            trace.placed_nanos = Some(placed_time);
+            observe_component_latencies(&trace);
+        }
+
+        let latency_micros = hft_types::latency_micros(order.timestamp_nanos, placed_time).unwrap_or(0.0);
+
+        info!(
+            "ORDER PLACED [{}]: {:?} {} x {} @ {} {:?}/{:?} (latency: {:.2}µs)",
+            order.order_id,
+            order.side,
+            order.quantity,
+            order.symbol,
+            order.price,
+            order.order_type,
+            order.time_in_force,
+            latency_micros
+        );
+
+        ORDERS_PLACED.inc();
+        self.audit(|| AuditEvent::OrderPlaced(Box::new(order.clone())));
+
+        self.submitted_qty += order.quantity;
+        let (order_id, client) = (order.order_id, order.client_id.clone());
+        if let Some(deadline) = deadline.filter(|&deadline| monotonic_nanos() > deadline) {
+            let error = self.timeout_error(order_id, deadline, "before matching");
+            self.reject_timed_out(order_id, &error);
+            return Err(error);
+        }
+        let now = self.clock.now_nanos();
+        let Some(result) = self.matcher.submit(self.match_step, order, now, deadline) else {
+            // Still running; settled by `settle_late_matches` once it returns
+            return Err(self.timeout_error(order_id, deadline.unwrap_or_default(), "in matching"));
+        };
+        if result.cancelled_qty > 0.0 {
+            info!("CANCELLED [{}]: {} unfilled", order_id, result.cancelled_qty);
+            self.audit(|| AuditEvent::Cancel {
+                order_id,
+                cancelled_qty: result.cancelled_qty,
+                reason: "unfilled on arrival".to_string(),
+            });
+            let cancelled_qty = result.cancelled_qty;
+            self.notify(&client, Message::CancelAck { order_id, cancelled_qty });
+        }
+        let ack = OrderAck {
+            order_id,
+            filled_qty: result.filled_qty,
+            resting_qty: result.resting_qty,
+            cancelled_qty: result.cancelled_qty,
+        };
+        self.placed.insert(ack.clone());
+        self.record_result(result);
+        // Self-trade prevention can take quantity off an order that still rests
+        if ack.cancelled_qty > 0.0 && ack.resting_qty > 0.0 {
+            self.update_state(order_id, |state| state.amend(ack.resting_qty));
+        } else if ack.cancelled_qty > 0.0 {
+            self.update_state(order_id, OrderState::cancel);
+        }
+
+        Ok(ack)
+    }
+
+    /// Count an order that ran past its processing deadline at `stage`
+    fn timeout_error(&self, order_id: u64, deadline: u128, stage: &str) -> HftError {
+        ORDERS_TIMED_OUT.inc();
+        let overrun = monotonic_nanos().saturating_sub(deadline);
+        HftError::Timeout(format!(
+            "order {} ran {}µs past its deadline {}",
+            order_id,
+            overrun / 1_000,
+            stage
+        ))
+    }
+
+    fn reject_timed_out(&mut self, order_id: u64, error: &HftError) {
+        self.update_state(order_id, OrderState::reject);
+        self.audit(|| AuditEvent::Reject {
+            order_id,
+            reason: error.to_string(),
+        });
+    }
+
+    /// Book matches that came back after their order was reported timed
+    /// out; `wait` blocks for ones still running. Whatever rests is
+    /// pulled, and one that filled is remembered so a retry is answered
+    /// with what happened rather than trading again. One that didn't fill
+    /// is rejected.
+    fn settle_late_matches(&mut self, wait: bool) {
+        for (order_id, result) in self.matcher.late_results(wait) {
+            let mut pulled_qty = 0.0;
+            if result.resting_qty > 0.0 {
+                let pulled = self.matcher.engine().cancel(order_id);
+                if let Some(pulled) = pulled {
+                    pulled_qty = pulled.quantity;
+                    self.audit(|| AuditEvent::Cancel {
+                        order_id,
+                        cancelled_qty: pulled.quantity,
+                        reason: "processing deadline passed".to_string(),
+                    });
+                    let cancelled_qty = pulled.quantity;
+                    self.notify(&pulled.client_id, Message::CancelAck { order_id, cancelled_qty });
+                }
+            }
+            if result.filled_qty == 0.0 {
+                self.record_result(result);
+                let error = HftError::Timeout(format!("order {} matched past its deadline", order_id));
+                self.reject_timed_out(order_id, &error);
+                continue;
+            }
+            warn!("LATE FILL [{}]: {} filled after timing out", order_id, result.filled_qty);
+            self.placed.insert(OrderAck {
+                order_id,
+                filled_qty: result.filled_qty,
+                resting_qty: result.resting_qty - pulled_qty,
+                cancelled_qty: result.cancelled_qty + pulled_qty,
+            });
+            self.record_result(result);
+            self.update_state(order_id, OrderState::cancel);
+        }
+    }
+
+    /// Checks a new order must pass before it reaches the matching engine
+    fn admit(&mut self, order: &Order) -> HftResult<()> {
+        order.validate()?;
+        if let (Some(max_micros), Some(trace)) = (self.max_signal_age_micros, &order.latency_trace) {
+            let age_micros = (trace.age_nanos(monotonic_nanos()) / 1_000) as u64;
+            if age_micros > max_micros {
+                ORDERS_REJECTED_STALE.inc();
+                return Err(HftError::StaleSignal { age_micros, max_micros });
+            }
+        }
+        // A flatten has to close whatever the position is, lots or not
+        let symbol_config = self
+            .universe
+            .as_ref()
+            .and_then(|universe| universe.get(&order.symbol))
+            .filter(|_| order.order_id < INTERNAL_ORDER_ID_BASE);
+        if let Some(Err(e)) = symbol_config.map(|config| config.validate_order(order)) {
+            ORDERS_REJECTED_SIZE.inc();
+            return Err(e);
+        }
+        // Flattening only reduces exposure, so gateway orders still go out
+        if order.order_id < INTERNAL_ORDER_ID_BASE && self.circuit_open() {
+            let reason = self.breaker.reason().map(ToString::to_string).unwrap_or_default();
+            return Err(HftError::CircuitOpen(reason));
+        }
+        if let Err(e) = self.limiter.check(&order.symbol, self.clock.now_nanos()) {
+            ORDERS_THROTTLED.inc();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Move an order's lifecycle state, logging an illegal transition
+    /// rather than failing whatever caused it
+    fn update_state(&mut self, order_id: u64, change: impl FnOnce(&mut OrderState) -> HftResult<()>) {
+        if let Err(e) = self.orders.update(order_id, change) {
+            warn!("Order state not updated: {}", e);
+        }
+    }
+
+    /// Pull a resting order, answering with a `CancelAck` or `CancelReject`
+    fn cancel_order(&mut self, order_id: u64) -> Message {
+        if let Some(Err(e)) = self.orders.get(order_id).map(|state| state.clone().cancel()) {
+            warn!("CANCEL REJECTED [{}]: {}", order_id, e);
+            return Message::CancelReject {
+                order_id,
+                reason: CancelRejectReason::NotOpen,
+            };
+        }
+        let cancelled = self.matcher.engine().cancel(order_id);
+        match cancelled {
+            Some(order) => {
+                ORDERS_CANCELLED.inc();
+                self.update_state(order_id, OrderState::cancel);
+                self.audit(|| AuditEvent::Cancel {
+                    order_id,
+                    cancelled_qty: order.quantity,
+                    reason: "cancel requested".to_string(),
+                });
+                info!("CANCELLED [{}]: {} unfilled", order_id, order.quantity);
+                Message::CancelAck {
+                    order_id,
+                    cancelled_qty: order.quantity,
+                }
+            }
+            None => Message::CancelReject {
+                order_id,
+                reason: if self.placed.contains(order_id) {
+                    CancelRejectReason::NotOpen
+                } else {
+                    CancelRejectReason::UnknownOrder
+                },
+            },
+        }
+    }
+
+    /// Amend a resting order in place of a cancel and new order, so there
+    /// is no moment with neither on the book. Held to the same circuit
+    /// breaker check as a new order.
+    fn replace_order(
+        &mut self,
+        order_id: u64,
+        new_price: f64,
+        new_quantity: f64,
+        keep_priority: bool,
+    ) -> HftResult<OrderAck> {
+        if !(new_price.is_finite() && new_price > 0.0) {
+            return Err(HftError::InvalidPrice(new_price));
+        }
+        if !(new_quantity.is_finite() && new_quantity > 0.0) {
+            return Err(HftError::InvalidQuantity(new_quantity));
+        }
+        if order_id < INTERNAL_ORDER_ID_BASE && self.circuit_open() {
+            let reason = self.breaker.reason().map(ToString::to_string).unwrap_or_default();
+            return Err(HftError::CircuitOpen(reason));
+        }
+        // Finished orders can't be amended
+        if let Some(state) = self.orders.get(order_id) {
+            state.clone().amend(new_quantity)?;
+        }
+
+        let now = self.clock.now_nanos();
+        let result = self
+            .matcher
+            .engine()
+            .replace(order_id, new_price, new_quantity, keep_priority, now)
+            .ok_or(HftError::OrderNotFound(order_id))?;
+        ORDERS_REPLACED.inc();
+        self.update_state(order_id, |state| state.amend(new_quantity));
+        info!(
+            "REPLACED [{}]: {} @ {} ({} priority)",
+            order_id,
+            new_quantity,
+            new_price,
+            if keep_priority { "keep" } else { "reset" }
+        );
+
+        let ack = OrderAck {
+            order_id,
+            filled_qty: result.filled_qty,
+            resting_qty: result.resting_qty,
+            cancelled_qty: result.cancelled_qty,
+        };
+        self.record_result(result);
+        Ok(ack)
+    }
+
+    /// Book the fills from a match into positions and the fill metrics,
+    /// and tell the clients involved
+    fn record_result(&mut self, result: MatchResult) {
+        // Both sides of each match, so a resting order's fills count
+        // against its own submitted quantity
+        self.filled_qty += result.fills.iter().map(|fill| fill.fill_qty).sum::<f64>();
+
+        for self_trade in &result.self_trades {
+            SELF_TRADES_PREVENTED.inc();
+            let (order_id, remaining) = (self_trade.resting_order_id, self_trade.resting_remaining);
+            info!("SELF-TRADE PREVENTED [{}]: {} left resting", order_id, remaining);
+            let cancelled_qty = self.orders.get(order_id).map_or(0.0, |state| state.remaining() - remaining);
+            if cancelled_qty > 0.0 {
+                self.audit(|| AuditEvent::Cancel {
+                    order_id,
+                    cancelled_qty,
+                    reason: "self-trade prevention".to_string(),
+                });
+                self.notify(&self_trade.client_id, Message::CancelAck { order_id, cancelled_qty });
+            }
+            if remaining > 0.0 {
+                self.update_state(order_id, |state| state.amend(remaining));
+            } else {
+                self.update_state(order_id, OrderState::cancel);
+            }
+        }
+
+        let mut touched = Vec::new();
+        for fill in &result.fills {
+            let client = self.accounts.apply_fill(fill);
+            if !touched.iter().any(|touched: &String| touched == client) {
+                touched.push(client.to_string());
+            }
+            if let Err(e) = self.orders.update(fill.order_id, |state| state.fill(fill.fill_qty, fill.fill_price)) {
+                // Orders that never came through `place_order` have no state
+                if !matches!(e, HftError::OrderNotFound(_)) {
+                    warn!("Order state not updated: {}", e);
+                }
+            }
+            info!(
+                "FILL [{}]: {} {} x {} @ {}",
+                fill.order_id, fill.side, fill.fill_qty, fill.symbol, fill.fill_price
+            );
+            self.audit(|| AuditEvent::Fill(fill.clone()));
+            self.notify(&fill.client_id, Message::Fill(fill.clone()));
+        }
+        FILLS.inc_by(result.fills.len() as u64);
+        if self.submitted_qty > 0.0 {
+            FILL_RATIO.set(self.filled_qty / self.submitted_qty);
+        }
+        if !result.fills.is_empty() {
+            FEES_PAID.set(self.accounts.fees());
+            let now = self.clock.now_nanos();
+            for client in touched {
+                self.record_performance(&client, now);
+            }
+            self.circuit_open();
+        }
+    }
+
+    fn snapshot(&self) -> GatewaySnapshot {
+        GatewaySnapshot {
+            order_id: self.order_id,
+            trading_day: self.trading_day,
+            positions: self.accounts.snapshot(),
+            open_orders: self.matcher.engine().open_orders(),
+            order_states: self.orders.live().cloned().collect(),
+            placed: self.placed.snapshot(),
+        }
+    }
+
+    /// Save state if the snapshot interval has passed, or regardless with `force`
+    fn save_snapshot(&mut self, force: bool) {
+        let Some(mut snapshotter) = self.snapshotter.take() else {
+            return;
+        };
+        let result = if force {
+            snapshotter.save(&self.snapshot())
+        } else {
+            snapshotter.save_if_due(|| self.snapshot()).map(|_| ())
+        };
+        if let Err(e) = result {
+            warn!("Failed to save snapshot to {}: {}", snapshotter.path().display(), e);
+        }
+        self.snapshotter = Some(snapshotter);
+    }
+
+    /// Resume from a snapshot instead of flat
+    fn restore(&mut self, snapshot: GatewaySnapshot) {
+        self.order_id = snapshot.order_id.max(self.order_id);
+        self.trading_day = snapshot.trading_day;
+        self.accounts.restore(snapshot.positions);
+        self.matcher.engine().restore(snapshot.open_orders);
+        for state in snapshot.order_states {
+            self.orders.restore(state);
+        }
+        for ack in snapshot.placed {
+            self.placed.insert(ack);
+        }
+    }
+
+    /// Let the breaker and the performance gauges see `client`'s realized
+    /// PnL; any one client's loss is enough to trip the breaker
+    fn record_performance(&mut self, client: &str, now_nanos: u128) {
+        let Some(account) = self.accounts.get_mut(client) else {
+            return;
+        };
+        let realized = account.positions.realized_pnl();
+        self.breaker.on_realized_pnl(realized, now_nanos);
+        let performance = &mut account.performance;
+        performance.record_equity(realized, now_nanos);
+        MAX_DRAWDOWN.with_label_values(&[client]).set(performance.max_drawdown());
+        DRAWDOWN_DURATION
+            .with_label_values(&[client])
+            .set(performance.drawdown_nanos() as f64 / 1e9);
+        ROLLING_SHARPE.with_label_values(&[client]).set(performance.sharpe());
+    }
+}
+
+// Accept strategy connections and forward decoded messages to the gateway loop
+fn serve(listener: TcpListener, tx: mpsc::Sender<Inbound>) -> Result<()> {
+    loop {
+        let (stream, addr) = listener.accept()?;
+        info!("Strategy connected from {}", addr);
+
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let input = TcpTransport::new(stream);
+            let result = input
+                .try_clone()
+                .map_err(HftError::from)
+                .and_then(|output| handle_connection(input, output, tx));
+            if let Err(e) = result {
+                warn!("Connection from {} closed: {}", addr, e);
+            }
+        });
+    }
+}
+
+/// Keep a subscription to the feed handler's tick stream at `addr`,
+/// reconnecting after `FEED_RETRY` until the gateway goes away
+fn follow_feed(addr: SocketAddr, tx: mpsc::Sender<Inbound>) {
+    while !tx.is_closed() {
+        match TcpTransport::connect(addr) {
+            Ok(input) => {
+                info!("Following the feed at {} for books", addr);
+                if let Err(e) = subscribe_feed(input, tx.clone()) {
+                    warn!("Lost the feed at {}: {}", addr, e);
+                }
+            }
+            Err(e) => warn!("Feed {} unreachable, retrying in {:?}: {}", addr, FEED_RETRY, e),
+        }
+        std::thread::sleep(FEED_RETRY);
+    }
+}
+
+/// Queue the ticks and book snapshots the feed handler publishes on `input`
+/// for the gateway loop, which keeps the books the pre-trade checks price
+/// against. Returns when the feed hangs up or the gateway goes away.
+pub fn subscribe_feed(mut input: impl Transport, tx: mpsc::Sender<Inbound>) -> HftResult<()> {
+    // Nothing the gateway does with market data is answered
+    let (reply, _) = std::sync::mpsc::channel();
+    while let Some(message) = input.recv_message()? {
+        let message = match message {
+            Message::EnrichedTick(enriched) => Message::Tick(enriched.tick),
+            book @ Message::OrderBookUpdate(_) => book,
+            _ => continue,
+        };
+        ORDER_QUEUE_DEPTH.inc();
+        let reply = reply.clone();
+        if tx.blocking_send(Inbound { message, reply }).is_err() {
+            ORDER_QUEUE_DEPTH.dec();
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Decode messages from one strategy connection and queue them for the
+/// gateway loop, until the strategy hangs up or the gateway goes away.
+/// Fills, cancels and rejects of its orders are written back to `output`.
+pub fn handle_connection(
+    input: impl Transport,
+    output: impl Transport + 'static,
+    tx: mpsc::Sender<Inbound>,
+) -> HftResult<()> {
+    let (reply, replies) = std::sync::mpsc::channel();
+    let writer = std::thread::spawn(move || write_replies(output, replies));
+    let result = read_messages(input, &reply, tx);
+    // The gateway holds on to `reply`, so the writer is told directly
+    let _ = reply.send(Message::Shutdown);
+    let _ = writer.join();
+    result
+}
+
+/// Send the gateway's replies down a connection until it closes
+fn write_replies(mut output: impl Transport, replies: std::sync::mpsc::Receiver<Message>) {
+    for message in replies {
+        if matches!(message, Message::Shutdown) {
+            return;
+        }
+        if let Err(e) = output.send_message(&message) {
+            warn!("Failed to reply to a strategy: {}", e);
+            return;
+        }
+    }
+}
+
+fn read_messages(
+    mut input: impl Transport,
+    reply: &std::sync::mpsc::Sender<Message>,
+    tx: mpsc::Sender<Inbound>,
+) -> HftResult<()> {
+    loop {
+        let payload = match input.recv() {
+            Ok(Some(payload)) => payload,
+            Ok(None) => return Ok(()),
+            // A bogus length can't be skipped safely, so the connection goes
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                DROPPED_FRAMES.inc();
+                return Err(frame_error(e));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        // The length prefix keeps framing intact, so a bad payload only costs one frame
+        match Message::deserialize(&payload) {
+            Ok(message) => {
+                ORDER_QUEUE_DEPTH.inc();
+                let reply = reply.clone();
+                if tx.blocking_send(Inbound { message, reply }).is_err() {
+                    ORDER_QUEUE_DEPTH.dec();
+                    return Ok(());
+                }
+            }
+            Err(e) => {
+                DROPPED_FRAMES.inc();
+                warn!("Dropping malformed frame ({} bytes): {}", payload.len(), e);
+            }
+        }
+    }
+}
+
+/// The framing error a transport wrapped in `e`, if any
+fn frame_error(e: std::io::Error) -> HftError {
+    let kind = e.kind();
+    match e.into_inner() {
+        Some(inner) => match inner.downcast::<HftError>() {
+            Ok(frame) => *frame,
+            Err(inner) => std::io::Error::new(kind, inner).into(),
+        },
+        None => std::io::Error::from(kind).into(),
+    }
+}
+
+fn cli() -> Command {
+    Command::new("order_gateway")
+        .about("Accepts framed orders over TCP and simulates their execution")
+        .arg(
+            Arg::new("listen")
+                .long("listen")
+                .value_name("ADDR")
+                .help("TCP address to accept strategy connections on")
+                .value_parser(parse_socket_addr)
+                .default_value("127.0.0.1:9004"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("PATH")
+                .help("Read gateway settings from the [gateway] and [circuit_breaker] sections of this file")
+                .value_parser(|s: &str| GatewayConfig::from_file(s).map_err(|e| e.to_string())),
+        )
+        .arg(cli::universe("Reject orders that break the lot_size or min_notional of their symbol in the [[symbols]] of a shared config file"))
+        .arg(
+            Arg::new("audit-log")
+                .long("audit-log")
+                .value_name("PATH")
+                .help("Append every placement, fill, cancel and reject to this JSONL file")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("snapshot")
+                .long("snapshot")
+                .value_name("PATH")
+                .help("Save positions and open orders here periodically, and resume from it on startup")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("snapshot-interval-ms")
+                .long("snapshot-interval-ms")
+                .value_name("MS")
+                .help("Time between snapshots")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::new("order-timeout-ms")
+                .long("order-timeout-ms")
+                .value_name("MS")
+                .help("Reject orders not placed within this many milliseconds, unless they carry their own max_processing_micros; overrides the config file")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("feed-addr")
+                .long("feed-addr")
+                .value_name("ADDR")
+                .help("Follow the feed handler's tick stream at its --publish address for the books the aggression check prices against")
+                .value_parser(parse_socket_addr),
+        )
+        .arg(
+            Arg::new("max-signal-age-micros")
+                .long("max-signal-age-micros")
+                .value_name("MICROS")
+                .help("Reject orders whose originating tick is older than this on arrival, overriding the config file")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("max-holding-secs")
+                .long("max-holding-secs")
+                .value_name("SECS")
+                .help("Flatten positions held longer than this, overriding the config file")
+                .value_parser(|s: &str| match s.parse::<f64>() {
+                    Ok(secs) if secs.is_finite() && secs > 0.0 => Ok(secs),
+                    _ => Err(format!("expected a positive number of seconds, got '{}'", s)),
+                }),
+        )
+        .arg(
+            Arg::new("holding-clock")
+                .long("holding-clock")
+                .value_name("CLOCK")
+                .help("Whether adding to a position restarts its holding time: keep_original (the default) or reset_on_add; overrides the config file")
+                .value_parser(|s: &str| s.parse::<HoldingClock>()),
+        )
+        .arg(
+            Arg::new("self-trade-prevention")
+                .long("self-trade-prevention")
+                .value_name("POLICY")
+                .help("When an order meets a resting order with its client_id: cancel_resting (the default), cancel_incoming or decrement_both; overrides the config file")
+                .value_parser(|s: &str| s.parse::<SelfTradePrevention>()),
+        )
+        .arg(cli::health_listen("0.0.0.0:9204"))
+        .arg(cli::log_format())
+}
+
+pub async fn run() -> Result<()> {
+    let args = cli::parse_args(cli());
+    hft_types::logging::init("order_gateway", *args.get_one("log-format").unwrap());
+
+    init_metrics();
+    let pusher = hft_types::pushgateway::MetricsPusher::from_env("order_gateway", &REGISTRY)?;
+    hft_types::heartbeat::spawn_emitter(
+        "order_gateway",
+        hft_types::heartbeat::DEFAULT_HEARTBEAT_ADDR,
+        Duration::from_secs(1),
+    )?;
+
+    let listen_addr: SocketAddr = *args.get_one("listen").unwrap();
+    let readiness = Readiness::new();
+    readiness.require("listener");
+    hft_types::health::spawn_server(*args.get_one("health-listen").unwrap(), readiness.clone(), &REGISTRY)?;
+    let listener = TcpListener::bind(listen_addr)?;
+    readiness.set("listener", true);
+
+    let (tx, rx) = mpsc::channel::<Inbound>(10_000);
+    if let Some(feed_addr) = args.get_one::<SocketAddr>("feed-addr").copied() {
+        let tx = tx.clone();
+        std::thread::spawn(move || follow_feed(feed_addr, tx));
+    }
+    std::thread::spawn(move || {
+        if let Err(e) = serve(listener, tx) {
+            warn!("Order listener failed: {}", e);
+        }
+    });
+
+    let mut config = args.get_one::<GatewayConfig>("config").cloned().unwrap_or_default();
+    if let Some(secs) = args.get_one::<f64>("max-holding-secs") {
+        config.max_holding_secs = Some(*secs);
+    }
+    if let Some(clock) = args.get_one::<HoldingClock>("holding-clock") {
+        config.holding_clock = *clock;
+    }
+    if let Some(policy) = args.get_one::<SelfTradePrevention>("self-trade-prevention") {
+        config.self_trade_prevention = *policy;
+    }
+    if let Some(ms) = args.get_one::<u64>("order-timeout-ms") {
+        config.order_timeout_micros = Some(ms * 1_000);
+    }
+    if let Some(max_micros) = args.get_one::<u64>("max-signal-age-micros") {
+        config.max_signal_age_micros = Some(*max_micros);
+    }
+    let mut gateway = OrderGateway::new(config);
+    if let Some(universe) = args.get_one::<SymbolUniverse>("universe") {
+        gateway = gateway.with_universe(universe.clone());
+    }
+    if let Some(path) = args.get_one::<PathBuf>("audit-log") {
+        info!("Auditing order activity to {}", path.display());
+        gateway = gateway.with_audit_log(AuditLog::open(path, "order_gateway")?);
+    }
+    if let Some(path) = args.get_one::<PathBuf>("snapshot") {
+        let snapshotter = Snapshotter::new(
+            path,
+            Duration::from_millis(*args.get_one::<u64>("snapshot-interval-ms").unwrap()),
+        );
+        if let Some(snapshot) = snapshotter.load::<GatewaySnapshot>()? {
+            info!(
+                "Resuming from {}: {} positions, {} open orders",
+                snapshotter.path().display(),
+                snapshot.positions.values().map(Vec::len).sum::<usize>(),
+                snapshot.open_orders.len()
+            );
+            gateway.restore(snapshot);
+        }
+        gateway = gateway.with_snapshotter(snapshotter);
+    }
+
+    info!("Order Gateway listening on {} - waiting for orders...", listen_addr);
+    tokio::select! {
+        () = gateway.run(rx) => {}
+        result = hft_types::shutdown::signal() => result?,
+    }
+
+    gateway.shutdown();
+    if let Some(pusher) = pusher {
+        pusher.finish();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::clock::MockClock;
+    use hft_types::lifecycle::OrderStatus;
+    use hft_types::messaging::{read_frame, write_message};
+    use hft_types::transport::ChannelTransport;
+    use hft_types::risk::PriceBasis;
+    use hft_types::{BookLevel, OrderBook, OrderSide};
+    use std::io::Write;
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        for args in [
+            &["--listen", "127.0.0.1:70000"][..],
+            &["--listen", "localhost"],
+            &["--no-such-flag"],
+        ] {
+            let result = cli().try_get_matches_from(std::iter::once(&"order_gateway").chain(args));
+            assert!(result.is_err(), "{:?} should fail", args);
+        }
+    }
+
+    #[test]
+    fn test_aggression_basis_classification() {
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel { price: 44990.0, quantity: 9.0 });
+        book.asks.push(BookLevel { price: 45010.0, quantity: 1.0 });
+
+        let mut bbo = OrderGateway::new(GatewayConfig {
+            aggression: AggressionCheck::new(PriceBasis::Bbo, 3.0),
+            ..GatewayConfig::default()
+        });
+        let mut fair = OrderGateway::new(GatewayConfig {
+            aggression: AggressionCheck::new(PriceBasis::FairValue { depth: 5 }, 3.0),
+            ..GatewayConfig::default()
+        });
+        bbo.books.apply_snapshot(book.clone());
+        fair.books.apply_snapshot(book);
+
+        // Mid is 45000 but bid pressure puts fair value at 45008:
+        // this buy is ~3.3bps through mid and only ~1.6bps through fair value
+        let order = Order::new(1, "BTC/USD".to_string(), OrderSide::Buy, 45015.0, 1.0, 1);
+
+        assert!(bbo.is_aggressive(&order));
+        assert!(!fair.is_aggressive(&order));
+
+        // No reference book, no flag
+        let order = Order::new(2, "ETH/USD".to_string(), OrderSide::Buy, 1e9, 1.0, 1);
+        assert!(!bbo.is_aggressive(&order));
+    }
+
+    #[test]
+    fn test_feed_books_reach_the_aggression_check() {
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            aggression: AggressionCheck::new(PriceBasis::Bbo, 3.0),
+            ..GatewayConfig::default()
+        });
+        let mut book = OrderBook::new("BTC/USD".to_string(), 1);
+        book.bids.push(BookLevel { price: 44990.0, quantity: 1.0 });
+        book.asks.push(BookLevel { price: 45010.0, quantity: 1.0 });
+
+        let (mut feed, input) = ChannelTransport::pair();
+        feed.send_message(&Message::OrderBookUpdate(book)).unwrap();
+        feed.send_message(&Message::Heartbeat { sender: "feed_handler".to_string(), timestamp: 1 }).unwrap();
+        drop(feed);
+        let (tx, mut rx) = mpsc::channel(10);
+        subscribe_feed(input, tx).unwrap();
+        while let Ok(inbound) = rx.try_recv() {
+            gateway.on_inbound(inbound);
+        }
+
+        let order = Order::new(1, "BTC/USD".to_string(), OrderSide::Buy, 45015.0, 1.0, 1);
+        assert!(gateway.is_aggressive(&order));
+    }
+
+    #[test]
+    fn test_gateway_section_is_read_from_config_file() {
+        let path = "/tmp/hft_test_gateway_config.toml";
+        std::fs::write(
+            path,
+            "[gateway]\naggression = { basis = { fair_value = { depth = 3 } }, max_through_bps = 2.0 }\n\n\
+             [gateway.fees]\ntaker_bps = 4.0\n\n\
+             [circuit_breaker]\nmax_realized_loss = 50.0\n",
+        )
+        .unwrap();
+
+        let config = GatewayConfig::from_file(path).unwrap();
+        assert_eq!(config.aggression.basis, PriceBasis::FairValue { depth: 3 });
+        assert_eq!(config.aggression.max_through_bps, 2.0);
+        assert_eq!(config.fees.default.taker_bps, 4.0);
+        assert_eq!(config.circuit_breaker.max_realized_loss, Some(50.0));
+        // Sections left out keep their defaults
+        assert_eq!(config.self_trade_prevention, SelfTradePrevention::CancelResting);
+
+        // The shipped config.toml loads as-is
+        let shipped = GatewayConfig::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/../config.toml")).unwrap();
+        assert_eq!(shipped.aggression.basis, PriceBasis::Bbo);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_strategy_order_is_placed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel::<Inbound>(16);
+        std::thread::spawn(move || serve(listener, tx));
+
+        let dropped_before = DROPPED_FRAMES.get();
+        let placed_before = ORDERS_PLACED.get();
+
+        // Send exactly what strategy_engine writes, with a garbage frame in between
+        tokio::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            stream.write_all(&5u32.to_be_bytes()).unwrap();
+            stream.write_all(b"nope!").unwrap();
+
+            let order = Order::new(42, "ETH/USD".to_string(), OrderSide::Sell, 2650.0, 1.0, 1);
+            write_message(&mut stream, &Message::Order(order)).unwrap();
+        })
+        .await
+        .unwrap();
+
+        let message = rx.recv().await.unwrap().message;
+        assert!(matches!(&message, Message::Order(o) if o.order_id == 42));
+        assert!(DROPPED_FRAMES.get() > dropped_before);
+
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        gateway.handle_message(message);
+        assert!(ORDERS_PLACED.get() > placed_before);
+    }
+
+    #[tokio::test]
+    async fn test_fills_and_rejects_go_back_down_the_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel::<Inbound>(16);
+        std::thread::spawn(move || serve(listener, tx));
+
+        let strategy = tokio::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            let eth = |id, side, quantity, client| {
+                Order::new(id, "ETH/USD".to_string(), side, 2650.0, quantity, 1).with_client_id(client)
+            };
+            let orders = [
+                eth(1, OrderSide::Sell, 1.0, "maker"),
+                eth(2, OrderSide::Buy, 0.4, "taker"),
+                eth(3, OrderSide::Buy, -1.0, "taker"),
+            ];
+            for order in orders {
+                write_message(&mut stream, &Message::Order(order)).unwrap();
+            }
+            (0..3)
+                .map(|_| read_frame(&mut stream).unwrap().parse_message().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        for _ in 0..3 {
+            gateway.on_inbound(rx.recv().await.unwrap());
+        }
+        let replies = strategy.await.unwrap();
+
+        // Both sides of the trade hear about it, and so does the rejected order
+        let mut filled: Vec<(u64, f64)> = replies
+            .iter()
+            .filter_map(|reply| match reply {
+                Message::Fill(fill) => Some((fill.order_id, fill.fill_qty)),
+                _ => None,
+            })
+            .collect();
+        filled.sort_by_key(|fill| fill.0);
+        assert_eq!(filled, vec![(1, 0.4), (2, 0.4)]);
+        assert!(replies
+            .iter()
+            .any(|reply| matches!(reply, Message::OrderReject { order_id: 3, .. })));
+    }
+
+    #[test]
+    fn test_cancels_are_answered_on_the_requesting_connection() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let (reply, replies) = std::sync::mpsc::channel();
+        let inbound = |message| Inbound { message, reply: reply.clone() };
+
+        let order = Order::new(1, "ETH/USD".to_string(), OrderSide::Sell, 2650.0, 1.0, 1);
+        gateway.on_inbound(inbound(Message::Order(order)));
+        gateway.on_inbound(inbound(Message::CancelOrder { order_id: 1 }));
+        gateway.on_inbound(inbound(Message::CancelOrder { order_id: 404 }));
+
+        let answers: Vec<Message> = replies.try_iter().collect();
+        assert!(matches!(
+            answers.as_slice(),
+            [
+                Message::CancelAck { order_id: 1, cancelled_qty },
+                Message::CancelReject { order_id: 404, reason: CancelRejectReason::UnknownOrder },
+            ] if *cancelled_qty == 1.0
+        ));
+    }
+
+    #[test]
+    fn test_throttled_order_is_rejected() {
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            rate_limit: RateLimitConfig {
+                max_orders_per_sec: 1.0,
+                burst: 1.0,
+                ..RateLimitConfig::default()
+            },
+            ..GatewayConfig::default()
+        });
+        let clock = MockClock::new(1_000_000_000);
+        gateway.clock = clock.shared();
+        let throttled_before = ORDERS_THROTTLED.get();
+
+        let order = |id| Order::new(id, "SOL/USD".to_string(), OrderSide::Buy, 100.0, 1.0, 1);
+        assert!(gateway.place_order(order(1)).is_ok());
+        assert!(matches!(
+            gateway.place_order(order(2)),
+            Err(hft_types::HftError::RateLimited(_))
+        ));
+        assert!(ORDERS_THROTTLED.get() > throttled_before);
+
+        // The bucket refills on the gateway's clock
+        clock.advance(Duration::from_millis(500));
+        assert!(gateway.place_order(order(3)).is_err());
+        clock.advance(Duration::from_millis(600));
+        assert!(gateway.place_order(order(4)).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_is_read_from_config_file() {
+        let path = "/tmp/hft_test_gateway_rate_limit.toml";
+        std::fs::write(path, "[gateway]\nrate_limit = { max_orders_per_sec = 1.0, burst = 2.0 }\n").unwrap();
+        let config = GatewayConfig::from_file(path).unwrap();
+        // Limits left out keep their defaults
+        assert_eq!(config.rate_limit.global_burst, RateLimitConfig::default().global_burst);
+
+        let mut gateway = OrderGateway::new(config);
+        gateway.clock = MockClock::new(1_000_000_000).shared();
+        let order = |id| Order::new(id, "SOL/USD".to_string(), OrderSide::Buy, 100.0, 1.0, 1);
+        assert!(gateway.place_order(order(1)).is_ok());
+        assert!(gateway.place_order(order(2)).is_ok());
+        assert!(matches!(gateway.place_order(order(3)), Err(HftError::RateLimited(_))));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_holding_limit_from_config_file_flattens() {
+        let path = "/tmp/hft_test_gateway_holding.toml";
+        std::fs::write(
+            path,
+            "[gateway]\nmax_holding_secs = 60.0\nholding_clock = \"reset_on_add\"\n\
+             rate_limit = { max_orders_per_sec = 1.0, burst = 1.0 }\n",
+        )
+        .unwrap();
+        let config = GatewayConfig::from_file(path).unwrap();
+        assert_eq!(config.holding_clock, HoldingClock::ResetOnAdd);
+        let mut gateway = OrderGateway::new(config);
+        let clock = MockClock::new(1_000_000_000);
+        gateway.clock = clock.shared();
+
+        // A long position opened at one second
+        gateway.record_result(MatchResult {
+            fills: vec![hft_types::matching::Fill {
+                order_id: 1,
+                symbol: "SOL/USD".to_string(),
+                side: OrderSide::Buy,
+                fill_price: 100.0,
+                fill_qty: 1.0,
+                timestamp_nanos: 1_000_000_000,
+                is_maker: false,
+                client_id: Some("mm".to_string()),
+            }],
+            ..MatchResult::default()
+        });
+        let tick = |secs: u128| MarketTick::new("SOL/USD".to_string(), 100.0, 1, secs * 1_000_000_000);
+        gateway.on_tick(&tick(30));
+        assert_eq!(gateway.order_id, INTERNAL_ORDER_ID_BASE);
+
+        // A rate-limited flatten is tried again on the next tick
+        gateway.place_order(resting_bid(2, 90.0)).unwrap();
+        gateway.on_tick(&tick(61));
+        assert_eq!(gateway.order_id, INTERNAL_ORDER_ID_BASE + 1);
+        assert_eq!(gateway.matcher.engine().best_prices("SOL/USD"), (Some(90.0), None));
+
+        // Past the limit the gateway sends its own sell to close it, for the
+        // client holding the position
+        clock.advance(Duration::from_secs(1));
+        gateway.on_tick(&tick(62));
+        assert_eq!(gateway.order_id, INTERNAL_ORDER_ID_BASE + 2);
+        assert_eq!(gateway.matcher.engine().best_prices("SOL/USD"), (Some(90.0), Some(100.0)));
+        let flatten = gateway.matcher.engine().open_orders().into_iter().find(|order| order.side == OrderSide::Sell).unwrap();
+        assert_eq!(flatten.client_id.as_deref(), Some("mm"));
+
+        std::fs::write(path, "[gateway]\nmax_holding_secs = -1.0\n").unwrap();
+        assert!(GatewayConfig::from_file(path).is_err());
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_circuit_breaker_blocks_strategy_orders() {
+        use hft_types::matching::{Fill, MatchResult};
+
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            circuit_breaker: CircuitBreakerConfig {
+                max_realized_loss: Some(5.0),
+                ..CircuitBreakerConfig::default()
+            },
+            ..GatewayConfig::default()
+        });
+        let fill = |side, price| Fill {
+            order_id: 1,
+            symbol: "SOL/USD".to_string(),
+            side,
+            fill_price: price,
+            fill_qty: 1.0,
+            timestamp_nanos: 1,
+            is_maker: false,
+            client_id: None,
+        };
+        // Bought at 100, sold at 90
+        gateway.record_result(MatchResult {
+            fills: vec![fill(OrderSide::Buy, 100.0), fill(OrderSide::Sell, 90.0)],
+            ..MatchResult::default()
+        });
+        assert!(gateway.breaker.reason().is_some());
+
+        let order = |id| Order::new(id, "SOL/USD".to_string(), OrderSide::Buy, 100.0, 1.0, 1);
+        match gateway.place_order(order(10)) {
+            Err(HftError::CircuitOpen(reason)) => assert!(reason.contains("realized loss"), "{}", reason),
+            other => panic!("expected the breaker to reject, got {:?}", other),
+        }
+        // Flatten orders still go out
+        assert!(gateway.place_order(order(INTERNAL_ORDER_ID_BASE + 1)).is_ok());
+
+        gateway.handle_message(Message::ResetCircuitBreaker);
+        assert_eq!(gateway.breaker.reason(), None);
+        assert!(gateway.place_order(order(10)).is_ok());
+    }
+
+    /// Non-marketable buy limit from a strategy
+    fn resting_bid(id: u64, price: f64) -> Order {
+        Order::new(id, "SOL/USD".to_string(), OrderSide::Buy, price, 1.0, 1)
+    }
+
+    #[test]
+    fn test_cancel_resting_order() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let cancelled_before = ORDERS_CANCELLED.get();
+        gateway.place_order(resting_bid(1, 100.0)).unwrap();
+
+        match gateway.cancel_order(1) {
+            Message::CancelAck { order_id, cancelled_qty } => {
+                assert_eq!((order_id, cancelled_qty), (1, 1.0));
+            }
+            other => panic!("expected a cancel ack, got {:?}", other),
+        }
+        assert_eq!(gateway.matcher.engine().resting_orders("SOL/USD"), 0);
+        assert_eq!(ORDERS_CANCELLED.get() - cancelled_before, 1);
+
+        // Gone now, but the gateway knows it placed it
+        assert!(matches!(
+            gateway.cancel_order(1),
+            Message::CancelReject { reason: CancelRejectReason::NotOpen, .. }
+        ));
+    }
+
+    #[test]
+    fn test_order_status_follows_fills() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let mut bid = resting_bid(1, 100.0);
+        bid.quantity = 2.0;
+        gateway.place_order(bid).unwrap();
+        let status = |gateway: &OrderGateway, id| gateway.orders.get(id).unwrap().status;
+        assert_eq!(status(&gateway, 1), OrderStatus::New);
+
+        let sell = |id| Order::new(id, "SOL/USD".to_string(), OrderSide::Sell, 100.0, 1.0, 1);
+        gateway.place_order(sell(2)).unwrap();
+        assert_eq!(status(&gateway, 1), OrderStatus::PartiallyFilled);
+        assert_eq!(status(&gateway, 2), OrderStatus::Filled);
+
+        gateway.place_order(sell(3)).unwrap();
+        let state = gateway.orders.get(1).unwrap();
+        assert_eq!((state.status, state.filled_qty, state.avg_fill_price), (OrderStatus::Filled, 2.0, 100.0));
+
+        // Too late to cancel or amend
+        assert!(matches!(
+            gateway.cancel_order(1),
+            Message::CancelReject { reason: CancelRejectReason::NotOpen, .. }
+        ));
+        assert!(matches!(
+            gateway.replace_order(1, 101.0, 1.0, false),
+            Err(HftError::IllegalTransition { from: OrderStatus::Filled, .. })
+        ));
+        assert_eq!(status(&gateway, 1), OrderStatus::Filled);
+
+        // An unfilled IOC remainder is cancelled; a bad order is rejected
+        let mut ioc = sell(4);
+        ioc.time_in_force = hft_types::TimeInForce::Ioc;
+        gateway.place_order(ioc).unwrap();
+        assert_eq!(status(&gateway, 4), OrderStatus::Cancelled);
+        assert!(gateway.place_order(Order::new(5, "SOL/USD".to_string(), OrderSide::Buy, -1.0, 1.0, 1)).is_err());
+        assert_eq!(status(&gateway, 5), OrderStatus::Rejected);
+    }
+
+    #[test]
+    fn test_maker_and_taker_fills_pay_their_own_rates() {
+        use hft_types::fees::FeeRates;
+
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            fees: FeeSchedule::new(FeeRates {
+                maker_bps: 1.0,
+                taker_bps: 10.0,
+                per_order: 0.5,
+            }),
+            ..GatewayConfig::default()
+        });
+        gateway.place_order(resting_bid(1, 100.0).with_client_id("mm")).unwrap();
+        gateway.place_order(resting_bid(2, 100.0).with_client_id("mm")).unwrap();
+        let sell = Order::new(3, "SOL/USD".to_string(), OrderSide::Sell, 100.0, 2.0, 1).with_client_id("taker");
+        gateway.place_order(sell).unwrap();
+
+        // 200 notional a side. The maker pays 1bp plus the flat fee on each
+        // of its two orders; the taker pays 10bp and one flat fee for its
+        // single order, though it filled twice
+        let mut account = |client| gateway.accounts.get_mut(client).unwrap().positions.clone();
+        assert!((account("mm").fees() - 1.02).abs() < 1e-9);
+        assert!((account("taker").fees() - 0.7).abs() < 1e-9);
+
+
+        // Each side keeps its own position; the fees are all the PnL so far
+        assert_eq!(account("mm").position("SOL/USD").unwrap().quantity, 2.0);
+        assert_eq!(account("taker").position("SOL/USD").unwrap().quantity, -2.0);
+        assert!((account("taker").realized_pnl() + 0.7).abs() < 1e-9);
+        assert!((gateway.accounts.fees() - 1.72).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_restart_resumes_from_snapshot() {
+        use hft_types::TimeInForce;
+
+        let path = "/tmp/hft_test_gateway_snapshot.json";
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        gateway.place_order(resting_bid(1, 100.0)).unwrap();
+        gateway.place_order(resting_bid(2, 99.0)).unwrap();
+        let sell = |id, price, qty| Order::new(id, "SOL/USD".to_string(), OrderSide::Sell, price, qty, 1);
+        // Leaves a resting ask behind after taking the 100 bid
+        gateway.place_order(sell(3, 100.0, 3.0)).unwrap();
+        let stop = sell(4, 0.0, 1.0)
+            .with_order_type(OrderType::Stop { trigger: 90.0 })
+            .with_time_in_force(TimeInForce::Gtc);
+        gateway.place_order(stop).unwrap();
+
+        let mut snapshotter = Snapshotter::new(path, Duration::ZERO);
+        assert!(snapshotter.save_if_due(|| gateway.snapshot()).unwrap());
+
+        // A fresh process loading the snapshot
+        let mut restarted = OrderGateway::new(GatewayConfig::default());
+        restarted.restore(snapshotter.load().unwrap().unwrap());
+        assert_eq!(restarted.accounts.snapshot(), gateway.accounts.snapshot());
+        assert_eq!(restarted.matcher.engine().open_orders().len(), 3);
+        assert_eq!(restarted.matcher.engine().resting_orders("SOL/USD"), gateway.matcher.engine().resting_orders("SOL/USD"));
+        assert_eq!(restarted.matcher.engine().pending_stops("SOL/USD"), 1);
+        assert_eq!(restarted.matcher.engine().best_prices("SOL/USD"), (Some(99.0), Some(100.0)));
+        assert_eq!(restarted.orders.live().count(), gateway.orders.live().count());
+
+        // A retransmission of an order placed before the restart gets its
+        // original ack rather than being placed again
+        let ack = restarted.place_order(resting_bid(2, 99.0)).unwrap();
+        assert_eq!((ack.order_id, ack.resting_qty), (2, 1.0));
+        assert_eq!(restarted.matcher.engine().open_orders().len(), 3);
+
+        // The restored orders are live: cancelable and fillable
+        assert!(matches!(restarted.cancel_order(2), Message::CancelAck { order_id: 2, .. }));
+        let buy = Order::new(5, "SOL/USD".to_string(), OrderSide::Buy, 100.0, 2.0, 1);
+        assert_eq!(restarted.place_order(buy).unwrap().filled_qty, 2.0);
+        assert_eq!(restarted.orders.get(3).unwrap().status, hft_types::lifecycle::OrderStatus::Filled);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_cancel_unknown_order_is_rejected() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let cancelled_before = ORDERS_CANCELLED.get();
+        match gateway.cancel_order(404) {
+            Message::CancelReject { order_id, reason } => {
+                assert_eq!((order_id, reason), (404, CancelRejectReason::UnknownOrder));
+            }
+            other => panic!("expected a cancel reject, got {:?}", other),
+        }
+        assert_eq!(ORDERS_CANCELLED.get(), cancelled_before);
+        assert!(matches!(
+            gateway.replace_order(404, 100.0, 1.0, true),
+            Err(HftError::OrderNotFound(404))
+        ));
+    }
+
+    #[test]
+    fn test_price_worsening_replace_loses_priority() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let replaced_before = ORDERS_REPLACED.get();
+        gateway.place_order(resting_bid(1, 100.0)).unwrap();
+        gateway.place_order(resting_bid(2, 99.0)).unwrap();
+
+        // Down to 99 behind order 2, even though priority was asked for
+        gateway.handle_message(Message::ReplaceOrder {
+            order_id: 1,
+            new_price: 99.0,
+            new_quantity: 1.0,
+            keep_priority: true,
+        });
+        assert_eq!(ORDERS_REPLACED.get() - replaced_before, 1);
+        assert_eq!(gateway.matcher.engine().best_prices("SOL/USD"), (Some(99.0), None));
+
+        let sell = Order::new(3, "SOL/USD".to_string(), OrderSide::Sell, 99.0, 1.0, 1);
+        let result = gateway.matcher.engine().submit(sell, 2);
+        let makers: Vec<u64> = result.fills.iter().map(|f| f.order_id).filter(|&id| id != 3).collect();
+        assert_eq!(makers, vec![2]);
+    }
+
+    #[test]
+    fn test_self_trade_prevented_through_gateway() {
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            self_trade_prevention: SelfTradePrevention::DecrementBoth,
+            ..GatewayConfig::default()
+        });
+        let prevented_before = SELF_TRADES_PREVENTED.get();
+        gateway.place_order(resting_bid(1, 100.0).with_client_id("mm")).unwrap();
+
+        let sell = Order::new(2, "SOL/USD".to_string(), OrderSide::Sell, 100.0, 0.25, 1).with_client_id("mm");
+        let ack = gateway.place_order(sell).unwrap();
+        assert_eq!((ack.filled_qty, ack.cancelled_qty), (0.0, 0.25));
+        assert_eq!(SELF_TRADES_PREVENTED.get() - prevented_before, 1);
+        assert_eq!(gateway.orders.get(2).unwrap().status, OrderStatus::Cancelled);
+        let resting = gateway.orders.get(1).unwrap();
+        assert_eq!((resting.status, resting.quantity), (OrderStatus::New, 0.75));
+        assert!(gateway.accounts.get_mut("mm").is_none());
+    }
+
+    #[test]
+    fn test_slow_matching_runs_past_deadline_and_rejects() {
+        fn hung_submit(engine: &mut MatchingEngine, order: Order, now: u128) -> MatchResult {
+            std::thread::sleep(Duration::from_millis(250));
+            engine.submit(order, now)
+        }
+
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            order_timeout_micros: Some(5_000),
+            ..GatewayConfig::default()
+        });
+        gateway.match_step = hung_submit;
+
+        // Rejected at the deadline, not once the match finally returns
+        let started = std::time::Instant::now();
+        let result = gateway.place_order(resting_bid(1, 100.0));
+        assert!(started.elapsed() < Duration::from_millis(150));
+        assert!(matches!(result, Err(HftError::Timeout(_))), "{:?}", result);
+        // A retry while it's still running isn't matched a second time
+        assert!(matches!(gateway.place_order(resting_bid(1, 100.0)), Err(HftError::Timeout(_))));
+
+        gateway.settle_late_matches(true);
+        assert_eq!(gateway.orders.get(1).unwrap().status, OrderStatus::Rejected);
+        // Not left resting behind the caller's back
+        assert_eq!(gateway.matcher.engine().resting_orders("SOL/USD"), 0);
+
+        // The order's own deadline overrides the gateway's
+        let patient = resting_bid(2, 100.0).with_max_processing(Duration::from_secs(5));
+        assert_eq!(gateway.place_order(patient).unwrap().resting_qty, 1.0);
+    }
+
+    #[test]
+    fn test_partly_filled_timeout_is_not_placed_again() {
+        fn slow_submit(engine: &mut MatchingEngine, order: Order, now: u128) -> MatchResult {
+            std::thread::sleep(Duration::from_millis(20));
+            engine.submit(order, now)
+        }
+
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        gateway
+            .place_order(Order::new(1, "SOL/USD".to_string(), OrderSide::Sell, 100.0, 1.0, 1))
+            .unwrap();
+        gateway.order_timeout_micros = Some(5_000);
+        gateway.match_step = slow_submit;
+
+        // Takes the one offered, then runs out of time with one left to rest
+        let buy = Order::new(2, "SOL/USD".to_string(), OrderSide::Buy, 100.0, 2.0, 1);
+        assert!(matches!(gateway.place_order(buy.clone()), Err(HftError::Timeout(_))));
+        gateway.settle_late_matches(true);
+        assert_eq!(gateway.matcher.engine().resting_orders("SOL/USD"), 0);
+
+        // The retry is answered with what happened, not traded again
+        gateway.order_timeout_micros = None;
+        gateway.match_step = MatchingEngine::submit;
+        gateway
+            .place_order(Order::new(3, "SOL/USD".to_string(), OrderSide::Sell, 100.0, 1.0, 1))
+            .unwrap();
+        let ack = gateway.place_order(buy).unwrap();
+        assert_eq!(
+            (ack.filled_qty, ack.resting_qty, ack.cancelled_qty),
+            (1.0, 0.0, 1.0)
+        );
+        // One each for the retried buy and the sell it took
+        assert_eq!(gateway.filled_qty, 2.0);
+        assert_eq!(gateway.matcher.engine().resting_orders("SOL/USD"), 1);
+    }
+
+    #[test]
+    fn test_orders_from_stale_ticks_are_rejected() {
+        let mut gateway = OrderGateway::new(GatewayConfig {
+            max_signal_age_micros: Some(1_000),
+            ..GatewayConfig::default()
+        });
+        let stale_before = ORDERS_REJECTED_STALE.get();
+        let order = |id, tick_age_nanos| {
+            let now = monotonic_nanos();
+            Order::new(id, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 1.0, now)
+                .with_latency_trace(LatencyTrace::new(now - tick_age_nanos))
+        };
+
+        let result = gateway.place_order(order(41, 50_000_000));
+        assert!(
+            matches!(result, Err(HftError::StaleSignal { max_micros: 1_000, age_micros }) if age_micros >= 50_000),
+            "{:?}",
+            result
+        );
+        assert_eq!(ORDERS_REJECTED_STALE.get() - stale_before, 1);
+        assert_eq!(gateway.orders.get(41).unwrap().status, OrderStatus::Rejected);
+
+        // A fresh tick passes, and so does an order with no trace to judge
+        gateway.place_order(order(42, 100_000)).unwrap();
+        gateway
+            .place_order(Order::new(43, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 1.0, 1))
+            .unwrap();
+        assert_eq!(ORDERS_REJECTED_STALE.get() - stale_before, 1);
+    }
+
+    #[test]
+    fn test_max_signal_age_is_read_from_config_file() {
+        let path = "/tmp/hft_test_gateway_signal_age.toml";
+        std::fs::write(path, "[gateway]\nmax_signal_age_micros = 1000\n").unwrap();
+        let config = GatewayConfig::from_file(path).unwrap();
+        assert_eq!(config.max_signal_age_micros, Some(1_000));
+
+        let mut gateway = OrderGateway::new(config);
+        let now = monotonic_nanos();
+        let stale = Order::new(51, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 1.0, now)
+            .with_latency_trace(LatencyTrace::new(now - 50_000_000));
+        assert!(matches!(
+            gateway.place_order(stale),
+            Err(HftError::StaleSignal { max_micros: 1_000, .. })
+        ));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_odd_lots_and_dust_orders_are_rejected() {
+        let universe = SymbolUniverse::new(vec![hft_types::SymbolConfig {
+            symbol: "DOGE/USD".to_string(),
+            base_price: 0.1,
+            thresholds: None,
+            tick_size: None,
+            lot_size: Some(10.0),
+            min_notional: Some(5.0),
+            activity_weight: None,
+        }])
+        .unwrap();
+        let mut gateway = OrderGateway::new(GatewayConfig::default()).with_universe(universe);
+        let rejected_before = ORDERS_REJECTED_SIZE.get();
+        let order = |id, symbol: &str, qty| Order::new(id, symbol.to_string(), OrderSide::Buy, 0.1, qty, 1);
+
+        assert!(matches!(
+            gateway.place_order(order(1, "DOGE/USD", 55.0)),
+            Err(HftError::OddLot { lot_size, .. }) if lot_size == 10.0
+        ));
+        assert!(matches!(
+            gateway.place_order(order(2, "DOGE/USD", 40.0)),
+            Err(HftError::BelowMinNotional { min_notional, .. }) if min_notional == 5.0
+        ));
+        assert_eq!(ORDERS_REJECTED_SIZE.get() - rejected_before, 2);
+        assert_eq!(gateway.orders.get(2).unwrap().status, OrderStatus::Rejected);
+
+        gateway.place_order(order(3, "DOGE/USD", 50.0)).unwrap();
+        // Symbols outside the universe aren't held to anyone's rules
+        gateway.place_order(order(4, "PEPE/USD", 1.5)).unwrap();
+        // Nor is a flatten, which has to close whatever is held
+        gateway.place_order(order(INTERNAL_ORDER_ID_BASE + 1, "DOGE/USD", 3.0)).unwrap();
+        assert_eq!(ORDERS_REJECTED_SIZE.get() - rejected_before, 2);
+    }
+
+    #[test]
+    fn test_order_types_through_gateway() {
+        use hft_types::TimeInForce;
+
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let order = |id, side, price, qty| Order::new(id, "AVAX/USD".to_string(), side, price, qty, 1);
+        let tick = |price, ts| MarketTick::new("AVAX/USD".to_string(), price, 1, ts);
+
+        // A limit without a usable price is rejected before matching
+        assert!(matches!(
+            gateway.place_order(order(1, OrderSide::Buy, 0.0, 1.0)),
+            Err(hft_types::HftError::InvalidPrice(_))
+        ));
+
+        let day = NANOS_PER_DAY * 20_000;
+        gateway.on_tick(&tick(25.0, day));
+        gateway.place_order(order(2, OrderSide::Sell, 25.5, 1.0)).unwrap();
+        gateway
+            .place_order(order(3, OrderSide::Buy, 24.0, 1.0).with_time_in_force(TimeInForce::Day))
+            .unwrap();
+
+        // The stop sits out until a tick trades through 25.4, then lifts the ask
+        let stop = order(4, OrderSide::Buy, 0.0, 1.0).with_order_type(OrderType::Stop { trigger: 25.4 });
+        gateway.place_order(stop).unwrap();
+        assert_eq!(gateway.matcher.engine().pending_stops("AVAX/USD"), 1);
+        gateway.on_tick(&tick(25.4, day + 1));
+        assert_eq!(gateway.matcher.engine().pending_stops("AVAX/USD"), 0);
+        assert_eq!(gateway.matcher.engine().best_prices("AVAX/USD"), (Some(24.0), None));
+
+        // First tick of the next day expires the Day bid
+        gateway.on_tick(&tick(25.0, day + NANOS_PER_DAY));
+        assert_eq!(gateway.matcher.engine().resting_orders("AVAX/USD"), 0);
+    }
+
+    #[test]
+    fn test_duplicate_order_id_places_once() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+
+        gateway
+            .place_order(Order::new(7, "BTC/USD".to_string(), OrderSide::Sell, 45000.0, 1.0, 1))
+            .unwrap();
+        let buy = Order::new(8, "BTC/USD".to_string(), OrderSide::Buy, 45000.0, 2.0, 1);
+        let first = gateway.place_order(buy.clone()).unwrap();
+        assert_eq!(first.order_id, 8);
+        assert_eq!(first.filled_qty, 1.0);
+        assert_eq!(first.resting_qty, 1.0);
+
+        // The retry gets the original ack and nothing new rests or fills
+        let retry = gateway.place_order(buy).unwrap();
+        assert_eq!(retry, first);
+        assert_eq!(gateway.matcher.engine().resting_orders("BTC/USD"), 1);
+        assert_eq!(gateway.filled_qty, 2.0);
+    }
+
+    #[test]
+    fn test_fill_ratio_counts_both_sides_of_a_match() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+
+        // Both orders trade in full, the resting sell as much as the buy
+        gateway
+            .place_order(Order::new(1, "BTC/USD".to_string(), OrderSide::Sell, 45000.0, 1.0, 1))
+            .unwrap();
+        gateway
+            .place_order(Order::new(2, "BTC/USD".to_string(), OrderSide::Buy, 45000.0, 1.0, 1))
+            .unwrap();
+        assert_eq!(gateway.filled_qty / gateway.submitted_qty, 1.0);
+    }
+
+    #[test]
+    fn test_stage_latencies_are_observed_per_component() {
+        let mut gateway = OrderGateway::new(GatewayConfig::default());
+        let ingest_before = (FEED_INGEST_LATENCY.get_sample_count(), FEED_INGEST_LATENCY.get_sample_sum());
+        let decision_before = STRATEGY_DECISION_LATENCY.get_sample_count();
+        let place_before = GATEWAY_PLACE_LATENCY.get_sample_count();
+
+        let now = monotonic_nanos();
+        let trace = LatencyTrace {
+            tick_nanos: now - 10_000,
+            recv_nanos: Some(now - 7_000),
+            decoded_nanos: Some(now - 6_500),
+            signal_nanos: Some(now - 4_500),
+            order_nanos: Some(now - 4_000),
+            placed_nanos: None,
+        };
+        let order = Order::new(31, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 1.0, now)
+            .with_latency_trace(trace);
+        gateway.place_order(order).unwrap();
+
+        assert_eq!(FEED_INGEST_LATENCY.get_sample_count() - ingest_before.0, 1);
+        assert!((FEED_INGEST_LATENCY.get_sample_sum() - ingest_before.1 - 3.5).abs() < 1e-9);
+        assert_eq!(STRATEGY_DECISION_LATENCY.get_sample_count() - decision_before, 1);
+        assert_eq!(GATEWAY_PLACE_LATENCY.get_sample_count() - place_before, 1);
+
+        // Orders without a trace leave the histograms alone
+        gateway
+            .place_order(Order::new(32, "ETH/USD".to_string(), OrderSide::Buy, 2500.0, 1.0, now))
+            .unwrap();
+        assert_eq!(GATEWAY_PLACE_LATENCY.get_sample_count() - place_before, 1);
+    }
+
+    #[test]
+    fn test_oversized_frame_closes_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, _rx) = mpsc::channel::<Inbound>(16);
+        let dropped_before = DROPPED_FRAMES.get();
+
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(&u32::MAX.to_be_bytes()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let input = TcpTransport::new(stream);
+        let output = input.try_clone().unwrap();
+
+        assert!(matches!(
+            handle_connection(input, output, tx),
+            Err(HftError::FrameTooLarge { .. })
+        ));
+        assert!(DROPPED_FRAMES.get() > dropped_before);
+    }
+}
@@ -0,0 +1,101 @@
+use hft_types::latency::monotonic_nanos;
+use hft_types::matching::{MatchResult, MatchingEngine};
+use hft_types::Order;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Hands an order to the engine; swapped out by tests to stand in for a
+/// slow downstream
+pub type MatchStep = fn(&mut MatchingEngine, Order, u128) -> MatchResult;
+
+struct Request {
+    step: MatchStep,
+    order: Order,
+    now: u128,
+    reply: Sender<MatchResult>,
+}
+
+/// Owns the matching engine and runs matches with a deadline on a worker
+/// thread, so a hung match can't hold the gateway past the deadline. A
+/// match given up on keeps running and its result is collected later with
+/// `late_results`, since whatever it filled has still traded.
+pub struct Matcher {
+    engine: Arc<Mutex<MatchingEngine>>,
+    requests: Sender<Request>,
+    /// Matches that outran their deadline, by order id
+    late: Vec<(u64, Receiver<MatchResult>)>,
+}
+
+impl Matcher {
+    pub fn new(engine: MatchingEngine) -> Self {
+        let engine = Arc::new(Mutex::new(engine));
+        let (requests, worker) = mpsc::channel::<Request>();
+        let shared = Arc::clone(&engine);
+        std::thread::Builder::new()
+            .name("matcher".to_string())
+            .spawn(move || {
+                for request in worker {
+                    let mut engine = shared.lock().unwrap_or_else(PoisonError::into_inner);
+                    let result = (request.step)(&mut engine, request.order, request.now);
+                    drop(engine);
+                    request.reply.send(result).ok();
+                }
+            })
+            .expect("failed to spawn the matching thread");
+        Self {
+            engine,
+            requests,
+            late: Vec::new(),
+        }
+    }
+
+    /// The engine, once any match still running on the worker lets go of it
+    pub fn engine(&self) -> MutexGuard<'_, MatchingEngine> {
+        self.engine.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Match `order` with `step`, giving up once `deadline` (in
+    /// `monotonic_nanos`) passes. Without a deadline the match runs here.
+    pub fn submit(&mut self, step: MatchStep, order: Order, now: u128, deadline: Option<u128>) -> Option<MatchResult> {
+        let Some(deadline) = deadline else {
+            return Some(step(&mut self.engine(), order, now));
+        };
+        let order_id = order.order_id;
+        let (reply, result) = mpsc::channel();
+        self.requests
+            .send(Request { step, order, now, reply })
+            .expect("the matching thread has stopped");
+        let remaining = deadline.saturating_sub(monotonic_nanos());
+        match result.recv_timeout(Duration::from_nanos(remaining as u64)) {
+            Ok(result) => Some(result),
+            Err(RecvTimeoutError::Timeout) => {
+                self.late.push((order_id, result));
+                None
+            }
+            Err(RecvTimeoutError::Disconnected) => panic!("the matching thread has stopped"),
+        }
+    }
+
+    /// Whether a match given up on for `order_id` is still running
+    pub fn is_late(&self, order_id: u64) -> bool {
+        self.late.iter().any(|(late, _)| *late == order_id)
+    }
+
+    /// Results of matches given up on that have since finished; with `wait`
+    /// every outstanding one is waited for
+    pub fn late_results(&mut self, wait: bool) -> Vec<(u64, MatchResult)> {
+        let mut finished = Vec::new();
+        self.late.retain(|(order_id, result)| {
+            let received = if wait { result.recv().ok() } else { result.try_recv().ok() };
+            match received {
+                Some(result) => {
+                    finished.push((*order_id, result));
+                    false
+                }
+                None => true,
+            }
+        });
+        finished
+    }
+}
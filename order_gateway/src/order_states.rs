@@ -0,0 +1,115 @@
+use crate::ORDERS_BY_STATUS;
+use hft_types::lifecycle::{OrderState, OrderStatus};
+use hft_types::{HftError, HftResult};
+use std::collections::{HashMap, VecDeque};
+
+/// Lifecycle state of every live order plus the most recent finished
+/// ones, kept so late requests against them get a proper rejection.
+/// `gateway_orders_by_status` follows every change.
+pub struct OrderStates {
+    states: HashMap<u64, OrderState>,
+    /// Finished order ids, oldest first
+    finished: VecDeque<u64>,
+    /// Finished orders remembered
+    capacity: usize,
+}
+
+impl OrderStates {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            states: HashMap::new(),
+            finished: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn get(&self, order_id: u64) -> Option<&OrderState> {
+        self.states.get(&order_id)
+    }
+
+    /// Start tracking a new order. A previous state under the same id, from
+    /// a rejected attempt, is replaced.
+    pub fn open(&mut self, order_id: u64, quantity: f64) {
+        if let Some(previous) = self.states.insert(order_id, OrderState::new(order_id, quantity)) {
+            gauge(previous.status).dec();
+        }
+        gauge(OrderStatus::New).inc();
+    }
+
+    /// Orders that can still fill or be cancelled
+    pub fn live(&self) -> impl Iterator<Item = &OrderState> {
+        self.states.values().filter(|s| !s.status.is_terminal())
+    }
+
+    /// Track a live order saved from `live`, as it was
+    pub fn restore(&mut self, state: OrderState) {
+        gauge(state.status).inc();
+        if let Some(previous) = self.states.insert(state.order_id, state) {
+            gauge(previous.status).dec();
+        }
+    }
+
+    /// Apply `change` to an order's state, which rejects illegal transitions
+    pub fn update<T>(
+        &mut self,
+        order_id: u64,
+        change: impl FnOnce(&mut OrderState) -> HftResult<T>,
+    ) -> HftResult<T> {
+        let state = self
+            .states
+            .get_mut(&order_id)
+            .ok_or(HftError::OrderNotFound(order_id))?;
+        let from = state.status;
+        let result = change(state)?;
+        let to = state.status;
+
+        if from != to {
+            gauge(from).dec();
+            gauge(to).inc();
+            if to.is_terminal() {
+                self.finished.push_back(order_id);
+                self.evict();
+            }
+        }
+        Ok(result)
+    }
+
+    fn evict(&mut self) {
+        while self.finished.len() > self.capacity {
+            let Some(order_id) = self.finished.pop_front() else {
+                break;
+            };
+            // Skip ids reopened by a retry since they finished
+            if self.states.get(&order_id).is_some_and(|s| s.status.is_terminal()) {
+                if let Some(state) = self.states.remove(&order_id) {
+                    gauge(state.status).dec();
+                }
+            }
+        }
+    }
+}
+
+fn gauge(status: OrderStatus) -> prometheus::IntGauge {
+    ORDERS_BY_STATUS.with_label_values(&[status.as_str()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_orders_are_evicted_oldest_first() {
+        let mut states = OrderStates::new(2);
+        for order_id in 1..=3 {
+            states.open(order_id, 1.0);
+            states.update(order_id, |s| s.fill(1.0, 100.0)).unwrap();
+        }
+        assert!(states.get(1).is_none());
+        assert_eq!(states.get(3).unwrap().status, OrderStatus::Filled);
+
+        // Live orders are never evicted
+        states.open(4, 1.0);
+        assert!(matches!(states.update(5, OrderState::cancel), Err(HftError::OrderNotFound(5))));
+        assert_eq!(states.get(4).unwrap().status, OrderStatus::New);
+    }
+}
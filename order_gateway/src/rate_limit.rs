@@ -0,0 +1,143 @@
+use hft_types::{HftError, HftResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Order rate limits: a bucket per symbol plus a global ceiling
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub max_orders_per_sec: f64,
+    pub burst: f64,
+    pub global_max_orders_per_sec: f64,
+    pub global_burst: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_orders_per_sec: 100.0,
+            burst: 20.0,
+            global_max_orders_per_sec: 500.0,
+            global_burst: 100.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    /// Unset until first checked; the bucket starts full either way
+    last_refill_nanos: Option<u128>,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec,
+            last_refill_nanos: None,
+        }
+    }
+
+    fn refill(&mut self, now_nanos: u128) {
+        let last = self.last_refill_nanos.get_or_insert(now_nanos);
+        let elapsed = now_nanos.saturating_sub(*last) as f64 / 1_000_000_000.0;
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        *last = (*last).max(now_nanos);
+    }
+}
+
+/// Token-bucket limiter consulted before every order is placed
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    global: TokenBucket,
+    per_symbol: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let global = TokenBucket::new(config.global_burst, config.global_max_orders_per_sec);
+        Self {
+            config,
+            global,
+            per_symbol: HashMap::new(),
+        }
+    }
+
+    /// Take one token from both the symbol and global buckets, or neither
+    pub fn check(&mut self, symbol: &str, now_nanos: u128) -> HftResult<()> {
+        let config = &self.config;
+        let bucket = self
+            .per_symbol
+            .entry(symbol.to_string())
+            .or_insert_with(|| TokenBucket::new(config.burst, config.max_orders_per_sec));
+
+        bucket.refill(now_nanos);
+        self.global.refill(now_nanos);
+
+        if bucket.tokens < 1.0 {
+            return Err(HftError::RateLimited(symbol.to_string()));
+        }
+        if self.global.tokens < 1.0 {
+            return Err(HftError::RateLimited("global".to_string()));
+        }
+
+        bucket.tokens -= 1.0;
+        self.global.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u128 = 1_000_000;
+
+    fn limiter(burst: f64, global_burst: f64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            max_orders_per_sec: 10.0,
+            burst,
+            global_max_orders_per_sec: 100.0,
+            global_burst,
+        })
+    }
+
+    #[test]
+    fn test_burst_then_throttle_then_refill() {
+        let mut limiter = limiter(3.0, 100.0);
+        let start = 1_000 * MS;
+
+        for _ in 0..3 {
+            assert!(limiter.check("BTC/USD", start).is_ok());
+        }
+        assert!(matches!(
+            limiter.check("BTC/USD", start),
+            Err(HftError::RateLimited(symbol)) if symbol == "BTC/USD"
+        ));
+
+        // Other symbols have their own bucket
+        assert!(limiter.check("ETH/USD", start).is_ok());
+
+        // 10/sec refills one token every 100ms
+        assert!(limiter.check("BTC/USD", start + 50 * MS).is_err());
+        assert!(limiter.check("BTC/USD", start + 150 * MS).is_ok());
+    }
+
+    #[test]
+    fn test_global_ceiling() {
+        let mut limiter = limiter(5.0, 2.0);
+        let start = 1_000 * MS;
+
+        assert!(limiter.check("BTC/USD", start).is_ok());
+        assert!(limiter.check("ETH/USD", start).is_ok());
+        assert!(matches!(
+            limiter.check("SOL/USD", start),
+            Err(HftError::RateLimited(scope)) if scope == "global"
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+//! Replays a fixed recording through the simulator, feed handler, strategy
+//! engine and order gateway, each running its own stage loop, wired
+//! together with in-memory transports instead of sockets.
+
+use feed_handler::{strategy_consumer, FeedHandler, Overflow, TickForwarder};
+use hft_types::config::{build_strategy, StrategyConfig, ThresholdBand};
+use hft_types::replay::MarketReplayer;
+use hft_types::sizing::PositionSizer;
+use hft_types::strategies::SyncAdapter;
+use hft_types::transport::ChannelTransport;
+use hft_types::{Order, OrderSide};
+use market_simulator::MarketSimulator;
+use order_gateway::{handle_connection, GatewayConfig, OrderGateway};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+use strategy_engine::{gateway_replies, order_sender, FeedClient, FeedEvent, StrategyRunner};
+
+const RECORDING: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/data/pipeline_ticks.jsonl"
+);
+
+/// Threshold signals sized under a 3.0 position cap
+fn strategy_runner(order_tx: crossbeam::channel::Sender<Order>) -> StrategyRunner {
+    let band = |low, high| ThresholdBand { low, high };
+    let config = StrategyConfig::Threshold {
+        order_size: 2.0,
+        thresholds: HashMap::from([
+            ("BTC/USD".to_string(), band(44900.0, 45100.0)),
+            ("ETH/USD".to_string(), band(2990.0, 3010.0)),
+        ]),
+    };
+    StrategyRunner::new(
+        Box::new(SyncAdapter(build_strategy(&config))),
+        order_tx,
+        Duration::from_secs(3600),
+        Duration::ZERO,
+    )
+    .with_order_ids(0, 0, 1)
+    .with_position_sizer(PositionSizer::new(3.0))
+}
+
+/// Run the recording through every stage, each on its own thread, and
+/// return the gateway once the last order has been handled
+fn run_pipeline() -> OrderGateway {
+    let (sim_out, feed_in) = ChannelTransport::pair();
+    let (feed_out, client_in) = ChannelTransport::pair();
+    let (strategy_out, gateway_in) = ChannelTransport::pair();
+    let (gateway_out, strategy_in) = ChannelTransport::pair();
+    let (tick_tx, tick_rx) = crossbeam::channel::bounded(1024);
+    let (event_tx, event_rx) = crossbeam::channel::unbounded();
+    let (order_tx, order_rx) = crossbeam::channel::bounded(1024);
+    let (gateway_tx, gateway_rx) = tokio::sync::mpsc::channel(1024);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut feed = runtime
+        .block_on(FeedHandler::new(
+            "127.0.0.1:0".parse().unwrap(),
+            TickForwarder::new(tick_tx, tick_rx.clone(), Overflow::Block),
+            1,
+        ))
+        .unwrap();
+
+    // Each stage ends when the one before it hangs up. The runner also
+    // hears from the gateway, so it is told to stop once the feed is done.
+    let replies_tx = event_tx.clone();
+    let stages = [
+        thread::spawn(move || feed.run_transport(feed_in).unwrap()),
+        thread::spawn(move || strategy_consumer(tick_rx, feed_out)),
+        thread::spawn(move || {
+            FeedClient::new("pipeline", event_tx.clone()).receive(client_in);
+            event_tx.send(FeedEvent::Shutdown).unwrap();
+        }),
+        thread::spawn(move || gateway_replies(strategy_in, replies_tx)),
+        thread::spawn(move || strategy_runner(order_tx).run(event_rx)),
+        thread::spawn(move || {
+            let mut to_gateway = Some(strategy_out);
+            order_sender(order_rx, || {
+                to_gateway
+                    .take()
+                    .ok_or_else(|| std::io::ErrorKind::NotConnected.into())
+            });
+        }),
+        thread::spawn(move || handle_connection(gateway_in, gateway_out, gateway_tx).unwrap()),
+    ];
+
+    let mut simulator = MarketSimulator::with_transport(
+        sim_out,
+        vec![
+            ("BTC/USD".to_string(), 45000.0),
+            ("ETH/USD".to_string(), 3000.0),
+        ],
+    )
+    .unwrap()
+    .with_replay(MarketReplayer::new(RECORDING).unwrap());
+    let mut gateway = OrderGateway::new(GatewayConfig::default());
+    runtime.block_on(async {
+        simulator.run(100_000).await.unwrap();
+        drop(simulator);
+        gateway.run(gateway_rx).await;
+    });
+    for stage in stages {
+        stage.join().unwrap();
+    }
+    gateway
+}
+
+/// Id, symbol, side, price and quantity of each resting order, by id
+fn resting(gateway: &OrderGateway) -> Vec<(u64, String, OrderSide, f64, f64)> {
+    let mut orders: Vec<_> = gateway
+        .engine()
+        .open_orders()
+        .into_iter()
+        .map(|o| (o.order_id, o.symbol, o.side, o.price, o.quantity))
+        .collect();
+    orders.sort_by_key(|order| order.0);
+    orders
+}
+
+#[test]
+fn test_recording_produces_golden_orders() {
+    let gateway = run_pipeline();
+
+    // None of them cross, so every order sent is resting
+    let orders = resting(&gateway);
+    let summary: Vec<(u64, &str, OrderSide, f64, f64)> = orders
+        .iter()
+        .map(|(id, symbol, side, price, quantity)| {
+            (*id, symbol.as_str(), side.clone(), *price, *quantity)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (1, "BTC/USD", OrderSide::Buy, 44850.0, 1.5),
+            (2, "ETH/USD", OrderSide::Sell, 3015.0, 1.5),
+            // Full strength, but the 3.0 cap only leaves room for 1.5; the
+            // 44600 tick after it is sized out entirely
+            (3, "BTC/USD", OrderSide::Buy, 44700.0, 1.5),
+            (4, "ETH/USD", OrderSide::Buy, 2985.0, 1.5),
+            (5, "BTC/USD", OrderSide::Sell, 45200.0, 2.0),
+        ]
+    );
+    assert_eq!(
+        gateway.engine().best_prices("BTC/USD"),
+        (Some(44850.0), Some(45200.0))
+    );
+    assert_eq!(
+        gateway.engine().best_prices("ETH/USD"),
+        (Some(2985.0), Some(3015.0))
+    );
+
+    // Same recording, same orders
+    assert_eq!(resting(&run_pipeline()), orders);
+}
@@ -23,7 +23,7 @@ sleep 1
 run_in_terminal "cargo run --release --bin feed_handler"
 sleep 1
 
-run_in_terminal "cargo run --release --bin strategy_engine"
+run_in_terminal "cargo run --release --bin strategy_engine -- --feed-addr 127.0.0.1:9002"
 sleep 1
 
 run_in_terminal "cargo run --release --bin order_gateway"
@@ -0,0 +1,52 @@
+use hft_types::config::StrategyConfig;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// How often the runner looks at the config file
+pub const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the strategy config file so a rewrite is picked up without a
+/// restart. Contents are compared rather than mtimes, which can be too
+/// coarse to tell two quick rewrites apart.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    /// Contents last looked at, valid or not
+    seen: Option<String>,
+}
+
+impl ConfigWatcher {
+    /// Start watching from the file's current contents, which the caller
+    /// has already loaded
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let seen = std::fs::read_to_string(&path).ok();
+        Self { path, seen }
+    }
+
+    /// The new config if the file changed since the last call and parses
+    /// and validates. A bad rewrite is logged and skipped until the file
+    /// changes again.
+    pub fn poll(&mut self) -> Option<StrategyConfig> {
+        let contents = std::fs::read_to_string(&self.path).ok();
+        if contents.is_none() || contents == self.seen {
+            return None;
+        }
+        self.seen = contents;
+
+        match StrategyConfig::from_file(&self.path) {
+            Ok(config) => {
+                info!("Reloaded strategy config from {}", self.path.display());
+                Some(config)
+            }
+            Err(e) => {
+                error!(
+                    "Rejected strategy config reload from {}; keeping the current one: {}",
+                    self.path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use hft_types::{OrderSide, TradingSignal};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Last signal let through for a symbol
+#[derive(Debug, Clone)]
+struct LastSignal {
+    side: OrderSide,
+    timestamp_nanos: u128,
+    price: f64,
+    /// A tick for the symbol produced no signal since, i.e. the price
+    /// came back inside the strategy's band
+    rearmed: bool,
+}
+
+/// Suppresses repeats of a symbol's last signal side until the cooldown has
+/// passed or the symbol has had a quiet tick, so a price parked beyond a
+/// threshold yields one order rather than one per tick
+pub struct SignalDebouncer {
+    cooldown_nanos: u128,
+    last: HashMap<String, LastSignal>,
+}
+
+impl SignalDebouncer {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown_nanos: cooldown.as_nanos(),
+            last: HashMap::new(),
+        }
+    }
+
+    /// Whether `signal` should be acted on at `now_nanos`; records it if so
+    pub fn allow(&mut self, signal: &TradingSignal, now_nanos: u128) -> bool {
+        if let Some(last) = self.last.get(&signal.symbol) {
+            let repeat = last.side == signal.side && !last.rearmed;
+            if repeat && now_nanos.saturating_sub(last.timestamp_nanos) < self.cooldown_nanos {
+                tracing::debug!(
+                    "Suppressed repeat {} {} @ {} (last signal @ {})",
+                    signal.side,
+                    signal.symbol,
+                    signal.price,
+                    last.price
+                );
+                return false;
+            }
+        }
+
+        self.last.insert(
+            signal.symbol.clone(),
+            LastSignal {
+                side: signal.side.clone(),
+                timestamp_nanos: now_nanos,
+                price: signal.price,
+                rearmed: false,
+            },
+        );
+        true
+    }
+
+    /// A tick for `symbol` produced no signal
+    pub fn rearm(&mut self, symbol: &str) {
+        if let Some(last) = self.last.get_mut(symbol) {
+            last.rearmed = true;
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use crate::FEED_DISCONNECTED_SECONDS;
+use crossbeam::channel::Sender;
+use hft_types::health::Readiness;
+use hft_types::matching::Fill;
+use hft_types::messaging::Message;
+use hft_types::orderbook::BookSync;
+use hft_types::transport::{TcpTransport, Transport};
+use hft_types::{EnrichedTick, OrderBook};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// First wait after a failed connect; doubles per failure up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Outage after which per-symbol warmup history is no longer trusted
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// Readiness check held until the feed is connected
+const READINESS_CHECK: &str = "feed";
+
+/// What the strategy runner reacts to: the feed link, the order
+/// gateway's replies and the engine's own controls
+#[derive(Debug)]
+pub enum FeedEvent {
+    Tick(EnrichedTick),
+    /// Full book for one symbol, as sent or rebuilt from deltas
+    Snapshot(OrderBook),
+    /// The link came back after an outage. Trading should wait for a fresh
+    /// snapshot; `history_stale` says the outage outlasted the warmup
+    /// threshold.
+    Reconnected {
+        history_stale: bool,
+    },
+    /// Session over: close every position and stop trading. Raised by the
+    /// engine itself, e.g. at `--flatten-at`, rather than by the feed.
+    Flatten,
+    /// Start trading again after a flatten. Raised by the engine at
+    /// `--resume-at`.
+    Resume,
+    /// The process was asked to stop: the runner saves its state and
+    /// returns. Raised by the engine on Ctrl-C or SIGTERM.
+    Shutdown,
+    /// The gateway filled some of an order; positions move on these
+    Fill(Fill),
+    /// This much of an order left the gateway's book unfilled
+    Cancelled { order_id: u64, cancelled_qty: f64 },
+    /// The gateway refused an order, so none of it will fill
+    Rejected { order_id: u64 },
+}
+
+impl FeedEvent {
+    /// The gateway answering for one of the engine's orders
+    pub fn is_gateway_reply(&self) -> bool {
+        matches!(self, FeedEvent::Fill(_) | FeedEvent::Cancelled { .. } | FeedEvent::Rejected { .. })
+    }
+}
+
+/// TCP client for the feed handler's tick stream. Reconnects with
+/// exponential backoff; nothing is buffered while the link is down, since
+/// trading on replayed ticks would be trading on stale prices.
+pub struct FeedClient {
+    addr: String,
+    events: Sender<FeedEvent>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    stale_after: Duration,
+    readiness: Option<Readiness>,
+}
+
+impl FeedClient {
+    pub fn new(addr: &str, events: Sender<FeedEvent>) -> Self {
+        Self {
+            addr: addr.to_string(),
+            events,
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            stale_after: DEFAULT_STALE_AFTER,
+            readiness: None,
+        }
+    }
+
+    /// Clear warmup history after an outage of at least `stale_after`
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Report the link's state as the `feed` check of `readiness`
+    pub fn with_readiness(mut self, readiness: Readiness) -> Self {
+        readiness.require(READINESS_CHECK);
+        self.readiness = Some(readiness);
+        self
+    }
+
+    fn set_ready(&self, ready: bool) {
+        if let Some(readiness) = &self.readiness {
+            readiness.set(READINESS_CHECK, ready);
+        }
+    }
+
+    /// Receive until the runner hangs up
+    pub fn run(self) {
+        let mut backoff = self.initial_backoff;
+        // Set while the link is down: (outage start, time not yet counted)
+        let mut outage: Option<(Instant, Instant)> = None;
+
+        loop {
+            let transport = match TcpTransport::connect(&self.addr) {
+                Ok(transport) => transport,
+                Err(e) => {
+                    let now = Instant::now();
+                    let (_, counted) = outage.get_or_insert((now, now));
+                    FEED_DISCONNECTED_SECONDS.inc_by(now.duration_since(*counted).as_secs_f64());
+                    *counted = now;
+                    warn!(
+                        "Feed {} unreachable, retrying in {:?}: {}",
+                        self.addr, backoff, e
+                    );
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    continue;
+                }
+            };
+            backoff = self.initial_backoff;
+            self.set_ready(true);
+
+            if let Some((since, counted)) = outage.take() {
+                let now = Instant::now();
+                FEED_DISCONNECTED_SECONDS.inc_by(now.duration_since(counted).as_secs_f64());
+                let gap = now.duration_since(since);
+                let history_stale = gap >= self.stale_after;
+                info!(
+                    "Reconnected to feed {} after {:?}{}",
+                    self.addr,
+                    gap,
+                    if history_stale {
+                        "; warmup history is stale"
+                    } else {
+                        ""
+                    }
+                );
+                if self
+                    .events
+                    .send(FeedEvent::Reconnected { history_stale })
+                    .is_err()
+                {
+                    return;
+                }
+            } else {
+                info!("Connected to feed {}", self.addr);
+            }
+
+            if !self.receive(transport) {
+                return;
+            }
+            warn!("Lost connection to feed {}", self.addr);
+            self.set_ready(false);
+            let now = Instant::now();
+            outage = Some((now, now));
+        }
+    }
+
+    /// Forward messages from `input` until the link drops; false once the
+    /// runner is gone. Book deltas are applied to the last snapshot; after a
+    /// gap the feed is asked once to resync and the symbol's deltas are
+    /// dropped until it does.
+    pub fn receive(&self, mut input: impl Transport) -> bool {
+        let mut books: HashMap<String, BookSync> = HashMap::new();
+        // Symbols asked to resync that have had no snapshot since
+        let mut resyncing: HashSet<String> = HashSet::new();
+        loop {
+            let message = match input.recv().map(|frame| frame.map(|frame| Message::deserialize(&frame))) {
+                Ok(Some(Ok(message))) => message,
+                Ok(Some(Err(e))) => {
+                    warn!("Skipping undecodable feed message: {}", e);
+                    continue;
+                }
+                Ok(None) | Err(_) => return true,
+            };
+            let event = match message {
+                Message::EnrichedTick(tick) => FeedEvent::Tick(tick),
+                Message::OrderBookUpdate(book) => {
+                    resyncing.remove(&book.symbol);
+                    books.entry(book.symbol.clone()).or_default().on_snapshot(book.clone());
+                    FeedEvent::Snapshot(book)
+                }
+                Message::BookDelta(delta) => {
+                    match books.entry(delta.symbol.clone()).or_default().on_delta(&delta) {
+                        Ok(book) => FeedEvent::Snapshot(book.clone()),
+                        Err(_) if resyncing.contains(&delta.symbol) => continue,
+                        Err(e) => {
+                            warn!("{}; requesting a resync", e);
+                            resyncing.insert(delta.symbol.clone());
+                            let request = Message::BookResync { symbol: delta.symbol };
+                            if let Err(e) = input.send_message(&request) {
+                                warn!("Failed to request a resync: {}", e);
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Message::Heartbeat { .. } => continue,
+                other => {
+                    tracing::debug!("Ignoring feed message {:?}", other);
+                    continue;
+                }
+            };
+            if self.events.send(event).is_err() {
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::{unbounded, Receiver};
+    use hft_types::messaging::{read_frame, write_message};
+    use hft_types::{MarketTick, OrderSide};
+    use std::net::TcpListener;
+
+    /// Accept one client, send a snapshot and `ticks` ticks, then shut down
+    fn serve_once(listener: TcpListener, ticks: u128) -> std::thread::JoinHandle<()> {
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let book = OrderBook::new("BTC/USD".to_string(), 1);
+            write_message(&mut stream, &Message::OrderBookUpdate(book)).unwrap();
+            for i in 1..=ticks {
+                let tick = EnrichedTick {
+                    tick: MarketTick::new("BTC/USD".to_string(), 45000.0, 10, i),
+                    receive_time_nanos: i,
+                    decoded_nanos: None,
+                    latency_micros: 1.0,
+                };
+                write_message(&mut stream, &Message::EnrichedTick(tick)).unwrap();
+            }
+        })
+    }
+
+    #[test]
+    fn test_book_deltas_rebuild_the_book_and_gaps_request_resync() {
+        use hft_types::orderbook::BookDelta;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut first = OrderBook::new("BTC/USD".to_string(), 1);
+        first.upsert_bid(100.0, 1.0);
+        let mut second = first.clone();
+        second.upsert_ask(101.0, 2.0);
+        second.timestamp_nanos = 2;
+        let mut third = second.clone();
+        third.remove_level(OrderSide::Buy, 100.0);
+        third.timestamp_nanos = 3;
+        let mut fourth = third.clone();
+        fourth.upsert_ask(102.0, 1.0);
+        fourth.timestamp_nanos = 4;
+
+        let deltas = [
+            BookDelta::between(&first, &second, 1),
+            BookDelta::between(&second, &third, 3),
+            BookDelta::between(&third, &fourth, 4),
+        ];
+        let snapshot = first.clone();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            write_message(&mut stream, &Message::OrderBookUpdate(snapshot)).unwrap();
+            // Delta 2 never arrives, and more follow the gap
+            for delta in deltas {
+                write_message(&mut stream, &Message::BookDelta(delta)).unwrap();
+            }
+            let request = read_frame(&mut stream).unwrap().parse_message().unwrap();
+            // One request for the gap, not one per delta after it
+            stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+            assert!(read_frame(&mut stream).is_err());
+            request
+        });
+
+        let (tx, events) = unbounded();
+        let client = FeedClient::new(&addr.to_string(), tx);
+        std::thread::spawn(move || client.run());
+
+        assert!(matches!(next(&events), FeedEvent::Snapshot(book) if book == first));
+        assert!(matches!(next(&events), FeedEvent::Snapshot(book) if book == second));
+        match server.join().unwrap() {
+            Message::BookResync { symbol } => assert_eq!(symbol, "BTC/USD"),
+            other => panic!("expected a resync request, got {:?}", other),
+        }
+        // Nothing was made of the deltas after the gap; the link dropping
+        // once the server is done may still be reported
+        while let Ok(event) = events.recv_timeout(Duration::from_millis(100)) {
+            assert!(!matches!(event, FeedEvent::Snapshot(_)), "{:?}", event);
+        }
+    }
+
+    fn next(events: &Receiver<FeedEvent>) -> FeedEvent {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("feed event")
+    }
+
+    #[test]
+    fn test_reconnects_after_feed_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve_once(listener, 3);
+
+        let (tx, events) = unbounded();
+        let mut client = FeedClient::new(&addr.to_string(), tx).with_stale_after(Duration::ZERO);
+        client.initial_backoff = Duration::from_millis(10);
+        client.max_backoff = Duration::from_millis(50);
+        std::thread::spawn(move || client.run());
+
+        assert!(matches!(next(&events), FeedEvent::Snapshot(_)));
+        for _ in 0..3 {
+            assert!(matches!(next(&events), FeedEvent::Tick(_)));
+        }
+
+        // Kill the feed and leave it down long enough for failed retries
+        server.join().unwrap();
+        let disconnected_before = FEED_DISCONNECTED_SECONDS.get();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let server = serve_once(TcpListener::bind(addr).unwrap(), 2);
+        match next(&events) {
+            FeedEvent::Reconnected { history_stale } => assert!(history_stale),
+            other => panic!("expected a reconnect, got {:?}", other),
+        }
+        assert!(FEED_DISCONNECTED_SECONDS.get() > disconnected_before);
+
+        // The new session starts with its snapshot and carries on
+        assert!(matches!(next(&events), FeedEvent::Snapshot(_)));
+        for i in 1..=2 {
+            match next(&events) {
+                FeedEvent::Tick(enriched) => assert_eq!(enriched.tick.timestamp_nanos, i),
+                other => panic!("expected a tick, got {:?}", other),
+            }
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_ready_only_while_feed_is_connected() {
+        // Reserve a port with nothing listening on it yet
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let readiness = Readiness::new();
+        let health = hft_types::health::spawn_server("127.0.0.1:0".parse().unwrap(), readiness.clone(), &prometheus::Registry::new()).unwrap();
+
+        let (tx, events) = unbounded();
+        let mut client = FeedClient::new(&addr.to_string(), tx).with_readiness(readiness.clone());
+        client.initial_backoff = Duration::from_millis(10);
+        client.max_backoff = Duration::from_millis(20);
+        std::thread::spawn(move || client.run());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(hft_types::health::probe(health, "/healthz").unwrap(), 200);
+        assert_eq!(hft_types::health::probe(health, "/readyz").unwrap(), 503);
+
+        // Hold the connection open until the test has checked readiness
+        let (hang_up, hung_up) = unbounded::<()>();
+        let listener = TcpListener::bind(addr).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let book = OrderBook::new("BTC/USD".to_string(), 1);
+            write_message(&mut stream, &Message::OrderBookUpdate(book)).unwrap();
+            let _ = hung_up.recv();
+        });
+        assert!(matches!(next(&events), FeedEvent::Reconnected { .. }));
+        assert!(matches!(next(&events), FeedEvent::Snapshot(_)));
+        assert_eq!(hft_types::health::probe(health, "/readyz").unwrap(), 200);
+
+        // Not ready again once the feed goes away
+        drop(hang_up);
+        server.join().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while readiness.is_ready() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(hft_types::health::probe(health, "/readyz").unwrap(), 503);
+    }
+}
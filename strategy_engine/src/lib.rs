@@ -0,0 +1,1711 @@
+mod config_watch;
+mod debounce;
+mod feed_client;
+mod shard;
+
+use anyhow::Result;
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use hft_types::audit::{AuditEvent, AuditLog};
+use hft_types::backpressure::{ChannelMonitor, DEFAULT_SUSTAIN, SAMPLE_INTERVAL};
+use hft_types::latency::{monotonic_nanos, LatencyCollector, LatencyTrace};
+use hft_types::logging::LogFormat;
+use hft_types::messaging::Message;
+use config_watch::{ConfigWatcher, RELOAD_CHECK_INTERVAL};
+use debounce::SignalDebouncer;
+pub use feed_client::{FeedClient, FeedEvent};
+use shard::ShardedDispatcher;
+use hft_types::clock::{SharedClock, SystemClock};
+use hft_types::health::Readiness;
+use hft_types::config::{build_guarded_strategy, StrategyConfig, SymbolUniverse, ThresholdBand};
+use hft_types::orderbook::OrderBookManager;
+use hft_types::risk::{CircuitBreaker, CircuitBreakerConfig};
+use hft_types::sizing::PositionSizer;
+use hft_types::snapshot::Snapshotter;
+use hft_types::transport::{TcpTransport, Transport};
+use hft_types::strategies::{AsyncStrategy, NonFinitePolicy, SyncAdapter, NONFINITE_VALUES};
+use hft_types::{EnrichedTick, MarketTick, Order, OrderSide, SignalType, StreamKey, TradingSignal};
+use lazy_static::lazy_static;
+use prometheus::{Counter, IntCounter, IntGauge, Registry};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+    pub static ref SIGNALS_GENERATED: IntCounter = IntCounter::new(
+        "strategy_signals_generated_total",
+        "Total number of trading signals generated"
+    )
+    .unwrap();
+    pub static ref ORDERS_SENT: IntCounter = IntCounter::new(
+        "strategy_orders_sent_total",
+        "Total number of orders sent to gateway"
+    )
+    .unwrap();
+    pub static ref SIGNALS_SUPPRESSED: IntCounter = IntCounter::new(
+        "signals_suppressed_total",
+        "Repeat signals dropped by the debounce"
+    )
+    .unwrap();
+    pub static ref SIGNALS_SIZED_OUT: IntCounter = IntCounter::new(
+        "signals_sized_out_total",
+        "Signals dropped because the position sizer left nothing to trade"
+    )
+    .unwrap();
+    pub static ref CIRCUIT_BREAKER_OPEN: IntGauge = IntGauge::new(
+        "circuit_breaker_open",
+        "1 while the circuit breaker is blocking new orders"
+    )
+    .unwrap();
+    pub static ref STALE_BOOKS: IntGauge = IntGauge::new(
+        "strategy_stale_books",
+        "Order books with no update within the staleness window"
+    )
+    .unwrap();
+    pub static ref FEED_DISCONNECTED_SECONDS: Counter = Counter::new(
+        "feed_disconnected_seconds",
+        "Time spent without a connection to the feed handler"
+    )
+    .unwrap();
+}
+
+/// A book with no update for this long is treated as a frozen feed
+const MAX_BOOK_AGE: Duration = Duration::from_secs(5);
+
+/// Default minimum gap between repeat signals, overridden by `--signal-cooldown-ms`
+const DEFAULT_SIGNAL_COOLDOWN: Duration = Duration::from_secs(1);
+
+/// Where /healthz and /readyz are served unless --health-listen says otherwise
+const DEFAULT_HEALTH_ADDR: &str = "0.0.0.0:9203";
+
+/// Time between state snapshots with `--snapshot`
+const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Ticks and book updates buffered from the feed
+const FEED_CHANNEL_CAPACITY: usize = 100_000;
+
+/// Orders buffered for the gateway
+const ORDER_CHANNEL_CAPACITY: usize = 10_000;
+
+/// `client_id` stamped on orders unless --client-id says otherwise
+const DEFAULT_CLIENT_ID: &str = "strategy_engine";
+
+/// How long a shutdown waits for the gateway to fill its closing orders
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+pub fn init_metrics() {
+    REGISTRY
+        .register(Box::new(SIGNALS_GENERATED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(ORDERS_SENT.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(SIGNALS_SUPPRESSED.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(SIGNALS_SIZED_OUT.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(STALE_BOOKS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(CIRCUIT_BREAKER_OPEN.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(FEED_DISCONNECTED_SECONDS.clone()))
+        .unwrap();
+    REGISTRY
+        .register(Box::new(NONFINITE_VALUES.clone()))
+        .unwrap();
+}
+
+/// What a runner needs to pick up after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunnerSnapshot {
+    next_order_id: u64,
+    /// The sizer's positions, from orders sent
+    positions: HashMap<String, f64>,
+    /// Last feed sequence number handled per venue's stream of a symbol
+    last_sequences: HashMap<StreamKey, u64>,
+}
+
+/// Runs a configured strategy and turns its signals into orders.
+/// Synchronous strategies are driven through `SyncAdapter`.
+pub struct StrategyRunner {
+    strategy: Box<dyn AsyncStrategy>,
+    books: OrderBookManager,
+    debouncer: SignalDebouncer,
+    breaker: CircuitBreaker,
+    /// Scales signals by strength within the position cap
+    sizer: PositionSizer,
+    order_tx: Sender<Order>,
+    /// Stamped on every order, so the gateway books this engine's positions
+    /// separately and never matches it against itself
+    client_id: String,
+    /// Symbols to trade; everything else is ignored
+    universe: Option<SymbolUniverse>,
+    /// Set by a feed reconnect to the symbols with a fresh snapshot since;
+    /// ticks for every other symbol are skipped
+    resynced: Option<HashSet<String>>,
+    /// Set once positions are flattened; nothing more is traded until a
+    /// `FeedEvent::Resume`
+    flattened: bool,
+    last_sequences: HashMap<StreamKey, u64>,
+    /// Sequences restored from a snapshot: ticks up to these were handled
+    /// before the restart and are skipped if the feed delivers them again
+    resume_after: HashMap<StreamKey, u64>,
+    /// Last sequence skipped as a redelivery per stream; one lower than
+    /// this means the feed restarted its numbering instead
+    redelivered: HashMap<StreamKey, u64>,
+    next_order_id: u64,
+    /// Gap between this runner's order ids, so shards never collide
+    order_id_step: u64,
+    latency: LatencyCollector,
+    /// Times signal cooldowns, book staleness and the circuit breaker
+    clock: SharedClock,
+    report_interval: Duration,
+    last_report: Instant,
+    /// Swaps in a rewritten `--config` without a restart
+    config_watcher: Option<ConfigWatcher>,
+    /// How reloaded strategies treat NaN and infinite signals
+    nonfinite_policy: NonFinitePolicy,
+    last_reload_check: Instant,
+    snapshotter: Option<Snapshotter>,
+    /// Where signals and the orders sent for them are recorded; shared
+    /// by every shard
+    audit: Option<Arc<AuditLog>>,
+}
+
+/// Threshold strategy used when no `--config` is given
+fn default_config() -> StrategyConfig {
+    let band = |low, high| ThresholdBand { low, high };
+    StrategyConfig::Threshold {
+        order_size: 1.0,
+        thresholds: HashMap::from([
+            ("BTC/USD".to_string(), band(44000.0, 46000.0)),
+            ("ETH/USD".to_string(), band(2400.0, 2600.0)),
+            ("SOL/USD".to_string(), band(95.0, 105.0)),
+            ("AVAX/USD".to_string(), band(24.0, 26.0)),
+        ]),
+    }
+}
+
+/// First order id for a run: the wall clock in nanoseconds. A restart
+/// without `--snapshot` then numbers its orders above everything the last
+/// run sent, which the gateway's duplicate window would otherwise answer
+/// with stale acks.
+fn order_id_base() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+impl StrategyRunner {
+    pub fn new(
+        strategy: Box<dyn AsyncStrategy>,
+        order_tx: Sender<Order>,
+        report_interval: Duration,
+        signal_cooldown: Duration,
+    ) -> Self {
+        Self {
+            strategy,
+            books: OrderBookManager::new(),
+            debouncer: SignalDebouncer::new(signal_cooldown),
+            breaker: CircuitBreaker::new(CircuitBreakerConfig::default()),
+            sizer: PositionSizer::default(),
+            order_tx,
+            client_id: DEFAULT_CLIENT_ID.to_string(),
+            universe: None,
+            resynced: None,
+            flattened: false,
+            last_sequences: HashMap::new(),
+            resume_after: HashMap::new(),
+            redelivered: HashMap::new(),
+            next_order_id: 0,
+            order_id_step: 1,
+            latency: LatencyCollector::new(10_000),
+            clock: SystemClock::shared(),
+            report_interval,
+            last_report: Instant::now(),
+            config_watcher: None,
+            nonfinite_policy: NonFinitePolicy::default(),
+            last_reload_check: Instant::now(),
+            snapshotter: None,
+            audit: None,
+        }
+    }
+
+    /// Send orders as `client_id`
+    pub fn with_client_id(mut self, client_id: &str) -> Self {
+        self.client_id = client_id.to_string();
+        self
+    }
+
+    /// Only trade the symbols in `universe`
+    fn with_universe(mut self, universe: SymbolUniverse) -> Self {
+        self.universe = Some(universe);
+        self
+    }
+
+    /// Halt orders per `config` instead of the never-tripping default
+    fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker = CircuitBreaker::new(config);
+        self
+    }
+
+    /// Size orders within a per-symbol position cap
+    pub fn with_position_sizer(mut self, sizer: PositionSizer) -> Self {
+        self.sizer = sizer;
+        self
+    }
+
+    /// Assign order ids `base + shard + shards`, `base + shard + 2 * shards`,
+    /// ... so that runners on different shards never reuse each other's ids
+    pub fn with_order_ids(mut self, base: u64, shard: u64, shards: u64) -> Self {
+        self.next_order_id = base + shard;
+        self.order_id_step = shards.max(1);
+        self
+    }
+
+    /// Pick up rewrites of the strategy config file
+    fn with_config_watcher(mut self, watcher: ConfigWatcher) -> Self {
+        self.config_watcher = Some(watcher);
+        self
+    }
+
+    /// Build reloaded strategies to handle NaN and infinite signals per
+    /// `policy`
+    fn with_nonfinite_policy(mut self, policy: NonFinitePolicy) -> Self {
+        self.nonfinite_policy = policy;
+        self
+    }
+
+    /// Save state with `snapshotter` while running
+    fn with_snapshotter(mut self, snapshotter: Snapshotter) -> Self {
+        self.snapshotter = Some(snapshotter);
+        self
+    }
+
+    /// Record signals and orders to `log`
+    fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Append to the audit log, if one is kept
+    fn audit(&self, event: impl FnOnce() -> AuditEvent) {
+        if let Some(log) = &self.audit {
+            log.record(event());
+        }
+    }
+
+    fn snapshot(&self) -> RunnerSnapshot {
+        RunnerSnapshot {
+            next_order_id: self.next_order_id,
+            positions: self.sizer.positions().clone(),
+            last_sequences: self.last_sequences.clone(),
+        }
+    }
+
+    /// Resume from a snapshot instead of flat. Strategy history is not
+    /// saved and warms up again.
+    fn restore(&mut self, snapshot: RunnerSnapshot) {
+        self.next_order_id = self.next_order_id.max(snapshot.next_order_id);
+        for (symbol, position) in &snapshot.positions {
+            self.strategy.on_position(symbol, *position);
+        }
+        self.sizer.restore(snapshot.positions);
+        self.resume_after = snapshot.last_sequences.clone();
+        self.last_sequences = snapshot.last_sequences;
+    }
+
+    /// Save state if the snapshot interval has passed, or regardless with `force`
+    fn save_snapshot(&mut self, force: bool) {
+        let Some(mut snapshotter) = self.snapshotter.take() else {
+            return;
+        };
+        let result = if force {
+            snapshotter.save(&self.snapshot())
+        } else {
+            snapshotter.save_if_due(|| self.snapshot()).map(|_| ())
+        };
+        if let Err(e) = result {
+            warn!("Failed to save snapshot to {}: {}", snapshotter.path().display(), e);
+        }
+        self.snapshotter = Some(snapshotter);
+    }
+
+    /// Swap in a changed strategy config between ticks. Books, debounce
+    /// state, the circuit breaker and order ids carry over; the strategy's
+    /// own warmup history starts again.
+    fn check_config_reload(&mut self) {
+        let Some(config) = self.config_watcher.as_mut().and_then(ConfigWatcher::poll) else {
+            return;
+        };
+        self.strategy = Box::new(SyncAdapter(build_guarded_strategy(&config, self.nonfinite_policy)));
+        info!("Strategy is now {}", self.strategy.name());
+    }
+
+    async fn on_feed_event(&mut self, event: FeedEvent) {
+        match event {
+            FeedEvent::Tick(enriched) => {
+                if self.flattened {
+                    tracing::debug!("Flattened; skipping {} tick", enriched.tick.symbol);
+                    return;
+                }
+                if self
+                    .resynced
+                    .as_ref()
+                    .is_some_and(|resynced| !resynced.contains(&enriched.tick.symbol))
+                {
+                    tracing::debug!("Awaiting a snapshot; skipping {} tick", enriched.tick.symbol);
+                    return;
+                }
+                if let Some(sequence) = enriched.tick.sequence {
+                    let stream = enriched.tick.stream();
+                    if let Some(&last) = self.resume_after.get(&stream) {
+                        let restarted = self
+                            .redelivered
+                            .get(&stream)
+                            .is_some_and(|&previous| sequence < previous);
+                        if sequence <= last && !restarted {
+                            tracing::debug!("{} #{} was handled before the restart; skipping", stream, sequence);
+                            self.redelivered.insert(stream, sequence);
+                            return;
+                        }
+                        if restarted {
+                            info!("{} went back to #{}; the feed restarted, trading it again", stream, sequence);
+                        }
+                        self.resume_after.remove(&stream);
+                        self.redelivered.remove(&stream);
+                    }
+                    self.last_sequences.insert(stream, sequence);
+                }
+                self.process_tick(enriched).await;
+            }
+            FeedEvent::Snapshot(book) => {
+                if let Some(resynced) = &mut self.resynced {
+                    if resynced.insert(book.symbol.clone()) {
+                        info!("Fresh {} snapshot; resuming trading it", book.symbol);
+                    }
+                }
+                self.books.apply_snapshot(book);
+            }
+            FeedEvent::Reconnected { history_stale } => {
+                if history_stale {
+                    warn!("Feed outage outlasted warmup; clearing strategy history");
+                    self.strategy.reset_history();
+                }
+                self.resynced = Some(HashSet::new());
+            }
+            FeedEvent::Flatten => {
+                self.flatten();
+            }
+            FeedEvent::Resume => {
+                if self.flattened {
+                    info!("Resuming trading");
+                    self.flattened = false;
+                }
+            }
+            // `run` stops on it
+            FeedEvent::Shutdown => {}
+            reply => self.on_gateway_reply(reply),
+        }
+    }
+
+    /// Keep the position budget in step with what the gateway did with
+    /// the orders sent
+    fn on_gateway_reply(&mut self, reply: FeedEvent) {
+        match reply {
+            FeedEvent::Fill(fill) => {
+                self.sizer.on_fill(&fill);
+                self.strategy.on_position(&fill.symbol, self.sizer.position(&fill.symbol));
+            }
+            FeedEvent::Cancelled { order_id, cancelled_qty } => self.sizer.on_cancel(order_id, cancelled_qty),
+            FeedEvent::Rejected { order_id } => self.sizer.on_reject(order_id),
+            other => tracing::debug!("Not a gateway reply: {:?}", other),
+        }
+    }
+
+    /// Close every filled position and stop trading until a resume. The
+    /// strategy prices its own closing orders; whatever the runner still
+    /// holds after those is closed at the touch of the last book seen.
+    /// Closing orders skip the debounce and the circuit breaker. Returns
+    /// the ids of the closing orders sent.
+    fn flatten(&mut self) -> Vec<u64> {
+        if self.flattened {
+            return Vec::new();
+        }
+        self.flattened = true;
+        let mut signals = self.strategy.flatten();
+
+        let mut residual = self.sizer.positions().clone();
+        for signal in &signals {
+            *residual.entry(signal.symbol.clone()).or_default() += match signal.side {
+                OrderSide::Buy => signal.quantity,
+                OrderSide::Sell => -signal.quantity,
+            };
+        }
+        let mut residual: Vec<(String, f64)> = residual
+            .into_iter()
+            .filter(|(_, position)| position.abs() > 1e-9)
+            .collect();
+        residual.sort_by(|a, b| a.0.cmp(&b.0));
+        for (symbol, position) in residual {
+            let Some((bid, ask)) = self.books.get_bbo(&symbol) else {
+                warn!("No price for {}; leaving {} open", symbol, position);
+                continue;
+            };
+            let (side, price) = if position > 0.0 {
+                (OrderSide::Sell, bid)
+            } else {
+                (OrderSide::Buy, ask)
+            };
+            signals.push(TradingSignal {
+                symbol,
+                side,
+                price,
+                quantity: position.abs(),
+                signal_type: SignalType::Threshold,
+                timestamp_nanos: self.clock.now_nanos(),
+                strength: 1.0,
+            });
+        }
+
+        info!("Flattening {} positions and stopping", signals.len());
+        let mut closing = Vec::new();
+        for signal in signals {
+            let order = Order::new(
+                0,
+                signal.symbol,
+                signal.side,
+                signal.price,
+                signal.quantity,
+                monotonic_nanos(),
+            );
+            if self.send_order(order) {
+                closing.push(self.next_order_id);
+            }
+        }
+        self.strategy.reset();
+        closing
+    }
+
+    /// Take in the gateway's replies until the `closing` orders are done or
+    /// `SHUTDOWN_GRACE` runs out, so the state saved on the way out holds
+    /// their fills. Anything else that arrives meanwhile is dropped.
+    fn await_closing_orders(&mut self, feed_rx: &Receiver<FeedEvent>, closing: &[u64]) {
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        while closing.iter().any(|&order_id| self.sizer.is_working(order_id)) {
+            match feed_rx.recv_deadline(deadline) {
+                Ok(event) => self.on_gateway_reply(event),
+                Err(_) => {
+                    warn!("Closing orders still working at shutdown; saving positions without them");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Number `order`, stamp this engine's client id and hand it to the
+    /// gateway, counting it against the position budget until the gateway
+    /// says how it ended. False if the order channel is full.
+    fn send_order(&mut self, mut order: Order) -> bool {
+        self.next_order_id += self.order_id_step;
+        order.order_id = self.next_order_id;
+        order.client_id = Some(self.client_id.clone());
+        match self.order_tx.try_send(order.clone()) {
+            Ok(_) => {
+                ORDERS_SENT.inc();
+                self.audit(|| AuditEvent::OrderPlaced(Box::new(order.clone())));
+                self.sizer
+                    .on_order(order.order_id, &order.symbol, &order.side, order.quantity);
+                info!(
+                    "Order sent: {} {} @ {}",
+                    order.side, order.symbol, order.price
+                );
+                true
+            }
+            Err(e) => {
+                warn!("Failed to send order: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn process_tick(&mut self, enriched: EnrichedTick) {
+        let symbol = &enriched.tick.symbol;
+        if self.universe.as_ref().is_some_and(|universe| !universe.contains(symbol)) {
+            tracing::debug!("Ignoring tick for unconfigured symbol {}", symbol);
+            return;
+        }
+        let mut trace = LatencyTrace::from_enriched(&enriched);
+
+        // Book-aware and multi-leg strategies only trade through this path
+        self.books.update_from_tick(&enriched.tick);
+        let now = self.clock.now_nanos();
+        self.breaker.on_tick(&enriched.tick, now);
+        if let Some(book) = self.books.get_book(symbol) {
+            self.breaker.on_book(book, now);
+        }
+        let halted = self.breaker.is_open(now);
+        CIRCUIT_BREAKER_OPEN.set(halted as i64);
+
+        let signals = self.strategy.process_tick_with_book(&enriched, &self.books).await;
+        if !signals.iter().any(|s| s.symbol == enriched.tick.symbol) {
+            self.debouncer.rearm(&enriched.tick.symbol);
+        }
+        for signal in signals {
+            SIGNALS_GENERATED.inc();
+            self.audit(|| AuditEvent::SignalGenerated(signal.clone()));
+            if halted {
+                tracing::debug!("Circuit breaker open; dropping {} {} signal", signal.side, signal.symbol);
+                continue;
+            }
+            if !self.debouncer.allow(&signal, self.clock.now_nanos()) {
+                SIGNALS_SUPPRESSED.inc();
+                continue;
+            }
+            let symbol_config = self
+                .universe
+                .as_ref()
+                .and_then(|universe| universe.get(&signal.symbol));
+            let lot_size = symbol_config.and_then(|config| config.lot_size);
+            let quantity = self.sizer.size(&signal, lot_size);
+            if quantity <= 0.0 {
+                SIGNALS_SIZED_OUT.inc();
+                tracing::debug!(
+                    "Nothing to trade for {} {} signal at strength {:.2}",
+                    signal.side,
+                    signal.symbol,
+                    signal.strength
+                );
+                continue;
+            }
+            let signal_nanos = monotonic_nanos();
+            let order = Order::new(
+                0,
+                signal.symbol,
+                signal.side,
+                signal.price,
+                quantity,
+                signal_nanos,
+            );
+            // Too small to be worth sending, e.g. under the minimum notional
+            if let Some(Err(e)) = symbol_config.map(|config| config.validate_order(&order)) {
+                SIGNALS_SIZED_OUT.inc();
+                tracing::debug!("Not sending {} {} order: {}", order.side, order.symbol, e);
+                continue;
+            }
+            trace.signal_nanos = Some(signal_nanos);
+            // Stamped on the way out so the gateway can attribute its share
+            trace.order_nanos = Some(monotonic_nanos());
+            if self.send_order(order.with_latency_trace(trace.clone())) {
+                self.latency.record(&trace);
+            }
+        }
+    }
+
+    /// Handle feed events until the feed hangs up or a shutdown, which
+    /// flattens first
+    pub fn run(&mut self, feed_rx: Receiver<FeedEvent>) {
+        info!("Strategy engine started ({})", self.strategy.name());
+
+        // Ticks are handled one at a time, so async strategies need only a
+        // single-threaded runtime to await on
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build strategy runtime");
+
+        loop {
+            // Wake for the next report even when the feed goes quiet, which
+            // is when stale books matter most
+            match feed_rx.recv_deadline(self.last_report + self.report_interval) {
+                Ok(FeedEvent::Shutdown) => {
+                    let closing = self.flatten();
+                    self.await_closing_orders(&feed_rx, &closing);
+                    break;
+                }
+                Ok(event) => runtime.block_on(self.on_feed_event(event)),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if self.last_reload_check.elapsed() >= RELOAD_CHECK_INTERVAL {
+                self.check_config_reload();
+                self.last_reload_check = Instant::now();
+            }
+
+            if self.last_report.elapsed() >= self.report_interval {
+                self.report_latency();
+                self.report_stale_books();
+                self.last_report = Instant::now();
+            }
+            self.save_snapshot(false);
+        }
+
+        self.report_latency();
+        self.save_snapshot(true);
+        if let Some(log) = &self.audit {
+            log.flush();
+        }
+    }
+
+    /// Publish and log books that have stopped updating
+    fn report_stale_books(&self) {
+        let stale = self
+            .books
+            .stale_symbols(self.clock.now_nanos(), MAX_BOOK_AGE.as_nanos());
+        STALE_BOOKS.set(stale.len() as i64);
+        if !stale.is_empty() {
+            warn!("Stale books: {}", stale.join(", "));
+        }
+    }
+
+    /// Log the tick-to-order latency budget accumulated since the last report
+    fn report_latency(&mut self) {
+        let report = self.latency.report();
+        if let Some(p99_micros) = report.iter().map(|stage| stage.p99_micros).reduce(f64::max) {
+            // Judged on the slowest stage
+            self.breaker.on_latency_p99(p99_micros, self.clock.now_nanos());
+        }
+        for stage in report {
+            info!(
+                "Latency budget [{}]: n={} p50={:.2}µs p99={:.2}µs",
+                stage.stage, stage.count, stage.p50_micros, stage.p99_micros
+            );
+        }
+        self.latency.reset();
+    }
+}
+
+// In a real system, this would receive from feed_handler via IPC
+// For this demo, we'll simulate receiving ticks
+fn mock_tick_generator(tx: Sender<FeedEvent>) {
+    let mut counter = 0u64;
+
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+
+        let symbols = ["BTC/USD", "ETH/USD", "SOL/USD", "AVAX/USD"];
+        let prices = [
+            43900.0 + (counter % 300) as f64,
+            2380.0 + (counter % 300) as f64,
+            94.0 + (counter % 15) as f64,
+            23.5 + (counter % 4) as f64,
+        ];
+
+        for (i, symbol) in symbols.iter().enumerate() {
+            let tick = MarketTick::new(symbol.to_string(), prices[i], counter % 100, timestamp - 1000);
+
+            let enriched = EnrichedTick {
+                tick,
+                receive_time_nanos: timestamp,
+                decoded_nanos: None,
+                latency_micros: 1.0,
+            };
+
+            if tx.send(FeedEvent::Tick(enriched)).is_err() {
+                break;
+            }
+        }
+
+        counter += 1;
+    }
+}
+
+/// Forward orders to the order gateway over a transport from `connect`,
+/// connecting again if the link drops
+pub fn order_sender<T: Transport>(order_rx: Receiver<Order>, mut connect: impl FnMut() -> std::io::Result<T>) {
+    let mut transport: Option<T> = None;
+
+    for order in order_rx.iter() {
+        if transport.is_none() {
+            match connect() {
+                Ok(t) => {
+                    info!("Connected to order gateway");
+                    transport = Some(t);
+                }
+                Err(e) => {
+                    warn!("Order gateway unreachable, dropping order {}: {}", order.order_id, e);
+                    continue;
+                }
+            }
+        }
+
+        if let Some(t) = transport.as_mut() {
+            if let Err(e) = t.send_message(&Message::Order(order)) {
+                warn!("Lost connection to order gateway: {}", e);
+                transport = None;
+            }
+        }
+    }
+}
+
+/// Hand the gateway's replies about this engine's orders to the runner,
+/// until the connection closes
+pub fn gateway_replies(mut input: impl Transport, events: Sender<FeedEvent>) {
+    loop {
+        let message = match input.recv_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Lost the order gateway's replies: {}", e);
+                return;
+            }
+        };
+        let event = match message {
+            Message::Fill(fill) => FeedEvent::Fill(fill),
+            Message::CancelAck { order_id, cancelled_qty } => FeedEvent::Cancelled { order_id, cancelled_qty },
+            Message::OrderReject { order_id, reason } => {
+                warn!("Order {} rejected: {}", order_id, reason);
+                FeedEvent::Rejected { order_id }
+            }
+            other => {
+                tracing::debug!("Ignoring gateway reply: {:?}", other);
+                continue;
+            }
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+/// Value following `flag` on the command line, if any
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Time from now until the next `HH:MM` UTC, as given to `flag`
+fn until_utc(flag: &str, hh_mm: &str) -> Result<Duration> {
+    let parsed = hh_mm
+        .split_once(':')
+        .and_then(|(hours, minutes)| Some((hours.parse::<u64>().ok()?, minutes.parse::<u64>().ok()?)))
+        .filter(|&(hours, minutes)| hours < 24 && minutes < 60);
+    let Some((hours, minutes)) = parsed else {
+        anyhow::bail!("{} takes a UTC time of day as HH:MM, not {:?}", flag, hh_mm);
+    };
+    const DAY: u64 = 24 * 60 * 60;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let target = Duration::from_secs(hours * 60 * 60 + minutes * 60);
+    let today = Duration::from_secs(now.as_secs() % DAY) + Duration::from_nanos(now.subsec_nanos() as u64);
+    Ok(match target.checked_sub(today) {
+        Some(wait) if !wait.is_zero() => wait,
+        _ => target + Duration::from_secs(DAY) - today,
+    })
+}
+
+/// Raise `event` every day at the UTC time of day given to `flag`, if it
+/// was given, until the runner has stopped
+fn schedule_daily(flag: &'static str, feed_tx: &Sender<FeedEvent>, event: fn() -> FeedEvent) -> Result<()> {
+    let Some(at) = arg_value(flag) else {
+        return Ok(());
+    };
+    let wait = until_utc(flag, &at)?;
+    info!("{:?} daily at {} UTC, first in {:?}", event(), at, wait);
+    let tx = feed_tx.clone();
+    std::thread::spawn(move || {
+        let mut wait = wait;
+        loop {
+            std::thread::sleep(wait);
+            if tx.send(event()).is_err() {
+                return;
+            }
+            // Validated above, so only the day can have moved on
+            wait = until_utc(flag, &at).unwrap_or(Duration::from_secs(24 * 60 * 60));
+        }
+    });
+    Ok(())
+}
+
+/// Entry point of the `strategy_engine` binary
+pub fn run() -> Result<()> {
+    let log_format: LogFormat = match arg_value("--log-format").or_else(|| std::env::var("HFT_LOG_FORMAT").ok()) {
+        Some(format) => format.parse().map_err(anyhow::Error::msg)?,
+        None => LogFormat::default(),
+    };
+    hft_types::logging::init("strategy_engine", log_format);
+
+    init_metrics();
+    let pusher = hft_types::pushgateway::MetricsPusher::from_env("strategy_engine", &REGISTRY)?;
+    hft_types::heartbeat::spawn_emitter(
+        "strategy_engine",
+        hft_types::heartbeat::DEFAULT_HEARTBEAT_ADDR,
+        Duration::from_secs(1),
+    )?;
+
+    let health_addr: SocketAddr = arg_value("--health-listen")
+        .as_deref()
+        .unwrap_or(DEFAULT_HEALTH_ADDR)
+        .parse()?;
+    let readiness = Readiness::new();
+    hft_types::health::spawn_server(health_addr, readiness.clone(), &REGISTRY)?;
+
+    let universe = arg_value("--universe")
+        .map(SymbolUniverse::from_file)
+        .transpose()?;
+    let config = match (arg_value("--config"), &universe) {
+        (Some(path), _) => StrategyConfig::from_file(&path)?,
+        (None, Some(universe)) => universe.threshold_config(1.0),
+        (None, None) => default_config(),
+    };
+
+    // Channel from feed_handler
+    let (feed_tx, feed_rx) = bounded::<FeedEvent>(FEED_CHANNEL_CAPACITY);
+
+    // Channel to order_gateway
+    let (order_tx, order_rx) = bounded::<Order>(ORDER_CHANNEL_CAPACITY);
+
+    // Sample both channels so backpressure is visible before they fill
+    let watermark: Option<f64> = arg_value("--channel-watermark")
+        .map(|percent| percent.parse::<f64>())
+        .transpose()?;
+    anyhow::ensure!(
+        watermark.is_none_or(|percent| percent > 0.0 && percent <= 100.0),
+        "--channel-watermark must be a percentage in (0, 100]"
+    );
+    let monitor = |name: &str, capacity: usize| -> Result<ChannelMonitor> {
+        let monitor = ChannelMonitor::register(name, capacity, &REGISTRY)?;
+        Ok(match watermark {
+            Some(percent) => monitor.with_watermark(percent / 100.0, DEFAULT_SUSTAIN),
+            None => monitor,
+        })
+    };
+    let mut feed_monitor = monitor("feed", FEED_CHANNEL_CAPACITY)?;
+    let mut order_monitor = monitor("order", ORDER_CHANNEL_CAPACITY)?;
+    let (feed_depth, order_depth) = (feed_rx.clone(), order_rx.clone());
+    std::thread::spawn(move || loop {
+        let now = Instant::now();
+        feed_monitor.sample(feed_depth.len(), now);
+        order_monitor.sample(order_depth.len(), now);
+        std::thread::sleep(SAMPLE_INTERVAL);
+    });
+
+    // Sessions: close out and stop trading at one UTC time of day, and
+    // trade again from another
+    schedule_daily("--flatten-at", &feed_tx, || FeedEvent::Flatten)?;
+    schedule_daily("--resume-at", &feed_tx, || FeedEvent::Resume)?;
+
+    let replies_tx = feed_tx.clone();
+    let shutdown_tx = feed_tx.clone();
+    hft_types::shutdown::spawn_listener(move || {
+        // The runner is already gone if the feed closed first
+        let _ = shutdown_tx.send(FeedEvent::Shutdown);
+    })?;
+
+    match arg_value("--feed-addr") {
+        Some(addr) => {
+            let mut client = FeedClient::new(&addr, feed_tx).with_readiness(readiness);
+            if let Some(ms) = arg_value("--feed-stale-after-ms") {
+                client = client.with_stale_after(Duration::from_millis(ms.parse()?));
+            }
+            std::thread::spawn(move || client.run());
+        }
+        // Without a feed, simulate one
+        None => {
+            std::thread::spawn(move || {
+                mock_tick_generator(feed_tx);
+            });
+        }
+    }
+
+    // Spawn order sender to order_gateway
+    let gateway_addr = "127.0.0.1:9004";
+    info!("Sending orders to the order gateway at {}", gateway_addr);
+    let sender = std::thread::spawn(move || {
+        order_sender(order_rx, || {
+            let transport = TcpTransport::connect(gateway_addr)?;
+            // Fills, cancels and rejects come back on the same connection
+            let replies = transport.try_clone()?;
+            let events = replies_tx.clone();
+            std::thread::spawn(move || gateway_replies(replies, events));
+            Ok(transport)
+        });
+    });
+
+    let breaker_config = match arg_value("--config") {
+        Some(path) => CircuitBreakerConfig::from_file(&path)?,
+        None => CircuitBreakerConfig::default(),
+    };
+
+    let signal_cooldown = match arg_value("--signal-cooldown-ms") {
+        Some(ms) => Duration::from_millis(ms.parse()?),
+        None => DEFAULT_SIGNAL_COOLDOWN,
+    };
+
+    if let Some(universe) = &universe {
+        info!(
+            "Trading {}",
+            universe.names().collect::<Vec<_>>().join(", ")
+        );
+    }
+    let config_path = arg_value("--config");
+    let max_position: f64 = match arg_value("--max-position") {
+        Some(max) => max.parse()?,
+        None => f64::INFINITY,
+    };
+    let shards: usize = match arg_value("--shards") {
+        Some(n) => n.parse()?,
+        None => 1,
+    };
+    anyhow::ensure!(shards > 0, "--shards must be at least 1");
+    // One core per shard, in shard order; shards past the list aren't pinned
+    let strategy_cores: Vec<usize> = match arg_value("--strategy-cores") {
+        Some(cores) => cores
+            .split(',')
+            .map(|core| core.trim().parse())
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    // Shards trade disjoint symbols, so each keeps its own snapshot file
+    let snapshot_path = arg_value("--snapshot");
+    let snapshot_for = move |shard: usize| {
+        snapshot_path.as_ref().map(|path| match shards {
+            1 => path.clone(),
+            _ => format!("{}.{}", path, shard),
+        })
+    };
+    let snapshot_interval = match arg_value("--snapshot-interval-ms") {
+        Some(ms) => Duration::from_millis(ms.parse()?),
+        None => DEFAULT_SNAPSHOT_INTERVAL,
+    };
+    // Read up front so an unreadable snapshot stops startup rather than
+    // trading from flat
+    let snapshots = (0..shards)
+        .map(|shard| match snapshot_for(shard) {
+            Some(path) => hft_types::snapshot::load::<RunnerSnapshot, _>(path),
+            None => Ok(None),
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let nonfinite_policy: NonFinitePolicy = match arg_value("--nonfinite-policy") {
+        Some(policy) => policy.parse()?,
+        None => NonFinitePolicy::default(),
+    };
+
+    let audit = arg_value("--audit-log")
+        .map(|path| {
+            info!("Auditing signals and orders to {}", path);
+            AuditLog::open(&path, "strategy_engine").map(Arc::new)
+        })
+        .transpose()?;
+
+    // Shards of one engine share a client id and so never trade with each other
+    let client_id = arg_value("--client-id").unwrap_or_else(|| DEFAULT_CLIENT_ID.to_string());
+
+    // Each shard gets its own strategy instance and circuit breaker
+    let order_id_base = order_id_base();
+    let make_runner = move |shard: usize| {
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_guarded_strategy(&config, nonfinite_policy))),
+            order_tx.clone(),
+            Duration::from_secs(10),
+            signal_cooldown,
+        )
+        .with_nonfinite_policy(nonfinite_policy)
+        .with_circuit_breaker(breaker_config.clone())
+        .with_position_sizer(PositionSizer::new(max_position))
+        .with_order_ids(order_id_base, shard as u64, shards as u64)
+        .with_client_id(&client_id);
+        if let Some(path) = &config_path {
+            runner = runner.with_config_watcher(ConfigWatcher::new(path));
+        }
+        if let Some(universe) = &universe {
+            runner = runner.with_universe(universe.clone());
+        }
+        if let Some(log) = &audit {
+            runner = runner.with_audit_log(Arc::clone(log));
+        }
+        if let Some(path) = snapshot_for(shard) {
+            runner = runner.with_snapshotter(Snapshotter::new(&path, snapshot_interval));
+            if let Some(snapshot) = snapshots[shard].clone() {
+                info!(
+                    "Resuming from {}: {} positions, next order id {}",
+                    path,
+                    snapshot.positions.len(),
+                    snapshot.next_order_id
+                );
+                runner.restore(snapshot);
+            }
+        }
+        runner
+    };
+
+    // Run strategy
+    if shards == 1 {
+        if let Some(&core) = strategy_cores.first() {
+            hft_types::affinity::pin_current("strategy", core);
+        }
+        make_runner(0).run(feed_rx);
+        drop(make_runner);
+    } else {
+        info!("Partitioning symbols across {} strategy shards", shards);
+        ShardedDispatcher::spawn(shards, 100_000, move |shard, events| {
+            if let Some(&core) = strategy_cores.get(shard) {
+                hft_types::affinity::pin_current(&format!("strategy-shard-{}", shard), core);
+            }
+            make_runner(shard).run(events)
+        })
+        .run(feed_rx);
+    }
+
+    // Every runner has returned and dropped its order channel, so this
+    // waits only for orders already queued to go out
+    let _ = sender.join();
+    if let Some(pusher) = pusher {
+        pusher.finish();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::clock::MockClock;
+    use hft_types::config::build_strategy;
+    use hft_types::matching::Fill;
+
+    const MS: u128 = 1_000_000;
+
+    fn tick(price: f64, timestamp_nanos: u128) -> EnrichedTick {
+        tick_for("SOL/USD", price, timestamp_nanos)
+    }
+
+    fn tick_for(symbol: &str, price: f64, timestamp_nanos: u128) -> EnrichedTick {
+        EnrichedTick {
+            tick: MarketTick::new(symbol.to_string(), price, 10, timestamp_nanos),
+            receive_time_nanos: timestamp_nanos,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        }
+    }
+
+    /// The gateway's reply that all of `order` traded at its price
+    fn filled(order: &Order) -> FeedEvent {
+        FeedEvent::Fill(Fill {
+            order_id: order.order_id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            fill_price: order.price,
+            fill_qty: order.quantity,
+            timestamp_nanos: order.timestamp_nanos,
+            is_maker: false,
+            client_id: order.client_id.clone(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_repeat_signals_are_debounced() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::from_millis(100),
+        );
+        let clock = MockClock::new(0);
+        runner.clock = clock.shared();
+        let suppressed_before = SIGNALS_SUPPRESSED.get();
+
+        // Ten ticks above the 95-105 band within the cooldown
+        for i in 0..10 {
+            clock.set((i + 1) * MS);
+            runner.process_tick(tick(110.0, (i + 1) * MS)).await;
+        }
+        assert_eq!(order_rx.try_iter().count(), 1);
+        assert_eq!(SIGNALS_SUPPRESSED.get() - suppressed_before, 9);
+
+        // Still above the band once the cooldown has elapsed
+        clock.set(200 * MS);
+        runner.process_tick(tick(110.0, 200 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+
+        // Back inside the band re-arms it immediately
+        clock.advance(Duration::from_millis(1));
+        runner.process_tick(tick(100.0, 201 * MS)).await;
+        runner.process_tick(tick(110.0, 202 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+
+        // The opposite side is never a repeat
+        runner.process_tick(tick(90.0, 203 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_advancing_clock_past_cooldown_allows_repeat() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+        );
+        let clock = MockClock::new(1_000 * MS);
+        runner.clock = clock.shared();
+
+        // Tick timestamps no longer matter; only the clock does
+        runner.process_tick(tick(110.0, MS)).await;
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+
+        clock.advance(Duration::from_millis(999));
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        clock.advance(Duration::from_millis(1));
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_flatten_closes_positions_and_stops_trading() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        );
+
+        // Above the 95-105 band twice: short 2 once both fill
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, MS))).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 2 * MS))).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(runner.sizer.position("SOL/USD"), 0.0);
+        for order in &orders {
+            runner.on_feed_event(filled(order)).await;
+        }
+        assert_eq!(runner.sizer.position("SOL/USD"), -2.0);
+
+        runner.on_feed_event(FeedEvent::Flatten).await;
+        let closing: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(closing.len(), 1);
+        assert_eq!(closing[0].side, OrderSide::Buy);
+        assert_eq!(closing[0].quantity, 2.0);
+        assert_eq!(closing[0].price, runner.books.get_bbo("SOL/USD").unwrap().1);
+        runner.on_feed_event(filled(&closing[0])).await;
+        assert_eq!(runner.sizer.position("SOL/USD"), 0.0);
+
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 3 * MS))).await;
+        runner.on_feed_event(FeedEvent::Flatten).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        // The next session trades again
+        runner.on_feed_event(FeedEvent::Resume).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 4 * MS))).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_stops_the_runner_and_saves_state() {
+        let path = "/tmp/hft_test_strategy_shutdown.json";
+        std::fs::remove_file(path).ok();
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_snapshotter(Snapshotter::new(path, Duration::from_secs(3600)));
+
+        // The feed is still connected, so only the shutdown ends the run.
+        // Order 1 shorts 1, and the shutdown sends order 2 to buy it back.
+        let sol = |order_id, side, price| Order::new(order_id, "SOL/USD".to_string(), side, price, 1.0, MS);
+        let (feed_tx, feed_rx) = bounded::<FeedEvent>(100);
+        feed_tx.send(FeedEvent::Tick(tick(110.0, MS))).unwrap();
+        feed_tx.send(filled(&sol(1, OrderSide::Sell, 110.0))).unwrap();
+        feed_tx.send(FeedEvent::Shutdown).unwrap();
+        feed_tx.send(FeedEvent::Tick(tick(110.0, 2 * MS))).unwrap();
+        feed_tx.send(filled(&sol(2, OrderSide::Buy, 110.0))).unwrap();
+        runner.run(feed_rx);
+
+        // Nothing is traded after the shutdown but the closing order, and
+        // its fill is in the saved state
+        let sides: Vec<OrderSide> = order_rx.try_iter().map(|order| order.side).collect();
+        assert_eq!(sides, vec![OrderSide::Sell, OrderSide::Buy]);
+        let saved: RunnerSnapshot = hft_types::snapshot::load(path).unwrap().unwrap();
+        assert_eq!(saved.next_order_id, 2);
+        assert_eq!(saved.positions["SOL/USD"], 0.0);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_stale_books_reported_while_the_feed_is_quiet() {
+        let (order_tx, _order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_millis(10),
+            Duration::ZERO,
+        );
+        let clock = MockClock::new(MS);
+        runner.clock = clock.shared();
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(runner.process_tick(tick(100.0, MS)));
+        clock.advance(MAX_BOOK_AGE * 2);
+
+        // Nothing arrives until the shutdown, yet the report still runs
+        let (feed_tx, feed_rx) = bounded::<FeedEvent>(100);
+        let quiet = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            feed_tx.send(FeedEvent::Shutdown).unwrap();
+        });
+        runner.run(feed_rx);
+        quiet.join().unwrap();
+        assert_eq!(STALE_BOOKS.get(), 1);
+    }
+
+    #[test]
+    fn test_until_utc_waits_at_most_a_day() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        for at in ["00:00", "12:30", "23:59"] {
+            let wait = until_utc("--flatten-at", at).unwrap();
+            assert!(!wait.is_zero() && wait <= day, "{}: {:?}", at, wait);
+        }
+        for bad in ["24:00", "12:60", "noon", "12", "-1:00"] {
+            assert!(until_utc("--flatten-at", bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trading_waits_for_snapshot_after_reconnect() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        );
+
+        runner.on_feed_event(FeedEvent::Reconnected { history_stale: true }).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, MS))).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        let snapshot = hft_types::OrderBook::new("SOL/USD".to_string(), MS);
+        runner.on_feed_event(FeedEvent::Snapshot(snapshot)).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 2 * MS))).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+
+        // Each symbol waits for its own snapshot
+        runner.on_feed_event(FeedEvent::Tick(tick_for("ETH/USD", 2700.0, 3 * MS))).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+        let snapshot = hft_types::OrderBook::new("ETH/USD".to_string(), 3 * MS);
+        runner.on_feed_event(FeedEvent::Snapshot(snapshot)).await;
+        runner.on_feed_event(FeedEvent::Tick(tick_for("ETH/USD", 2700.0, 4 * MS))).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_symbols_outside_universe_are_not_traded() {
+        let universe = SymbolUniverse::new(vec![hft_types::SymbolConfig {
+            symbol: "ETH/USD".to_string(),
+            base_price: 2500.0,
+            thresholds: Some(ThresholdBand { low: 2400.0, high: 2600.0 }),
+            tick_size: None,
+            lot_size: None,
+            min_notional: None,
+            activity_weight: None,
+        }])
+        .unwrap();
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        // The default bands cover SOL/USD too, but the universe does not
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_universe(universe);
+
+        runner.process_tick(tick(110.0, MS)).await;
+        runner.process_tick(tick_for("ETH/USD", 2700.0, 2 * MS)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].symbol, "ETH/USD");
+    }
+
+    #[tokio::test]
+    async fn test_orders_sized_by_strength_within_position_cap() {
+        let universe = SymbolUniverse::new(vec![hft_types::SymbolConfig {
+            symbol: "SOL/USD".to_string(),
+            base_price: 100.0,
+            thresholds: Some(ThresholdBand { low: 95.0, high: 105.0 }),
+            tick_size: None,
+            lot_size: Some(0.25),
+            min_notional: None,
+            activity_weight: None,
+        }])
+        .unwrap();
+        let config = StrategyConfig::Threshold {
+            order_size: 2.0,
+            thresholds: HashMap::from([("SOL/USD".to_string(), ThresholdBand { low: 95.0, high: 105.0 })]),
+        };
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&config))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_universe(universe)
+        .with_position_sizer(PositionSizer::new(3.0));
+        let sized_out_before = SIGNALS_SIZED_OUT.get();
+
+        // Just past the band is a weak signal, far past it a full one
+        runner.process_tick(tick(105.5, MS)).await;
+        runner.process_tick(tick(100.0, 2 * MS)).await;
+        runner.process_tick(tick(120.0, 3 * MS)).await;
+        // 3.0 working short after that: only lots back down to the cap are left
+        runner.process_tick(tick(100.0, 4 * MS)).await;
+        runner.process_tick(tick(130.0, 5 * MS)).await;
+        let quantities: Vec<f64> = order_rx.try_iter().map(|order| order.quantity).collect();
+        assert_eq!(quantities, vec![1.0, 2.0]);
+        assert_eq!(SIGNALS_SIZED_OUT.get() - sized_out_before, 1);
+
+        // Buying back is still allowed
+        runner.process_tick(tick(80.0, 6 * MS)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].side.clone(), orders[0].quantity), (hft_types::OrderSide::Buy, 2.0));
+    }
+
+    #[tokio::test]
+    async fn test_unfilled_orders_give_back_their_budget() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_position_sizer(PositionSizer::new(1.0));
+
+        // The first short is still working, so the cap leaves no room
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, MS))).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 2 * MS))).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+
+        // Rejected: none of it will fill, so the next signal trades
+        let order_id = orders[0].order_id;
+        runner.on_feed_event(FeedEvent::Rejected { order_id }).await;
+        runner.on_feed_event(FeedEvent::Tick(tick(110.0, 3 * MS))).await;
+        let order = order_rx.try_recv().unwrap();
+
+        // Half fills and the rest is cancelled: short 0.5 with 0.5 to go
+        let FeedEvent::Fill(fill) = filled(&order) else {
+            unreachable!()
+        };
+        runner.on_feed_event(FeedEvent::Fill(Fill { fill_qty: 0.5, ..fill })).await;
+        let (order_id, cancelled_qty) = (order.order_id, 0.5);
+        runner.on_feed_event(FeedEvent::Cancelled { order_id, cancelled_qty }).await;
+        assert_eq!(runner.sizer.position("SOL/USD"), -0.5);
+        assert_eq!(runner.sizer.budget("SOL/USD", &OrderSide::Sell), 0.5);
+        assert_eq!(order_rx.try_iter().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_restart_resumes_from_snapshot() {
+        let path = "/tmp/hft_test_strategy_snapshot.json";
+        let runner = |order_tx| {
+            StrategyRunner::new(
+                Box::new(SyncAdapter(build_strategy(&default_config()))),
+                order_tx,
+                Duration::from_secs(10),
+                Duration::ZERO,
+            )
+            .with_position_sizer(PositionSizer::new(5.0))
+            .with_snapshotter(Snapshotter::new(path, Duration::from_secs(3600)))
+        };
+        let sequenced = |price, sequence: u64| {
+            let mut enriched = tick(price, sequence as u128 * MS);
+            enriched.tick.sequence = Some(sequence);
+            FeedEvent::Tick(enriched)
+        };
+
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut before = runner(order_tx);
+        before.on_feed_event(sequenced(110.0, 1)).await;
+        before.on_feed_event(sequenced(112.0, 2)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 2);
+        for order in &orders {
+            before.on_feed_event(filled(order)).await;
+        }
+        before.save_snapshot(true);
+
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut after = runner(order_tx);
+        after.restore(after.snapshotter.as_ref().unwrap().load().unwrap().unwrap());
+        assert_eq!(after.sizer.positions(), before.sizer.positions());
+        assert_eq!(after.sizer.position("SOL/USD"), -2.0);
+
+        // A redelivered tick isn't traded twice; new ones carry on the order ids
+        after.on_feed_event(sequenced(112.0, 2)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+        after.on_feed_event(sequenced(111.0, 3)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].order_id, before.next_order_id + 1);
+        assert_eq!(after.last_sequences[&StreamKey::new("SOL/USD", None)], 3);
+
+        // Another venue's stream of the symbol numbers its own sequences
+        let mut other_venue = tick(110.0, 4 * MS);
+        other_venue.tick = other_venue.tick.with_sequence(1).with_venue("B");
+        after.on_feed_event(FeedEvent::Tick(other_venue)).await;
+        assert_eq!(after.last_sequences[&StreamKey::new("SOL/USD", Some("B"))], 1);
+
+        // A feed that restarts its numbering while redelivering isn't
+        // skipped until it climbs back past the snapshot
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut restarted = runner(order_tx);
+        restarted.restore(restarted.snapshotter.as_ref().unwrap().load().unwrap().unwrap());
+        restarted.on_feed_event(sequenced(112.0, 2)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+        restarted.on_feed_event(sequenced(111.0, 1)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+        assert!(restarted.resume_after.is_empty());
+        assert_eq!(restarted.last_sequences[&StreamKey::new("SOL/USD", None)], 1);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_orders_under_min_notional_are_not_sent() {
+        let universe = SymbolUniverse::new(vec![hft_types::SymbolConfig {
+            symbol: "SOL/USD".to_string(),
+            base_price: 100.0,
+            thresholds: Some(ThresholdBand { low: 95.0, high: 105.0 }),
+            tick_size: None,
+            lot_size: None,
+            min_notional: Some(150.0),
+            activity_weight: None,
+        }])
+        .unwrap();
+        let config = StrategyConfig::Threshold {
+            order_size: 2.0,
+            thresholds: HashMap::from([("SOL/USD".to_string(), ThresholdBand { low: 95.0, high: 105.0 })]),
+        };
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&config))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_universe(universe);
+        let sized_out_before = SIGNALS_SIZED_OUT.get();
+
+        // A weak signal sizes to 1.1 x 105.5, under the minimum
+        runner.process_tick(tick(105.5, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+        assert_eq!(SIGNALS_SIZED_OUT.get() - sized_out_before, 1);
+
+        // A full-strength one is worth 2 x 120, and keeps the first order id
+        runner.process_tick(tick(100.0, 2 * MS)).await;
+        runner.process_tick(tick(120.0, 3 * MS)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].order_id, orders[0].quantity), (1, 2.0));
+    }
+
+    #[tokio::test]
+    async fn test_config_rewrite_swaps_thresholds() {
+        let path = "/tmp/hft_test_strategy_reload.toml";
+        let write_band = |low: f64, high: f64| {
+            let config = format!(
+                "[strategy]\ntype = \"threshold\"\norder_size = 1.0\n\
+                 [strategy.thresholds]\n\"SOL/USD\" = {{ low = {:?}, high = {:?} }}\n",
+                low, high
+            );
+            std::fs::write(path, config).unwrap();
+        };
+        write_band(95.0, 105.0);
+
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&StrategyConfig::from_file(path).unwrap()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_config_watcher(ConfigWatcher::new(path));
+
+        runner.check_config_reload();
+        runner.process_tick(tick(100.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        // 100 is now below the band
+        write_band(101.0, 110.0);
+        runner.check_config_reload();
+        runner.process_tick(tick(100.0, 2 * MS)).await;
+        let orders: Vec<Order> = order_rx.try_iter().collect();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].side, hft_types::OrderSide::Buy);
+
+        // An invalid rewrite leaves the current thresholds in place
+        write_band(120.0, 110.0);
+        runner.check_config_reload();
+        runner.process_tick(tick(111.0, 3 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+        // Order ids carry on across reloads
+        assert_eq!(runner.next_order_id, 2);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_shards_assign_disjoint_order_ids() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut ids = Vec::new();
+        for shard in 0..3 {
+            let mut runner = StrategyRunner::new(
+                Box::new(SyncAdapter(build_strategy(&default_config()))),
+                order_tx.clone(),
+                Duration::from_secs(10),
+                Duration::ZERO,
+            )
+            .with_order_ids(0, shard, 3);
+            runner.process_tick(tick(110.0, MS)).await;
+            runner.process_tick(tick(90.0, 2 * MS)).await;
+            ids.extend(order_rx.try_iter().map(|order| order.order_id));
+        }
+        ids.sort();
+        assert_eq!(ids, vec![3, 4, 5, 6, 7, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_orders_carry_the_engine_client_id() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx.clone(),
+            Duration::from_secs(10),
+            Duration::ZERO,
+        );
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_recv().unwrap().client_id.as_deref(), Some(DEFAULT_CLIENT_ID));
+
+        // Flattening orders are stamped too
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_client_id("desk-a");
+        runner.process_tick(tick(110.0, MS)).await;
+        let order = order_rx.try_recv().unwrap();
+        runner.on_feed_event(filled(&order)).await;
+        runner.flatten();
+        let client_ids: Vec<_> = std::iter::once(order.client_id)
+            .chain(order_rx.try_iter().map(|order| order.client_id))
+            .collect();
+        assert_eq!(client_ids.len(), 2);
+        assert!(client_ids.iter().all(|id| id.as_deref() == Some("desk-a")));
+    }
+
+    #[tokio::test]
+    async fn test_restart_without_snapshot_does_not_reuse_order_ids() {
+        let mut last_id = 0;
+        for _ in 0..2 {
+            let (order_tx, order_rx) = bounded::<Order>(100);
+            let mut runner = StrategyRunner::new(
+                Box::new(SyncAdapter(build_strategy(&default_config()))),
+                order_tx,
+                Duration::from_secs(10),
+                Duration::ZERO,
+            )
+            .with_order_ids(order_id_base(), 0, 1);
+            runner.process_tick(tick(110.0, MS)).await;
+            runner.process_tick(tick(90.0, 2 * MS)).await;
+            for order in order_rx.try_iter() {
+                assert!(order.order_id > last_id);
+                last_id = order.order_id;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_breaker_halts_orders() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_circuit_breaker(CircuitBreakerConfig {
+            cooldown_secs: 0.5,
+            ..CircuitBreakerConfig::default()
+        });
+        let clock = MockClock::new(1000 * MS);
+        runner.clock = clock.shared();
+        runner.breaker.trip(hft_types::risk::TripReason::Manual, 1000 * MS);
+
+        runner.process_tick(tick(110.0, 1000 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        // The cooldown runs on the runner's clock, not tick timestamps
+        runner.process_tick(tick(110.0, 1500 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        // Cooldown over with no new trigger
+        clock.advance(Duration::from_millis(500));
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_latency_trip_and_cooldown_share_the_runner_clock() {
+        let (order_tx, order_rx) = bounded::<Order>(100);
+        let mut runner = StrategyRunner::new(
+            Box::new(SyncAdapter(build_strategy(&default_config()))),
+            order_tx,
+            Duration::from_secs(10),
+            Duration::ZERO,
+        )
+        .with_circuit_breaker(CircuitBreakerConfig {
+            max_p99_latency_micros: Some(1.0),
+            cooldown_secs: 0.5,
+            ..CircuitBreakerConfig::default()
+        });
+        let clock = MockClock::new(1000 * MS);
+        runner.clock = clock.shared();
+        runner.latency.record(&LatencyTrace {
+            recv_nanos: Some(10_000),
+            decoded_nanos: Some(20_000),
+            signal_nanos: Some(30_000),
+            ..LatencyTrace::new(0)
+        });
+        runner.report_latency();
+
+        // Ticks stamped long after the trip don't end the cooldown early
+        runner.process_tick(tick(110.0, 5000 * MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 0);
+
+        clock.advance(Duration::from_millis(500));
+        runner.process_tick(tick(110.0, MS)).await;
+        assert_eq!(order_rx.try_iter().count(), 1);
+    }
+}
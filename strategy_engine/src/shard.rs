@@ -0,0 +1,185 @@
+use crate::feed_client::FeedEvent;
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// Shard for `symbol` out of `shards`. FNV-1a rather than the std hasher,
+/// whose per-process seed would move symbols between runs.
+pub fn shard_for(symbol: &str, shards: usize) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in symbol.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % shards.max(1) as u64) as usize
+}
+
+/// Fans feed events out to worker threads by symbol. Every event for a
+/// symbol lands on the same worker, in order, so per-symbol strategy state
+/// stays on one thread without locks.
+pub struct ShardedDispatcher {
+    workers: Vec<Sender<FeedEvent>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ShardedDispatcher {
+    /// Start `shards` workers, each running `worker(shard, events)` on its
+    /// own thread until its channel closes
+    pub fn spawn<F>(shards: usize, capacity: usize, worker: F) -> Self
+    where
+        F: Fn(usize, Receiver<FeedEvent>) + Send + Sync + 'static,
+    {
+        let worker = Arc::new(worker);
+        let (workers, handles) = (0..shards.max(1))
+            .map(|shard| {
+                let (tx, rx) = bounded(capacity);
+                let worker = Arc::clone(&worker);
+                let handle = std::thread::Builder::new()
+                    .name(format!("strategy-shard-{}", shard))
+                    .spawn(move || worker(shard, rx))
+                    .expect("failed to spawn strategy shard");
+                (tx, handle)
+            })
+            .unzip();
+        Self { workers, handles }
+    }
+
+    /// Route one event. Feed-wide events go to every worker.
+    pub fn dispatch(&self, event: FeedEvent) {
+        let symbol = match &event {
+            FeedEvent::Tick(enriched) => &enriched.tick.symbol,
+            FeedEvent::Snapshot(book) => &book.symbol,
+            FeedEvent::Reconnected { history_stale } => {
+                for worker in &self.workers {
+                    let event = FeedEvent::Reconnected {
+                        history_stale: *history_stale,
+                    };
+                    if worker.send(event).is_err() {
+                        warn!("Strategy shard stopped; dropping reconnect");
+                    }
+                }
+                return;
+            }
+            FeedEvent::Flatten => {
+                for worker in &self.workers {
+                    if worker.send(FeedEvent::Flatten).is_err() {
+                        warn!("Strategy shard stopped; its positions stay open");
+                    }
+                }
+                return;
+            }
+            FeedEvent::Resume => {
+                for worker in &self.workers {
+                    let _ = worker.send(FeedEvent::Resume);
+                }
+                return;
+            }
+            FeedEvent::Shutdown => {
+                for worker in &self.workers {
+                    // A shard that already stopped has nothing left to save
+                    let _ = worker.send(FeedEvent::Shutdown);
+                }
+                return;
+            }
+            FeedEvent::Fill(fill) => &fill.symbol,
+            // Only the shard that sent the order knows it; the rest ignore it
+            FeedEvent::Cancelled { order_id, cancelled_qty } => {
+                for worker in &self.workers {
+                    let _ = worker.send(FeedEvent::Cancelled {
+                        order_id: *order_id,
+                        cancelled_qty: *cancelled_qty,
+                    });
+                }
+                return;
+            }
+            FeedEvent::Rejected { order_id } => {
+                for worker in &self.workers {
+                    let _ = worker.send(FeedEvent::Rejected { order_id: *order_id });
+                }
+                return;
+            }
+        };
+        let shard = shard_for(symbol, self.workers.len());
+        if self.workers[shard].send(event).is_err() {
+            warn!("Strategy shard {} stopped; dropping event", shard);
+        }
+    }
+
+    /// Dispatch until the feed closes or a shutdown, then let the workers
+    /// drain and exit. Shards flatten on the way out, so after a shutdown
+    /// the gateway's replies are still passed on until every shard is done.
+    pub fn run(self, feed_rx: Receiver<FeedEvent>) {
+        for event in feed_rx.iter() {
+            let shutdown = matches!(event, FeedEvent::Shutdown);
+            self.dispatch(event);
+            if shutdown {
+                while !self.handles.iter().all(|handle| handle.is_finished()) {
+                    match feed_rx.recv_timeout(Duration::from_millis(10)) {
+                        Ok(event) if event.is_gateway_reply() => self.dispatch(event),
+                        Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                break;
+            }
+        }
+        drop(self.workers);
+        for handle in self.handles {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hft_types::{EnrichedTick, MarketTick};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_symbols_stick_to_one_shard() {
+        let handled: Arc<Mutex<Vec<(usize, String, u128)>>> = Arc::default();
+        let seen = Arc::clone(&handled);
+        let dispatcher = ShardedDispatcher::spawn(4, 64, move |shard, events| {
+            for event in events.iter() {
+                if let FeedEvent::Tick(enriched) = event {
+                    seen.lock()
+                        .unwrap()
+                        .push((shard, enriched.tick.symbol, enriched.tick.timestamp_nanos));
+                }
+            }
+        });
+
+        let (feed_tx, feed_rx) = bounded(1024);
+        let symbols = ["BTC/USD", "ETH/USD", "SOL/USD", "AVAX/USD", "DOGE/USD"];
+        let sent = 500;
+        for i in 0..sent {
+            let tick = MarketTick::new(symbols[i % symbols.len()].to_string(), 100.0, 1, i as u128);
+            feed_tx
+                .send(FeedEvent::Tick(EnrichedTick {
+                    tick,
+                    receive_time_nanos: i as u128,
+                    decoded_nanos: None,
+                    latency_micros: 0.0,
+                }))
+                .unwrap();
+        }
+        drop(feed_tx);
+        dispatcher.run(feed_rx);
+
+        let handled = handled.lock().unwrap();
+        assert_eq!(handled.len(), sent);
+        let mut last: HashMap<&str, (usize, u128)> = HashMap::new();
+        for (shard, symbol, timestamp) in handled.iter() {
+            assert_eq!(*shard, shard_for(symbol, 4));
+            if let Some((previous_shard, previous_timestamp)) = last.get(symbol.as_str()) {
+                assert_eq!(previous_shard, shard, "{} moved shards", symbol);
+                assert!(previous_timestamp < timestamp, "{} out of order", symbol);
+            }
+            last.insert(symbol, (*shard, *timestamp));
+        }
+    }
+}
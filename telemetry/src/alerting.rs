@@ -0,0 +1,480 @@
+use crate::LatencySummary;
+use prometheus::proto::{Bucket, Histogram};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Give up on a webhook that hasn't answered in this long
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Limits the alerter checks each interval; unset limits never fire
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlertRules {
+    pub max_p99_latency_micros: Option<f64>,
+    /// Dropped orders as a fraction of orders submitted
+    pub max_order_drop_rate: Option<f64>,
+}
+
+/// What the metrics looked like over one interval; `None` when there was
+/// nothing to measure
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Observation {
+    pub p99_latency_micros: Option<f64>,
+    pub order_drop_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+/// Body POSTed to the webhook when an alert starts or stops firing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub alert: String,
+    pub status: AlertStatus,
+    pub value: f64,
+    pub threshold: f64,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+/// Tracks which alerts are firing so each breach is announced once when it
+/// starts and once when it clears, however many intervals it lasts
+#[derive(Debug, Default)]
+pub struct Alerter {
+    rules: AlertRules,
+    firing: BTreeMap<String, bool>,
+}
+
+impl Alerter {
+    pub fn new(rules: AlertRules) -> Self {
+        Self {
+            rules,
+            firing: BTreeMap::new(),
+        }
+    }
+
+    /// Alerts whose state `observation` changes. An interval with nothing
+    /// to measure leaves an alert as it was.
+    pub fn transitions(&self, observation: &Observation, timestamp: u64) -> Vec<AlertEvent> {
+        let checks = [
+            (
+                "p99_latency",
+                observation.p99_latency_micros,
+                self.rules.max_p99_latency_micros,
+            ),
+            (
+                "order_drop_rate",
+                observation.order_drop_rate,
+                self.rules.max_order_drop_rate,
+            ),
+        ];
+        checks
+            .into_iter()
+            .filter_map(|(alert, value, threshold)| {
+                let (value, threshold) = (value?, threshold?);
+                let breached = value > threshold;
+                let firing = self.firing.get(alert).copied().unwrap_or(false);
+                (breached != firing).then(|| AlertEvent {
+                    alert: alert.to_string(),
+                    status: if breached {
+                        AlertStatus::Firing
+                    } else {
+                        AlertStatus::Resolved
+                    },
+                    value,
+                    threshold,
+                    timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Mark `event` as delivered. Until it is, the next evaluation reports
+    /// the same transition again.
+    pub fn record(&mut self, event: &AlertEvent) {
+        self.firing
+            .insert(event.alert.clone(), event.status == AlertStatus::Firing);
+    }
+}
+
+/// The feed handler's tick latency histogram
+const LATENCY_METRIC: &str = "feed_latency_micros";
+/// The gateway's order counters
+const PLACED_METRIC: &str = "gateway_orders_placed_total";
+const DROPPED_METRIC: &str = "gateway_dropped_frames_total";
+
+/// Where the alerts read from unless `--alert-source` says otherwise: the
+/// feed handler's and the gateway's `/metrics` on their default health ports
+pub const DEFAULT_SOURCES: [&str; 2] = [
+    "http://127.0.0.1:9202/metrics",
+    "http://127.0.0.1:9204/metrics",
+];
+
+/// The cumulative values alerts are worked out from, as the services
+/// export them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Readings {
+    pub latency: Histogram,
+    pub placed: u64,
+    pub dropped: u64,
+}
+
+impl Readings {
+    /// Pick the readings out of a Prometheus text exposition, ignoring
+    /// every other metric
+    pub fn parse(text: &str) -> Self {
+        let mut readings = Self::default();
+        let mut buckets = Vec::new();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (series, rest) = match line.find('}') {
+                Some(end) => line.split_at(end + 1),
+                None => match line.split_once(' ') {
+                    Some(split) => split,
+                    None => continue,
+                },
+            };
+            let Some(Ok(value)) = rest.split_whitespace().next().map(str::parse::<f64>) else {
+                continue;
+            };
+            let (name, labels) = series.split_once('{').unwrap_or((series, ""));
+            match name.strip_prefix(LATENCY_METRIC) {
+                Some("_sum") => readings.latency.set_sample_sum(value),
+                Some("_count") => readings.latency.set_sample_count(value as u64),
+                Some("_bucket") => {
+                    let bound = labels
+                        .split(',')
+                        .find_map(|label| label.trim().strip_prefix("le=\""))
+                        .and_then(|le| le.trim_end_matches(['"', '}']).parse::<f64>().ok());
+                    // The +Inf bucket is implied by the count
+                    if let Some(bound) = bound.filter(|bound| bound.is_finite()) {
+                        buckets.push((bound, value as u64));
+                    }
+                }
+                _ if name == PLACED_METRIC => readings.placed = value as u64,
+                _ if name == DROPPED_METRIC => readings.dropped = value as u64,
+                _ => {}
+            }
+        }
+        buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (bound, cumulative) in buckets {
+            let mut bucket = Bucket::default();
+            bucket.set_upper_bound(bound);
+            bucket.set_cumulative_count(cumulative);
+            readings.latency.mut_bucket().push(bucket);
+        }
+        readings
+    }
+
+    /// Add `other`'s readings, as scraped from another service
+    fn merge(&mut self, other: Readings) {
+        self.placed += other.placed;
+        self.dropped += other.dropped;
+        if self.latency.get_sample_count() == 0 {
+            self.latency = other.latency;
+        } else if other.latency.get_bucket().len() == self.latency.get_bucket().len() {
+            let count = self.latency.get_sample_count() + other.latency.get_sample_count();
+            let sum = self.latency.get_sample_sum() + other.latency.get_sample_sum();
+            self.latency.set_sample_count(count);
+            self.latency.set_sample_sum(sum);
+            for (bucket, theirs) in self.latency.mut_bucket().iter_mut().zip(other.latency.get_bucket()) {
+                bucket.set_cumulative_count(bucket.get_cumulative_count() + theirs.get_cumulative_count());
+            }
+        }
+    }
+}
+
+/// GET every source's `/metrics` and add the readings up. Fails if any
+/// source can't be read, since a missing service would look like a reset.
+pub async fn scrape(client: &reqwest::Client, sources: &[String]) -> reqwest::Result<Readings> {
+    let mut readings = Readings::default();
+    for source in sources {
+        let text = client.get(source).send().await?.error_for_status()?.text().await?;
+        readings.merge(Readings::parse(&text));
+    }
+    Ok(readings)
+}
+
+/// Turns cumulative readings into per-interval ones: the p99 of the
+/// latencies observed since the last reading, and the share of orders
+/// dropped. A service that restarts resets its counters, and the interval
+/// it happens in counts from zero.
+pub struct MetricsWindow {
+    last: Readings,
+}
+
+impl MetricsWindow {
+    pub fn new(baseline: Readings) -> Self {
+        Self { last: baseline }
+    }
+
+    pub fn observe(&mut self, current: Readings) -> Observation {
+        let last = std::mem::replace(&mut self.last, current.clone());
+        let reset = current.latency.get_sample_count() < last.latency.get_sample_count()
+            || current.latency.get_bucket().len() != last.latency.get_bucket().len();
+        let mut delta = current.latency;
+        if !reset {
+            delta.set_sample_count(delta.get_sample_count() - last.latency.get_sample_count());
+            delta.set_sample_sum(delta.get_sample_sum() - last.latency.get_sample_sum());
+            for (bucket, last) in delta.mut_bucket().iter_mut().zip(last.latency.get_bucket()) {
+                bucket.set_cumulative_count(
+                    bucket.get_cumulative_count().saturating_sub(last.get_cumulative_count()),
+                );
+            }
+        }
+
+        let since = |current: u64, last: u64| if current < last { current } else { current - last };
+        let new_dropped = since(current.dropped, last.dropped);
+        let submitted = since(current.placed, last.placed) + new_dropped;
+
+        Observation {
+            p99_latency_micros: LatencySummary::from_histogram(&delta).p99,
+            order_drop_rate: (submitted > 0).then(|| new_dropped as f64 / submitted as f64),
+        }
+    }
+}
+
+/// POST `event` to `url` as JSON, failing on anything but a 2xx
+pub async fn notify(
+    client: &reqwest::Client,
+    url: &str,
+    event: &AlertEvent,
+) -> reqwest::Result<()> {
+    client
+        .post(url)
+        .header("content-type", "application/json")
+        .body(serde_json::to_vec(event).expect("alert events serialize"))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Scrape `sources` every `interval` and check each interval's readings
+/// against the alerter's rules, notifying `webhook` of each alert that
+/// starts or stops firing
+pub async fn run(
+    mut alerter: Alerter,
+    sources: Vec<String>,
+    webhook: String,
+    interval: Duration,
+) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .expect("failed to build webhook client");
+    let mut ticker = tokio::time::interval(interval);
+    let mut window: Option<MetricsWindow> = None;
+
+    loop {
+        ticker.tick().await;
+        let readings = match scrape(&client, &sources).await {
+            Ok(readings) => readings,
+            Err(e) => {
+                warn!("Skipping alert check, metrics unavailable: {}", e);
+                continue;
+            }
+        };
+        // The first reading is the baseline for a full window
+        let Some(window) = window.as_mut() else {
+            window = Some(MetricsWindow::new(readings));
+            continue;
+        };
+        let observation = window.observe(readings);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for event in alerter.transitions(&observation, now) {
+            match notify(&client, &webhook, &event).await {
+                Ok(()) => {
+                    info!(
+                        "Alert {} {:?}: {:.2} vs threshold {:.2}",
+                        event.alert, event.status, event.value, event.threshold
+                    );
+                    alerter.record(&event);
+                }
+                Err(e) => warn!(
+                    "Failed to notify {} of {} alert: {}",
+                    webhook, event.alert, e
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use hft_types::health::{spawn_server, Readiness};
+    use prometheus::{HistogramOpts, IntCounter, Registry};
+    use std::sync::{Arc, Mutex};
+
+    fn rules() -> AlertRules {
+        AlertRules {
+            max_p99_latency_micros: Some(100.0),
+            max_order_drop_rate: Some(0.05),
+        }
+    }
+
+    /// The metrics the feed handler and the gateway export, each in its
+    /// own registry and served the way the services serve them
+    struct Services {
+        latency: prometheus::Histogram,
+        placed: IntCounter,
+        dropped: IntCounter,
+        sources: Vec<String>,
+    }
+
+    fn services() -> Services {
+        let (feed, gateway) = (Registry::new(), Registry::new());
+        let latency = prometheus::Histogram::with_opts(
+            HistogramOpts::new(LATENCY_METRIC, "test").buckets(hft_types::latency::latency_buckets()),
+        )
+        .unwrap();
+        let placed = IntCounter::new(PLACED_METRIC, "test").unwrap();
+        let dropped = IntCounter::new(DROPPED_METRIC, "test").unwrap();
+        feed.register(Box::new(latency.clone())).unwrap();
+        gateway.register(Box::new(placed.clone())).unwrap();
+        gateway.register(Box::new(dropped.clone())).unwrap();
+        let sources = [feed, gateway]
+            .iter()
+            .map(|registry| {
+                let addr = spawn_server("127.0.0.1:0".parse().unwrap(), Readiness::new(), registry).unwrap();
+                format!("http://{}/metrics", addr)
+            })
+            .collect();
+        Services {
+            latency,
+            placed,
+            dropped,
+            sources,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_window_measures_each_interval_alone() {
+        let services = services();
+        let client = reqwest::Client::new();
+        let read = || scrape(&client, &services.sources);
+        let mut window = MetricsWindow::new(read().await.unwrap());
+        assert_eq!(window.observe(read().await.unwrap()), Observation::default());
+
+        for _ in 0..100 {
+            services.latency.observe(2000.0);
+        }
+        services.placed.inc_by(9);
+        services.dropped.inc();
+        let slow = window.observe(read().await.unwrap());
+        assert!(slow.p99_latency_micros.unwrap() > 1000.0);
+        assert_eq!(slow.order_drop_rate, Some(0.1));
+
+        // Earlier slow samples no longer count
+        for _ in 0..100 {
+            services.latency.observe(3.0);
+        }
+        services.placed.inc_by(10);
+        let fast = window.observe(read().await.unwrap());
+        assert!(fast.p99_latency_micros.unwrap() <= 5.0);
+        assert_eq!(fast.order_drop_rate, Some(0.0));
+
+        // A restarted gateway starts its counters again
+        let restarted = Readings {
+            placed: 4,
+            dropped: 0,
+            ..read().await.unwrap()
+        };
+        assert_eq!(window.observe(restarted).order_drop_rate, Some(0.0));
+
+        // A service that can't be reached fails the scrape
+        let mut sources = services.sources.clone();
+        sources.push("http://127.0.0.1:1/metrics".to_string());
+        assert!(scrape(&client, &sources).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_breach_is_posted_once_then_resolved() {
+        let received: Arc<Mutex<Vec<AlertEvent>>> = Arc::default();
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let received = received.clone();
+                move |Json(event): Json<AlertEvent>| async move {
+                    received.lock().unwrap().push(event);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let mut alerter = Alerter::new(rules());
+        let slow = Observation {
+            p99_latency_micros: Some(250.0),
+            order_drop_rate: Some(0.0),
+        };
+        let recovered = Observation {
+            p99_latency_micros: Some(40.0),
+            order_drop_rate: Some(0.0),
+        };
+        for (timestamp, observation) in [
+            slow,
+            slow,
+            slow,
+            Observation::default(),
+            recovered,
+            recovered,
+        ]
+        .iter()
+        .enumerate()
+        {
+            for event in alerter.transitions(observation, timestamp as u64) {
+                notify(&client, &url, &event).await.unwrap();
+                alerter.record(&event);
+            }
+        }
+
+        let received = received.lock().unwrap();
+        let summary: Vec<(&str, AlertStatus, u64)> = received
+            .iter()
+            .map(|e| (e.alert.as_str(), e.status, e.timestamp))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("p99_latency", AlertStatus::Firing, 0),
+                ("p99_latency", AlertStatus::Resolved, 4),
+            ]
+        );
+        assert_eq!((received[0].value, received[0].threshold), (250.0, 100.0));
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried() {
+        let mut alerter = Alerter::new(rules());
+        let dropping = Observation {
+            p99_latency_micros: None,
+            order_drop_rate: Some(0.2),
+        };
+        // Nothing listening: delivery fails and the alert stays pending
+        let client = reqwest::Client::new();
+        for event in alerter.transitions(&dropping, 0) {
+            assert!(notify(&client, "http://127.0.0.1:1/hook", &event)
+                .await
+                .is_err());
+        }
+        let pending = alerter.transitions(&dropping, 1);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].alert, "order_drop_rate");
+
+        alerter.record(&pending[0]);
+        assert!(alerter.transitions(&dropping, 2).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Datasource the dashboard points at unless the request names another;
+/// matches the one provisioned under `infra/grafana`
+pub const DEFAULT_DATASOURCE: &str = "Prometheus";
+
+#[derive(Debug, Deserialize)]
+pub struct DashboardParams {
+    /// Name of the Prometheus datasource in the Grafana importing it
+    pub datasource: Option<String>,
+}
+
+/// One PromQL query and its legend
+struct Target(&'static str, &'static str);
+
+/// A time series panel on a 12-wide grid, two panels to a row
+fn panel(id: u64, title: &str, unit: &str, targets: &[Target]) -> Value {
+    let targets: Vec<Value> = targets
+        .iter()
+        .zip('A'..)
+        .map(|(Target(expr, legend), ref_id)| {
+            json!({
+                "datasource": { "type": "prometheus", "uid": "${datasource}" },
+                "expr": expr,
+                "legendFormat": legend,
+                "refId": ref_id.to_string(),
+            })
+        })
+        .collect();
+    let index = id - 1;
+    json!({
+        "id": id,
+        "title": title,
+        "type": "timeseries",
+        "datasource": { "type": "prometheus", "uid": "${datasource}" },
+        "gridPos": { "h": 8, "w": 12, "x": (index % 2) * 12, "y": (index / 2) * 8 },
+        "fieldConfig": { "defaults": { "unit": unit }, "overrides": [] },
+        "targets": targets,
+    })
+}
+
+/// Grafana dashboard over the services' metrics, ready to import. Panels
+/// query through a `datasource` variable preset to `datasource`.
+pub fn dashboard(datasource: &str) -> Value {
+    let panels = vec![
+        panel(
+            1,
+            "Tick Throughput",
+            "ops",
+            &[
+                Target("sum(rate(feed_ticks_received_total[1m]))", "received"),
+                Target("sum(rate(feed_ticks_dropped_total[1m]))", "dropped"),
+                Target("sum(rate(feed_invalid_ticks_total[1m]))", "invalid"),
+            ],
+        ),
+        panel(
+            2,
+            "Feed Latency",
+            "µs",
+            &[
+                Target(
+                    "histogram_quantile(0.5, sum by (le) (rate(feed_latency_micros_bucket[1m])))",
+                    "p50",
+                ),
+                Target(
+                    "histogram_quantile(0.99, sum by (le) (rate(feed_latency_micros_bucket[1m])))",
+                    "p99",
+                ),
+                Target(
+                    "histogram_quantile(0.999, sum by (le) (rate(feed_latency_micros_bucket[1m])))",
+                    "p99.9",
+                ),
+            ],
+        ),
+        panel(
+            3,
+            "Order Rate",
+            "ops",
+            &[
+                Target("sum(rate(gateway_orders_placed_total[1m]))", "placed"),
+                Target("sum(rate(gateway_fills_total[1m]))", "filled"),
+                Target("sum(rate(orders_throttled_total[1m]))", "throttled"),
+                Target("sum(rate(gateway_dropped_frames_total[1m]))", "dropped frames"),
+            ],
+        ),
+        panel(
+            4,
+            "Gateway Latency p99",
+            "µs",
+            &[
+                Target(
+                    "histogram_quantile(0.99, sum by (le) (rate(gateway_feed_ingest_micros_bucket[1m])))",
+                    "feed ingest",
+                ),
+                Target(
+                    "histogram_quantile(0.99, sum by (le) (rate(gateway_strategy_decision_micros_bucket[1m])))",
+                    "strategy decision",
+                ),
+                Target(
+                    "histogram_quantile(0.99, sum by (le) (rate(gateway_place_micros_bucket[1m])))",
+                    "place",
+                ),
+            ],
+        ),
+        panel(
+            5,
+            "Signals",
+            "ops",
+            &[
+                Target("sum(rate(strategy_signals_generated_total[1m]))", "generated"),
+                Target("sum(rate(strategy_orders_sent_total[1m]))", "orders sent"),
+                Target("sum(rate(signals_suppressed_total[1m]))", "suppressed"),
+            ],
+        ),
+        panel(
+            6,
+            "Services Up",
+            "none",
+            &[Target("service_up", "{{service}}")],
+        ),
+    ];
+
+    json!({
+        "title": "HFT Trading Metrics",
+        "uid": "hft-demo",
+        "schemaVersion": 39,
+        "refresh": "5s",
+        "time": { "from": "now-15m", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Prometheus",
+                "type": "datasource",
+                "query": "prometheus",
+                "current": { "text": datasource, "value": datasource },
+            }]
+        },
+        "panels": panels,
+    })
+}
@@ -1,19 +1,42 @@
+mod alerting;
+mod dashboard;
+
+use alerting::{AlertRules, Alerter};
+use dashboard::{DashboardParams, DEFAULT_DATASOURCE};
 use anyhow::Result;
 use axum::{
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    extract::{Path, Query},
     response::{IntoResponse, Response},
     routing::get,
-    Router,
+    Json, Router,
 };
+use clap::{value_parser, Arg, ArgAction, Command};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
 use futures_util::{SinkExt, StreamExt};
+use hft_types::clock::{SharedClock, SystemClock};
+use hft_types::health::Readiness;
+use hft_types::heartbeat::{HeartbeatMonitor, Liveness, DEFAULT_HEARTBEAT_ADDR};
+use hft_types::cli;
+use hft_types::messaging::{self, parse_socket_addr};
+use hft_types::orderbook::{BookDelta, OrderBookManager, SharedBooks};
+use hft_types::transport::{TcpTransport, Transport};
+use hft_types::{MarketTick, OrderBook};
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
-use serde::Serialize;
-use std::sync::Arc;
-use std::time::Duration;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
 use tokio::sync::broadcast;
 use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{info, warn};
 
 lazy_static! {
     pub static ref REGISTRY: Registry = Registry::new();
@@ -27,9 +50,7 @@ lazy_static! {
 
     pub static ref LATENCY_HISTOGRAM: Histogram = Histogram::with_opts(
         HistogramOpts::new("feed_latency_micros", "Tick processing latency in microseconds")
-            .buckets(vec![
-                1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0
-            ])
+            .buckets(hft_types::latency::latency_buckets())
     )
     .unwrap();
 
@@ -38,12 +59,57 @@ lazy_static! {
         "Total number of orders placed"
     )
     .unwrap();
+
+    pub static ref SERVICE_UP: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("service_up", "1 if the service has sent a recent heartbeat"),
+        &["service"]
+    )
+    .unwrap();
 }
 
 pub fn init_metrics() {
     REGISTRY.register(Box::new(TICKS_RECEIVED.clone())).unwrap();
     REGISTRY.register(Box::new(LATENCY_HISTOGRAM.clone())).unwrap();
     REGISTRY.register(Box::new(ORDERS_PLACED.clone())).unwrap();
+    REGISTRY.register(Box::new(SERVICE_UP.clone())).unwrap();
+}
+
+/// Services expected to heartbeat; reported down until first heard from
+const MONITORED_SERVICES: [&str; 4] = [
+    "market_simulator",
+    "feed_handler",
+    "strategy_engine",
+    "order_gateway",
+];
+
+/// Set `service_up` for every service the monitor knows about
+fn update_service_gauges(monitor: &HeartbeatMonitor, now_nanos: u128) {
+    for (service, liveness) in monitor.statuses(now_nanos) {
+        SERVICE_UP
+            .with_label_values(&[service.as_str()])
+            .set(i64::from(liveness == Liveness::Alive));
+    }
+}
+
+// Receive heartbeats and refresh `service_up` once a second
+async fn monitor_heartbeats(socket: UdpSocket, mut monitor: HeartbeatMonitor, clock: SharedClock) {
+    let mut buf = [0u8; 1024];
+    let mut check = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => match received {
+                Ok((n, _)) => match hft_types::messaging::Message::deserialize(&buf[..n]) {
+                    Ok(message) => {
+                        monitor.on_message(&message, clock.now_nanos());
+                    }
+                    Err(e) => warn!("Ignoring malformed heartbeat: {}", e),
+                },
+                Err(e) => warn!("Heartbeat socket error: {}", e),
+            },
+            _ = check.tick() => update_service_gauges(&monitor, clock.now_nanos()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -70,9 +136,9 @@ impl MetricsSnapshot {
             0.0
         };
 
-        // For demo purposes, simulate percentiles
-        let p50 = mean * 0.8;
-        let p99 = mean * 1.5;
+        let summary = LatencySummary::from_registry(&REGISTRY);
+        let p50 = summary.p50.unwrap_or(0.0);
+        let p99 = summary.p99.unwrap_or(0.0);
 
         Self {
             ticks_received: ticks,
@@ -88,6 +154,137 @@ impl MetricsSnapshot {
     }
 }
 
+/// How often `simulate_metrics` broadcasts a snapshot
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The most recent snapshots, oldest first, for clients that connect late
+struct MetricsHistory {
+    capacity: usize,
+    snapshots: VecDeque<MetricsSnapshot>,
+}
+
+impl MetricsHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            snapshots: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    /// Enough capacity to cover `retention` at one snapshot per `SNAPSHOT_INTERVAL`
+    fn with_retention(retention: Duration) -> Self {
+        Self::new((retention.as_millis() / SNAPSHOT_INTERVAL.as_millis()) as usize)
+    }
+
+    fn push(&mut self, snapshot: MetricsSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    fn snapshots(&self) -> Vec<MetricsSnapshot> {
+        self.snapshots.iter().cloned().collect()
+    }
+}
+
+type SharedHistory = Arc<RwLock<MetricsHistory>>;
+
+// Keep every broadcast snapshot in the history
+async fn record_history(mut rx: broadcast::Receiver<MetricsSnapshot>, history: SharedHistory) {
+    loop {
+        match rx.recv().await {
+            Ok(snapshot) => history.write().unwrap().push(snapshot),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("History recorder lagged, {} snapshots missed", missed)
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn history_handler(history: SharedHistory) -> Json<Vec<MetricsSnapshot>> {
+    Json(history.read().unwrap().snapshots())
+}
+
+/// Latency percentiles estimated from the `feed_latency_micros` histogram.
+/// Values are `None` until there are samples.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+struct LatencySummary {
+    count: u64,
+    p50: Option<f64>,
+    p90: Option<f64>,
+    p99: Option<f64>,
+    /// Upper bound of the highest non-empty bucket
+    max: Option<f64>,
+}
+
+impl LatencySummary {
+    fn from_registry(registry: &Registry) -> Self {
+        registry
+            .gather()
+            .iter()
+            .find(|family| family.get_name() == "feed_latency_micros")
+            .and_then(|family| family.get_metric().first())
+            .map(|metric| Self::from_histogram(metric.get_histogram()))
+            .unwrap_or_default()
+    }
+
+    fn from_histogram(histogram: &prometheus::proto::Histogram) -> Self {
+        let count = histogram.get_sample_count();
+        if count == 0 {
+            return Self::default();
+        }
+
+        // Buckets hold cumulative counts by upper bound
+        let buckets: Vec<(f64, u64)> = histogram
+            .get_bucket()
+            .iter()
+            .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+            .collect();
+        let last_bound = buckets.last().map(|&(bound, _)| bound).unwrap_or(0.0);
+
+        let percentile = |q: f64| {
+            let rank = q * count as f64;
+            let mut lower = (0.0, 0u64);
+            for &(bound, cumulative) in &buckets {
+                if cumulative as f64 >= rank {
+                    // Interpolate linearly within the bucket
+                    let in_bucket = (cumulative - lower.1) as f64;
+                    let fraction = if in_bucket > 0.0 {
+                        (rank - lower.1 as f64) / in_bucket
+                    } else {
+                        1.0
+                    };
+                    return lower.0 + (bound - lower.0) * fraction;
+                }
+                lower = (bound, cumulative);
+            }
+            // Beyond the last finite bucket
+            last_bound
+        };
+
+        let max = buckets
+            .iter()
+            .find(|&&(_, cumulative)| cumulative == count)
+            .map(|&(bound, _)| bound)
+            .unwrap_or(last_bound);
+
+        Self {
+            count,
+            p50: Some(percentile(0.50)),
+            p90: Some(percentile(0.90)),
+            p99: Some(percentile(0.99)),
+            max: Some(max),
+        }
+    }
+}
+
+// Feed latency percentiles as JSON
+async fn latency_handler() -> Json<LatencySummary> {
+    Json(LatencySummary::from_registry(&REGISTRY))
+}
+
 // Prometheus metrics endpoint
 async fn metrics_handler() -> Response {
     let encoder = TextEncoder::new();
@@ -101,28 +298,77 @@ async fn metrics_handler() -> Response {
         .unwrap()
 }
 
+/// Per-message encoding negotiated by the client via `?compression=deflate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WsCompression {
+    #[default]
+    None,
+    Deflate,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WsParams {
+    #[serde(default)]
+    compression: WsCompression,
+    /// Replay the buffered history before live updates
+    #[serde(default)]
+    backlog: bool,
+}
+
+/// Encode a JSON payload as a WebSocket frame: text when uncompressed,
+/// raw-deflated binary when the client negotiated compression
+fn encode_frame(json: String, compression: WsCompression) -> Message {
+    match compression {
+        WsCompression::None => Message::Text(json),
+        WsCompression::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+            match encoder.write_all(json.as_bytes()).and_then(|_| encoder.finish()) {
+                Ok(bytes) => Message::Binary(bytes),
+                Err(_) => Message::Text(json),
+            }
+        }
+    }
+}
+
 // WebSocket handler for live metrics
 async fn ws_handler(
     ws: WebSocketUpgrade,
+    params: WsParams,
     metrics_tx: Arc<broadcast::Sender<MetricsSnapshot>>,
+    history: SharedHistory,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, metrics_tx))
+    let backlog = params.backlog.then(|| history.read().unwrap().snapshots());
+    ws.on_upgrade(move |socket| handle_socket(socket, params.compression, metrics_tx, backlog))
 }
 
-async fn handle_socket(socket: WebSocket, metrics_tx: Arc<broadcast::Sender<MetricsSnapshot>>) {
+async fn handle_socket(
+    socket: WebSocket,
+    compression: WsCompression,
+    metrics_tx: Arc<broadcast::Sender<MetricsSnapshot>>,
+    backlog: Option<Vec<MetricsSnapshot>>,
+) {
     let (mut sender, mut receiver) = socket.split();
     let mut rx = metrics_tx.subscribe();
 
+    for snapshot in backlog.into_iter().flatten() {
+        if let Ok(json) = serde_json::to_string(&snapshot) {
+            if sender.send(encode_frame(json, compression)).await.is_err() {
+                return;
+            }
+        }
+    }
+
     // Send initial snapshot
     if let Ok(snapshot) = serde_json::to_string(&MetricsSnapshot::capture()) {
-        let _ = sender.send(Message::Text(snapshot)).await;
+        let _ = sender.send(encode_frame(snapshot, compression)).await;
     }
 
     // Spawn task to send metrics updates
     let mut send_task = tokio::spawn(async move {
         while let Ok(snapshot) = rx.recv().await {
             if let Ok(json) = serde_json::to_string(&snapshot) {
-                if sender.send(Message::Text(json)).await.is_err() {
+                if sender.send(encode_frame(json, compression)).await.is_err() {
                     break;
                 }
             }
@@ -143,9 +389,201 @@ async fn handle_socket(socket: WebSocket, metrics_tx: Arc<broadcast::Sender<Metr
     }
 }
 
+/// Levels per side streamed in each `/book` snapshot
+const BOOK_DEPTH: usize = 10;
+
+/// Books published by the feed, with the latest snapshot per symbol kept
+/// so new subscribers start from the current state. Subscribers read the
+/// latest books lock-free, so they never contend with the publisher.
+#[derive(Clone)]
+struct BookFeed {
+    tx: Arc<broadcast::Sender<OrderBook>>,
+    latest: Arc<SharedBooks>,
+}
+
+impl BookFeed {
+    fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            tx: Arc::new(tx),
+            latest: Arc::new(SharedBooks::new()),
+        }
+    }
+
+    fn publish(&self, book: OrderBook) {
+        self.latest.publish(book.clone());
+        let _ = self.tx.send(book);
+    }
+}
+
+/// A book trimmed to the top `BOOK_DEPTH` levels per side
+fn top_of_book(book: &OrderBook) -> OrderBook {
+    let mut top = book.clone();
+    top.bids.truncate(BOOK_DEPTH);
+    top.asks.truncate(BOOK_DEPTH);
+    top
+}
+
+// WebSocket handler for one symbol's live book
+async fn book_ws_handler(
+    ws: WebSocketUpgrade,
+    symbol: String,
+    params: WsParams,
+    books: BookFeed,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_book_socket(socket, symbol, params.compression, books))
+}
+
+async fn handle_book_socket(
+    mut socket: WebSocket,
+    symbol: String,
+    compression: WsCompression,
+    books: BookFeed,
+) {
+    // Subscribe before reading the snapshot so no update falls in between
+    let mut rx = books.tx.subscribe();
+    let Some(current) = books.latest.load(&symbol) else {
+        let close = CloseFrame {
+            code: close_code::POLICY,
+            reason: format!("unknown symbol {}", symbol).into(),
+        };
+        let _ = socket.send(Message::Close(Some(close))).await;
+        return;
+    };
+
+    // Each subscriber gets a snapshot, then deltas against the last book
+    // it was sent, so a lagging subscriber skips states but never levels
+    let mut sent = top_of_book(&current);
+    let mut sequence = 0;
+    if let Ok(json) = serde_json::to_string(&messaging::Message::OrderBookUpdate(sent.clone())) {
+        if socket.send(encode_frame(json, compression)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        let message = tokio::select! {
+            update = rx.recv() => match update {
+                Ok(book) if book.symbol == symbol => {
+                    let top = top_of_book(&book);
+                    let delta = BookDelta::between(&sent, &top, sequence + 1);
+                    sent = top;
+                    if delta.is_empty() {
+                        continue;
+                    }
+                    sequence = delta.sequence;
+                    messaging::Message::BookDelta(delta)
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) if is_resync(&text) => {
+                    sent = books.latest.load(&symbol).map_or(sent, |book| top_of_book(&book));
+                    sequence = 0;
+                    messaging::Message::OrderBookUpdate(sent.clone())
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if let Ok(json) = serde_json::to_string(&message) {
+            if socket.send(encode_frame(json, compression)).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Whether a client message asks for a fresh snapshot
+fn is_resync(text: &str) -> bool {
+    matches!(
+        serde_json::from_str(text),
+        Ok(messaging::Message::BookResync { .. })
+    )
+}
+
+/// Wait between attempts to reach the feed handler
+const FEED_RETRY: Duration = Duration::from_secs(1);
+
+/// Publish the books of the feed handler's tick stream at `addr`,
+/// reconnecting after `FEED_RETRY` whenever it drops
+fn follow_feed(addr: SocketAddr, books: BookFeed) {
+    loop {
+        match TcpTransport::connect(addr) {
+            Ok(input) => {
+                info!("Following the feed at {} for books", addr);
+                if let Err(e) = publish_feed_books(input, &books) {
+                    warn!("Lost the feed at {}: {}", addr, e);
+                }
+            }
+            Err(e) => warn!("Feed {} unreachable, retrying in {:?}: {}", addr, FEED_RETRY, e),
+        }
+        std::thread::sleep(FEED_RETRY);
+    }
+}
+
+/// Keep `books` current from the snapshots and ticks on `input` until the
+/// feed hangs up
+fn publish_feed_books(mut input: impl Transport, books: &BookFeed) -> std::io::Result<()> {
+    let mut manager = OrderBookManager::new();
+    while let Some(message) = input.recv_message()? {
+        let symbol = match message {
+            messaging::Message::OrderBookUpdate(book) => {
+                let symbol = book.symbol.clone();
+                manager.apply_snapshot(book);
+                symbol
+            }
+            messaging::Message::EnrichedTick(enriched) => {
+                manager.update_from_tick(&enriched.tick);
+                enriched.tick.symbol
+            }
+            _ => continue,
+        };
+        if let Some(book) = manager.snapshot(&symbol) {
+            books.publish(book);
+        }
+    }
+    Ok(())
+}
+
+/// Symbols and base prices for the demo book feed
+const SIMULATED_BOOKS: [(&str, f64); 4] = [
+    ("BTC/USD", 45000.0),
+    ("ETH/USD", 2500.0),
+    ("SOL/USD", 100.0),
+    ("AVAX/USD", 25.0),
+];
+
+// Simulate book updates for demo, as the feed would publish them, when
+// there is no --feed-addr to follow
+async fn simulate_books(books: BookFeed) {
+    let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+    let mut manager = OrderBookManager::new();
+    let mut counter = 0u64;
+
+    loop {
+        interval.tick().await;
+        counter += 1;
+
+        let timestamp_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        for (symbol, base_price) in SIMULATED_BOOKS {
+            let price = base_price * (1.0 + ((counter % 20) as f64 - 10.0) / 1000.0);
+            let tick = MarketTick::new(symbol.to_string(), price, 10 + counter % 90, timestamp_nanos);
+            manager.update_from_tick(&tick);
+            if let Some(book) = manager.snapshot(symbol) {
+                books.publish(book);
+            }
+        }
+    }
+}
+
 // Simulate metric updates for demo
 async fn simulate_metrics(tx: broadcast::Sender<MetricsSnapshot>) {
-    let mut interval = tokio::time::interval(Duration::from_millis(500));
+    let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
     let mut counter = 0u64;
 
     loop {
@@ -159,7 +597,7 @@ async fn simulate_metrics(tx: broadcast::Sender<MetricsSnapshot>) {
         }
 
         // Simulate orders every 10 iterations
-        if counter % 10 == 0 {
+        if counter.is_multiple_of(10) {
             ORDERS_PLACED.inc();
         }
 
@@ -169,40 +607,479 @@ async fn simulate_metrics(tx: broadcast::Sender<MetricsSnapshot>) {
     }
 }
 
+fn router(
+    metrics_tx: Arc<broadcast::Sender<MetricsSnapshot>>,
+    history: SharedHistory,
+    books: BookFeed,
+) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/latency", get(latency_handler))
+        .route("/dashboard.json", get(|Query(params): Query<DashboardParams>| async move {
+            let datasource = params.datasource.as_deref().unwrap_or(DEFAULT_DATASOURCE);
+            Json(dashboard::dashboard(datasource))
+        }))
+        .route("/history", get({
+            let history = history.clone();
+            move || history_handler(history)
+        }))
+        .route("/ws", get({
+            let tx = metrics_tx.clone();
+            move |ws, Query(params): Query<WsParams>| ws_handler(ws, params, tx, history)
+        }))
+        // Wildcard so `/book/BTC/USD` works without escaping the slash
+        .route("/book/*symbol", get(
+            move |ws, Path(symbol): Path<String>, Query(params): Query<WsParams>| {
+                book_ws_handler(ws, symbol, params, books)
+            }
+        ))
+        .layer(CorsLayer::permissive())
+}
+
+fn cli() -> Command {
+    Command::new("telemetry")
+        .about("Serves metrics, latency and live updates over HTTP and WebSocket")
+        .arg(
+            Arg::new("listen")
+                .long("listen")
+                .value_name("ADDR")
+                .help("HTTP address to serve on")
+                .value_parser(parse_socket_addr)
+                .default_value("0.0.0.0:9090"),
+        )
+        .arg(
+            Arg::new("feed-addr")
+                .long("feed-addr")
+                .value_name("ADDR")
+                .help("Stream /book from the feed handler's tick stream at its --publish address instead of simulated books")
+                .value_parser(parse_socket_addr),
+        )
+        .arg(
+            Arg::new("heartbeat-listen")
+                .long("heartbeat-listen")
+                .value_name("ADDR")
+                .help("UDP address to receive service heartbeats on")
+                .value_parser(parse_socket_addr)
+                .default_value(DEFAULT_HEARTBEAT_ADDR),
+        )
+        .arg(
+            Arg::new("history-secs")
+                .long("history-secs")
+                .value_name("SECS")
+                .help("How much snapshot history /history and ?backlog=true keep")
+                .value_parser(value_parser!(u64).range(1..=86_400))
+                .default_value("600"),
+        )
+        .arg(
+            Arg::new("alert-webhook")
+                .long("alert-webhook")
+                .value_name("URL")
+                .help("POST a JSON alert here when a latency or drop-rate limit is breached, and again when it recovers")
+                .env("HFT_ALERT_WEBHOOK_URL"),
+        )
+        .arg(
+            Arg::new("alert-p99-micros")
+                .long("alert-p99-micros")
+                .value_name("MICROS")
+                .help("Alert when p99 feed latency over an interval exceeds this")
+                .value_parser(value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("alert-drop-rate")
+                .long("alert-drop-rate")
+                .value_name("FRACTION")
+                .help("Alert when the fraction of orders dropped over an interval exceeds this")
+                .value_parser(value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("alert-source")
+                .long("alert-source")
+                .value_name("URL")
+                .help("Prometheus /metrics endpoint alerts are measured from; repeat for each service")
+                .action(ArgAction::Append)
+                .default_values(alerting::DEFAULT_SOURCES),
+        )
+        .arg(
+            Arg::new("alert-interval-secs")
+                .long("alert-interval-secs")
+                .value_name("SECS")
+                .help("How often alert limits are checked")
+                .value_parser(value_parser!(u64).range(1..=3_600))
+                .default_value("10"),
+        )
+        .arg(cli::log_format())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    let args = cli::parse_args(cli());
+    hft_types::logging::init("telemetry", *args.get_one("log-format").unwrap());
 
     init_metrics();
+    let pusher = hft_types::pushgateway::MetricsPusher::from_env("telemetry", &REGISTRY)?;
 
     // Broadcast channel for metrics updates
     let (metrics_tx, _) = broadcast::channel::<MetricsSnapshot>(100);
     let metrics_tx = Arc::new(metrics_tx);
 
+    // Buffer recent snapshots for late joiners
+    let history_secs: u64 = *args.get_one("history-secs").unwrap();
+    let history = Arc::new(RwLock::new(MetricsHistory::with_retention(Duration::from_secs(history_secs))));
+    tokio::spawn(record_history(metrics_tx.subscribe(), history.clone()));
+
     // Spawn metrics simulator
     let tx_clone = metrics_tx.clone();
     tokio::spawn(async move {
         simulate_metrics((*tx_clone).clone()).await;
     });
 
-    // Build router
-    let app = Router::new()
-        .route("/metrics", get(metrics_handler))
-        .route("/ws", get({
-            let tx = metrics_tx.clone();
-            move |ws| ws_handler(ws, tx)
-        }))
-        .layer(CorsLayer::permissive());
+    // Books from the feed handler, or simulated without one
+    let books = BookFeed::new(256);
+    match args.get_one::<SocketAddr>("feed-addr").copied() {
+        Some(feed_addr) => {
+            let books = books.clone();
+            std::thread::spawn(move || follow_feed(feed_addr, books));
+        }
+        None => {
+            tokio::spawn(simulate_books(books.clone()));
+        }
+    }
+
+    // Track service liveness from heartbeats
+    let heartbeat_addr: SocketAddr = *args.get_one("heartbeat-listen").unwrap();
+    let heartbeat_socket = UdpSocket::bind(heartbeat_addr).await?;
+    let mut monitor = HeartbeatMonitor::new(Duration::from_secs(3));
+    for service in MONITORED_SERVICES {
+        monitor.watch(service);
+    }
+    tokio::spawn(monitor_heartbeats(heartbeat_socket, monitor, SystemClock::shared()));
+    info!("  Heartbeats: udp://{}", heartbeat_addr);
 
-    let addr = "0.0.0.0:9090";
+    if let Some(webhook) = args.get_one::<String>("alert-webhook") {
+        let rules = AlertRules {
+            max_p99_latency_micros: args.get_one("alert-p99-micros").copied(),
+            max_order_drop_rate: args.get_one("alert-drop-rate").copied(),
+        };
+        if rules == AlertRules::default() {
+            warn!("--alert-webhook is set but no alert limits are; nothing will fire");
+        }
+        let sources: Vec<String> = args.get_many::<String>("alert-source").unwrap().cloned().collect();
+        let interval = Duration::from_secs(*args.get_one("alert-interval-secs").unwrap());
+        info!(
+            "Checking alert limits every {:?} against {}, notifying {}",
+            interval,
+            sources.join(", "),
+            webhook
+        );
+        tokio::spawn(alerting::run(Alerter::new(rules), sources, webhook.clone(), interval));
+    }
+
+    // Everything telemetry depends on is bound by the time it serves
+    let app = router(metrics_tx, history, books).merge(hft_types::health::router(Readiness::new()));
+
+    let addr: SocketAddr = *args.get_one("listen").unwrap();
     info!("Telemetry server running on http://{}", addr);
     info!("  Prometheus: http://{}/metrics", addr);
-    info!("  WebSocket:  ws://{}/ws", addr);
+    info!("  Latency:    http://{}/latency", addr);
+    info!("  History:    http://{}/history", addr);
+    info!("  Grafana:    http://{}/dashboard.json (?datasource=NAME)", addr);
+    info!("  WebSocket:  ws://{}/ws (?compression=deflate&backlog=true)", addr);
+    info!("  Books:      ws://{}/book/<SYMBOL> (?compression=deflate)", addr);
+    info!("  Probes:     http://{}/healthz, http://{}/readyz", addr, addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            if let Err(e) = hft_types::shutdown::signal().await {
+                warn!("Can't listen for shutdown signals: {}", e);
+                std::future::pending::<()>().await;
+            }
+        })
+        .await?;
 
+    if let Some(pusher) = pusher {
+        pusher.finish();
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use prometheus::core::Metric;
+    use std::io::Read;
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        for args in [
+            &["--listen", "127.0.0.1:70000"][..],
+            &["--listen", "localhost"],
+            &["--no-such-flag"],
+        ] {
+            let result = cli().try_get_matches_from(std::iter::once(&"telemetry").chain(args));
+            assert!(result.is_err(), "{:?} should fail", args);
+        }
+    }
+
+    #[test]
+    fn test_deflate_frame_round_trip() {
+        let json = serde_json::to_string(&MetricsSnapshot::capture()).unwrap();
+
+        let Message::Binary(bytes) = encode_frame(json.clone(), WsCompression::Deflate) else {
+            panic!("expected a binary frame");
+        };
+        let mut decoded = String::new();
+        DeflateDecoder::new(bytes.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, json);
+
+        // Clients that didn't negotiate compression get plain text
+        assert_eq!(encode_frame(json.clone(), WsCompression::None), Message::Text(json));
+    }
+
+    #[test]
+    fn test_service_up_follows_heartbeats() {
+        let mut monitor = HeartbeatMonitor::new(Duration::from_secs(3));
+        monitor.watch("test_service_down");
+        monitor.record("test_service_alive", 1_000_000_000);
+
+        update_service_gauges(&monitor, 2_000_000_000);
+        assert_eq!(SERVICE_UP.with_label_values(&["test_service_alive"]).get(), 1);
+        assert_eq!(SERVICE_UP.with_label_values(&["test_service_down"]).get(), 0);
+
+        update_service_gauges(&monitor, 10_000_000_000);
+        assert_eq!(SERVICE_UP.with_label_values(&["test_service_alive"]).get(), 0);
+    }
+
+    async fn get_json(app: Router, path: &str) -> serde_json::Value {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_latency_endpoint_percentiles() {
+        let _ = REGISTRY.register(Box::new(LATENCY_HISTOGRAM.clone()));
+        let (tx, _) = broadcast::channel::<MetricsSnapshot>(1);
+        let app = router(
+            Arc::new(tx),
+            Arc::new(RwLock::new(MetricsHistory::new(1))),
+            BookFeed::new(1),
+        );
+
+        // A histogram with no samples has no percentiles
+        let empty = Histogram::with_opts(HistogramOpts::new("empty", "empty")).unwrap();
+        let summary = LatencySummary::from_histogram(empty.metric().get_histogram());
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.p99, None);
+
+        for value in [3.0, 7.0, 20.0, 40.0, 80.0, 200.0, 400.0, 800.0, 2000.0] {
+            for _ in 0..10 {
+                LATENCY_HISTOGRAM.observe(value);
+            }
+        }
+
+        let body = get_json(app, "/latency").await;
+        assert!(body["count"].as_u64().unwrap() >= 90);
+        let p50 = body["p50"].as_f64().unwrap();
+        let p90 = body["p90"].as_f64().unwrap();
+        let p99 = body["p99"].as_f64().unwrap();
+        let max = body["max"].as_f64().unwrap();
+
+        assert!(0.0 < p50 && p50 <= p90 && p90 <= p99 && p99 <= max, "{}", body);
+        // 2000µs falls in the (1000, 2500] bucket
+        assert!(max <= 2500.0);
+    }
+
+    #[tokio::test]
+    async fn test_history_endpoint_keeps_latest_snapshots() {
+        let (tx, _) = broadcast::channel::<MetricsSnapshot>(16);
+        let tx = Arc::new(tx);
+        let history = Arc::new(RwLock::new(MetricsHistory::new(5)));
+        let recorder = tokio::spawn(record_history(tx.subscribe(), history.clone()));
+
+        for timestamp in 1..=8 {
+            tx.send(MetricsSnapshot {
+                timestamp,
+                ..MetricsSnapshot::capture()
+            })
+            .unwrap();
+        }
+        // Wait for the recorder to catch up
+        while history.read().unwrap().snapshots.back().map(|s| s.timestamp) != Some(8) {
+            tokio::task::yield_now().await;
+        }
+
+        let body = get_json(router(tx, history, BookFeed::new(1)), "/history").await;
+        let timestamps: Vec<u64> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["timestamp"].as_u64().unwrap())
+            .collect();
+        assert_eq!(timestamps, vec![4, 5, 6, 7, 8]);
+        recorder.abort();
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_queries_known_metrics() {
+        let (tx, _) = broadcast::channel::<MetricsSnapshot>(1);
+        let history = Arc::new(RwLock::new(MetricsHistory::new(1)));
+        let app = router(Arc::new(tx), history, BookFeed::new(1));
+
+        let body = get_json(app.clone(), "/dashboard.json?datasource=Prod%20Prometheus").await;
+        let panels = body["panels"].as_array().unwrap();
+        let exprs: Vec<&str> = panels
+            .iter()
+            .flat_map(|p| p["targets"].as_array().unwrap())
+            .map(|t| t["expr"].as_str().unwrap())
+            .collect();
+        for metric in [
+            "feed_ticks_received_total",
+            "feed_latency_micros_bucket",
+            "gateway_orders_placed_total",
+            "gateway_fills_total",
+            "gateway_place_micros_bucket",
+            "strategy_signals_generated_total",
+            "service_up",
+        ] {
+            assert!(exprs.iter().any(|e| e.contains(metric)), "no panel queries {}", metric);
+        }
+        let titles: Vec<&str> = panels.iter().map(|p| p["title"].as_str().unwrap()).collect();
+        assert!(titles.contains(&"Tick Throughput"));
+        assert!(titles.contains(&"Feed Latency"));
+        assert!(titles.contains(&"Order Rate"));
+
+        // Every panel goes through the datasource variable, preset from the query
+        assert!(panels
+            .iter()
+            .all(|p| p["datasource"]["uid"] == "${datasource}"));
+        let variable = &body["templating"]["list"][0];
+        assert_eq!(variable["name"], "datasource");
+        assert_eq!(variable["current"]["value"], "Prod Prometheus");
+
+        let body = get_json(app, "/dashboard.json").await;
+        assert_eq!(body["templating"]["list"][0]["current"]["value"], DEFAULT_DATASOURCE);
+    }
+
+    async fn serve(app: Router) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    #[test]
+    fn test_feed_books_are_published() {
+        use hft_types::transport::ChannelTransport;
+        use hft_types::EnrichedTick;
+
+        let books = BookFeed::new(16);
+        let mut rx = books.tx.subscribe();
+        let mut snapshot = OrderBook::new("BTC/USD".to_string(), 1);
+        snapshot.upsert_bid(44990.0, 1.0);
+        snapshot.upsert_ask(45010.0, 1.0);
+
+        let (mut feed, input) = ChannelTransport::pair();
+        feed.send_message(&messaging::Message::OrderBookUpdate(snapshot.clone())).unwrap();
+        let tick = EnrichedTick {
+            tick: MarketTick::new("BTC/USD".to_string(), 45100.0, 10, 2),
+            receive_time_nanos: 2,
+            decoded_nanos: None,
+            latency_micros: 1.0,
+        };
+        feed.send_message(&messaging::Message::EnrichedTick(tick)).unwrap();
+        drop(feed);
+        publish_feed_books(input, &books).unwrap();
+
+        // The snapshot as sent, then the book the tick moved
+        assert_eq!(rx.try_recv().unwrap(), snapshot);
+        let moved = rx.try_recv().unwrap();
+        assert_ne!(moved, snapshot);
+        assert_eq!(books.latest.load("BTC/USD").as_deref(), Some(&moved));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_book_socket_streams_snapshot_then_deltas() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let (tx, _) = broadcast::channel::<MetricsSnapshot>(1);
+        let books = BookFeed::new(16);
+        let mut manager = OrderBookManager::new();
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 45000.0, 10, 1));
+        books.publish(manager.get_book("BTC/USD").unwrap().clone());
+        let addr = serve(router(
+            Arc::new(tx),
+            Arc::new(RwLock::new(MetricsHistory::new(1))),
+            books.clone(),
+        ))
+        .await;
+
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/book/BTC/USD", addr))
+            .await
+            .unwrap();
+        let (mut requests, mut replies) = socket.split();
+        let mut next_message = async || loop {
+            if let WsMessage::Text(text) = replies.next().await.unwrap().unwrap() {
+                return serde_json::from_str::<messaging::Message>(&text).unwrap();
+            }
+        };
+        let messaging::Message::OrderBookUpdate(mut book) = next_message().await else {
+            panic!("expected a snapshot first");
+        };
+        assert_eq!(book.timestamp_nanos, 1);
+
+        // Other symbols' updates are filtered out; this symbol's come as deltas
+        manager.update_from_tick(&MarketTick::new("ETH/USD".to_string(), 2500.0, 10, 2));
+        books.publish(manager.get_book("ETH/USD").unwrap().clone());
+        manager.update_from_tick(&MarketTick::new("BTC/USD".to_string(), 45100.0, 10, 3));
+        books.publish(manager.get_book("BTC/USD").unwrap().clone());
+
+        let messaging::Message::BookDelta(delta) = next_message().await else {
+            panic!("expected a delta");
+        };
+        assert_eq!((delta.symbol.as_str(), delta.sequence), ("BTC/USD", 1));
+        delta.apply(&mut book);
+        // As the client sees it, after the wire's float parsing
+        let expected: OrderBook =
+            serde_json::from_str(&serde_json::to_string(&top_of_book(manager.get_book("BTC/USD").unwrap())).unwrap())
+                .unwrap();
+        assert_eq!(book, expected);
+        assert!(book.bids.len() <= BOOK_DEPTH && !book.bids.is_empty());
+
+        // Asking to resync starts over from a snapshot
+        let resync = messaging::Message::BookResync { symbol: "BTC/USD".to_string() };
+        requests
+            .send(WsMessage::Text(serde_json::to_string(&resync).unwrap()))
+            .await
+            .unwrap();
+        match next_message().await {
+            messaging::Message::OrderBookUpdate(snapshot) => assert_eq!(snapshot, expected),
+            other => panic!("expected a snapshot, got {:?}", other),
+        }
+
+        // Unknown symbols are closed with a reason
+        let (mut unknown, _) = tokio_tungstenite::connect_async(format!("ws://{}/book/DOGE/USD", addr))
+            .await
+            .unwrap();
+        let Some(Ok(WsMessage::Close(Some(frame)))) = unknown.next().await else {
+            panic!("expected a close frame");
+        };
+        assert!(frame.reason.contains("unknown symbol DOGE/USD"), "{}", frame.reason);
+    }
+}